@@ -0,0 +1,50 @@
+//! Times the `NearestNeighbor` and `Linear` resample policies on `f32`
+//! and `f64` values, in nanoseconds per output value.
+//!
+//! Run with `cargo run --release --example resample_bench`.
+
+extern crate j2ds;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use j2ds::*;
+
+const INPUT_LEN: usize = 4096;
+const ROUNDS: usize = 2000;
+
+fn time<T, P>(name: &str, mut policy: P, values: &[T], output_len: usize)
+where
+    T: Clone + Default,
+    P: ResamplePolicy<T>,
+{
+    // Split the input like a ring buffer that has wrapped around
+    let (first, second) = values.split_at(values.len() / 3);
+    let mut output = vec![T::default(); output_len];
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let input = Samples::new(black_box(first), black_box(second));
+        policy.resample(input, &mut output);
+        black_box(&mut output);
+    }
+    let per_value = start.elapsed().as_nanos() as f64 / (ROUNDS * output_len) as f64;
+    println!(
+        "{:<12} {:>5} -> {:>5}: {:.3} ns/value",
+        name,
+        values.len(),
+        output_len,
+        per_value
+    );
+}
+
+fn main() {
+    let f32s: Vec<f32> = (0..INPUT_LEN).map(|i| (i as f32 * 0.01).sin()).collect();
+    let f64s: Vec<f64> = f32s.iter().map(|&v| f64::from(v)).collect();
+
+    for &output_len in &[INPUT_LEN - 37, INPUT_LEN + 37] {
+        time("nearest f32", NearestNeighbor, &f32s, output_len);
+        time("nearest f64", NearestNeighbor, &f64s, output_len);
+        time("linear f32", Linear, &f32s, output_len);
+        time("linear f64", Linear, &f64s, output_len);
+    }
+}
@@ -0,0 +1,114 @@
+use std::alloc::{self, Layout};
+use std::cmp;
+use std::mem;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+/// A fixed-length heap allocation whose first element is aligned to
+/// a caller-chosen boundary
+///
+/// This is mostly useful as the storage of a `RingBuffer` (see
+/// `RingBuffer::new_aligned`), so that SIMD code can use aligned loads
+/// on the values.
+pub struct AlignedBuffer<T> {
+    ptr: NonNull<T>,
+    len: usize,
+    layout: Layout,
+}
+
+// The buffer owns its values just like a `Box<[T]>` would
+unsafe impl<T: Send> Send for AlignedBuffer<T> {}
+unsafe impl<T: Sync> Sync for AlignedBuffer<T> {}
+
+impl<T: Clone> AlignedBuffer<T> {
+    /// Allocate `len` copies of `value`, with the first one aligned to
+    /// at least `align` bytes. `align` must be a power of two
+    pub fn new(len: usize, value: T, align: usize) -> AlignedBuffer<T> {
+        assert!(align.is_power_of_two());
+
+        let align = cmp::max(align, mem::align_of::<T>());
+        let size = mem::size_of::<T>()
+            .checked_mul(len)
+            .expect("capacity overflow");
+        let layout = Layout::from_size_align(size, align).expect("invalid layout");
+
+        let ptr = if size == 0 {
+            // Nothing is ever read through this pointer, it just has
+            // to be non-null and suitably aligned
+            NonNull::new(align as *mut T).unwrap()
+        } else {
+            // Safety: the layout has a non-zero size
+            let raw = unsafe { alloc::alloc(layout) } as *mut T;
+            match NonNull::new(raw) {
+                Some(p) => p,
+                None => alloc::handle_alloc_error(layout),
+            }
+        };
+
+        for i in 0..len {
+            // Safety: `i` is within the allocation, and the slot has
+            // not been initialized yet. If `clone` panics the values
+            // written so far are leaked, which is safe
+            unsafe { ptr::write(ptr.as_ptr().add(i), value.clone()) };
+        }
+
+        AlignedBuffer { ptr, len, layout }
+    }
+}
+
+impl<T> AlignedBuffer<T> {
+    /// Returns the alignment of the first element in bytes
+    pub fn alignment(&self) -> usize {
+        self.layout.align()
+    }
+}
+
+impl<T> AsRef<[T]> for AlignedBuffer<T> {
+    fn as_ref(&self) -> &[T] {
+        // Safety: all `len` values were initialized in `new`
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> AsMut<[T]> for AlignedBuffer<T> {
+    fn as_mut(&mut self) -> &mut [T] {
+        // Safety: all `len` values were initialized in `new`
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> Drop for AlignedBuffer<T> {
+    fn drop(&mut self) {
+        // Safety: the values are initialized and the allocation was
+        // made with exactly this layout
+        unsafe {
+            ptr::drop_in_place(self.as_mut());
+            if self.layout.size() != 0 {
+                alloc::dealloc(self.ptr.as_ptr() as *mut u8, self.layout);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_aligned_buffer() {
+    use std::rc::Rc;
+
+    let b = AlignedBuffer::new(5, 1.5f32, 64);
+    assert_eq!(b.as_ref(), &[1.5; 5]);
+    assert_eq!(b.as_ref().as_ptr() as usize % 64, 0);
+    assert_eq!(b.alignment(), 64);
+
+    // Values are dropped along with the buffer
+    let shared = Rc::new(());
+    let mut b = AlignedBuffer::new(3, shared.clone(), 32);
+    assert_eq!(Rc::strong_count(&shared), 4);
+    b.as_mut()[0] = Rc::new(());
+    assert_eq!(Rc::strong_count(&shared), 3);
+    drop(b);
+    assert_eq!(Rc::strong_count(&shared), 1);
+
+    let empty = AlignedBuffer::new(0, 0u64, 128);
+    assert!(empty.as_ref().is_empty());
+    assert_eq!(empty.as_ref().as_ptr() as usize % 128, 0);
+}
@@ -0,0 +1,319 @@
+use std::hash::Hash;
+
+use cache::Cache;
+use lru::LruCache;
+
+/// A cache that adapts between evicting the least recently and the
+/// least frequently used entries, using the Adaptive Replacement Cache
+/// (ARC) algorithm
+///
+/// Entries used once are kept apart from entries used again, and the
+/// cache also remembers the keys it recently evicted from each group.
+/// A miss on a remembered key shows which group was too small, and the
+/// split between the two is moved toward it. This way a scan can't push
+/// out the frequently used entries, but a shift to a new working set is
+/// still picked up quickly. Every operation is O(1).
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut cache = ArcCache::new(4);
+/// for &key in &[1, 2, 1, 2] {
+///     if cache.get(&key).is_none() {
+///         cache.put(key, key * 10);
+///     }
+/// }
+///
+/// // A long scan over keys that are never used again
+/// for key in 100..200 {
+///     cache.put(key, 0);
+/// }
+/// assert!(cache.contains_key(&1) && cache.contains_key(&2));
+/// ```
+pub struct ArcCache<K, V> {
+    // Entries used once since they were inserted, and entries used
+    // more than once
+    recent: LruCache<K, V>,
+    frequent: LruCache<K, V>,
+    // The keys recently evicted from each of the above
+    recent_ghosts: LruCache<K, ()>,
+    frequent_ghosts: LruCache<K, ()>,
+    // The target number of entries in `recent`
+    target: usize,
+    size: usize,
+    on_evict: Option<Box<dyn FnMut(K, V)>>,
+}
+
+impl<K: Hash + Eq + Clone, V> ArcCache<K, V> {
+    /// Create an empty cache that holds up to `size` entries
+    pub fn new(size: usize) -> ArcCache<K, V> {
+        assert!(size > 0);
+        ArcCache {
+            recent: LruCache::new(size),
+            frequent: LruCache::new(size),
+            recent_ghosts: LruCache::new(size),
+            frequent_ghosts: LruCache::new(2 * size),
+            target: 0,
+            size,
+            on_evict: None,
+        }
+    }
+
+    /// Pass each entry that is evicted to make room for a new one to
+    /// `callback`. Entries that are removed or replaced explicitly
+    /// aren't passed to it
+    pub fn on_evict<F: FnMut(K, V) + 'static>(&mut self, callback: F) {
+        self.on_evict = Some(Box::new(callback));
+    }
+
+    /// Insert `value` under `key`, and return the value it replaced, if
+    /// any. If the cache is full, an entry is evicted first
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old) = self.recent.remove(&key) {
+            self.frequent.put(key, value);
+            return Some(old);
+        }
+        if self.frequent.contains_key(&key) {
+            return self.frequent.put(key, value);
+        }
+
+        if self.recent_ghosts.contains_key(&key) {
+            // The recent entries needed more room
+            let step = (self.frequent_ghosts.len() / self.recent_ghosts.len()).max(1);
+            self.target = (self.target + step).min(self.size);
+            self.recent_ghosts.remove(&key);
+            self.make_room(false);
+            self.frequent.put(key, value);
+        } else if self.frequent_ghosts.contains_key(&key) {
+            // The frequent entries needed more room
+            let step = (self.recent_ghosts.len() / self.frequent_ghosts.len()).max(1);
+            self.target = self.target.saturating_sub(step);
+            self.frequent_ghosts.remove(&key);
+            self.make_room(true);
+            self.frequent.put(key, value);
+        } else {
+            let recent_side = self.recent.len() + self.recent_ghosts.len();
+            let total = recent_side + self.frequent.len() + self.frequent_ghosts.len();
+            if recent_side >= self.size {
+                if self.recent.len() < self.size {
+                    self.recent_ghosts.pop_lru();
+                    self.make_room(false);
+                } else if let Some((old_key, old_value)) = self.recent.pop_lru() {
+                    self.evicted(old_key, old_value);
+                }
+            } else if total >= self.size {
+                if total >= 2 * self.size {
+                    self.frequent_ghosts.pop_lru();
+                }
+                self.make_room(false);
+            }
+            self.recent.put(key, value);
+        }
+        None
+    }
+
+    /// Return the value under `key` and count it as used, or `None` if
+    /// it isn't in the cache
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// Return the value under `key` for modification and count it as
+    /// used, or `None` if it isn't in the cache
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        if let Some(value) = self.recent.remove(key) {
+            self.frequent.put(key.clone(), value);
+        }
+        self.frequent.get_mut(key)
+    }
+
+    /// Return the value under `key` without counting it as used, or
+    /// `None` if it isn't in the cache
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.recent.peek(key).or_else(|| self.frequent.peek(key))
+    }
+
+    /// Returns true if there is an entry under `key`
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.peek(key).is_some()
+    }
+
+    /// Remove the entry under `key` and return its value, or `None` if
+    /// it isn't in the cache
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.recent_ghosts.remove(key);
+        self.frequent_ghosts.remove(key);
+        self.recent
+            .remove(key)
+            .or_else(|| self.frequent.remove(key))
+    }
+
+    /// Remove every entry from the cache, and forget the evicted keys
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.frequent.clear();
+        self.recent_ghosts.clear();
+        self.frequent_ghosts.clear();
+        self.target = 0;
+    }
+
+    /// Return the number of entries the cache is currently aiming to
+    /// keep for keys that have only been used once
+    pub fn recent_target(&self) -> usize {
+        self.target
+    }
+
+    /// Return the number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.recent.len() + self.frequent.len()
+    }
+
+    /// Returns true if there are no entries in the cache
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the max number of entries the cache holds
+    pub fn max_len(&self) -> usize {
+        self.size
+    }
+
+    // Evict an entry if the cache is full, from the recent entries if
+    // there are more than the target, and otherwise from the frequent
+    // ones. `frequent_hit` is whether the key being added was
+    // remembered from the frequent entries
+    fn make_room(&mut self, frequent_hit: bool) {
+        if self.len() < self.size {
+            return;
+        }
+        let from_recent = !self.recent.is_empty()
+            && (self.recent.len() > self.target
+                || (frequent_hit && self.recent.len() == self.target));
+        if from_recent {
+            let (key, value) = self.recent.pop_lru().unwrap();
+            self.recent_ghosts.put(key.clone(), ());
+            self.evicted(key, value);
+        } else if let Some((key, value)) = self.frequent.pop_lru() {
+            self.frequent_ghosts.put(key.clone(), ());
+            self.evicted(key, value);
+        }
+    }
+
+    fn evicted(&mut self, key: K, value: V) {
+        if let Some(ref mut callback) = self.on_evict {
+            callback(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for ArcCache<K, V> {
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        ArcCache::put(self, key, value)
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        ArcCache::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        ArcCache::get_mut(self, key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        ArcCache::peek(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        ArcCache::remove(self, key)
+    }
+
+    fn on_evict<F: FnMut(K, V) + 'static>(&mut self, callback: F) {
+        ArcCache::on_evict(self, callback)
+    }
+
+    fn clear(&mut self) {
+        ArcCache::clear(self)
+    }
+
+    fn len(&self) -> usize {
+        ArcCache::len(self)
+    }
+
+    fn max_len(&self) -> usize {
+        ArcCache::max_len(self)
+    }
+}
+
+#[test]
+fn test_arc_cache() {
+    let mut cache = ArcCache::new(2);
+    assert!(cache.is_empty());
+    assert_eq!(cache.put('a', 1), None);
+    assert_eq!(cache.put('a', 2), Some(1));
+    *cache.get_mut(&'a').unwrap() += 1;
+    assert_eq!(cache.peek(&'a'), Some(&3));
+
+    cache.put('b', 1);
+    cache.put('c', 1);
+    assert_eq!(cache.len(), 2);
+    assert!(cache.contains_key(&'a'));
+    assert!(!cache.contains_key(&'b'));
+
+    // A remembered key moves the target toward the recent entries
+    cache.put('b', 2);
+    assert_eq!(cache.recent_target(), 1);
+    assert_eq!(cache.get(&'b'), Some(&2));
+    assert_eq!(cache.len(), 2);
+
+    assert_eq!(cache.remove(&'b'), Some(2));
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.recent_target(), 0);
+}
+
+#[test]
+fn test_arc_cache_adapts() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let evictions = Rc::new(RefCell::new(0));
+    let mut cache = ArcCache::new(10);
+    {
+        let evictions = evictions.clone();
+        cache.on_evict(move |_, _| *evictions.borrow_mut() += 1);
+    }
+
+    let access = |cache: &mut ArcCache<u32, u32>, key| {
+        let hit = cache.get(&key).is_some();
+        if !hit {
+            cache.put(key, key);
+        }
+        hit
+    };
+
+    // A hot set used repeatedly, with long scans in between
+    for round in 0..20 {
+        for key in (0..5).chain(0..5) {
+            access(&mut cache, key);
+        }
+        for key in 0..20 {
+            access(&mut cache, 1000 * (round + 1) + key);
+        }
+    }
+    assert!((0..5).all(|key| access(&mut cache, key)));
+    assert!(cache.len() <= 10);
+
+    // Then a new working set, which the cache switches to
+    let mut hits = 0;
+    for _ in 0..5 {
+        for key in 500..508 {
+            if access(&mut cache, key) {
+                hits += 1;
+            }
+        }
+    }
+    assert!(hits >= 3 * 8, "{}", hits);
+    assert!(*RefCell::borrow(&evictions) > 0);
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::slice;
+
+use error::Error;
+
+/// A vector that holds up to `N` values inline, without ever
+/// allocating
+///
+/// Values are stored in order and can be accessed randomly through the
+/// slice the vector dereferences to. Adding a value to a full vector
+/// fails instead of growing it.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut v = ArrayVec::<u32, 4>::new();
+/// v.push(1);
+/// v.push(3);
+/// v.insert(1, 2);
+/// assert_eq!(&v[..], &[1, 2, 3]);
+///
+/// assert!(v.push(4));
+/// assert!(!v.push(5));
+/// assert_eq!(v.remove(0), 1);
+/// assert_eq!(v.iter().sum::<u32>(), 9);
+/// ```
+pub struct ArrayVec<T, const N: usize> {
+    // The first `len` values are initialized
+    values: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayVec<T, N> {
+    /// Create an empty vector
+    pub fn new() -> ArrayVec<T, N> {
+        ArrayVec {
+            // Safety: an array of `MaybeUninit` doesn't need to be
+            // initialized
+            values: unsafe { MaybeUninit::uninit().assume_init() },
+            len: 0,
+        }
+    }
+
+    /// Add `value` to the end of the vector. Returns false if the
+    /// vector is full
+    pub fn push(&mut self, value: T) -> bool {
+        self.try_push(value).is_ok()
+    }
+
+    /// Add `value` to the end of the vector, or return `Error::Full`
+    /// if the vector is full
+    pub fn try_push(&mut self, value: T) -> Result<(), Error> {
+        self.try_insert(self.len, value)
+    }
+
+    /// Remove the last value from the vector and return it, or `None`
+    /// if the vector is empty
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // Safety: the value was initialized, and is no longer counted
+        // in `len` so it won't be read or dropped again
+        Some(unsafe { self.values[self.len].as_ptr().read() })
+    }
+
+    /// Insert `value` at `index`, shifting the values after it to the
+    /// right. Returns false if the vector is full. Panics if `index` is
+    /// greater than the length
+    pub fn insert(&mut self, index: usize, value: T) -> bool {
+        self.try_insert(index, value).is_ok()
+    }
+
+    /// Insert `value` at `index` like `insert`, or return `Error::Full`
+    /// if the vector is full
+    pub fn try_insert(&mut self, index: usize, value: T) -> Result<(), Error> {
+        assert!(index <= self.len, "insertion index out of bounds");
+        if self.len == N {
+            return Err(Error::Full {
+                needed: 1,
+                available: 0,
+            });
+        }
+        // Safety: there is room for one more value, so the values from
+        // `index` can be moved right by one and the gap filled
+        unsafe {
+            let p = self.as_mut_ptr().add(index);
+            ptr::copy(p, p.add(1), self.len - index);
+            ptr::write(p, value);
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove the value at `index` and return it, shifting the values
+    /// after it to the left. Panics if `index` is out of bounds
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "removal index out of bounds");
+        self.len -= 1;
+        // Safety: the value at `index` is initialized, and the values
+        // after it are moved over it once it has been read
+        unsafe {
+            let p = self.as_mut_ptr().add(index);
+            let value = ptr::read(p);
+            ptr::copy(p.add(1), p, self.len - index);
+            value
+        }
+    }
+
+    /// Remove the value at `index` and return it, replacing it with the
+    /// last value. This doesn't preserve the order, but is O(1). Panics
+    /// if `index` is out of bounds
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "removal index out of bounds");
+        let last = self.len - 1;
+        self.as_mut_slice().swap(index, last);
+        self.pop().unwrap()
+    }
+
+    /// Shorten the vector to `len` values, dropping the rest. Does
+    /// nothing if it is already shorter
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop();
+        }
+    }
+
+    /// Remove all values from the vector
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Return the values as a slice
+    pub fn as_slice(&self) -> &[T] {
+        // Safety: the first `len` values are initialized
+        unsafe { slice::from_raw_parts(self.values.as_ptr() as *const T, self.len) }
+    }
+
+    /// Return the values as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        // Safety: the first `len` values are initialized
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+
+    /// Returns the number of values in the vector
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no values in the vector
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if no more values can be added
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Returns the number of free slots in the vector
+    pub fn capacity(&self) -> usize {
+        N - self.len
+    }
+
+    /// Returns the max number of values that can ever be stored in the
+    /// vector
+    pub fn max_len(&self) -> usize {
+        N
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.values.as_mut_ptr() as *mut T
+    }
+}
+
+impl<T, const N: usize> Drop for ArrayVec<T, N> {
+    fn drop(&mut self) {
+        // Safety: the first `len` values are initialized, and are never
+        // used again
+        unsafe { ptr::drop_in_place(self.as_mut_slice()) };
+    }
+}
+
+impl<T, const N: usize> Default for ArrayVec<T, N> {
+    fn default() -> ArrayVec<T, N> {
+        ArrayVec::new()
+    }
+}
+
+impl<T, const N: usize> Deref for ArrayVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for ArrayVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a ArrayVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for ArrayVec<T, N> {
+    fn clone(&self) -> ArrayVec<T, N> {
+        let mut v = ArrayVec::new();
+        for value in self {
+            v.push(value.clone());
+        }
+        v
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for ArrayVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for ArrayVec<T, N> {
+    fn eq(&self, other: &ArrayVec<T, N>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for ArrayVec<T, N> {}
+
+#[test]
+fn test_array_vec() {
+    let mut v = ArrayVec::<i32, 4>::new();
+    assert!(v.is_empty());
+    assert_eq!(v.pop(), None);
+    assert_eq!(v.max_len(), 4);
+
+    for i in 0..4 {
+        v.push(i);
+    }
+    assert!(v.is_full());
+    assert_eq!(v.capacity(), 0);
+    assert_eq!(
+        v.try_push(4),
+        Err(Error::Full {
+            needed: 1,
+            available: 0
+        })
+    );
+    assert!(!v.insert(0, 4));
+
+    assert_eq!(v.remove(1), 1);
+    assert_eq!(v.swap_remove(0), 0);
+    assert_eq!(&v[..], &[3, 2]);
+    assert!(v.insert(2, 7));
+    assert!(v.insert(0, 8));
+    v[1] = 5;
+    assert_eq!(format!("{:?}", v), "[8, 5, 2, 7]");
+    assert_eq!(v.clone(), v);
+
+    v.truncate(1);
+    assert_eq!(v.pop(), Some(8));
+    assert_eq!(v.len(), 0);
+}
+
+#[test]
+fn test_array_vec_drop() {
+    use std::rc::Rc;
+
+    let value = Rc::new(());
+    {
+        let mut v = ArrayVec::<Rc<()>, 8>::new();
+        for _ in 0..5 {
+            v.push(value.clone());
+        }
+        drop(v.remove(2));
+        assert_eq!(Rc::strong_count(&value), 5);
+        v.truncate(3);
+        assert_eq!(Rc::strong_count(&value), 4);
+    }
+    assert_eq!(Rc::strong_count(&value), 1);
+}
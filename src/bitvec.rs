@@ -0,0 +1,265 @@
+// Each rank entry covers a block of this many words
+const BLOCK_WORDS: usize = 8;
+
+/// A growable vector of bits, which can also count the set bits before
+/// a position (`rank`) and find the position of the n-th set bit
+/// (`select`)
+///
+/// An index of the number of set bits before each 512 bit block is kept
+/// up to date as the vector changes, so `rank` is O(1) and `select` is
+/// O(log n). Pushing and popping bits are O(1), but changing a bit in
+/// the middle of the vector has to update the index after it, which is
+/// O(n).
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut bits = BitVec::new();
+/// for &bit in &[true, false, false, true, true, false, true] {
+///     bits.push(bit);
+/// }
+///
+/// assert_eq!(bits.rank(4), 2);
+/// assert_eq!(bits.select(2), Some(4));
+/// assert_eq!(bits.select(4), None);
+/// assert_eq!(bits.count_ones(), 4);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct BitVec {
+    // Bits past `len` in the last word are always 0
+    words: Vec<u64>,
+    len: usize,
+    // The number of set bits before each block of words
+    ranks: Vec<usize>,
+    ones: usize,
+}
+
+impl BitVec {
+    /// Create an empty vector
+    pub fn new() -> BitVec {
+        BitVec::default()
+    }
+
+    /// Create a vector of `len` copies of `bit`
+    pub fn with_len(len: usize, bit: bool) -> BitVec {
+        let mut bits = BitVec::new();
+        for _ in 0..len {
+            bits.push(bit);
+        }
+        bits
+    }
+
+    /// Add `bit` to the end of the vector
+    pub fn push(&mut self, bit: bool) {
+        if self.len % 64 == 0 {
+            if self.words.len() % BLOCK_WORDS == 0 {
+                self.ranks.push(self.ones);
+            }
+            self.words.push(0);
+        }
+        self.len += 1;
+        if bit {
+            self.words[(self.len - 1) / 64] |= 1 << ((self.len - 1) % 64);
+            self.ones += 1;
+        }
+    }
+
+    /// Remove the last bit from the vector and return it, or `None` if
+    /// the vector is empty
+    pub fn pop(&mut self) -> Option<bool> {
+        let last = self.len.checked_sub(1)?;
+        let bit = self.get(last).unwrap();
+        if bit {
+            self.words[last / 64] &= !(1 << (last % 64));
+            self.ones -= 1;
+        }
+        self.len = last;
+        if self.len % 64 == 0 {
+            self.words.pop();
+            if self.words.len() % BLOCK_WORDS == 0 {
+                self.ranks.pop();
+            }
+        }
+        Some(bit)
+    }
+
+    /// Return the bit at `index`, or `None` if it is out of bounds
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index < self.len {
+            Some(self.words[index / 64] & (1 << (index % 64)) != 0)
+        } else {
+            None
+        }
+    }
+
+    /// Change the bit at `index` to `bit`. Panics if `index` is out of
+    /// bounds
+    pub fn set(&mut self, index: usize, bit: bool) {
+        assert!(index < self.len, "index out of bounds");
+        if self.get(index) == Some(bit) {
+            return;
+        }
+        self.words[index / 64] ^= 1 << (index % 64);
+
+        let later = &mut self.ranks[index / 64 / BLOCK_WORDS + 1..];
+        if bit {
+            self.ones += 1;
+            later.iter_mut().for_each(|r| *r += 1);
+        } else {
+            self.ones -= 1;
+            later.iter_mut().for_each(|r| *r -= 1);
+        }
+    }
+
+    /// Return the number of set bits before `index`. Panics if `index`
+    /// is greater than the length
+    pub fn rank(&self, index: usize) -> usize {
+        assert!(index <= self.len, "index out of bounds");
+        if index == self.len {
+            return self.ones;
+        }
+        let word = index / 64;
+        let block = word / BLOCK_WORDS;
+        let full: u32 = self.words[block * BLOCK_WORDS..word]
+            .iter()
+            .map(|w| w.count_ones())
+            .sum();
+        let partial = self.words[word] & ((1 << (index % 64)) - 1);
+        self.ranks[block] + (full + partial.count_ones()) as usize
+    }
+
+    /// Return the position of the set bit with `rank` set bits before
+    /// it, i.e. the `rank + 1`-th set bit, or `None` if there are not
+    /// that many
+    pub fn select(&self, rank: usize) -> Option<usize> {
+        if rank >= self.ones {
+            return None;
+        }
+        let block = self.ranks.partition_point(|&r| r <= rank) - 1;
+        let mut left = rank - self.ranks[block];
+        for (i, &word) in self.words[block * BLOCK_WORDS..].iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if left < ones {
+                let pos = (block * BLOCK_WORDS + i) * 64 + select_in_word(word, left);
+                return Some(pos);
+            }
+            left -= ones;
+        }
+        unreachable!("rank index is out of date")
+    }
+
+    /// Return the number of set bits
+    pub fn count_ones(&self) -> usize {
+        self.ones
+    }
+
+    /// Iterate over the bits, from the first one
+    pub fn iter(&self) -> Bits<'_> {
+        Bits {
+            bits: self,
+            index: 0,
+        }
+    }
+
+    /// Remove all bits from the vector
+    pub fn clear(&mut self) {
+        self.words.clear();
+        self.ranks.clear();
+        self.len = 0;
+        self.ones = 0;
+    }
+
+    /// Return the number of bits in the vector
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no bits in the vector
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+// Return the position of the set bit in `word` with `rank` set bits
+// below it
+fn select_in_word(mut word: u64, rank: usize) -> usize {
+    for _ in 0..rank {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+/// An iterator over the bits of a `BitVec`
+pub struct Bits<'a> {
+    bits: &'a BitVec,
+    index: usize,
+}
+
+impl<'a> Iterator for Bits<'a> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        let bit = self.bits.get(self.index)?;
+        self.index += 1;
+        Some(bit)
+    }
+}
+
+#[test]
+fn test_bit_vec() {
+    let mut bits = BitVec::with_len(3, true);
+    assert_eq!(bits.iter().collect::<Vec<_>>(), vec![true; 3]);
+    assert_eq!(bits.get(3), None);
+
+    bits.set(1, false);
+    assert_eq!(bits.pop(), Some(true));
+    assert_eq!(bits.pop(), Some(false));
+    assert_eq!(bits.count_ones(), 1);
+    bits.clear();
+    assert!(bits.is_empty());
+    assert_eq!(bits.pop(), None);
+    assert_eq!(bits.rank(0), 0);
+    assert_eq!(bits.select(0), None);
+}
+
+#[test]
+fn test_bit_vec_rank_select() {
+    // A pattern that is irregular over several blocks
+    let pattern: Vec<bool> = (0..3000u64)
+        .map(|i| (i * i + 7 * i) % 11 < 3 || (1000..1100).contains(&i))
+        .collect();
+    let mut bits = BitVec::new();
+    for &bit in &pattern {
+        bits.push(bit);
+    }
+
+    let check = |bits: &BitVec, pattern: &[bool]| {
+        let mut ones = vec![];
+        for (i, &bit) in pattern.iter().enumerate() {
+            assert_eq!(bits.rank(i), ones.len());
+            if bit {
+                ones.push(i);
+            }
+        }
+        assert_eq!(bits.rank(pattern.len()), ones.len());
+        for (rank, &pos) in ones.iter().enumerate() {
+            assert_eq!(bits.select(rank), Some(pos));
+        }
+        assert_eq!(bits.select(ones.len()), None);
+    };
+    check(&bits, &pattern);
+
+    let mut pattern = pattern;
+    for &i in &[0, 63, 64, 511, 512, 1050, 2999] {
+        pattern[i] = !pattern[i];
+        bits.set(i, pattern[i]);
+    }
+    check(&bits, &pattern);
+
+    for _ in 0..1500 {
+        assert_eq!(bits.pop(), pattern.pop());
+    }
+    check(&bits, &pattern);
+}
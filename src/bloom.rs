@@ -0,0 +1,202 @@
+use std::collections::hash_map::DefaultHasher;
+use std::f64::consts::LN_2;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+// The largest value of a 4 bit counter. A counter that reaches it
+// stays there, since it's no longer known how many items it counts
+const SATURATED: u8 = 15;
+
+/// A Bloom filter that can also forget items, by keeping a small
+/// counter in each bucket instead of a single bit
+///
+/// Like a plain Bloom filter, `contains` never misses an item that was
+/// inserted, but may wrongly report items that weren't. Counters are 4
+/// bits each. Once a counter saturates it is never decremented again,
+/// so removing items can never cause a false negative, though the
+/// bucket then stays set for good.
+///
+/// Only remove items that were inserted; removing anything else may
+/// forget items that were.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut admitted = CountingBloomFilter::with_rate(1000, 0.01);
+/// admitted.insert("/index.html");
+/// admitted.insert("/style.css");
+/// assert!(admitted.contains("/index.html"));
+///
+/// assert!(admitted.remove("/index.html"));
+/// assert!(!admitted.contains("/index.html"));
+/// assert!(admitted.contains("/style.css"));
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CountingBloomFilter<T: ?Sized> {
+    // Two counters per byte, the even bucket in the low nibble
+    counters: Vec<u8>,
+    buckets: usize,
+    hashes: u32,
+    item: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized + Hash> CountingBloomFilter<T> {
+    /// Create an empty filter with `buckets` counters, which sets
+    /// `hashes` of them for each item
+    pub fn new(buckets: usize, hashes: u32) -> CountingBloomFilter<T> {
+        assert!(buckets > 0 && hashes > 0);
+        CountingBloomFilter {
+            counters: vec![0; (buckets + 1) / 2],
+            buckets,
+            hashes,
+            item: PhantomData,
+        }
+    }
+
+    /// Create an empty filter sized so that, holding `items` items,
+    /// about `rate` of the lookups of other items are false positives
+    pub fn with_rate(items: usize, rate: f64) -> CountingBloomFilter<T> {
+        assert!(items > 0 && rate > 0.0 && rate < 1.0);
+        let buckets = (-(items as f64) * rate.ln() / (LN_2 * LN_2)).ceil();
+        let hashes = (buckets / items as f64 * LN_2).round().max(1.0);
+        CountingBloomFilter::new(buckets as usize, hashes as u32)
+    }
+
+    /// Add `item` to the filter
+    pub fn insert(&mut self, item: &T) {
+        let (h1, h2) = hash_pair(item);
+        for i in 0..self.hashes {
+            let bucket = self.bucket(h1, h2, i);
+            let count = self.counter(bucket);
+            if count < SATURATED {
+                self.set_counter(bucket, count + 1);
+            }
+        }
+    }
+
+    /// Remove `item` from the filter. Returns false and leaves the
+    /// filter unchanged if `item` definitely isn't in it
+    pub fn remove(&mut self, item: &T) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+        let (h1, h2) = hash_pair(item);
+        for i in 0..self.hashes {
+            let bucket = self.bucket(h1, h2, i);
+            let count = self.counter(bucket);
+            if count < SATURATED {
+                self.set_counter(bucket, count - 1);
+            }
+        }
+        true
+    }
+
+    /// Returns true if `item` may be in the filter, or false if it
+    /// definitely isn't
+    pub fn contains(&self, item: &T) -> bool {
+        let (h1, h2) = hash_pair(item);
+        (0..self.hashes).all(|i| self.counter(self.bucket(h1, h2, i)) > 0)
+    }
+
+    /// Remove every item from the filter, including saturated ones
+    pub fn clear(&mut self) {
+        self.counters.iter_mut().for_each(|c| *c = 0);
+    }
+
+    /// Return the number of counters that have saturated
+    pub fn saturated(&self) -> usize {
+        (0..self.buckets)
+            .filter(|&b| self.counter(b) == SATURATED)
+            .count()
+    }
+
+    /// Return the number of counters
+    pub fn buckets(&self) -> usize {
+        self.buckets
+    }
+
+    /// Return the number of counters set for each item
+    pub fn hashes(&self) -> u32 {
+        self.hashes
+    }
+
+    fn bucket(&self, h1: u64, h2: u64, i: u32) -> usize {
+        (h1.wrapping_add(u64::from(i).wrapping_mul(h2)) % self.buckets as u64) as usize
+    }
+
+    fn counter(&self, bucket: usize) -> u8 {
+        (self.counters[bucket / 2] >> (4 * (bucket % 2))) & 0xf
+    }
+
+    fn set_counter(&mut self, bucket: usize, count: u8) {
+        let shift = 4 * (bucket % 2);
+        let byte = &mut self.counters[bucket / 2];
+        *byte = (*byte & !(0xf << shift)) | (count << shift);
+    }
+}
+
+/// Return two independent hashes of `item`, for deriving any number of
+/// bucket indices by double hashing. The second one is always odd
+pub(crate) fn hash_pair<T: ?Sized + Hash>(item: &T) -> (u64, u64) {
+    let mut hasher = DefaultHasher::new();
+    item.hash(&mut hasher);
+    let h1 = hasher.finish();
+    let mut hasher = DefaultHasher::new();
+    hasher.write_u64(h1);
+    (h1, hasher.finish() | 1)
+}
+
+#[test]
+fn test_counting_bloom_filter() {
+    let mut filter = CountingBloomFilter::new(64, 3);
+    assert_eq!((filter.buckets(), filter.hashes()), (64, 3));
+    assert!(!filter.contains(&1));
+    assert!(!filter.remove(&1));
+
+    for i in 0..5 {
+        filter.insert(&i);
+    }
+    filter.insert(&2);
+    assert!((0..5).all(|i| filter.contains(&i)));
+
+    // Inserted twice, so it takes two removals to forget
+    assert!(filter.remove(&2));
+    assert!(filter.contains(&2));
+    assert!(filter.remove(&2));
+    assert!(!filter.contains(&2));
+    assert!([0, 1, 3, 4].iter().all(|i| filter.contains(i)));
+
+    filter.clear();
+    assert!(!filter.contains(&0));
+}
+
+#[test]
+fn test_counting_bloom_filter_saturation() {
+    let mut filter = CountingBloomFilter::new(1, 1);
+    for _ in 0..20 {
+        filter.insert("a");
+    }
+    assert_eq!(filter.saturated(), 1);
+    for _ in 0..30 {
+        assert!(filter.remove("a"));
+    }
+    assert!(filter.contains("a"));
+}
+
+#[test]
+fn test_counting_bloom_filter_rate() {
+    let mut filter = CountingBloomFilter::with_rate(1000, 0.01);
+    for i in 0..1000u32 {
+        filter.insert(&i);
+    }
+    assert!((0..1000u32).all(|i| filter.contains(&i)));
+    let false_positives = (1000..11_000u32).filter(|i| filter.contains(i)).count();
+    assert!(false_positives < 200, "{}", false_positives);
+
+    for i in 0..500u32 {
+        assert!(filter.remove(&i));
+    }
+    assert!((500..1000u32).all(|i| filter.contains(&i)));
+}
@@ -0,0 +1,67 @@
+/// The interface shared by the bounded caches, so that code can switch
+/// eviction policies without changing its call sites
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// fn lookup<C: Cache<u32, u32>>(cache: &mut C, key: u32) -> u32 {
+///     if let Some(&value) = cache.get(&key) {
+///         return value;
+///     }
+///     let value = key * key;
+///     cache.put(key, value);
+///     value
+/// }
+///
+/// let mut lru = LruCache::new(16);
+/// let mut lfu = LfuCache::new(16);
+/// assert_eq!(lookup(&mut lru, 7), 49);
+/// assert_eq!(lookup(&mut lfu, 7), 49);
+/// assert!(lfu.contains_key(&7));
+/// ```
+pub trait Cache<K, V> {
+    /// Insert `value` under `key`, and return the value it replaced, if
+    /// any. If the cache is full, an entry is evicted first
+    fn put(&mut self, key: K, value: V) -> Option<V>;
+
+    /// Return the value under `key` and count it as used, or `None` if
+    /// it isn't in the cache
+    fn get(&mut self, key: &K) -> Option<&V>;
+
+    /// Return the value under `key` for modification and count it as
+    /// used, or `None` if it isn't in the cache
+    fn get_mut(&mut self, key: &K) -> Option<&mut V>;
+
+    /// Return the value under `key` without counting it as used, or
+    /// `None` if it isn't in the cache
+    fn peek(&self, key: &K) -> Option<&V>;
+
+    /// Remove the entry under `key` and return its value, or `None` if
+    /// it isn't in the cache
+    fn remove(&mut self, key: &K) -> Option<V>;
+
+    /// Pass each entry that is evicted to make room for a new one to
+    /// `callback`
+    fn on_evict<F: FnMut(K, V) + 'static>(&mut self, callback: F);
+
+    /// Remove every entry from the cache
+    fn clear(&mut self);
+
+    /// Return the number of entries in the cache
+    fn len(&self) -> usize;
+
+    /// Returns the max number of entries the cache holds
+    fn max_len(&self) -> usize;
+
+    /// Returns true if there is an entry under `key`
+    fn contains_key(&self, key: &K) -> bool {
+        self.peek(key).is_some()
+    }
+
+    /// Returns true if there are no entries in the cache
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
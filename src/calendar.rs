@@ -0,0 +1,301 @@
+use std::collections::VecDeque;
+use std::mem;
+
+// The fewest buckets the calendar shrinks to
+const MIN_BUCKETS: usize = 8;
+
+// How many of the earliest events are used to pick a bucket width
+const WIDTH_SAMPLES: usize = 25;
+
+// The average of some gaps between times, which may be huge
+fn mean(gaps: &[u64]) -> u64 {
+    let sum: u128 = gaps.iter().map(|&gap| u128::from(gap)).sum();
+    (sum / gaps.len().max(1) as u128) as u64
+}
+
+#[derive(Debug, Clone)]
+struct Event<T> {
+    time: u64,
+    // Keeps events at the same time in the order they were pushed
+    order: u64,
+    value: T,
+}
+
+/// A priority queue of events by time, for simulations with very many
+/// pending events
+///
+/// Like a desk calendar, time is cut into "days" of `bucket_width`
+/// ticks, and each bucket holds the events on its day of every "year",
+/// sorted by time. Popping walks the days in order, so when the width
+/// suits the spacing of the events, `push` and `pop` are O(1) on
+/// average. The number of buckets doubles or halves as the queue grows
+/// and shrinks, and the width is then picked again from the spacing of
+/// the earliest events. Events at the same time come out in the order
+/// they were pushed.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut queue = CalendarQueue::new();
+/// for packet in 0..1000u64 {
+///     queue.push(packet * 7 % 1000, packet);
+/// }
+/// assert_eq!(queue.pop(), Some((0, 0)));
+/// assert_eq!(queue.pop(), Some((1, 143)));
+///
+/// let mut due = 0;
+/// while queue.pop_due(500).is_some() {
+///     due += 1;
+/// }
+/// assert_eq!(due, 499);
+/// assert_eq!(queue.peek(), Some((501, &643)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CalendarQueue<T> {
+    buckets: Vec<VecDeque<Event<T>>>,
+    width: u64,
+    // The time of the last event popped, which the search starts from
+    last: u64,
+    next_order: u64,
+    len: usize,
+}
+
+impl<T> Default for CalendarQueue<T> {
+    fn default() -> CalendarQueue<T> {
+        CalendarQueue::new()
+    }
+}
+
+impl<T> CalendarQueue<T> {
+    /// Create an empty queue
+    pub fn new() -> CalendarQueue<T> {
+        CalendarQueue {
+            buckets: (0..MIN_BUCKETS).map(|_| VecDeque::new()).collect(),
+            width: 1,
+            last: 0,
+            next_order: 0,
+            len: 0,
+        }
+    }
+
+    /// Add `value` at `time`
+    pub fn push(&mut self, time: u64, value: T) {
+        let event = Event {
+            time,
+            order: self.next_order,
+            value,
+        };
+        self.next_order += 1;
+        // Events in the past are allowed, but the search has to start
+        // from them
+        self.last = self.last.min(time);
+        self.insert(event);
+        self.len += 1;
+        if self.len > 2 * self.buckets.len() {
+            let count = 2 * self.buckets.len();
+            self.resize(count);
+        }
+    }
+
+    /// Remove the earliest event and return it with its time, or `None`
+    /// if the queue is empty
+    pub fn pop(&mut self) -> Option<(u64, T)> {
+        let bucket = self.find()?;
+        let event = self.buckets[bucket].pop_front().unwrap();
+        self.last = event.time;
+        self.len -= 1;
+        if self.len < self.buckets.len() / 2 && self.buckets.len() > MIN_BUCKETS {
+            let count = self.buckets.len() / 2;
+            self.resize(count);
+        }
+        Some((event.time, event.value))
+    }
+
+    /// Remove and return the earliest event that is due at or before
+    /// `now`, along with its time
+    pub fn pop_due(&mut self, now: u64) -> Option<(u64, T)> {
+        match self.peek() {
+            Some((time, _)) if time <= now => self.pop(),
+            _ => None,
+        }
+    }
+
+    /// Return the earliest event and its time, or `None` if the queue is
+    /// empty
+    pub fn peek(&self) -> Option<(u64, &T)> {
+        let event = self.buckets[self.find()?].front().unwrap();
+        Some((event.time, &event.value))
+    }
+
+    /// Return the number of ticks each bucket covers
+    pub fn bucket_width(&self) -> u64 {
+        self.width
+    }
+
+    /// Return the number of buckets
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Remove every event from the queue
+    pub fn clear(&mut self) {
+        self.buckets = (0..MIN_BUCKETS).map(|_| VecDeque::new()).collect();
+        self.len = 0;
+    }
+
+    /// Return the number of events in the queue
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn index(&self, time: u64) -> usize {
+        ((time / self.width) % self.buckets.len() as u64) as usize
+    }
+
+    fn insert(&mut self, event: Event<T>) {
+        let index = self.index(event.time);
+        let bucket = &mut self.buckets[index];
+        let at = bucket.partition_point(|e| e.time <= event.time);
+        bucket.insert(at, event);
+    }
+
+    // Return the bucket holding the earliest event
+    fn find(&self) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        // Walk one year of days from the last event, looking for an
+        // event on the day being looked at
+        let mut index = self.index(self.last);
+        let mut end = (self.last / self.width)
+            .saturating_add(1)
+            .saturating_mul(self.width);
+        for _ in 0..self.buckets.len() {
+            if let Some(event) = self.buckets[index].front() {
+                if event.time < end {
+                    return Some(index);
+                }
+            }
+            index = (index + 1) % self.buckets.len();
+            end = end.saturating_add(self.width);
+        }
+        // The events are sparse, so search every bucket directly
+        (0..self.buckets.len())
+            .filter(|&i| !self.buckets[i].is_empty())
+            .min_by_key(|&i| self.buckets[i][0].time)
+    }
+
+    // Spread the events over `count` buckets, with a width picked from
+    // the spacing of the earliest events
+    fn resize(&mut self, count: usize) {
+        let mut events: Vec<Event<T>> = vec![];
+        for bucket in mem::take(&mut self.buckets) {
+            events.extend(bucket);
+        }
+        events.sort_by_key(|e| (e.time, e.order));
+
+        let gaps: Vec<u64> = events
+            .windows(2)
+            .take(WIDTH_SAMPLES)
+            .map(|pair| pair[1].time - pair[0].time)
+            .collect();
+        if !gaps.is_empty() {
+            // Ignore gaps far above average, which are probably between
+            // clusters of events
+            let average = mean(&gaps);
+            let near: Vec<u64> = gaps
+                .into_iter()
+                .filter(|&gap| gap <= average.saturating_mul(2))
+                .collect();
+            self.width = mean(&near).saturating_mul(3).max(1);
+        }
+
+        self.buckets = (0..count).map(|_| VecDeque::new()).collect();
+        for event in events {
+            // The events are sorted, so each bucket stays sorted
+            let index = self.index(event.time);
+            self.buckets[index].push_back(event);
+        }
+    }
+}
+
+#[test]
+fn test_calendar_queue() {
+    let mut queue = CalendarQueue::new();
+    assert!(queue.is_empty());
+    assert_eq!(queue.pop(), None);
+    assert_eq!(queue.peek(), None);
+
+    queue.push(40, 'b');
+    queue.push(10, 'a');
+    queue.push(40, 'c');
+    queue.push(u64::MAX, 'z');
+    assert_eq!(queue.len(), 4);
+    assert_eq!(queue.pop_due(5), None);
+    assert_eq!(queue.pop_due(10), Some((10, 'a')));
+    assert_eq!(queue.pop(), Some((40, 'b')));
+
+    // An event in the past still comes out first
+    queue.push(20, 'x');
+    assert_eq!(queue.peek(), Some((20, &'x')));
+    assert_eq!(queue.pop(), Some((20, 'x')));
+    assert_eq!(queue.pop(), Some((40, 'c')));
+    assert_eq!(queue.pop(), Some((u64::MAX, 'z')));
+    assert_eq!(queue.pop(), None);
+
+    queue.push(3, 'y');
+    queue.clear();
+    assert!(queue.is_empty());
+    assert_eq!(queue.pop(), None);
+
+    // Huge gaps between times don't overflow the width
+    for i in 0..20 {
+        let time = if i % 2 == 0 { i } else { u64::MAX - i };
+        queue.push(time, 'h');
+    }
+    assert_eq!(queue.pop(), Some((0, 'h')));
+    assert_eq!(queue.pop(), Some((2, 'h')));
+    assert_eq!(queue.len(), 18);
+}
+
+#[test]
+fn test_calendar_queue_resize() {
+    use rng::{JitterRng, XorShiftRng};
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    // A simulation where each event schedules later ones, checked
+    // against a BinaryHeap
+    let mut rng = XorShiftRng::new(29);
+    let mut queue = CalendarQueue::new();
+    let mut expected = BinaryHeap::new();
+    let mut id = 0u64;
+    for _ in 0..5000 {
+        let time = rng.next_u64() % 100_000;
+        queue.push(time, id);
+        expected.push(Reverse((time, id)));
+        id += 1;
+    }
+    assert!(queue.bucket_count() >= 2048);
+    assert!(queue.bucket_width() > 1);
+
+    while let Some((time, value)) = queue.pop() {
+        assert_eq!(expected.pop(), Some(Reverse((time, value))));
+        if id < 20_000 && rng.next_u64() % 4 != 0 {
+            let later = time + rng.next_u64() % 200_000;
+            queue.push(later, id);
+            expected.push(Reverse((later, id)));
+            id += 1;
+        }
+        assert_eq!(queue.len(), expected.len());
+    }
+    assert!(expected.is_empty());
+    assert_eq!(queue.bucket_count(), MIN_BUCKETS);
+}
@@ -1,6 +1,66 @@
+use std::fmt;
+use std::iter;
+use std::ops::{Add, Div, Rem, Sub};
+use std::slice;
+use std::time::{Duration, Instant};
+
+use error::Error;
+use rng::JitterRng;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::{self, Arbitrary, Unstructured};
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// The integer types a `Clock` can count with
+pub trait Counter:
+    Copy
+    + Ord
+    + fmt::Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    /// Returns 0
+    fn zero() -> Self;
+
+    /// Returns 1
+    fn one() -> Self;
+
+    /// Returns the value as an `f64`, possibly rounded
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_counter {
+    ($($t:ty),*) => {
+        $(
+            impl Counter for $t {
+                fn zero() -> $t {
+                    0
+                }
+
+                fn one() -> $t {
+                    1
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_counter!(u8, u16, u32, u64, u128, usize);
+
 /// An increasing counter that ticks up until a particular count is
 /// reached, which then resets itself
 ///
+/// The counter is a `u64` by default, but can be any `Counter` type,
+/// e.g. `u32` to match a target's cycle accounting or `u128` for very
+/// long-running simulations. Use `with_counter` to create those.
+///
 /// Example:
 ///
 /// ```rust
@@ -13,49 +73,151 @@
 ///         // Do something special...
 ///     }
 /// }
+///
+/// let mut narrow: Clock<u32> = Clock::with_counter(1000);
+/// assert_eq!(narrow.tick_by(2500u32), 2);
 /// ```
 #[derive(Debug, Eq, PartialEq)]
-pub struct Clock {
-    count: u64,
-    period: u64,
+pub struct Clock<C = u64> {
+    count: C,
+    period: C,
+    running: bool,
 }
 
 impl Clock {
     /// Create a new clock that cycles every `period` ticks
     pub fn new(period: u64) -> Clock {
+        Clock::with_counter(period)
+    }
+}
+
+impl<C: Counter> Clock<C> {
+    /// Create a new clock like `new`, counting with the type of
+    /// `period`
+    pub fn with_counter(period: C) -> Clock<C> {
         Clock {
-            count: 0,
-            period: period,
+            count: C::zero(),
+            period,
+            running: true,
         }
     }
 
     /// Increment the current count by 1. If this is the `period`-th
-    /// tick, the counter is reset and `true` is returned.
+    /// tick, the counter is reset and `true` is returned. Does nothing
+    /// and returns `false` while the clock is paused
     pub fn tick(&mut self) -> bool {
-        self.count += 1;
+        if !self.running {
+            return false;
+        }
+        self.count = self.count + C::one();
         assert!(self.count <= self.period);
         if self.count >= self.period {
-            self.count = 0;
+            self.count = C::zero();
             true
         } else {
             false
         }
     }
 
+    /// Advance the current count by `n` ticks at once, and return how
+    /// many times the clock wrapped (i.e. how many `tick` calls would
+    /// have returned `true`)
+    pub fn tick_by(&mut self, n: C) -> C {
+        if !self.running {
+            return C::zero();
+        }
+        // Compare against the remaining ticks rather than computing
+        // `count + n`, which could overflow
+        let remaining = self.remaining();
+        if n < remaining {
+            self.count = self.count + n;
+            return C::zero();
+        }
+        let past = n - remaining;
+        self.count = past % self.period;
+        C::one() + past / self.period
+    }
+
+    /// Undo one `tick`: decrement the current count by 1. Returns
+    /// `true` if this steps back over a wrap, i.e. if the `tick` being
+    /// undone returned `true`. Does nothing and returns `false` while
+    /// the clock is paused
+    pub fn untick(&mut self) -> bool {
+        self.tick_back_by(C::one()) > C::zero()
+    }
+
+    /// Undo `n` ticks at once, and return how many wraps were stepped
+    /// back over. `tick_back_by(n)` exactly reverses `tick_by(n)`
+    pub fn tick_back_by(&mut self, n: C) -> C {
+        if !self.running {
+            return C::zero();
+        }
+        if n <= self.count {
+            self.count = self.count - n;
+            return C::zero();
+        }
+        // Steps needed to go back past zero, at least 1
+        let behind = n - self.count - C::one();
+        self.count = self.period - C::one() - behind % self.period;
+        C::one() + behind / self.period
+    }
+
     /// Reset the current count
     pub fn reset(&mut self) {
-        self.count = 0;
+        self.count = C::zero();
+    }
+
+    /// Stop the clock; ticks are ignored until `resume` is called. The
+    /// current count is kept
+    pub fn pause(&mut self) {
+        self.running = false;
+    }
+
+    /// Start the clock again after `pause`
+    pub fn resume(&mut self) {
+        self.running = true;
+    }
+
+    /// Returns true unless the clock is paused
+    pub fn is_running(&self) -> bool {
+        self.running
     }
 
     /// Return the current count
-    pub fn count(&self) -> u64 {
+    pub fn count(&self) -> C {
         self.count
     }
 
     /// Return the period of the clock
-    pub fn period(&self) -> u64 {
+    pub fn period(&self) -> C {
         self.period
     }
+
+    /// Return the number of ticks until the clock next wraps, which is
+    /// between 1 and the period
+    pub fn remaining(&self) -> C {
+        self.period - self.count
+    }
+
+    /// Return how far through its period the clock is, from 0 up to
+    /// (but not including) 1
+    pub fn phase(&self) -> f64 {
+        self.count.to_f64() / self.period.to_f64()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Clock {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Clock> {
+        let period = u.int_in_range(1..=u64::MAX)?;
+        let count = u.int_in_range(0..=period - 1)?;
+        let running = bool::arbitrary(u)?;
+        Ok(Clock {
+            count,
+            period,
+            running,
+        })
+    }
 }
 
 #[test]
@@ -85,149 +247,2570 @@ fn test_clock() {
     assert!(c.tick());
 }
 
-/// A periodic timer with rising and falling edges
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
-pub struct Timer {
-    period: u64,
-    next_start: u64,
-    next_stop: u64,
+#[test]
+fn test_clock_counter_width() {
+    let mut c: Clock<u8> = Clock::with_counter(200);
+    assert_eq!(c.tick_by(250), 1);
+    assert_eq!(c.count(), 50);
+    assert_eq!(c.tick_by(255), 1);
+    assert_eq!(c.count(), 105);
+    assert_eq!(c.tick_back_by(255), 1);
+    assert_eq!(c.count(), 50);
+
+    let mut c = Clock::with_counter(u128::MAX);
+    c.tick_by(u128::MAX - 1);
+    assert_eq!(c.tick_by(u128::MAX), 1);
+    assert_eq!(c.count(), u128::MAX - 1);
+    assert_eq!(c.tick_by(1), 1);
+    assert_eq!(c.count(), 0);
 }
 
-/// Indicates which edge of the timer was just hit
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
-pub enum TimerEvent {
-    RisingEdge,
-    FallingEdge,
+#[test]
+fn test_clock_remaining() {
+    let mut c = Clock::new(4);
+    assert_eq!(c.remaining(), 4);
+    assert_eq!(c.phase(), 0.0);
+    c.tick_by(3);
+    assert_eq!(c.remaining(), 1);
+    assert_eq!(c.phase(), 0.75);
+
+    let mut c = RationalClock::new(5, 2);
+    assert_eq!(c.remaining(), 3);
+    c.tick_by(2);
+    assert_eq!(c.remaining(), 1);
+    assert_eq!(c.phase(), 0.8);
+    c.tick();
+    // The next period is only two ticks long
+    assert_eq!(c.remaining(), 2);
 }
 
-impl Timer {
-    /// Creaste a new timer that activates every `period` ticks,
-    /// starts at the given `offset` timer, and lasts for `duration`
-    /// ticks. The offset and duration must be less than the
-    /// period. The duration may be 0, and in that case the timer will
-    /// only emit `RisingEdge` events
-    pub fn new(period: u64, offset: u64, duration: u64) -> Timer {
-        assert!(offset < period);
-        assert!(duration < period);
+#[test]
+fn test_clock_untick() {
+    let mut c = Clock::new(3);
+    assert!(c.untick());
+    assert_eq!(c.count(), 2);
+    assert!(!c.untick());
+    assert_eq!(c.count(), 1);
 
-        Timer {
-            period,
-            next_start: offset,
-            next_stop: offset + duration,
+    for &n in &[0, 1, 2, 3, 7, 100] {
+        for start in 0..3 {
+            let mut c = Clock::new(3);
+            c.tick_by(start);
+            let wraps = c.tick_by(n);
+            assert_eq!(c.tick_back_by(n), wraps);
+            assert_eq!(c.count(), start);
         }
     }
 
-    /// Get the next tick that will emit a `RisingEdge` event
-    pub fn next_start_time(&self) -> u64 {
-        self.next_start
+    let mut c = Clock::new(u64::MAX);
+    assert_eq!(c.tick_back_by(u64::MAX), 1);
+    assert_eq!(c.count(), 0);
+
+    c.pause();
+    assert!(!c.untick());
+    assert_eq!(c.count(), 0);
+}
+
+#[test]
+fn test_clock_pause() {
+    let mut c = Clock::new(2);
+    assert!(c.is_running());
+    assert!(!c.tick());
+
+    c.pause();
+    assert!(!c.is_running());
+    assert!(!c.tick());
+    assert_eq!(c.tick_by(10), 0);
+    assert_eq!(c.count(), 1);
+
+    c.resume();
+    assert!(c.tick());
+}
+
+#[test]
+fn test_clock_tick_by() {
+    let mut c = Clock::new(4);
+    assert_eq!(c.tick_by(3), 0);
+    assert_eq!(c.count(), 3);
+    assert_eq!(c.tick_by(1), 1);
+    assert_eq!(c.count(), 0);
+    assert_eq!(c.tick_by(0), 0);
+    assert_eq!(c.tick_by(10), 2);
+    assert_eq!(c.count(), 2);
+
+    let mut c = Clock::new(u64::MAX);
+    c.tick_by(u64::MAX - 1);
+    assert_eq!(c.tick_by(u64::MAX), 1);
+    assert_eq!(c.count(), u64::MAX - 1);
+}
+
+/// A `Clock` that calls the functions registered with `on_wrap` each
+/// time it wraps, so behavior can be attached to a clock declaratively
+/// instead of checking every `tick` result
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+/// use std::cell::Cell;
+/// use std::rc::Rc;
+///
+/// let frames = Rc::new(Cell::new(0));
+/// let mut clock = CallbackClock::new(Clock::new(100));
+/// let f = frames.clone();
+/// clock.on_wrap(move |wraps| f.set(f.get() + wraps));
+///
+/// clock.tick_by(250);
+/// assert_eq!(frames.get(), 2);
+/// ```
+pub struct CallbackClock<'a> {
+    clock: Clock,
+    callbacks: Vec<Box<dyn FnMut(u64) + 'a>>,
+}
+
+impl<'a> CallbackClock<'a> {
+    /// Wrap `clock`, with no callbacks registered yet
+    pub fn new(clock: Clock) -> CallbackClock<'a> {
+        CallbackClock {
+            clock,
+            callbacks: Vec::new(),
+        }
     }
 
-    /// Get the next tick that will emit a `FallingEdge` event
-    pub fn next_stop_time(&self) -> u64 {
-        self.next_stop
+    /// Register `callback` to be called whenever the clock wraps,
+    /// with the number of wraps. Callbacks are called in the order
+    /// they were registered
+    pub fn on_wrap<F: FnMut(u64) + 'a>(&mut self, callback: F) {
+        self.callbacks.push(Box::new(callback));
     }
 
-    /// Get the next tick that will produce any `TimerEvent`
-    pub fn next_event_time(&self) -> u64 {
-        if self.next_start < self.next_stop {
-            self.next_start
-        } else {
-            self.next_stop
-        }
+    /// Increment the current count by 1, calling the callbacks with 1
+    /// if the clock wrapped. Returns whether the clock wrapped
+    pub fn tick(&mut self) -> bool {
+        self.tick_by(1) > 0
     }
 
-    /// Runs the timer until either the given absolute `time` is
-    /// reached, or until the next event occurs. You should generally
-    /// run this function in a loop, as multiple events may have
-    /// occured in the time elapsed.
-    pub fn update(&mut self, time: u64) -> Option<TimerEvent> {
-        if self.next_start <= self.next_stop && self.next_start <= time {
-            if self.next_stop == self.next_start {
-                self.next_stop += self.period;
+    /// Advance the current count by `n` ticks at once. If the clock
+    /// wrapped, the callbacks are called once each with the number of
+    /// wraps. Returns the number of wraps
+    pub fn tick_by(&mut self, n: u64) -> u64 {
+        let wraps = self.clock.tick_by(n);
+        if wraps > 0 {
+            for callback in self.callbacks.iter_mut() {
+                callback(wraps);
             }
-            self.next_start += self.period;
-            Some(TimerEvent::RisingEdge)
-        } else if self.next_stop <= time {
-            self.next_stop += self.period;
-            Some(TimerEvent::FallingEdge)
-        } else {
-            None
         }
+        wraps
     }
 
-    /// Indicates if the timer is currently between a `RisingEdge` and
-    /// `FallingEdge` event
-    pub fn is_active(&self) -> bool {
-        self.next_start > self.next_stop
+    /// Return the wrapped clock
+    pub fn clock(&self) -> &Clock {
+        &self.clock
     }
-}
 
-/// Given a list of `timers`, return the next tick that any of the
-/// timers will emit a `TimerEvent`
-pub fn next_timer_event(timers: &[Timer]) -> u64 {
-    timers
-        .iter()
-        .map(|t| t.next_event_time())
-        .min()
-        .unwrap_or(0)
+    /// Return the wrapped clock for modification
+    pub fn clock_mut(&mut self) -> &mut Clock {
+        &mut self.clock
+    }
+
+    /// Unwrap the clock, dropping the callbacks
+    pub fn into_clock(self) -> Clock {
+        self.clock
+    }
 }
 
 #[test]
-fn test_timer() {
-    let mut timer = Timer::new(100, 13, 20);
+fn test_callback_clock() {
+    let mut log = vec![];
+    {
+        let mut total = 0;
+        let mut clock = CallbackClock::new(Clock::new(3));
+        clock.on_wrap(|w| log.push(w));
+        clock.on_wrap(|w| total += w);
 
-    assert_eq!(timer.next_start_time(), 13);
-    assert_eq!(timer.next_stop_time(), 13 + 20);
+        assert!(!clock.tick());
+        assert!(!clock.tick());
+        assert!(clock.tick());
+        assert_eq!(clock.tick_by(7), 2);
+        clock.clock_mut().pause();
+        assert_eq!(clock.tick_by(7), 0);
+        assert_eq!(clock.clock().count(), 1);
+        drop(clock);
+        assert_eq!(total, 3);
+    }
+    assert_eq!(log, vec![1, 2]);
+}
 
-    // Come up to just before the next start time
-    assert_eq!(timer.update(12), None);
-    assert_eq!(timer.next_start_time(), 13);
-    assert_eq!(timer.next_stop_time(), 13 + 20);
+/// Identifies a clock within a `ClockDomain`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct ClockId(usize);
 
-    assert_eq!(timer.update(13), Some(TimerEvent::RisingEdge));
-    assert_eq!(timer.next_start_time(), 13 + 100);
-    assert_eq!(timer.next_stop_time(), 13 + 20);
+/// A set of clocks that are all advanced together. Each clock is
+/// driven either by the domain's ticks directly, or by the wraps of
+/// another clock in the domain (as a divider)
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut domain = ClockDomain::new();
+/// let cpu = domain.add(Clock::new(4));
+/// let video = domain.add(Clock::new(6));
+/// let frame = domain.add_divider(video, Clock::new(2));
+///
+/// let wrapped: Vec<_> = domain.tick_by(12).collect();
+/// assert_eq!(wrapped, vec![(cpu, 3), (video, 2), (frame, 1)]);
+/// ```
+#[derive(Debug, Eq, PartialEq, Default)]
+pub struct ClockDomain {
+    clocks: Vec<Clock>,
+    // The clock whose wraps tick each clock, if any
+    sources: Vec<Option<ClockId>>,
+    // How many times each clock wrapped during the last step
+    wraps: Vec<u64>,
+}
 
-    // Overshooting should still get the falling edge event
-    assert_eq!(timer.update(13 + 20 + 5), Some(TimerEvent::FallingEdge));
-    assert_eq!(timer.next_start_time(), 13 + 100);
-    assert_eq!(timer.next_stop_time(), 13 + 20 + 100);
+impl ClockDomain {
+    /// Create an empty domain
+    pub fn new() -> ClockDomain {
+        ClockDomain::default()
+    }
 
-    let mut v = vec![];
-    while let Some(e) = timer.update(300) {
-        v.push(e);
+    /// Add `clock`, driven by the domain's ticks
+    pub fn add(&mut self, clock: Clock) -> ClockId {
+        self.insert(clock, None)
     }
-    // Ensure the events are interleaved properly when there are
-    // several pending
+
+    /// Add `clock`, driven by the wraps of `source`
+    pub fn add_divider(&mut self, source: ClockId, clock: Clock) -> ClockId {
+        assert!(source.0 < self.clocks.len());
+        self.insert(clock, Some(source))
+    }
+
+    fn insert(&mut self, clock: Clock, source: Option<ClockId>) -> ClockId {
+        self.clocks.push(clock);
+        self.sources.push(source);
+        self.wraps.push(0);
+        ClockId(self.clocks.len() - 1)
+    }
+
+    /// Advance every clock by one tick; see `tick_by`
+    pub fn tick(&mut self) -> Wrapped<'_> {
+        self.tick_by(1)
+    }
+
+    /// Advance every clock by `n` ticks, and return the clocks that
+    /// wrapped along with how many times they did
+    pub fn tick_by(&mut self, n: u64) -> Wrapped<'_> {
+        // Sources are always added before the clocks they drive, so
+        // their wraps for this step are already known
+        for i in 0..self.clocks.len() {
+            let ticks = match self.sources[i] {
+                Some(source) => self.wraps[source.0],
+                None => n,
+            };
+            self.wraps[i] = self.clocks[i].tick_by(ticks);
+        }
+        Wrapped {
+            wraps: self.wraps.iter().enumerate(),
+        }
+    }
+
+    /// Return the clock identified by `id`
+    pub fn get(&self, id: ClockId) -> &Clock {
+        &self.clocks[id.0]
+    }
+
+    /// Return the clock identified by `id` for modification
+    pub fn get_mut(&mut self, id: ClockId) -> &mut Clock {
+        &mut self.clocks[id.0]
+    }
+
+    /// Return the number of clocks in the domain
+    pub fn len(&self) -> usize {
+        self.clocks.len()
+    }
+
+    /// Returns true if the domain has no clocks
+    pub fn is_empty(&self) -> bool {
+        self.clocks.is_empty()
+    }
+}
+
+/// The clocks that wrapped during a step of a `ClockDomain`, created by
+/// `ClockDomain::tick_by`
+pub struct Wrapped<'a> {
+    wraps: iter::Enumerate<slice::Iter<'a, u64>>,
+}
+
+impl<'a> Iterator for Wrapped<'a> {
+    type Item = (ClockId, u64);
+
+    fn next(&mut self) -> Option<(ClockId, u64)> {
+        self.wraps
+            .by_ref()
+            .find(|&(_, &w)| w > 0)
+            .map(|(i, &w)| (ClockId(i), w))
+    }
+}
+
+#[test]
+fn test_clock_domain() {
+    let mut domain = ClockDomain::new();
+    assert!(domain.is_empty());
+    let a = domain.add(Clock::new(2));
+    let b = domain.add(Clock::new(3));
+    let a2 = domain.add_divider(a, Clock::new(2));
+    let a4 = domain.add_divider(a2, Clock::new(2));
+    assert_eq!(domain.len(), 4);
+
+    assert_eq!(domain.tick().count(), 0);
+    assert_eq!(domain.tick().collect::<Vec<_>>(), vec![(a, 1)]);
+    assert_eq!(domain.tick().collect::<Vec<_>>(), vec![(b, 1)]);
+    assert_eq!(domain.tick().collect::<Vec<_>>(), vec![(a, 1), (a2, 1)]);
+
+    domain.get_mut(b).pause();
     assert_eq!(
-        v,
-        vec![
-            TimerEvent::RisingEdge,
-            TimerEvent::FallingEdge,
-            TimerEvent::RisingEdge,
-            TimerEvent::FallingEdge,
-        ]
+        domain.tick_by(20).collect::<Vec<_>>(),
+        vec![(a, 10), (a2, 5), (a4, 3)]
     );
+    assert_eq!(domain.get(a4).count(), 0);
+    assert_eq!(domain.get(b).count(), 1);
 }
 
-#[test]
-fn test_timer_zero_duration() {
-    let mut timer = Timer::new(100, 13, 0);
+/// A one-shot counter that counts down to zero and reports its expiry
+/// once, like a watchdog or a one-shot hardware timer. Unlike `Clock`
+/// it doesn't start over by itself; call `reload` to arm it again
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut watchdog = Countdown::new(3);
+/// assert!(!watchdog.tick());
+/// assert!(!watchdog.tick());
+/// assert!(watchdog.tick());
+/// // Stays expired until reloaded
+/// assert!(!watchdog.tick());
+/// assert!(watchdog.is_expired());
+///
+/// watchdog.reload(2);
+/// assert_eq!(watchdog.remaining(), 2);
+/// ```
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Countdown {
+    remaining: u64,
+}
 
-    assert_eq!(timer.next_start_time(), 13);
-    assert_eq!(timer.update(12), None);
-    assert_eq!(timer.next_start_time(), 13);
+impl Countdown {
+    /// Create a new countdown that expires after `ticks` ticks. A
+    /// countdown created with 0 ticks starts out expired
+    pub fn new(ticks: u64) -> Countdown {
+        Countdown { remaining: ticks }
+    }
 
-    assert_eq!(timer.update(13), Some(TimerEvent::RisingEdge));
-    assert_eq!(timer.next_start_time(), 13 + 100);
+    /// Count down by 1. Returns `true` only on the tick that reaches
+    /// zero
+    pub fn tick(&mut self) -> bool {
+        self.tick_by(1)
+    }
 
-    assert_eq!(timer.update(13 + 100), Some(TimerEvent::RisingEdge));
+    /// Count down by `n` ticks at once. Returns `true` if zero was
+    /// reached during these ticks, and not before
+    pub fn tick_by(&mut self, n: u64) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining = self.remaining.saturating_sub(n);
+        self.remaining == 0
+    }
+
+    /// Start counting down from `ticks` again, whether or not the
+    /// countdown has expired
+    pub fn reload(&mut self, ticks: u64) {
+        self.remaining = ticks;
+    }
+
+    /// Return the number of ticks until expiry
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Returns true once the countdown has reached zero
+    pub fn is_expired(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Countdown {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Countdown> {
+        Ok(Countdown::new(u64::arbitrary(u)?))
+    }
 }
 
 #[test]
-fn test_next_timer_event() {
-    let t1 = Timer::new(100, 13, 0);
-    let t2 = Timer::new(100, 14, 0);
+fn test_countdown() {
+    let mut c = Countdown::new(10);
+    assert!(!c.tick_by(9));
+    assert_eq!(c.remaining(), 1);
+    assert!(c.tick_by(5));
+    assert!(c.is_expired());
+    assert!(!c.tick_by(5));
+
+    c.reload(1);
+    assert!(!c.is_expired());
+    assert!(c.tick());
+
+    let mut expired = Countdown::new(0);
+    assert!(expired.is_expired());
+    assert!(!expired.tick());
+}
 
-    assert_eq!(next_timer_event(&[t1, t2]), 13);
+/// A clock whose output is a square wave: high for the first
+/// `high_ticks` ticks of each period and low for the rest
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// // 25% duty cycle
+/// let mut c = SquareWaveClock::new(4, 1);
+/// assert!(c.is_high());
+/// assert_eq!(c.tick(), Some(TimerEvent::FallingEdge));
+/// assert_eq!(c.tick(), None);
+/// assert_eq!(c.tick(), None);
+/// assert_eq!(c.tick(), Some(TimerEvent::RisingEdge));
+/// ```
+#[derive(Debug, Eq, PartialEq)]
+pub struct SquareWaveClock {
+    clock: Clock,
+    high_ticks: u64,
+}
+
+impl SquareWaveClock {
+    /// Create a new clock that cycles every `period` ticks, starting
+    /// at the beginning of the high part. `high_ticks` may be anywhere
+    /// from 0 (always low) to `period` (always high)
+    pub fn new(period: u64, high_ticks: u64) -> SquareWaveClock {
+        assert!(high_ticks <= period);
+
+        SquareWaveClock {
+            clock: Clock::new(period),
+            high_ticks,
+        }
+    }
+
+    /// Increment the current count by 1, and return the edge if the
+    /// output changed level
+    pub fn tick(&mut self) -> Option<TimerEvent> {
+        let was_high = self.is_high();
+        self.clock.tick();
+        match (was_high, self.is_high()) {
+            (false, true) => Some(TimerEvent::RisingEdge),
+            (true, false) => Some(TimerEvent::FallingEdge),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the output is currently high
+    pub fn is_high(&self) -> bool {
+        self.clock.count() < self.high_ticks
+    }
+
+    /// Reset to the beginning of the high part
+    pub fn reset(&mut self) {
+        self.clock.reset();
+    }
+
+    /// Return the current count
+    pub fn count(&self) -> u64 {
+        self.clock.count()
+    }
+
+    /// Return the period of the clock
+    pub fn period(&self) -> u64 {
+        self.clock.period()
+    }
+
+    /// Return how many ticks of each period the output is high
+    pub fn high_ticks(&self) -> u64 {
+        self.high_ticks
+    }
+}
+
+#[test]
+fn test_square_wave_clock() {
+    let mut c = SquareWaveClock::new(5, 3);
+    let mut levels = vec![];
+    let mut edges = vec![];
+    for _ in 0..10 {
+        levels.push(c.is_high());
+        edges.push(c.tick());
+    }
+    assert_eq!(
+        levels,
+        vec![true, true, true, false, false, true, true, true, false, false]
+    );
+    assert_eq!(edges[2], Some(TimerEvent::FallingEdge));
+    assert_eq!(edges[4], Some(TimerEvent::RisingEdge));
+    assert_eq!(edges.iter().filter(|e| e.is_some()).count(), 4);
+
+    let mut always_high = SquareWaveClock::new(3, 3);
+    let mut always_low = SquareWaveClock::new(3, 0);
+    for _ in 0..6 {
+        assert_eq!(always_high.tick(), None);
+        assert_eq!(always_low.tick(), None);
+    }
+    assert!(always_high.is_high());
+    assert!(!always_low.is_high());
+}
+
+/// A clock with several square wave outputs that are evenly spaced
+/// through its period, all driven by one counter, e.g. four outputs
+/// for quadrature 0/90/180/270 degree phases. Each output is high for
+/// the first half of its own cycle. There can be up to 64 outputs
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut quadrature = MultiPhaseClock::new(8, 4);
+/// assert!(quadrature.is_high(0) && quadrature.is_high(3));
+///
+/// assert!(quadrature.tick().is_empty());
+/// let edges = quadrature.tick();
+/// assert!(edges.is_rising(1));
+/// assert!(edges.is_falling(3));
+/// ```
+#[derive(Debug, Eq, PartialEq)]
+pub struct MultiPhaseClock {
+    clock: Clock,
+    phases: u32,
+}
+
+/// The edges of each output of a `MultiPhaseClock` during one tick
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+pub struct PhaseEdges {
+    rising: u64,
+    falling: u64,
+}
+
+impl PhaseEdges {
+    /// Returns true if output `phase` went from low to high
+    pub fn is_rising(&self, phase: u32) -> bool {
+        self.rising & (1 << phase) != 0
+    }
+
+    /// Returns true if output `phase` went from high to low
+    pub fn is_falling(&self, phase: u32) -> bool {
+        self.falling & (1 << phase) != 0
+    }
+
+    /// Returns true if no output changed
+    pub fn is_empty(&self) -> bool {
+        self.rising == 0 && self.falling == 0
+    }
+}
+
+impl MultiPhaseClock {
+    /// Create a new clock that cycles every `period` ticks, with
+    /// `phases` outputs. Output `k` starts its cycle `k * period /
+    /// phases` ticks into the period. The period must be at least 2
+    /// ticks per output so that every output has a high and a low part
+    pub fn new(period: u64, phases: u32) -> MultiPhaseClock {
+        assert!(phases > 0 && phases <= 64);
+        assert!(period >= 2 * u64::from(phases));
+
+        MultiPhaseClock {
+            clock: Clock::new(period),
+            phases,
+        }
+    }
+
+    /// Increment the current count by 1, and return which outputs
+    /// changed level
+    pub fn tick(&mut self) -> PhaseEdges {
+        let before = self.levels();
+        self.clock.tick();
+        let after = self.levels();
+        PhaseEdges {
+            rising: after & !before,
+            falling: before & !after,
+        }
+    }
+
+    /// Returns true if output `phase` is currently high
+    pub fn is_high(&self, phase: u32) -> bool {
+        assert!(phase < self.phases);
+        self.levels() & (1 << phase) != 0
+    }
+
+    // Returns the level of every output as a bit mask
+    fn levels(&self) -> u64 {
+        let period = self.clock.period();
+        let count = self.clock.count();
+        (0..self.phases)
+            .filter(|&k| {
+                let offset = u64::from(k) * period / u64::from(self.phases);
+                (count + period - offset) % period < period / 2
+            })
+            .fold(0, |levels, k| levels | 1 << k)
+    }
+
+    /// Reset to the start of the period
+    pub fn reset(&mut self) {
+        self.clock.reset();
+    }
+
+    /// Return the current count
+    pub fn count(&self) -> u64 {
+        self.clock.count()
+    }
+
+    /// Return the period of the clock
+    pub fn period(&self) -> u64 {
+        self.clock.period()
+    }
+
+    /// Return the number of outputs
+    pub fn phases(&self) -> u32 {
+        self.phases
+    }
+}
+
+#[test]
+fn test_multi_phase_clock() {
+    let mut c = MultiPhaseClock::new(6, 3);
+    assert_eq!(c.phases(), 3);
+
+    let mut rising = vec![];
+    let mut falling = vec![];
+    for _ in 0..12 {
+        let edges = c.tick();
+        rising.push((0..3).filter(|&k| edges.is_rising(k)).collect::<Vec<_>>());
+        falling.push((0..3).filter(|&k| edges.is_falling(k)).collect::<Vec<_>>());
+    }
+    // Each output rises two ticks after the one before it, and falls
+    // three ticks after it rose
+    let expected_rising: Vec<Vec<u32>> = vec![vec![], vec![1], vec![], vec![2], vec![], vec![0]];
+    assert_eq!(&rising[..6], &expected_rising[..]);
+    assert_eq!(&rising[6..], &expected_rising[..]);
+    assert_eq!(falling[2], vec![0]);
+    assert_eq!(falling[4], vec![1]);
+    assert_eq!(falling[0], vec![2]);
+    assert!(c.tick().is_falling(2));
+    assert!(c.tick().is_rising(1));
+}
+
+/// A cascade of clocks where each clock's wrap is the tick of the next
+/// one, like a hardware divider tree
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// // Divide by 4, then by 3
+/// let mut chain = ClockChain::new(vec![Clock::new(4), Clock::new(3)]);
+/// for _ in 0..3 {
+///     assert_eq!(chain.tick(), 0);
+/// }
+/// // The first stage wraps, which ticks the second one
+/// assert_eq!(chain.tick(), 1);
+/// assert_eq!(chain.tick_by(8), 1);
+/// assert_eq!(chain.stage(1).count(), 0);
+/// ```
+#[derive(Debug, Eq, PartialEq)]
+pub struct ClockChain {
+    stages: Vec<Clock>,
+}
+
+impl ClockChain {
+    /// Create a chain from `stages`, where the first stage is ticked
+    /// directly
+    pub fn new(stages: Vec<Clock>) -> ClockChain {
+        ClockChain { stages }
+    }
+
+    /// Add `stage` to the end of the chain, driven by the wraps of the
+    /// current last stage
+    pub fn push(&mut self, stage: Clock) {
+        self.stages.push(stage);
+    }
+
+    /// Tick the first stage, and each following stage whose driver
+    /// wrapped. Returns how many stages wrapped; since a stage can only
+    /// wrap when all of the ones before it did, these are always the
+    /// first stages of the chain
+    pub fn tick(&mut self) -> usize {
+        let mut wrapped = 0;
+        for c in self.stages.iter_mut() {
+            if !c.tick() {
+                break;
+            }
+            wrapped += 1;
+        }
+        wrapped
+    }
+
+    /// Advance the first stage by `n` ticks and carry the wraps through
+    /// the chain. Returns how many times the last stage wrapped
+    pub fn tick_by(&mut self, n: u64) -> u64 {
+        self.stages.iter_mut().fold(n, |ticks, c| c.tick_by(ticks))
+    }
+
+    /// Reset every stage of the chain
+    pub fn reset(&mut self) {
+        for c in self.stages.iter_mut() {
+            c.reset();
+        }
+    }
+
+    /// Return the stage at `index`
+    pub fn stage(&self, index: usize) -> &Clock {
+        &self.stages[index]
+    }
+
+    /// Return the number of stages
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Return true if the chain has no stages
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+#[test]
+fn test_clock_chain() {
+    let mut chain = ClockChain::new(vec![Clock::new(2)]);
+    chain.push(Clock::new(2));
+    chain.push(Clock::new(3));
+    assert_eq!(chain.len(), 3);
+
+    let wrapped: Vec<usize> = (0..12).map(|_| chain.tick()).collect();
+    assert_eq!(wrapped, vec![0, 1, 0, 2, 0, 1, 0, 2, 0, 1, 0, 3]);
+
+    // 2 * 2 * 3 ticks per wrap of the last stage
+    assert_eq!(chain.tick_by(25), 2);
+    assert_eq!(chain.stage(0).count(), 1);
+    assert_eq!(chain.stage(1).count(), 0);
+    assert_eq!(chain.stage(2).count(), 0);
+
+    chain.reset();
+    assert_eq!(chain.stage(0).count(), 0);
+    assert_eq!(ClockChain::new(vec![]).tick(), 0);
+}
+
+/// A clock like `Clock` whose period is the fraction `numerator /
+/// denominator` ticks, e.g. `4_194_304 / 59.73` for a frame of a
+/// 4.19 MHz system at 59.73 Hz
+///
+/// The fractional part is carried from one period into the next, so
+/// the clock wraps after a whole number of ticks each time but never
+/// drifts from the exact rate over the long run.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// // 2.5 ticks per period
+/// let mut c = RationalClock::new(5, 2);
+/// assert_eq!(c.tick_by(3), 1);
+/// assert_eq!(c.tick_by(2), 1);
+/// assert_eq!(c.count(), 0);
+/// ```
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct RationalClock {
+    // Ticks since the last wrap, scaled by the denominator
+    count: u128,
+    numerator: u64,
+    denominator: u64,
+}
+
+impl RationalClock {
+    /// Create a new clock that cycles every `numerator / denominator`
+    /// ticks. The period must be at least one tick
+    pub fn new(numerator: u64, denominator: u64) -> RationalClock {
+        assert!(denominator > 0);
+        assert!(numerator >= denominator);
+
+        RationalClock {
+            count: 0,
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Increment the current count by 1. Returns `true` if a period
+    /// ended on this tick
+    pub fn tick(&mut self) -> bool {
+        self.tick_by(1) > 0
+    }
+
+    /// Advance the current count by `n` ticks at once, and return how
+    /// many periods ended
+    pub fn tick_by(&mut self, n: u64) -> u64 {
+        let total = self.count + u128::from(n) * u128::from(self.denominator);
+        let numerator = u128::from(self.numerator);
+        self.count = total % numerator;
+        (total / numerator) as u64
+    }
+
+    /// Reset the current count, discarding any carried fraction
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+
+    /// Return the number of whole ticks since the last wrap
+    pub fn count(&self) -> u64 {
+        (self.count / u128::from(self.denominator)) as u64
+    }
+
+    /// Return the period of the clock as `(numerator, denominator)`
+    pub fn period(&self) -> (u64, u64) {
+        (self.numerator, self.denominator)
+    }
+
+    /// Return the number of ticks until the clock next wraps
+    pub fn remaining(&self) -> u64 {
+        let left = u128::from(self.numerator) - self.count;
+        let denominator = u128::from(self.denominator);
+        ((left + denominator - 1) / denominator) as u64
+    }
+
+    /// Return how far through its period the clock is, from 0 up to
+    /// (but not including) 1
+    pub fn phase(&self) -> f64 {
+        self.count as f64 / self.numerator as f64
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for RationalClock {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<RationalClock> {
+        let denominator = u.int_in_range(1..=u64::MAX)?;
+        let numerator = u.int_in_range(denominator..=u64::MAX)?;
+        let mut clock = RationalClock::new(numerator, denominator);
+        clock.count = u.int_in_range(0..=u128::from(numerator) - 1)?;
+        Ok(clock)
+    }
+}
+
+#[test]
+fn test_rational_clock() {
+    // 4_194_304 / 59.73 ticks per frame
+    let mut c = RationalClock::new(419_430_400, 5973);
+    assert_eq!(c.period(), (419_430_400, 5973));
+
+    let mut frames = 0;
+    let mut ticks = 0;
+    while frames < 5973 {
+        if c.tick_by(1000) > 0 {
+            frames += 1;
+        }
+        ticks += 1000;
+    }
+    // Exactly 4_194_304 * 100 ticks for 5973 frames, give or take the
+    // last step
+    assert!((419_430_400..419_430_400 + 1000).contains(&ticks));
+
+    let mut c = RationalClock::new(3, 2);
+    let wraps: Vec<bool> = (0..6).map(|_| c.tick()).collect();
+    assert_eq!(wraps, vec![false, true, true, false, true, true]);
+    c.tick();
+    assert_eq!(c.count(), 1);
+    c.reset();
+    assert_eq!(c.count(), 0);
+    assert!(!c.tick());
+}
+
+/// A periodic timer with rising and falling edges
+///
+/// A timer can carry a `tag` of any type, e.g. the IRQ line it drives,
+/// which is returned along with its events by `update_tagged`.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Timer<T = ()> {
+    tag: T,
+    period: u64,
+    offset: u64,
+    duration: u64,
+    next_start: u64,
+    next_stop: u64,
+    // The time of the last `RisingEdge`, if there was one since the
+    // timer was created or restarted
+    last_start: Option<u64>,
+    armed: bool,
+    one_shot: bool,
+    missed: MissedEventPolicy,
+}
+
+/// Indicates which edge of the timer was just hit
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum TimerEvent {
+    RisingEdge,
+    FallingEdge,
+}
+
+/// How a `Timer` handles the edges it has already scheduled when its
+/// settings change
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ReconfigurePolicy {
+    /// Reschedule the current cycle as if it had started with the new
+    /// settings. Edges that this moves into the past are emitted by
+    /// the next `update`
+    Immediate,
+    /// Let the current cycle finish as scheduled, and apply the new
+    /// settings from the next `RisingEdge` on
+    NextCycle,
+}
+
+/// What a `Timer` does with the edges it missed when `update` is
+/// called more than a period after them
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum MissedEventPolicy {
+    /// Emit every missed edge, one per `update`
+    CatchUp,
+    /// Emit the first missed edge, then move the schedule forward by
+    /// whole periods so the next edge is less than a period late
+    Coalesce,
+    /// Move the schedule forward by whole periods so the next edge is
+    /// less than a period late, dropping the edges before it
+    Skip,
+}
+
+impl Timer {
+    /// Start building a timer whose settings are checked when it is
+    /// built, rather than asserted. The period must be set; the offset
+    /// and duration default to 0
+    ///
+    /// Example:
+    ///
+    /// ```rust
+    /// use j2ds::*;
+    ///
+    /// let timer = Timer::builder()
+    ///     .period(100)
+    ///     .offset(10)
+    ///     .duration(20)
+    ///     .tag("irq 3")
+    ///     .one_shot(true)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(timer.tag(), &"irq 3");
+    ///
+    /// let bad = Timer::builder().period(100).duration(101).build();
+    /// assert!(bad.is_err());
+    /// ```
+    pub fn builder() -> TimerBuilder {
+        TimerBuilder {
+            period: None,
+            offset: 0,
+            duration: 0,
+            tag: (),
+            one_shot: false,
+            missed: MissedEventPolicy::CatchUp,
+            jitter: 0,
+        }
+    }
+
+    /// Creaste a new timer that activates every `period` ticks,
+    /// starts at the given `offset` timer, and lasts for `duration`
+    /// ticks. An offset past the period is wrapped around to within it.
+    /// The duration may be 0, and in that case the timer will only
+    /// emit `RisingEdge` events. It may also be the whole period, and
+    /// then each `FallingEdge` is on the same tick as the next
+    /// `RisingEdge`, and comes just before it
+    pub fn new(period: u64, offset: u64, duration: u64) -> Timer {
+        Timer::with_tag(period, offset, duration, ())
+    }
+}
+
+impl<T> Timer<T> {
+    /// Create a new timer like `new` that carries `tag`
+    pub fn with_tag(period: u64, offset: u64, duration: u64, tag: T) -> Timer<T> {
+        assert!(period > 0);
+        assert!(duration <= period);
+        let offset = offset % period;
+
+        Timer {
+            tag,
+            period,
+            offset,
+            duration,
+            next_start: offset,
+            next_stop: offset + duration,
+            last_start: None,
+            armed: true,
+            one_shot: false,
+            missed: MissedEventPolicy::CatchUp,
+        }
+    }
+
+    /// Get the next tick that will emit a `RisingEdge` event
+    pub fn next_start_time(&self) -> u64 {
+        self.next_start
+    }
+
+    /// Get the next tick that will emit a `FallingEdge` event
+    pub fn next_stop_time(&self) -> u64 {
+        self.next_stop
+    }
+
+    /// Get the next tick that will produce any `TimerEvent`, or
+    /// `u64::MAX` if the timer has been cancelled
+    pub fn next_event_time(&self) -> u64 {
+        if !self.armed {
+            u64::MAX
+        } else if self.next_start < self.next_stop {
+            self.next_start
+        } else {
+            self.next_stop
+        }
+    }
+
+    /// Return the number of ticks from the absolute `time` until the
+    /// next edge, which is 0 if it is already due. Returns `None` if
+    /// the timer has been cancelled
+    pub fn time_until_next_event(&self, time: u64) -> Option<u64> {
+        if self.armed {
+            Some(self.next_event_time().saturating_sub(time))
+        } else {
+            None
+        }
+    }
+
+    /// Return how far through its period the timer is at the absolute
+    /// `time`, from 0 at a rising edge up to (but not including) 1
+    pub fn progress(&self, time: u64) -> f64 {
+        self.since_start(time) as f64 / self.period as f64
+    }
+
+    // Move the next rising edge by `delta` ticks, keeping an active
+    // timer's falling edge before it
+    fn shift_start(&mut self, delta: i64) {
+        let active = !self.rising_next();
+        let mut start = (i128::from(self.next_start) + i128::from(delta)).max(0) as u64;
+        if active {
+            start = start.max(self.next_stop + 1);
+        } else {
+            self.next_stop = start + self.duration;
+        }
+        self.next_start = start;
+    }
+
+    // Ticks from the last rising edge on the schedule to `time`
+    fn since_start(&self, time: u64) -> u64 {
+        let period = i128::from(self.period);
+        (i128::from(time) - i128::from(self.next_start)).rem_euclid(period) as u64
+    }
+
+    /// Runs the timer until either the given absolute `time` is
+    /// reached, or until the next event occurs. You should generally
+    /// run this function in a loop, as multiple events may have
+    /// occured in the time elapsed.
+    pub fn update(&mut self, time: u64) -> Option<TimerEvent> {
+        self.update_with_tick(time).map(|(_, event)| event)
+    }
+
+    /// Like `update`, but also return the tick the edge occured on,
+    /// which is earlier than `time` when the edge was overshot
+    pub fn update_with_tick(&mut self, time: u64) -> Option<(u64, TimerEvent)> {
+        if !self.armed {
+            return None;
+        }
+        if self.missed == MissedEventPolicy::Skip {
+            self.skip_missed(time);
+        }
+        let at = self.next_event_time();
+        let event = self.step(time);
+        if event.is_some() && self.missed == MissedEventPolicy::Coalesce {
+            self.skip_missed(time);
+        }
+        event.map(|event| (at, event))
+    }
+
+    fn step(&mut self, time: u64) -> Option<TimerEvent> {
+        if self.rising_next() && self.next_start <= time {
+            let start = self.next_start;
+            self.last_start = Some(start);
+            self.next_start = start + self.period;
+            self.next_stop = if self.duration > 0 {
+                start + self.duration
+            } else {
+                self.armed = !self.one_shot;
+                self.next_start
+            };
+            Some(TimerEvent::RisingEdge)
+        } else if self.next_stop <= time {
+            self.next_stop = self.next_start + self.duration;
+            self.armed = !self.one_shot;
+            Some(TimerEvent::FallingEdge)
+        } else {
+            None
+        }
+    }
+
+    // Move the schedule forward by whole periods, so that the next edge
+    // is less than a period before `time`
+    fn skip_missed(&mut self, time: u64) {
+        let next = self.next_event_time();
+        if time < next {
+            return;
+        }
+        let skipped = (time - next) / self.period * self.period;
+        self.next_start += skipped;
+        self.next_stop += skipped;
+        self.last_start = self.last_start.map(|start| start + skipped);
+    }
+
+    /// Set what the timer does with edges it missed. Timers start out
+    /// with `MissedEventPolicy::CatchUp`
+    pub fn set_missed_event_policy(&mut self, policy: MissedEventPolicy) {
+        self.missed = policy;
+    }
+
+    /// Return what the timer does with edges it missed
+    pub fn missed_event_policy(&self) -> MissedEventPolicy {
+        self.missed
+    }
+
+    /// Like `update`, but also return the timer's tag
+    pub fn update_tagged(&mut self, time: u64) -> Option<(TimerEvent, &T)> {
+        match self.update(time) {
+            Some(event) => Some((event, &self.tag)),
+            None => None,
+        }
+    }
+
+    /// Return the tag the timer carries
+    pub fn tag(&self) -> &T {
+        &self.tag
+    }
+
+    /// Return the tag the timer carries for modification
+    pub fn tag_mut(&mut self) -> &mut T {
+        &mut self.tag
+    }
+
+    /// Set whether the timer stops by itself after its next cycle, i.e.
+    /// after its next `FallingEdge`, or its next `RisingEdge` if the
+    /// duration is 0. Restarting a one-shot timer runs it once more
+    pub fn set_one_shot(&mut self, one_shot: bool) {
+        self.one_shot = one_shot;
+    }
+
+    /// Indicates if the timer stops by itself after its next cycle
+    pub fn is_one_shot(&self) -> bool {
+        self.one_shot
+    }
+
+    /// Return every edge up to the absolute `time`, along with the
+    /// tick it occured on, in order. The timer is only run as far as
+    /// the returned iterator is consumed
+    pub fn events_until(&mut self, time: u64) -> Edges<'_, T> {
+        Edges { timer: self, time }
+    }
+
+    /// Indicates if the timer is currently between a `RisingEdge` and
+    /// `FallingEdge` event
+    pub fn is_active(&self) -> bool {
+        self.armed && !self.rising_next()
+    }
+
+    // Whether the next edge is a `RisingEdge`, i.e. the timer isn't
+    // active. When both edges are on the same tick, the falling edge
+    // comes first if it ends a pulse as long as the period
+    fn rising_next(&self) -> bool {
+        self.next_start < self.next_stop
+            || (self.next_start == self.next_stop && self.duration < self.period)
+    }
+
+    /// Stop the timer, withdrawing any pending edges. A cancelled
+    /// timer emits no events until it is restarted, even if it was
+    /// active
+    pub fn cancel(&mut self) {
+        self.armed = false;
+    }
+
+    /// Indicates if the timer is running, i.e. it hasn't been
+    /// cancelled since it was created or last restarted
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    /// Re-anchor the timer so that its next `RisingEdge` is at the
+    /// absolute tick `offset`, keeping the period and duration. This
+    /// also restarts a cancelled timer
+    pub fn restart_at(&mut self, offset: u64) {
+        self.offset = offset % self.period;
+        self.next_start = offset;
+        self.next_stop = offset + self.duration;
+        self.last_start = None;
+        self.armed = true;
+    }
+
+    // Restart the timer with its next rising edge `offset` ticks after
+    // `tick`, keeping the offset
+    pub(crate) fn trigger(&mut self, tick: u64) {
+        let offset = self.offset;
+        self.restart_at(tick + offset);
+        self.offset = offset;
+    }
+
+    /// Return the number of ticks between rising edges
+    pub fn period(&self) -> u64 {
+        self.period
+    }
+
+    /// Return where in the period the rising edge falls
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Return the number of ticks the timer stays active for
+    pub fn duration(&self) -> u64 {
+        self.duration
+    }
+
+    /// Change the period while the timer is running. The period can't
+    /// be shorter than the duration, and the offset is wrapped around
+    /// to within it
+    pub fn set_period(&mut self, period: u64, policy: ReconfigurePolicy) {
+        assert!(period > 0);
+        assert!(self.duration <= period);
+
+        let offset = self.offset % period;
+        match (policy, self.last_start) {
+            (ReconfigurePolicy::Immediate, Some(start)) => {
+                self.next_start = start + period;
+                if !self.is_active() {
+                    self.next_stop = self.next_start + self.duration;
+                }
+            }
+            (ReconfigurePolicy::Immediate, None) => {
+                // Nothing has started yet, so the first rising edge
+                // moves to the new offset
+                self.next_start -= self.offset - offset;
+                self.next_stop = self.next_start + self.duration;
+            }
+            (ReconfigurePolicy::NextCycle, _) => {}
+        }
+        self.offset = offset;
+        self.period = period;
+    }
+
+    /// Change the offset while the timer is running, which moves the
+    /// next rising edge by the difference. With
+    /// `ReconfigurePolicy::Immediate`, an active timer's falling edge
+    /// moves along with it. An offset past the period is wrapped
+    /// around to within it
+    pub fn set_offset(&mut self, offset: u64, policy: ReconfigurePolicy) {
+        let offset = offset % self.period;
+
+        let shift = |time: u64| {
+            if offset >= self.offset {
+                time + (offset - self.offset)
+            } else {
+                time.saturating_sub(self.offset - offset)
+            }
+        };
+        let active = !self.rising_next();
+        let next_start = shift(self.next_start);
+        if !active {
+            self.next_stop = next_start + self.duration;
+        } else if policy == ReconfigurePolicy::Immediate {
+            self.next_stop = shift(self.next_stop);
+        }
+        self.next_start = next_start;
+        self.offset = offset;
+    }
+
+    /// Change the duration while the timer is running. The duration
+    /// can't be longer than the period
+    pub fn set_duration(&mut self, duration: u64, policy: ReconfigurePolicy) {
+        assert!(duration <= self.period);
+
+        let active = !self.rising_next();
+        self.duration = duration;
+        if active {
+            if policy == ReconfigurePolicy::Immediate {
+                // An active timer has always had a rising edge
+                let start = self.last_start.expect("active timer without a start");
+                self.next_stop = start + duration;
+            }
+        } else {
+            self.next_stop = self.next_start + duration;
+        }
+    }
+}
+
+/// Collects the settings for a `Timer`, created by `Timer::builder`
+#[derive(Debug, Clone)]
+pub struct TimerBuilder<T = ()> {
+    period: Option<u64>,
+    offset: u64,
+    duration: u64,
+    tag: T,
+    one_shot: bool,
+    missed: MissedEventPolicy,
+    jitter: u64,
+}
+
+impl<T> TimerBuilder<T> {
+    /// Set the number of ticks between rising edges
+    pub fn period(mut self, period: u64) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    /// Set the tick of the first rising edge, wrapped around to within
+    /// the period
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Set the number of ticks the timer stays active for; it can't be
+    /// longer than the period
+    pub fn duration(mut self, duration: u64) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Set the tag the timer carries; see `Timer::with_tag`
+    pub fn tag<U>(self, tag: U) -> TimerBuilder<U> {
+        TimerBuilder {
+            period: self.period,
+            offset: self.offset,
+            duration: self.duration,
+            tag,
+            one_shot: self.one_shot,
+            missed: self.missed,
+            jitter: self.jitter,
+        }
+    }
+
+    /// Set whether the timer stops after one cycle; see
+    /// `Timer::set_one_shot`
+    pub fn one_shot(mut self, one_shot: bool) -> Self {
+        self.one_shot = one_shot;
+        self
+    }
+
+    /// Set what the timer does with edges it missed; see
+    /// `Timer::set_missed_event_policy`
+    pub fn missed_event_policy(mut self, policy: MissedEventPolicy) -> Self {
+        self.missed = policy;
+        self
+    }
+
+    /// Set the maximum number of ticks each period varies by. A timer
+    /// with jitter must be built with `build_jittered`
+    pub fn jitter(mut self, jitter: u64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Create the timer, or return `Error::InvalidConfig` if the period
+    /// is missing or the settings contradict each other
+    pub fn build(self) -> Result<Timer<T>, Error> {
+        if self.jitter > 0 {
+            return Err(Error::InvalidConfig(
+                "jitter needs an RNG; use build_jittered",
+            ));
+        }
+        self.build_timer()
+    }
+
+    /// Create the timer like `build`, jittered with numbers from `rng`;
+    /// see `JitteredTimer`
+    pub fn build_jittered<R: JitterRng>(self, rng: R) -> Result<JitteredTimer<R, T>, Error> {
+        let jitter = self.jitter;
+        let timer = self.build_timer()?;
+        if jitter > 0 && jitter >= timer.period - timer.duration {
+            return Err(Error::InvalidConfig(
+                "jitter leaves no room for the duration",
+            ));
+        }
+        Ok(JitteredTimer::new(timer, jitter, rng))
+    }
+
+    fn build_timer(self) -> Result<Timer<T>, Error> {
+        let period = self
+            .period
+            .ok_or(Error::InvalidConfig("period is not set"))?;
+        if period == 0 {
+            return Err(Error::InvalidConfig("period is 0"));
+        }
+        if self.duration > period {
+            return Err(Error::InvalidConfig("duration is longer than the period"));
+        }
+
+        let mut timer = Timer::with_tag(period, self.offset, self.duration, self.tag);
+        timer.one_shot = self.one_shot;
+        timer.missed = self.missed;
+        Ok(timer)
+    }
+}
+
+/// The edges of a `Timer` up to some time, created by
+/// `Timer::events_until`
+pub struct Edges<'a, T: 'a = ()> {
+    timer: &'a mut Timer<T>,
+    time: u64,
+}
+
+impl<'a, T> Iterator for Edges<'a, T> {
+    type Item = (u64, TimerEvent);
+
+    fn next(&mut self) -> Option<(u64, TimerEvent)> {
+        self.timer.update_with_tick(self.time)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for Timer {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Timer> {
+        // Keep the values small enough that running the timer for a
+        // long time won't overflow
+        let period = u64::from(u.int_in_range(1..=u32::MAX)?);
+        let offset = u.int_in_range(0..=period - 1)?;
+        let duration = u.int_in_range(0..=period)?;
+        let cycles = u64::from(u16::arbitrary(u)?);
+        let active = duration > 0 && bool::arbitrary(u)?;
+
+        let mut timer = Timer::new(period, offset, duration);
+        timer.next_start += cycles * period;
+        timer.next_stop += cycles * period;
+        if active {
+            timer.next_start += period;
+        }
+        if timer.next_start >= period {
+            timer.last_start = Some(timer.next_start - period);
+        }
+        Ok(timer)
+    }
+}
+
+/// Given a list of `timers`, return the index of the timer that will
+/// emit the next `TimerEvent` along with the tick it will do so. Ties go
+/// to the lowest index. Returns `None` if there are no armed timers
+pub fn next_timer_event<T>(timers: &[Timer<T>]) -> Option<(usize, u64)> {
+    timers
+        .iter()
+        .enumerate()
+        .filter(|&(_, t)| t.is_armed())
+        .map(|(i, t)| (i, t.next_event_time()))
+        .min_by_key(|&(i, time)| (time, i))
+}
+
+#[test]
+fn test_timer() {
+    let mut timer = Timer::new(100, 13, 20);
+
+    assert_eq!(timer.next_start_time(), 13);
+    assert_eq!(timer.next_stop_time(), 13 + 20);
+
+    // Come up to just before the next start time
+    assert_eq!(timer.update(12), None);
+    assert_eq!(timer.next_start_time(), 13);
+    assert_eq!(timer.next_stop_time(), 13 + 20);
+
+    assert_eq!(timer.update(13), Some(TimerEvent::RisingEdge));
+    assert_eq!(timer.next_start_time(), 13 + 100);
+    assert_eq!(timer.next_stop_time(), 13 + 20);
+
+    // Overshooting should still get the falling edge event
+    assert_eq!(timer.update(13 + 20 + 5), Some(TimerEvent::FallingEdge));
+    assert_eq!(timer.next_start_time(), 13 + 100);
+    assert_eq!(timer.next_stop_time(), 13 + 20 + 100);
+
+    let mut v = vec![];
+    while let Some(e) = timer.update(300) {
+        v.push(e);
+    }
+    // Ensure the events are interleaved properly when there are
+    // several pending
+    assert_eq!(
+        v,
+        vec![
+            TimerEvent::RisingEdge,
+            TimerEvent::FallingEdge,
+            TimerEvent::RisingEdge,
+            TimerEvent::FallingEdge,
+        ]
+    );
+}
+
+#[test]
+fn test_timer_zero_duration() {
+    let mut timer = Timer::new(100, 13, 0);
+
+    assert_eq!(timer.next_start_time(), 13);
+    assert_eq!(timer.update(12), None);
+    assert_eq!(timer.next_start_time(), 13);
+
+    assert_eq!(timer.update(13), Some(TimerEvent::RisingEdge));
+    assert_eq!(timer.next_start_time(), 13 + 100);
+
+    assert_eq!(timer.update(13 + 100), Some(TimerEvent::RisingEdge));
+}
+
+#[test]
+fn test_next_timer_event() {
+    let t1 = Timer::new(100, 13, 0);
+    let t2 = Timer::new(100, 14, 0);
+
+    assert_eq!(next_timer_event(&[t1, t2]), Some((0, 13)));
+    assert_eq!(next_timer_event(&[t2, t1]), Some((1, 13)));
+    assert_eq!(next_timer_event(&[t1, t1]), Some((0, 13)));
+    assert_eq!(next_timer_event::<()>(&[]), None);
+}
+
+#[test]
+fn test_timer_full_duration() {
+    let mut timer = Timer::new(10, 23, 10);
+    assert_eq!(timer.offset(), 3);
+    assert_eq!(timer.next_start_time(), 3);
+    assert!(!timer.is_active());
+
+    let events: Vec<_> = timer.events_until(23).collect();
+    assert_eq!(
+        events,
+        vec![
+            (3, TimerEvent::RisingEdge),
+            (13, TimerEvent::FallingEdge),
+            (13, TimerEvent::RisingEdge),
+            (23, TimerEvent::FallingEdge),
+            (23, TimerEvent::RisingEdge),
+        ]
+    );
+    assert!(timer.is_active());
+    assert_eq!(timer.next_event_time(), 33);
+
+    timer.set_duration(4, ReconfigurePolicy::Immediate);
+    assert_eq!(
+        timer.update_with_tick(30),
+        Some((27, TimerEvent::FallingEdge))
+    );
+    timer.set_offset(15, ReconfigurePolicy::NextCycle);
+    assert_eq!(timer.offset(), 5);
+    assert_eq!(timer.next_start_time(), 35);
+}
+
+#[test]
+fn test_timer_builder() {
+    use rng::XorShiftRng;
+    let err = |b: TimerBuilder| b.build().unwrap_err();
+    assert_eq!(
+        err(Timer::builder()),
+        Error::InvalidConfig("period is not set")
+    );
+    assert_eq!(
+        err(Timer::builder().period(0)),
+        Error::InvalidConfig("period is 0")
+    );
+    assert_eq!(
+        err(Timer::builder().period(10).duration(11)),
+        Error::InvalidConfig("duration is longer than the period")
+    );
+    assert!(Timer::builder().period(10).jitter(2).build().is_err());
+    assert_eq!(
+        Timer::builder()
+            .period(10)
+            .duration(5)
+            .jitter(5)
+            .build_jittered(XorShiftRng::new(1))
+            .unwrap_err(),
+        Error::InvalidConfig("jitter leaves no room for the duration")
+    );
+
+    let timer = Timer::builder()
+        .period(10)
+        .offset(3)
+        .missed_event_policy(MissedEventPolicy::Skip)
+        .build()
+        .unwrap();
+    assert_eq!(timer, {
+        let mut t = Timer::new(10, 3, 0);
+        t.set_missed_event_policy(MissedEventPolicy::Skip);
+        t
+    });
+
+    let mut jittered = Timer::builder()
+        .period(10)
+        .duration(2)
+        .tag(7u8)
+        .jitter(3)
+        .build_jittered(XorShiftRng::new(1))
+        .unwrap();
+    assert_eq!(jittered.jitter(), 3);
+    assert_eq!(jittered.timer().tag(), &7);
+    assert!(jittered.events_until(100).count() >= 2 * 7);
+}
+
+#[test]
+fn test_timer_one_shot() {
+    let mut timer = Timer::new(100, 10, 20);
+    timer.set_one_shot(true);
+    assert!(timer.is_one_shot());
+    let events: Vec<_> = timer.events_until(1000).collect();
+    assert_eq!(
+        events,
+        vec![(10, TimerEvent::RisingEdge), (30, TimerEvent::FallingEdge)]
+    );
+    assert!(!timer.is_armed());
+
+    timer.restart_at(2000);
+    assert_eq!(timer.events_until(5000).count(), 2);
+
+    let mut timer = Timer::new(100, 10, 0);
+    timer.set_one_shot(true);
+    assert_eq!(timer.events_until(1000).count(), 1);
+}
+
+#[test]
+fn test_timer_tag() {
+    #[derive(Debug, Eq, PartialEq, Copy, Clone)]
+    enum Irq {
+        Vblank,
+        Serial,
+    }
+
+    let mut timers = vec![
+        Timer::with_tag(100, 90, 0, Irq::Vblank),
+        Timer::with_tag(30, 0, 10, Irq::Serial),
+    ];
+    let mut fired = vec![];
+    for timer in &mut timers {
+        while let Some((event, &irq)) = timer.update_tagged(95) {
+            fired.push((irq, event));
+        }
+    }
+    assert_eq!(fired.len(), 1 + 7);
+    assert_eq!(fired[0], (Irq::Vblank, TimerEvent::RisingEdge));
+    assert_eq!(next_timer_event(&timers), Some((1, 100)));
+
+    *timers[1].tag_mut() = Irq::Vblank;
+    assert_eq!(timers[1].tag(), &Irq::Vblank);
+    assert_eq!(
+        Timer::new(10, 0, 0).update_tagged(0),
+        Some((TimerEvent::RisingEdge, &()))
+    );
+}
+
+#[test]
+fn test_timer_events_until() {
+    let mut timer = Timer::new(100, 10, 30);
+    let events: Vec<_> = timer.events_until(230).collect();
+    assert_eq!(
+        events,
+        vec![
+            (10, TimerEvent::RisingEdge),
+            (40, TimerEvent::FallingEdge),
+            (110, TimerEvent::RisingEdge),
+            (140, TimerEvent::FallingEdge),
+            (210, TimerEvent::RisingEdge),
+        ]
+    );
+    assert!(timer.is_active());
+    assert_eq!(timer.events_until(230).count(), 0);
+
+    // Stopping early leaves the rest pending
+    assert_eq!(
+        timer.events_until(1000).next(),
+        Some((240, TimerEvent::FallingEdge))
+    );
+    assert_eq!(timer.next_event_time(), 310);
+
+    timer.cancel();
+    assert_eq!(timer.events_until(1000).count(), 0);
+}
+
+#[test]
+fn test_timer_missed_events() {
+    let mut timer = Timer::new(100, 10, 30);
+    assert_eq!(timer.missed_event_policy(), MissedEventPolicy::CatchUp);
+    assert_eq!(timer.events_until(1020).count(), 21);
+
+    let mut timer = Timer::new(100, 10, 30);
+    timer.set_missed_event_policy(MissedEventPolicy::Skip);
+    let events: Vec<_> = timer.events_until(1020).collect();
+    assert_eq!(events, vec![(1010, TimerEvent::RisingEdge)]);
+    assert!(timer.is_active());
+
+    let mut timer = Timer::new(100, 10, 30);
+    timer.set_missed_event_policy(MissedEventPolicy::Coalesce);
+    let events: Vec<_> = timer.events_until(1020).collect();
+    assert_eq!(
+        events,
+        vec![
+            (10, TimerEvent::RisingEdge),
+            (940, TimerEvent::FallingEdge),
+            (1010, TimerEvent::RisingEdge)
+        ]
+    );
+    // Keeping up doesn't drop anything
+    let events: Vec<_> = timer.events_until(1150).collect();
+    assert_eq!(
+        events,
+        vec![
+            (1040, TimerEvent::FallingEdge),
+            (1110, TimerEvent::RisingEdge),
+            (1140, TimerEvent::FallingEdge)
+        ]
+    );
+}
+
+#[test]
+fn test_timer_update_with_tick() {
+    let mut timer = Timer::new(100, 20, 50);
+    assert_eq!(timer.update_with_tick(10), None);
+    assert_eq!(
+        timer.update_with_tick(95),
+        Some((20, TimerEvent::RisingEdge))
+    );
+    assert_eq!(
+        timer.update_with_tick(95),
+        Some((70, TimerEvent::FallingEdge))
+    );
+    assert_eq!(timer.update_with_tick(95), None);
+    assert_eq!(
+        timer.update_with_tick(120),
+        Some((120, TimerEvent::RisingEdge))
+    );
+}
+
+#[test]
+fn test_timer_progress() {
+    let mut timer = Timer::new(100, 20, 50);
+    assert_eq!(timer.progress(20), 0.0);
+    assert_eq!(timer.progress(45), 0.25);
+    assert_eq!(timer.progress(10), 0.9);
+    assert_eq!(timer.time_until_next_event(5), Some(15));
+
+    assert_eq!(timer.update(30), Some(TimerEvent::RisingEdge));
+    assert_eq!(timer.progress(30), 0.1);
+    assert_eq!(timer.time_until_next_event(30), Some(40));
+    assert_eq!(timer.time_until_next_event(90), Some(0));
+
+    timer.cancel();
+    assert_eq!(timer.time_until_next_event(30), None);
+}
+
+#[test]
+fn test_timer_cancel_restart() {
+    let mut timer = Timer::new(100, 10, 20);
+    assert!(timer.is_armed());
+    assert_eq!(timer.update(10), Some(TimerEvent::RisingEdge));
+    assert!(timer.is_active());
+
+    timer.cancel();
+    assert!(!timer.is_armed());
+    assert!(!timer.is_active());
+    assert_eq!(timer.update(1000), None);
+    assert_eq!(timer.next_event_time(), u64::MAX);
+    assert_eq!(
+        next_timer_event(&[timer, Timer::new(50, 40, 0)]),
+        Some((1, 40))
+    );
+    assert_eq!(next_timer_event(&[timer]), None);
+
+    timer.restart_at(1005);
+    assert!(timer.is_armed());
+    assert_eq!(timer.next_start_time(), 1005);
+    assert_eq!(timer.next_stop_time(), 1025);
+    assert_eq!(timer.update(1010), Some(TimerEvent::RisingEdge));
+    assert_eq!(timer.update(1030), Some(TimerEvent::FallingEdge));
+    assert_eq!(timer.update(1030), None);
+    assert_eq!(timer.next_start_time(), 1105);
+}
+
+#[test]
+fn test_timer_reconfigure() {
+    // Halving the period mid-note keeps the edge phase
+    let mut timer = Timer::new(100, 0, 50);
+    assert_eq!(timer.update(0), Some(TimerEvent::RisingEdge));
+    timer.set_period(60, ReconfigurePolicy::NextCycle);
+    assert_eq!(timer.period(), 60);
+    assert_eq!(timer.next_start_time(), 100);
+    assert_eq!(timer.update(50), Some(TimerEvent::FallingEdge));
+    assert_eq!(timer.update(100), Some(TimerEvent::RisingEdge));
+    assert_eq!(timer.next_start_time(), 160);
+
+    timer.set_period(80, ReconfigurePolicy::Immediate);
+    assert_eq!(timer.next_start_time(), 180);
+    assert_eq!(timer.next_stop_time(), 150);
+
+    // Shortening an active pulse can put its end in the past
+    timer.set_duration(10, ReconfigurePolicy::NextCycle);
+    assert_eq!(timer.next_stop_time(), 150);
+    timer.set_duration(20, ReconfigurePolicy::Immediate);
+    assert_eq!(timer.duration(), 20);
+    assert_eq!(timer.next_stop_time(), 120);
+    assert_eq!(timer.update(130), Some(TimerEvent::FallingEdge));
+    assert_eq!(timer.next_stop_time(), 200);
+
+    timer.set_offset(30, ReconfigurePolicy::NextCycle);
+    assert_eq!(timer.offset(), 30);
+    assert_eq!(timer.next_start_time(), 210);
+    assert_eq!(timer.next_stop_time(), 230);
+    timer.set_offset(10, ReconfigurePolicy::Immediate);
+    assert_eq!(timer.next_start_time(), 190);
+
+    let mut timer = Timer::new(100, 0, 0);
+    timer.set_period(50, ReconfigurePolicy::Immediate);
+    assert_eq!(timer.update(0), Some(TimerEvent::RisingEdge));
+    assert_eq!(timer.update(49), None);
+    assert_eq!(timer.update(50), Some(TimerEvent::RisingEdge));
+
+    // Before the first rising edge, the edges follow the wrapped offset
+    let mut timer = Timer::new(100, 70, 10);
+    timer.set_period(50, ReconfigurePolicy::Immediate);
+    assert_eq!(timer.offset(), 20);
+    assert_eq!(timer.next_start_time(), 20);
+    assert_eq!(timer.next_stop_time(), 30);
+    assert_eq!(timer.update(20), Some(TimerEvent::RisingEdge));
+    assert_eq!(timer.next_start_time(), 70);
+}
+
+/// Converts elapsed real time into ticks at a fixed rate, for driving a
+/// `Clock` or `Timer` from a real-time loop
+///
+/// The ticks are computed from the total time since the start rather
+/// than summed up one poll at a time, so the fraction of a tick lost
+/// to rounding in each poll is carried forward and never accumulates
+/// into drift.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+/// use std::time::{Duration, Instant};
+///
+/// let start = Instant::now();
+/// let mut wall = WallClock::starting_at(1000, start);
+/// let mut clock = Clock::new(100);
+///
+/// assert_eq!(wall.drive_at(&mut clock, start + Duration::from_millis(250)), 2);
+/// assert_eq!(clock.count(), 50);
+/// assert_eq!(wall.time(), 250);
+/// ```
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct WallClock {
+    start: Instant,
+    rate: u64,
+    // Ticks handed out so far
+    ticks: u64,
+}
+
+impl WallClock {
+    /// Create a new wall clock that starts now and runs at `rate`
+    /// ticks per second
+    pub fn new(rate: u64) -> WallClock {
+        WallClock::starting_at(rate, Instant::now())
+    }
+
+    /// Create a new wall clock like `new` that starts at `start`
+    pub fn starting_at(rate: u64, start: Instant) -> WallClock {
+        assert!(rate > 0);
+        WallClock {
+            start,
+            rate,
+            ticks: 0,
+        }
+    }
+
+    /// Return the number of ticks that have passed since the last poll
+    pub fn poll(&mut self) -> u64 {
+        self.poll_at(Instant::now())
+    }
+
+    /// Like `poll`, but with the current time given as `now`. A `now`
+    /// earlier than a previous one produces no ticks
+    pub fn poll_at(&mut self, now: Instant) -> u64 {
+        let nanos = now.saturating_duration_since(self.start).as_nanos();
+        let total = (nanos * u128::from(self.rate) / NANOS_PER_SEC) as u64;
+        let ticks = total.saturating_sub(self.ticks);
+        self.ticks += ticks;
+        ticks
+    }
+
+    /// Advance `clock` by the ticks that have passed since the last
+    /// poll, and return how many times it wrapped
+    pub fn drive(&mut self, clock: &mut Clock) -> u64 {
+        let ticks = self.poll();
+        clock.tick_by(ticks)
+    }
+
+    /// Like `drive`, but with the current time given as `now`
+    pub fn drive_at(&mut self, clock: &mut Clock, now: Instant) -> u64 {
+        let ticks = self.poll_at(now);
+        clock.tick_by(ticks)
+    }
+
+    /// Return the number of ticks handed out so far, which is the
+    /// absolute time to pass to `Timer::update`
+    pub fn time(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Return the rate in ticks per second
+    pub fn rate(&self) -> u64 {
+        self.rate
+    }
+
+    /// Return the instant the clock started at
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+}
+
+#[test]
+fn test_wall_clock() {
+    let start = Instant::now();
+    let mut wall = WallClock::starting_at(3, start);
+    assert_eq!(wall.rate(), 3);
+
+    // 1.2 ticks per poll. Rounding each poll on its own would lose a
+    // tick by the end
+    let polls: Vec<u64> = (1..=5)
+        .map(|i| wall.poll_at(start + Duration::from_millis(400 * i)))
+        .collect();
+    assert_eq!(polls, vec![1, 1, 1, 1, 2]);
+    assert_eq!(wall.time(), 6);
+
+    // Going back in time doesn't produce or take back ticks
+    assert_eq!(wall.poll_at(start), 0);
+    assert_eq!(wall.poll_at(start + Duration::from_millis(2400)), 1);
+
+    let mut timer = Timer::new(4, 1, 2);
+    let mut events = vec![];
+    while let Some(event) = timer.update(wall.time()) {
+        events.push(event);
+    }
+    assert_eq!(events.len(), 4);
+    assert!(!timer.is_active());
+}
+
+/// A `Timer` whose period, offset, and duration are `Duration`s, and
+/// which is updated with `Instant`s
+///
+/// The edges are computed by a tick-based `Timer` counting nanoseconds
+/// since the start, so both behave the same.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+/// use std::time::{Duration, Instant};
+///
+/// let start = Instant::now();
+/// let ms = Duration::from_millis;
+/// let mut timer = RealTimer::starting_at(start, ms(100), ms(10), ms(20));
+///
+/// assert_eq!(timer.update(start + ms(5)), None);
+/// assert_eq!(timer.update(start + ms(15)), Some(TimerEvent::RisingEdge));
+/// assert_eq!(timer.next_event_instant(), Some(start + ms(30)));
+/// ```
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct RealTimer {
+    start: Instant,
+    timer: Timer,
+}
+
+impl RealTimer {
+    /// Create a new timer like `Timer::new` whose time starts now
+    pub fn new(period: Duration, offset: Duration, duration: Duration) -> RealTimer {
+        RealTimer::starting_at(Instant::now(), period, offset, duration)
+    }
+
+    /// Create a new timer like `new` whose time starts at `start`
+    pub fn starting_at(
+        start: Instant,
+        period: Duration,
+        offset: Duration,
+        duration: Duration,
+    ) -> RealTimer {
+        RealTimer {
+            start,
+            timer: Timer::new(nanos(period), nanos(offset), nanos(duration)),
+        }
+    }
+
+    /// Runs the timer until either `now` is reached, or until the next
+    /// event occurs, like `Timer::update`
+    pub fn update(&mut self, now: Instant) -> Option<TimerEvent> {
+        let time = self.time(now);
+        self.timer.update(time)
+    }
+
+    /// Like `update`, but also return the instant the edge occured at
+    pub fn update_with_instant(&mut self, now: Instant) -> Option<(Instant, TimerEvent)> {
+        let time = self.time(now);
+        let start = self.start;
+        self.timer
+            .update_with_tick(time)
+            .map(|(at, event)| (start + Duration::from_nanos(at), event))
+    }
+
+    /// Get the instant of the next edge, or `None` if the timer has
+    /// been cancelled
+    pub fn next_event_instant(&self) -> Option<Instant> {
+        if self.timer.is_armed() {
+            Some(self.start + Duration::from_nanos(self.timer.next_event_time()))
+        } else {
+            None
+        }
+    }
+
+    /// Return how long from `now` until the next edge, which is zero if
+    /// it is already due. Returns `None` if the timer has been
+    /// cancelled
+    pub fn time_until_next_event(&self, now: Instant) -> Option<Duration> {
+        self.timer
+            .time_until_next_event(self.time(now))
+            .map(Duration::from_nanos)
+    }
+
+    /// Return the instant the timer's time started at
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+
+    /// Return the underlying tick-based timer, counting nanoseconds
+    /// since the start
+    pub fn timer(&self) -> &Timer {
+        &self.timer
+    }
+
+    /// Return the underlying tick-based timer for modification, e.g. to
+    /// cancel it or change its policies
+    pub fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.timer
+    }
+
+    fn time(&self, now: Instant) -> u64 {
+        nanos(now.saturating_duration_since(self.start))
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    let nanos = duration.as_nanos();
+    assert!(nanos <= u128::from(u64::MAX), "duration too long");
+    nanos as u64
+}
+
+#[test]
+fn test_real_timer() {
+    let start = Instant::now();
+    let ms = Duration::from_millis;
+    let mut timer = RealTimer::starting_at(start, ms(50), ms(0), ms(10));
+    assert_eq!(timer.start(), start);
+
+    let mut events = vec![];
+    while let Some(event) = timer.update_with_instant(start + ms(125)) {
+        events.push(event);
+    }
+    assert_eq!(
+        events,
+        vec![
+            (start, TimerEvent::RisingEdge),
+            (start + ms(10), TimerEvent::FallingEdge),
+            (start + ms(50), TimerEvent::RisingEdge),
+            (start + ms(60), TimerEvent::FallingEdge),
+            (start + ms(100), TimerEvent::RisingEdge),
+            (start + ms(110), TimerEvent::FallingEdge),
+        ]
+    );
+    assert_eq!(timer.time_until_next_event(start + ms(125)), Some(ms(25)));
+    assert_eq!(timer.timer().period(), 50_000_000);
+
+    timer.timer_mut().cancel();
+    assert_eq!(timer.update(start + ms(1000)), None);
+    assert_eq!(timer.next_event_instant(), None);
+}
+
+/// A `Timer` whose periods each vary by up to `jitter` ticks either
+/// way, so that many timers with the same period don't stay in sync
+///
+/// The first rising edge is at the timer's offset as usual, and each
+/// following one is `period` ticks after the one before it, plus or
+/// minus a random amount up to `jitter`. The duration isn't jittered.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut heartbeat = JitteredTimer::new(Timer::new(1000, 0, 0), 100, XorShiftRng::new(7));
+/// let mut last = None;
+/// for (at, _) in heartbeat.events_until(100_000) {
+///     if let Some(last) = last {
+///         assert!(at - last >= 900 && at - last <= 1100);
+///     }
+///     last = Some(at);
+/// }
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct JitteredTimer<R, T = ()> {
+    timer: Timer<T>,
+    jitter: u64,
+    rng: R,
+}
+
+impl<R: JitterRng, T> JitteredTimer<R, T> {
+    /// Wrap `timer`, jittering its periods by up to `jitter` ticks with
+    /// numbers from `rng`. The shortest period must still be longer
+    /// than the duration
+    pub fn new(timer: Timer<T>, jitter: u64, rng: R) -> JitteredTimer<R, T> {
+        assert!(jitter == 0 || jitter < timer.period - timer.duration);
+        JitteredTimer { timer, jitter, rng }
+    }
+
+    /// Runs the timer until either the given absolute `time` is
+    /// reached, or until the next event occurs, like `Timer::update`
+    pub fn update(&mut self, time: u64) -> Option<TimerEvent> {
+        self.update_with_tick(time).map(|(_, event)| event)
+    }
+
+    /// Like `update`, but also return the tick the edge occured on
+    pub fn update_with_tick(&mut self, time: u64) -> Option<(u64, TimerEvent)> {
+        let edge = self.timer.update_with_tick(time);
+        if let Some((_, TimerEvent::RisingEdge)) = edge {
+            let span = u128::from(self.jitter) * 2 + 1;
+            let offset = (u128::from(self.rng.next_u64()) % span) as u64;
+            let timer = &mut self.timer;
+            let inactive = timer.next_start == timer.next_stop;
+            timer.next_start = timer.next_start + offset - self.jitter;
+            if inactive {
+                timer.next_stop = timer.next_start;
+            }
+        }
+        edge
+    }
+
+    /// Return every edge up to the absolute `time`, along with the
+    /// tick it occured on, like `Timer::events_until`
+    pub fn events_until(&mut self, time: u64) -> JitteredEdges<'_, R, T> {
+        JitteredEdges { timer: self, time }
+    }
+
+    /// Return the maximum number of ticks each period varies by
+    pub fn jitter(&self) -> u64 {
+        self.jitter
+    }
+
+    /// Return the underlying timer
+    pub fn timer(&self) -> &Timer<T> {
+        &self.timer
+    }
+
+    /// Return the underlying timer for modification
+    pub fn timer_mut(&mut self) -> &mut Timer<T> {
+        &mut self.timer
+    }
+
+    /// Return the wrapped timer, dropping the RNG
+    pub fn into_timer(self) -> Timer<T> {
+        self.timer
+    }
+}
+
+/// The edges of a `JitteredTimer` up to some time, created by
+/// `JitteredTimer::events_until`
+pub struct JitteredEdges<'a, R: 'a, T: 'a = ()> {
+    timer: &'a mut JitteredTimer<R, T>,
+    time: u64,
+}
+
+impl<'a, R: JitterRng, T> Iterator for JitteredEdges<'a, R, T> {
+    type Item = (u64, TimerEvent);
+
+    fn next(&mut self) -> Option<(u64, TimerEvent)> {
+        self.timer.update_with_tick(self.time)
+    }
+}
+
+#[test]
+fn test_jittered_timer() {
+    use rng::XorShiftRng;
+    // Cycle through the whole jitter range: -2, -1, 0, 1, 2
+    let mut n = 0;
+    let rng = move || {
+        n += 1;
+        n - 1
+    };
+    let mut timer = JitteredTimer::new(Timer::new(10, 5, 3), 2, rng);
+    assert_eq!(timer.jitter(), 2);
+
+    let rising: Vec<u64> = timer
+        .events_until(60)
+        .filter(|&(_, e)| e == TimerEvent::RisingEdge)
+        .map(|(at, _)| at)
+        .collect();
+    assert_eq!(rising, vec![5, 13, 22, 32, 43, 55]);
+    assert_eq!(timer.timer().next_start_time(), 63);
+
+    let mut a = JitteredTimer::new(Timer::new(100, 0, 0), 10, XorShiftRng::new(1));
+    let mut b = JitteredTimer::new(Timer::new(100, 0, 0), 10, XorShiftRng::new(2));
+    let a: Vec<_> = a.events_until(10_000).collect();
+    let b: Vec<_> = b.events_until(10_000).collect();
+    assert_ne!(a, b);
+    assert!(a.iter().all(|&(_, e)| e == TimerEvent::RisingEdge));
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn test_arbitrary() {
+    let data: Vec<u8> = (0..=255).rev().cycle().take(4096).collect();
+    let mut u = Unstructured::new(&data);
+    while !u.is_empty() {
+        let clock = Clock::arbitrary(&mut u).unwrap();
+        assert!(clock.count() < clock.period());
+
+        let mut timer = Timer::arbitrary(&mut u).unwrap();
+        let time = timer.next_event_time();
+        let was_active = timer.is_active();
+        let expected = if was_active {
+            TimerEvent::FallingEdge
+        } else {
+            TimerEvent::RisingEdge
+        };
+        assert_eq!(timer.update(time), Some(expected));
+    }
+}
+
+/// A pulse-width modulated output, with the duty cycle given as a
+/// fraction of the period
+///
+/// The edges come from a `Timer`, so the duty cycle and period can be
+/// changed while it runs without losing its phase. At a duty cycle of
+/// 0 or 1 the output stays low or high, and no edges are emitted.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut pwm = Pwm::new(100, 0.25);
+/// assert!(pwm.level(10));
+/// assert!(!pwm.level(30));
+///
+/// pwm.set_period(40, ReconfigurePolicy::Immediate);
+/// assert_eq!(pwm.high_ticks(), 10);
+/// assert_eq!(pwm.update_with_tick(100), Some((0, TimerEvent::RisingEdge)));
+/// assert_eq!(pwm.update_with_tick(100), Some((10, TimerEvent::FallingEdge)));
+/// ```
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Pwm {
+    timer: Timer,
+    duty: f64,
+    high: u64,
+}
+
+impl Pwm {
+    /// Create a new output with a period of `period` ticks, which is
+    /// high for the fraction `duty` of each period starting at tick 0
+    pub fn new(period: u64, duty: f64) -> Pwm {
+        let high = high_ticks(period, duty);
+        Pwm {
+            timer: Timer::new(period, 0, pulse_ticks(period, high)),
+            duty,
+            high,
+        }
+    }
+
+    /// Runs the output until either the given absolute `time` is
+    /// reached, or until the next edge, like `Timer::update`
+    pub fn update(&mut self, time: u64) -> Option<TimerEvent> {
+        self.update_with_tick(time).map(|(_, event)| event)
+    }
+
+    /// Like `update`, but also return the tick the edge occured on
+    pub fn update_with_tick(&mut self, time: u64) -> Option<(u64, TimerEvent)> {
+        if self.high == 0 || self.high == self.timer.period {
+            // Keep the timer going to hold the phase, but the output
+            // doesn't change
+            while self.timer.update(time).is_some() {}
+            None
+        } else {
+            self.timer.update_with_tick(time)
+        }
+    }
+
+    /// Return the level of the output at the absolute `time`, according
+    /// to the current schedule
+    pub fn level(&self, time: u64) -> bool {
+        self.timer.since_start(time) < self.high
+    }
+
+    /// Get the next tick that will emit an edge, or `u64::MAX` if the
+    /// output is constant
+    pub fn next_event_time(&self) -> u64 {
+        if self.high == 0 || self.high == self.timer.period {
+            u64::MAX
+        } else {
+            self.timer.next_event_time()
+        }
+    }
+
+    /// Change the duty cycle while the output is running
+    pub fn set_duty(&mut self, duty: f64, policy: ReconfigurePolicy) {
+        let period = self.timer.period;
+        self.high = high_ticks(period, duty);
+        self.duty = duty;
+        self.timer
+            .set_duration(pulse_ticks(period, self.high), policy);
+    }
+
+    /// Change the period while the output is running, keeping the duty
+    /// cycle
+    pub fn set_period(&mut self, period: u64, policy: ReconfigurePolicy) {
+        let high = high_ticks(period, self.duty);
+        let pulse = pulse_ticks(period, high);
+        // The pulse must fit in the period at every step
+        if period < self.timer.period {
+            self.timer.set_duration(pulse, policy);
+            self.timer.set_period(period, policy);
+        } else {
+            self.timer.set_period(period, policy);
+            self.timer.set_duration(pulse, policy);
+        }
+        self.high = high;
+    }
+
+    /// Return the duty cycle
+    pub fn duty(&self) -> f64 {
+        self.duty
+    }
+
+    /// Return the number of ticks the output is high for each period
+    pub fn high_ticks(&self) -> u64 {
+        self.high
+    }
+
+    /// Return the period in ticks
+    pub fn period(&self) -> u64 {
+        self.timer.period
+    }
+}
+
+fn high_ticks(period: u64, duty: f64) -> u64 {
+    assert!((0.0..=1.0).contains(&duty));
+    (duty * period as f64).round() as u64
+}
+
+// The duration of the timer driving an output that is high for `high`
+// ticks. A constant output uses a timer with no pulse, to keep the
+// phase
+fn pulse_ticks(period: u64, high: u64) -> u64 {
+    if high == period {
+        0
+    } else {
+        high
+    }
+}
+
+#[test]
+fn test_pwm() {
+    let mut pwm = Pwm::new(10, 0.3);
+    assert_eq!(pwm.duty(), 0.3);
+    assert_eq!(pwm.high_ticks(), 3);
+    let levels: Vec<bool> = (0..10).map(|t| pwm.level(t)).collect();
+    assert_eq!(levels.iter().filter(|&&l| l).count(), 3);
+    assert!(levels[0] && levels[2] && !levels[3]);
+
+    let edges: Vec<_> = pwm.timer.events_until(15).collect();
+    assert_eq!(edges.len(), 4);
+
+    // Constant outputs keep their phase but have no edges
+    pwm.set_duty(1.0, ReconfigurePolicy::NextCycle);
+    assert!(pwm.level(17));
+    assert_eq!(pwm.next_event_time(), u64::MAX);
+    assert_eq!(pwm.update(100), None);
+    pwm.set_duty(0.0, ReconfigurePolicy::NextCycle);
+    assert!(!pwm.level(100));
+    assert_eq!(pwm.update(105), None);
+
+    pwm.set_duty(0.5, ReconfigurePolicy::NextCycle);
+    assert_eq!(
+        pwm.update_with_tick(115),
+        Some((110, TimerEvent::RisingEdge))
+    );
+    assert_eq!(
+        pwm.update_with_tick(115),
+        Some((115, TimerEvent::FallingEdge))
+    );
+
+    pwm.set_period(4, ReconfigurePolicy::NextCycle);
+    assert_eq!(pwm.period(), 4);
+    assert_eq!(pwm.high_ticks(), 2);
+    assert_eq!(
+        pwm.update_with_tick(125),
+        Some((120, TimerEvent::RisingEdge))
+    );
+    assert_eq!(
+        pwm.update_with_tick(125),
+        Some((122, TimerEvent::FallingEdge))
+    );
+    assert_eq!(
+        pwm.update_with_tick(125),
+        Some((124, TimerEvent::RisingEdge))
+    );
+}
+
+/// A timer that gradually adjusts its period and phase to lock onto a
+/// stream of external events, like a phase-locked loop
+///
+/// Each external event is passed to `observe`, which measures how far
+/// it was from the nearest rising edge. A fraction of that error is
+/// corrected in the phase right away, and a smaller fraction is
+/// accumulated into the period, so that the timer ends up rising in
+/// step with the events even if their rate differs from the nominal
+/// period.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// // Lock a nominal 1000 tick frame onto a 1010 tick vsync
+/// let mut frame = PllTimer::new(1000, 0);
+/// for n in 0..500 {
+///     let vsync = 300 + n * 1010;
+///     while frame.update(vsync).is_some() {}
+///     frame.observe(vsync);
+/// }
+/// assert_eq!(frame.period(), 1010);
+/// assert!(frame.phase_error().abs() <= 5);
+/// ```
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct PllTimer {
+    timer: Timer,
+    // The period estimate, before rounding to whole ticks
+    period: f64,
+    // The part of the phase correction smaller than a tick, which is
+    // carried into the next one
+    phase: f64,
+    phase_gain: f64,
+    period_gain: f64,
+    error: i64,
+}
+
+impl PllTimer {
+    /// Create a new timer with a nominal `period`, which stays active
+    /// for `duration` ticks after each rising edge
+    pub fn new(period: u64, duration: u64) -> PllTimer {
+        PllTimer {
+            timer: Timer::new(period, 0, duration),
+            period: period as f64,
+            phase: 0.0,
+            phase_gain: 0.1,
+            period_gain: 0.01,
+            error: 0,
+        }
+    }
+
+    /// Set the fraction of each phase error that is corrected right
+    /// away, and the fraction that is added to the period. Larger
+    /// gains lock faster but follow jittery events more closely. The
+    /// defaults are 0.1 and 0.01
+    pub fn set_gains(&mut self, phase_gain: f64, period_gain: f64) {
+        assert!(phase_gain >= 0.0 && period_gain >= 0.0);
+        self.phase_gain = phase_gain;
+        self.period_gain = period_gain;
+    }
+
+    /// Feed the absolute tick of an external event, and adjust the
+    /// timer towards it. The timer should be updated up to the event
+    /// first, so the error is measured against its current schedule
+    pub fn observe(&mut self, tick: u64) {
+        let period = self.timer.period;
+        let since_start = self.timer.since_start(tick);
+        // Positive when the event came after the nearest rising edge
+        let error = if since_start <= period / 2 {
+            since_start as i64
+        } else {
+            since_start as i64 - period as i64
+        };
+        self.error = error;
+
+        let min_period = self.timer.duration.max(1) as f64;
+        self.period = (self.period + self.period_gain * error as f64).max(min_period);
+        let phase = self.phase + self.phase_gain * error as f64;
+        let shift = phase.round();
+        self.phase = phase - shift;
+        self.timer.shift_start(shift as i64);
+        self.timer
+            .set_period(self.period.round() as u64, ReconfigurePolicy::NextCycle);
+    }
+
+    /// Runs the timer until either the given absolute `time` is
+    /// reached, or until the next edge, like `Timer::update`
+    pub fn update(&mut self, time: u64) -> Option<TimerEvent> {
+        self.timer.update(time)
+    }
+
+    /// Like `update`, but also return the tick the edge occured on
+    pub fn update_with_tick(&mut self, time: u64) -> Option<(u64, TimerEvent)> {
+        self.timer.update_with_tick(time)
+    }
+
+    /// Get the next tick that will produce any `TimerEvent`
+    pub fn next_event_time(&self) -> u64 {
+        self.timer.next_event_time()
+    }
+
+    /// Return the current period in whole ticks
+    pub fn period(&self) -> u64 {
+        self.timer.period
+    }
+
+    /// Return how many ticks the last observed event came after the
+    /// nearest rising edge, or before it if negative
+    pub fn phase_error(&self) -> i64 {
+        self.error
+    }
+
+    /// Return the underlying timer
+    pub fn timer(&self) -> &Timer {
+        &self.timer
+    }
+}
+
+#[test]
+fn test_pll_timer() {
+    let mut pll = PllTimer::new(100, 10);
+    pll.set_gains(0.2, 0.02);
+
+    // Events every 97 ticks, a third of a period out of phase. The
+    // timer is run alongside them
+    let mut rising = vec![];
+    for n in 0..300 {
+        let event = 33 + n * 97;
+        while let Some((at, edge)) = pll.update_with_tick(event) {
+            if edge == TimerEvent::RisingEdge {
+                rising.push(at);
+            }
+        }
+        pll.observe(event);
+    }
+    assert_eq!(pll.period(), 97);
+    assert!(pll.phase_error().abs() <= 2);
+    let gaps: Vec<u64> = rising[rising.len() - 10..]
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .collect();
+    assert!(gaps.iter().all(|&g| (95..=99).contains(&g)));
+    assert!(pll.timer().is_armed());
+    assert!(pll.next_event_time() > 33 + 299 * 97);
 }
@@ -0,0 +1,186 @@
+use std::iter;
+use std::slice;
+
+/// A cycle counter with compare registers, like a hardware timer unit
+///
+/// The counter either runs freely or wraps back to 0 at a modulus.
+/// Each compare channel holds an optional value, and reports a match
+/// whenever the counter reaches that value.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut timer = FreeCounter::with_modulus(2, 1000);
+/// timer.set_compare(0, 100);
+/// timer.set_compare(1, 900);
+///
+/// let matches: Vec<_> = timer.advance(150).collect();
+/// assert_eq!(matches, vec![(0, 1)]);
+/// let matches: Vec<_> = timer.advance(2000).collect();
+/// assert_eq!(matches, vec![(0, 2), (1, 2)]);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct FreeCounter {
+    count: u64,
+    modulus: Option<u64>,
+    compares: Vec<Option<u64>>,
+    // How many times each channel matched during the last advance
+    matches: Vec<u64>,
+}
+
+impl FreeCounter {
+    /// Create a counter that starts at 0 and never wraps, with
+    /// `channels` compare channels that are all disabled
+    pub fn new(channels: usize) -> FreeCounter {
+        FreeCounter {
+            count: 0,
+            modulus: None,
+            compares: vec![None; channels],
+            matches: vec![0; channels],
+        }
+    }
+
+    /// Create a counter like `new` that wraps back to 0 when it
+    /// reaches `modulus`
+    pub fn with_modulus(channels: usize, modulus: u64) -> FreeCounter {
+        assert!(modulus > 0);
+        FreeCounter {
+            modulus: Some(modulus),
+            ..FreeCounter::new(channels)
+        }
+    }
+
+    /// Make `channel` match whenever the counter reaches `value`. With
+    /// a modulus, `value` must be below it
+    pub fn set_compare(&mut self, channel: usize, value: u64) {
+        assert!(self.modulus.map_or(true, |m| value < m));
+        self.compares[channel] = Some(value);
+    }
+
+    /// Disable `channel`
+    pub fn clear_compare(&mut self, channel: usize) {
+        self.compares[channel] = None;
+    }
+
+    /// Return the value of `channel`, or `None` if it is disabled
+    pub fn compare(&self, channel: usize) -> Option<u64> {
+        self.compares[channel]
+    }
+
+    /// Advance the counter by `n` ticks, and return the channels that
+    /// matched along with how many times they did. A channel matches
+    /// when the counter steps onto its value, so a match at the
+    /// current count was already reported
+    pub fn advance(&mut self, n: u64) -> Matches<'_> {
+        let start = u128::from(self.count);
+        let end = start + u128::from(n);
+        let modulus = self.modulus;
+
+        for (matches, compare) in self.matches.iter_mut().zip(self.compares.iter()) {
+            *matches = match *compare {
+                Some(value) => {
+                    let value = u128::from(value);
+                    // How many ticks in `0..=x` land on the value
+                    let hits = |x: u128| match modulus {
+                        _ if x < value => 0,
+                        Some(m) => (x - value) / u128::from(m) + 1,
+                        None => 1,
+                    };
+                    (hits(end) - hits(start)) as u64
+                }
+                None => 0,
+            };
+        }
+
+        self.count = match modulus {
+            Some(m) => (end % u128::from(m)) as u64,
+            None => {
+                assert!(end <= u128::from(u64::MAX), "counter overflow");
+                end as u64
+            }
+        };
+
+        Matches {
+            matches: self.matches.iter().enumerate(),
+        }
+    }
+
+    /// Set the current count. With a modulus, `count` must be below
+    /// it
+    pub fn set_count(&mut self, count: u64) {
+        assert!(self.modulus.map_or(true, |m| count < m));
+        self.count = count;
+    }
+
+    /// Return the current count
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Return the modulus, or `None` if the counter never wraps
+    pub fn modulus(&self) -> Option<u64> {
+        self.modulus
+    }
+
+    /// Return the number of compare channels
+    pub fn channels(&self) -> usize {
+        self.compares.len()
+    }
+}
+
+/// The channels that matched during a `FreeCounter::advance`
+pub struct Matches<'a> {
+    matches: iter::Enumerate<slice::Iter<'a, u64>>,
+}
+
+impl<'a> Iterator for Matches<'a> {
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<(usize, u64)> {
+        self.matches
+            .by_ref()
+            .find(|&(_, &m)| m > 0)
+            .map(|(i, &m)| (i, m))
+    }
+}
+
+#[test]
+fn test_free_counter() {
+    let mut c = FreeCounter::new(3);
+    assert_eq!(c.channels(), 3);
+    c.set_compare(0, 10);
+    c.set_compare(2, 10);
+    assert_eq!(c.advance(9).count(), 0);
+    assert_eq!(c.advance(1).collect::<Vec<_>>(), vec![(0, 1), (2, 1)]);
+    // Already at the value, so no new match
+    assert_eq!(c.advance(0).count(), 0);
+    assert_eq!(c.advance(1000).count(), 0);
+
+    c.clear_compare(2);
+    assert_eq!(c.compare(2), None);
+    c.set_count(0);
+    assert_eq!(c.advance(20).collect::<Vec<_>>(), vec![(0, 1)]);
+    assert_eq!(c.count(), 20);
+}
+
+#[test]
+fn test_free_counter_modulus() {
+    let mut c = FreeCounter::with_modulus(2, 4);
+    c.set_compare(0, 0);
+    c.set_compare(1, 3);
+
+    let mut by_tick = vec![];
+    for _ in 0..8 {
+        by_tick.push(c.advance(1).collect::<Vec<_>>());
+    }
+    assert_eq!(by_tick[2], vec![(1, 1)]);
+    assert_eq!(by_tick[3], vec![(0, 1)]);
+    assert_eq!(by_tick.iter().filter(|m| !m.is_empty()).count(), 4);
+
+    assert_eq!(c.count(), 0);
+    assert_eq!(c.advance(9).collect::<Vec<_>>(), vec![(0, 2), (1, 2)]);
+    assert_eq!(c.count(), 1);
+    assert_eq!(c.modulus(), Some(4));
+}
@@ -0,0 +1,272 @@
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::mem;
+
+use bloom::hash_pair;
+use error::Error;
+use rng::{JitterRng, XorShiftRng};
+
+const BUCKET_SIZE: usize = 4;
+// How many fingerprints an insert moves around before it gives up
+const MAX_KICKS: usize = 500;
+
+/// A probabilistic set that supports deletion, storing a 16 bit
+/// fingerprint of each item in one of two candidate buckets
+///
+/// Like a Bloom filter, `contains` never misses an item that was
+/// inserted, but may wrongly report items that weren't, here about
+/// 0.01% of them. It uses less space than a `CountingBloomFilter` for
+/// the same rate, and removing an item removes exactly one copy of its
+/// fingerprint.
+///
+/// When the buckets fill up, an insert moves existing fingerprints to
+/// their other bucket to make room. If that fails after 500 moves, the
+/// last fingerprint moved out is kept aside so nothing is lost, and the
+/// insert succeeds. Only once that spare slot is taken do inserts fail,
+/// with `Error::Full`, leaving the filter unchanged.
+///
+/// Only remove items that were inserted; removing anything else may
+/// forget an item with the same fingerprint.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut seen = CuckooFilter::with_capacity(1000);
+/// for word in "the quick brown fox".split(' ') {
+///     seen.insert(word);
+/// }
+/// assert!(seen.contains("quick"));
+///
+/// assert!(seen.remove("quick"));
+/// assert!(!seen.contains("quick"));
+/// assert_eq!(seen.len(), 3);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct CuckooFilter<T: ?Sized> {
+    // `BUCKET_SIZE` fingerprints per bucket, 0 for an empty entry
+    entries: Vec<u16>,
+    // The number of buckets - 1, which is a power of two - 1
+    mask: usize,
+    // A fingerprint that couldn't be placed, with one of its buckets
+    spare: Option<(usize, u16)>,
+    len: usize,
+    rng: XorShiftRng,
+    item: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized + Hash> CuckooFilter<T> {
+    /// Create an empty filter with room for about `items` items
+    pub fn with_capacity(items: usize) -> CuckooFilter<T> {
+        // Inserts start failing at around 95% occupancy
+        let buckets = (items as f64 / BUCKET_SIZE as f64 / 0.95).ceil() as usize;
+        let buckets = buckets.max(1).next_power_of_two();
+        CuckooFilter {
+            entries: vec![0; buckets * BUCKET_SIZE],
+            mask: buckets - 1,
+            spare: None,
+            len: 0,
+            rng: XorShiftRng::new(0x2545_f491_4f6c_dd1d),
+            item: PhantomData,
+        }
+    }
+
+    /// Add `item` to the filter. Returns false if the filter is full
+    pub fn insert(&mut self, item: &T) -> bool {
+        self.try_insert(item).is_ok()
+    }
+
+    /// Add `item` to the filter, or return `Error::Full` if the filter
+    /// is full
+    pub fn try_insert(&mut self, item: &T) -> Result<(), Error> {
+        if self.spare.is_some() {
+            return Err(Error::Full {
+                needed: 1,
+                available: 0,
+            });
+        }
+        let (i1, fp) = self.locate(item);
+        self.len += 1;
+        if self.put(i1, fp) || self.put(self.alt_bucket(i1, fp), fp) {
+            return Ok(());
+        }
+
+        let mut bucket = if self.rng.next_u64() & 1 == 0 {
+            i1
+        } else {
+            self.alt_bucket(i1, fp)
+        };
+        let mut fp = fp;
+        for _ in 0..MAX_KICKS {
+            let slot = bucket * BUCKET_SIZE + self.rng.next_u64() as usize % BUCKET_SIZE;
+            fp = mem::replace(&mut self.entries[slot], fp);
+            bucket = self.alt_bucket(bucket, fp);
+            if self.put(bucket, fp) {
+                return Ok(());
+            }
+        }
+        self.spare = Some((bucket, fp));
+        Ok(())
+    }
+
+    /// Remove one copy of `item` from the filter. Returns false if
+    /// `item` definitely isn't in it
+    pub fn remove(&mut self, item: &T) -> bool {
+        let (i1, fp) = self.locate(item);
+        let i2 = self.alt_bucket(i1, fp);
+
+        if let Some((bucket, spare)) = self.spare {
+            if spare == fp && (bucket == i1 || bucket == i2) {
+                self.spare = None;
+                self.len -= 1;
+                return true;
+            }
+        }
+        for &bucket in &[i1, i2] {
+            let entries = &mut self.entries[bucket * BUCKET_SIZE..][..BUCKET_SIZE];
+            if let Some(entry) = entries.iter_mut().find(|e| **e == fp) {
+                *entry = 0;
+                self.len -= 1;
+                // There may be room for the spare now
+                if let Some((bucket, spare)) = self.spare {
+                    if self.put(bucket, spare) || self.put(self.alt_bucket(bucket, spare), spare) {
+                        self.spare = None;
+                    }
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns true if `item` may be in the filter, or false if it
+    /// definitely isn't
+    pub fn contains(&self, item: &T) -> bool {
+        let (i1, fp) = self.locate(item);
+        let i2 = self.alt_bucket(i1, fp);
+        let in_bucket =
+            |bucket: usize| self.entries[bucket * BUCKET_SIZE..][..BUCKET_SIZE].contains(&fp);
+        let in_spare = match self.spare {
+            Some((bucket, spare)) => spare == fp && (bucket == i1 || bucket == i2),
+            None => false,
+        };
+        in_bucket(i1) || in_bucket(i2) || in_spare
+    }
+
+    /// Remove every item from the filter
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|e| *e = 0);
+        self.spare = None;
+        self.len = 0;
+    }
+
+    /// Return the number of items in the filter
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no items in the filter
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the max number of fingerprints the buckets can hold.
+    /// Inserts usually start failing a little before this
+    pub fn max_len(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Return the first bucket and the fingerprint of `item`
+    fn locate(&self, item: &T) -> (usize, u16) {
+        let (h1, h2) = hash_pair(item);
+        // 0 marks an empty entry, so it can't be a fingerprint
+        let fp = match (h2 >> 48) as u16 {
+            0 => 1,
+            fp => fp,
+        };
+        (h1 as usize & self.mask, fp)
+    }
+
+    // The other bucket `fp` can go in. Applying this twice gives back
+    // `bucket`, so it can be found from either one
+    fn alt_bucket(&self, bucket: usize, fp: u16) -> usize {
+        let h = u64::from(fp).wrapping_mul(0x5bd1_e995_5bd1_e995);
+        bucket ^ ((h >> 32) as usize & self.mask)
+    }
+
+    // Put `fp` in an empty entry of `bucket`, if there is one
+    fn put(&mut self, bucket: usize, fp: u16) -> bool {
+        let entries = &mut self.entries[bucket * BUCKET_SIZE..][..BUCKET_SIZE];
+        match entries.iter_mut().find(|e| **e == 0) {
+            Some(entry) => {
+                *entry = fp;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[test]
+fn test_cuckoo_filter() {
+    let mut filter = CuckooFilter::with_capacity(8);
+    assert!(filter.is_empty());
+    assert!(!filter.contains(&0));
+    assert!(!filter.remove(&0));
+
+    for i in 0..5 {
+        assert!(filter.insert(&i));
+    }
+    filter.insert(&3);
+    assert_eq!(filter.len(), 6);
+    assert!((0..5).all(|i| filter.contains(&i)));
+
+    assert!(filter.remove(&3));
+    assert!(filter.contains(&3));
+    assert!(filter.remove(&3));
+    assert!(!filter.contains(&3));
+
+    filter.clear();
+    assert!(filter.is_empty());
+    assert!(!filter.contains(&0));
+}
+
+#[test]
+fn test_cuckoo_filter_full() {
+    let mut filter = CuckooFilter::with_capacity(100);
+    let max_len = filter.max_len();
+
+    let mut inserted = 0u32;
+    while filter.try_insert(&inserted).is_ok() {
+        inserted += 1;
+    }
+    assert!(inserted as usize > max_len * 9 / 10);
+    assert!(inserted as usize <= max_len + 1);
+    assert_eq!(filter.len(), inserted as usize);
+    assert_eq!(
+        filter.try_insert(&inserted),
+        Err(Error::Full {
+            needed: 1,
+            available: 0
+        })
+    );
+
+    // Every inserted item is still found, including the spare one
+    assert!((0..inserted).all(|i| filter.contains(&i)));
+    for i in 0..inserted / 2 {
+        assert!(filter.remove(&i));
+    }
+    assert!((inserted / 2..inserted).all(|i| filter.contains(&i)));
+    assert!(filter.insert(&inserted));
+}
+
+#[test]
+fn test_cuckoo_filter_rate() {
+    let mut filter = CuckooFilter::with_capacity(1000);
+    for i in 0..1000u32 {
+        filter.insert(&i);
+    }
+    let false_positives = (1000..101_000u32).filter(|i| filter.contains(i)).count();
+    assert!(false_positives < 100, "{}", false_positives);
+}
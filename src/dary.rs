@@ -0,0 +1,254 @@
+use std::iter::FromIterator;
+use std::mem;
+use std::slice;
+
+/// A max-heap where each node has `D` children instead of two
+///
+/// It has the same API as `BinaryHeap`. A wider heap is shallower, so
+/// `push` does fewer comparisons, and `pop` looks at more children per
+/// level, but they sit next to each other in memory, which suits modern
+/// caches. An arity of 4 or 8 is often faster than 2 when keys are never
+/// changed in place. `D` must be at least 2.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut heap: DaryHeap<u32, 8> = vec![3, 1, 4, 1, 5].into();
+/// heap.push(9);
+/// assert_eq!(heap.peek(), Some(&9));
+/// assert_eq!(heap.pop(), Some(9));
+/// assert_eq!(heap.len(), 5);
+/// assert_eq!(heap.into_sorted_vec(), vec![1, 1, 3, 4, 5]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DaryHeap<T, const D: usize = 4> {
+    values: Vec<T>,
+}
+
+impl<T: Ord, const D: usize> Default for DaryHeap<T, D> {
+    fn default() -> DaryHeap<T, D> {
+        DaryHeap::new()
+    }
+}
+
+impl<T: Ord, const D: usize> DaryHeap<T, D> {
+    /// Create an empty heap
+    pub fn new() -> DaryHeap<T, D> {
+        DaryHeap::with_capacity(0)
+    }
+
+    /// Create an empty heap with room for `capacity` values before it
+    /// has to allocate
+    pub fn with_capacity(capacity: usize) -> DaryHeap<T, D> {
+        assert!(D >= 2, "heap arity must be at least 2");
+        DaryHeap {
+            values: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Add `value` to the heap
+    pub fn push(&mut self, value: T) {
+        self.values.push(value);
+        let index = self.values.len() - 1;
+        self.sift_up(index);
+    }
+
+    /// Remove the largest value and return it, or `None` if the heap is
+    /// empty
+    pub fn pop(&mut self) -> Option<T> {
+        let value = self.values.pop()?;
+        if self.values.is_empty() {
+            return Some(value);
+        }
+        let top = mem::replace(&mut self.values[0], value);
+        self.sift_down(0);
+        Some(top)
+    }
+
+    /// Return the largest value, or `None` if the heap is empty
+    pub fn peek(&self) -> Option<&T> {
+        self.values.first()
+    }
+
+    /// Move every value from `other` into this heap, leaving `other`
+    /// empty
+    pub fn append(&mut self, other: &mut DaryHeap<T, D>) {
+        if other.len() > self.len() {
+            mem::swap(self, other);
+        }
+        // Rebuilding is cheaper than pushing once `other` is large
+        if other.len() * 2 > self.len() {
+            self.values.append(&mut other.values);
+            self.rebuild();
+        } else {
+            for value in other.values.drain(..) {
+                self.push(value);
+            }
+        }
+    }
+
+    /// Iterate over the values in no particular order
+    pub fn iter(&self) -> DaryIter<'_, T> {
+        DaryIter {
+            iter: self.values.iter(),
+        }
+    }
+
+    /// Return the values in no particular order
+    pub fn into_vec(self) -> Vec<T> {
+        self.values
+    }
+
+    /// Return the values from smallest to largest
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut sorted = Vec::with_capacity(self.len());
+        while let Some(value) = self.pop() {
+            sorted.push(value);
+        }
+        sorted.reverse();
+        sorted
+    }
+
+    /// Remove every value from the heap
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Return the number of values in the heap
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if the heap is empty
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // Restore the heap order of the whole vector, in O(n)
+    fn rebuild(&mut self) {
+        if self.values.len() < 2 {
+            return;
+        }
+        let last_parent = (self.values.len() - 2) / D;
+        for index in (0..=last_parent).rev() {
+            self.sift_down(index);
+        }
+    }
+
+    fn sift_up(&mut self, mut index: usize) {
+        while index > 0 {
+            let parent = (index - 1) / D;
+            if self.values[index] <= self.values[parent] {
+                break;
+            }
+            self.values.swap(index, parent);
+            index = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut index: usize) {
+        loop {
+            let first = D * index + 1;
+            if first >= self.values.len() {
+                return;
+            }
+            let last = (first + D).min(self.values.len());
+            let mut largest = first;
+            for child in first + 1..last {
+                if self.values[child] > self.values[largest] {
+                    largest = child;
+                }
+            }
+            if self.values[largest] <= self.values[index] {
+                return;
+            }
+            self.values.swap(index, largest);
+            index = largest;
+        }
+    }
+}
+
+impl<T: Ord, const D: usize> From<Vec<T>> for DaryHeap<T, D> {
+    fn from(values: Vec<T>) -> DaryHeap<T, D> {
+        let mut heap = DaryHeap::new();
+        heap.values = values;
+        heap.rebuild();
+        heap
+    }
+}
+
+impl<T: Ord, const D: usize> FromIterator<T> for DaryHeap<T, D> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> DaryHeap<T, D> {
+        DaryHeap::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T: Ord, const D: usize> Extend<T> for DaryHeap<T, D> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+/// An iterator over the values of a `DaryHeap`, in no particular order
+pub struct DaryIter<'a, T: 'a> {
+    iter: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for DaryIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+}
+
+#[test]
+fn test_dary_heap() {
+    let mut heap = DaryHeap::<i32>::new();
+    assert!(heap.is_empty());
+    assert_eq!(heap.pop(), None);
+    assert_eq!(heap.peek(), None);
+
+    heap.extend(vec![5, -2, 8, 8, 0, 13, 7]);
+    assert_eq!(heap.len(), 7);
+    assert_eq!(heap.peek(), Some(&13));
+    assert_eq!(heap.iter().count(), 7);
+
+    let mut other: DaryHeap<i32> = (20..23).collect();
+    heap.append(&mut other);
+    assert!(other.is_empty());
+    assert_eq!(heap.pop(), Some(22));
+    assert_eq!(heap.pop(), Some(21));
+    assert_eq!(heap.clone().into_vec().len(), 8);
+    assert_eq!(heap.into_sorted_vec(), vec![-2, 0, 5, 7, 8, 8, 13, 20]);
+}
+
+#[test]
+fn test_dary_heap_arities() {
+    use rng::{JitterRng, XorShiftRng};
+
+    fn check<const D: usize>(values: &[u64]) {
+        let mut sorted = values.to_vec();
+        sorted.sort();
+        let mut heap: DaryHeap<u64, D> = values.iter().cloned().collect();
+        let mut pushed = DaryHeap::<u64, D>::new();
+        pushed.extend(values.iter().cloned());
+        while let Some(value) = heap.pop() {
+            assert_eq!(Some(value), sorted.pop());
+            assert_eq!(pushed.pop(), Some(value));
+        }
+        assert!(sorted.is_empty());
+    }
+
+    let mut rng = XorShiftRng::new(17);
+    let values: Vec<u64> = (0..1000).map(|_| rng.next_u64() % 500).collect();
+    check::<2>(&values);
+    check::<3>(&values);
+    check::<4>(&values);
+    check::<8>(&values);
+    check::<16>(&values[..10]);
+}
@@ -0,0 +1,52 @@
+use std::error;
+use std::fmt;
+
+/// The reason an operation on one of the collections failed
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Error {
+    /// There was not enough free space to add the values; `needed`
+    /// slots were required but only `available` were free
+    Full { needed: usize, available: usize },
+    /// There were not enough values to satisfy the request; `needed`
+    /// values were required but only `available` were present
+    Empty { needed: usize, available: usize },
+    /// A collection could not be created because its settings don't
+    /// make sense together; the value describes the problem
+    InvalidConfig(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Full { needed, available } => write!(
+                f,
+                "not enough room: needed {} slots but only {} available",
+                needed, available
+            ),
+            Error::Empty { needed, available } => write!(
+                f,
+                "not enough values: needed {} but only {} available",
+                needed, available
+            ),
+            Error::InvalidConfig(reason) => write!(f, "invalid configuration: {}", reason),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+#[test]
+fn test_error_display() {
+    let e = Error::Full {
+        needed: 3,
+        available: 1,
+    };
+    assert_eq!(
+        e.to_string(),
+        "not enough room: needed 3 slots but only 1 available"
+    );
+    assert_eq!(
+        Error::InvalidConfig("oops").to_string(),
+        "invalid configuration: oops"
+    );
+}
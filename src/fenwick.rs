@@ -0,0 +1,196 @@
+use std::ops::{Add, Range, Sub};
+
+/// A fixed length array that keeps running totals of its values, also
+/// known as a binary indexed tree
+///
+/// Changing a value and summing any prefix or range of the values are
+/// both O(log n), and it takes no more memory than the values
+/// themselves. As long as none of the values are negative, it can also
+/// find where the running total reaches a given amount, which makes it
+/// handy for ranking and for sampling from weighted sets.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut weights = FenwickTree::from_slice(&[5, 0, 2, 8, 1]);
+/// assert_eq!(weights.prefix_sum(3), 7);
+/// assert_eq!(weights.range_sum(2..5), 11);
+///
+/// weights.add(1, 4);
+/// assert_eq!(weights.total(), 20);
+/// // The running total first reaches 12 at index 3
+/// assert_eq!(weights.lower_bound(12), Some(3));
+/// assert_eq!(weights.lower_bound(21), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FenwickTree<T> {
+    // Node i (from 1) holds the sum of the `i & i.wrapping_neg()`
+    // values ending at index i - 1
+    tree: Vec<T>,
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> FenwickTree<T> {
+    /// Create a tree of `len` values, all set to the default (zero)
+    pub fn new(len: usize) -> FenwickTree<T> {
+        FenwickTree {
+            tree: vec![T::default(); len + 1],
+        }
+    }
+
+    /// Create a tree over a copy of `values`, in O(n)
+    pub fn from_slice(values: &[T]) -> FenwickTree<T> {
+        let mut tree = Vec::with_capacity(values.len() + 1);
+        tree.push(T::default());
+        tree.extend_from_slice(values);
+        for i in 1..tree.len() {
+            let parent = i + (i & i.wrapping_neg());
+            if parent < tree.len() {
+                tree[parent] = tree[parent] + tree[i];
+            }
+        }
+        FenwickTree { tree }
+    }
+
+    /// Add `delta` to the value at `index`
+    pub fn add(&mut self, index: usize, delta: T) {
+        assert!(index < self.len(), "index out of bounds");
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] = self.tree[i] + delta;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Set the value at `index` to `value`
+    pub fn set(&mut self, index: usize, value: T) {
+        let old = self.range_sum(index..index + 1);
+        // Every node that includes the value also includes the old one,
+        // so this can't go below zero even for unsigned types
+        let mut i = index + 1;
+        while i < self.tree.len() {
+            self.tree[i] = self.tree[i] - old + value;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    /// Return the value at `index`, or `None` if it is out of bounds
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < self.len() {
+            Some(self.range_sum(index..index + 1))
+        } else {
+            None
+        }
+    }
+
+    /// Return the sum of the first `len` values
+    pub fn prefix_sum(&self, len: usize) -> T {
+        assert!(len <= self.len(), "prefix out of bounds");
+        let mut sum = T::default();
+        let mut i = len;
+        while i > 0 {
+            sum = sum + self.tree[i];
+            i -= i & i.wrapping_neg();
+        }
+        sum
+    }
+
+    /// Return the sum of the values in `range`
+    pub fn range_sum(&self, range: Range<usize>) -> T {
+        if range.start >= range.end {
+            return T::default();
+        }
+        self.prefix_sum(range.end) - self.prefix_sum(range.start)
+    }
+
+    /// Return the sum of all the values
+    pub fn total(&self) -> T {
+        self.prefix_sum(self.len())
+    }
+
+    /// Return the smallest index where the running total, including the
+    /// value at that index, is at least `target`, or `None` if the total
+    /// of all the values is less than it. This assumes that none of the
+    /// values are negative
+    pub fn lower_bound(&self, target: T) -> Option<usize>
+    where
+        T: PartialOrd,
+    {
+        let len = self.len();
+        let mut pos = 0;
+        let mut remaining = target;
+        // The largest power of two no more than `len`
+        let mut step = (len + 1).next_power_of_two() / 2;
+        while step > 0 {
+            let next = pos + step;
+            if next <= len && self.tree[next] < remaining {
+                pos = next;
+                remaining = remaining - self.tree[next];
+            }
+            step /= 2;
+        }
+        if pos < len {
+            Some(pos)
+        } else {
+            None
+        }
+    }
+
+    /// Return the number of values in the tree
+    pub fn len(&self) -> usize {
+        self.tree.len() - 1
+    }
+
+    /// Returns true if the tree has no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[test]
+fn test_fenwick_tree() {
+    let values = [3u32, 0, 7, 1, 1, 0, 0, 9, 2, 4, 6, 5, 0];
+    let mut naive = values.to_vec();
+    let mut tree = FenwickTree::from_slice(&values);
+    assert_eq!(tree.len(), 13);
+
+    let mut other = FenwickTree::new(13);
+    for (index, &value) in values.iter().enumerate() {
+        other.add(index, value);
+    }
+    tree.add(4, 6);
+    other.add(4, 6);
+    naive[4] += 6;
+    tree.set(11, 0);
+    other.set(11, 0);
+    naive[11] = 0;
+
+    for end in 0..14 {
+        let expected: u32 = naive[..end].iter().sum();
+        assert_eq!(tree.prefix_sum(end), expected);
+        assert_eq!(other.prefix_sum(end), expected);
+    }
+    assert_eq!(tree.range_sum(2..5), 15);
+    assert_eq!(tree.range_sum(5..5), 0);
+    assert_eq!(tree.get(4), Some(7));
+    assert_eq!(tree.get(13), None);
+
+    for target in 0..tree.total() + 2 {
+        let expected = (0..naive.len()).find(|&i| tree.prefix_sum(i + 1) >= target);
+        assert_eq!(tree.lower_bound(target), expected);
+    }
+}
+
+#[test]
+fn test_fenwick_tree_empty() {
+    let tree: FenwickTree<i32> = FenwickTree::new(0);
+    assert!(tree.is_empty());
+    assert_eq!(tree.total(), 0);
+    assert_eq!(tree.lower_bound(0), None);
+
+    let mut one = FenwickTree::from_slice(&[2.5]);
+    one.add(0, 1.0);
+    assert_eq!(one.lower_bound(3.5), Some(0));
+    assert_eq!(one.lower_bound(3.6), None);
+}
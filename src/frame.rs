@@ -0,0 +1,124 @@
+use error::Error;
+use ring::RingBuffer;
+
+/// A FIFO buffer with fixed length that only ever adds or removes
+/// whole frames of `N` values at a time
+///
+/// Because every operation moves a complete frame or nothing at all,
+/// a reader can never observe a partially written frame.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// // Room for 4 stereo frames
+/// let mut frb = FrameRingBuffer::<i16, 2>::new(4, 0);
+/// frb.push_frame(&[1, -1]);
+/// frb.push_frame(&[2, -2]);
+///
+/// let mut frame = [0; 2];
+/// frb.pop_frame(&mut frame);
+/// assert_eq!(frame, [1, -1]);
+/// assert_eq!(frb.len(), 1);
+/// ```
+pub struct FrameRingBuffer<T: Clone, const N: usize> {
+    rb: RingBuffer<T>,
+}
+
+impl<T: Clone, const N: usize> FrameRingBuffer<T, N> {
+    /// Create a new buffer that can hold up to `frames` frames and
+    /// use `value` as the default value. `N` must not be 0
+    pub fn new(frames: usize, value: T) -> FrameRingBuffer<T, N> {
+        assert!(N > 0);
+
+        FrameRingBuffer {
+            rb: RingBuffer::new(frames * N, value),
+        }
+    }
+
+    /// Add `frame` to the end of the queue. Returns false if there is
+    /// not enough room in the queue
+    pub fn push_frame(&mut self, frame: &[T; N]) -> bool {
+        self.try_push_frame(frame).is_ok()
+    }
+
+    /// Add `frame` to the end of the queue, or return `Error::Full`
+    /// if there is not enough room in the queue. Sizes in the error
+    /// are counted in frames
+    pub fn try_push_frame(&mut self, frame: &[T; N]) -> Result<(), Error> {
+        self.rb.try_push_back_slice(frame).map_err(|_| Error::Full {
+            needed: 1,
+            available: 0,
+        })
+    }
+
+    /// Remove the first frame from the queue and copy it into
+    /// `frame`. If the queue is empty then `frame` is not modified and
+    /// the function returns false
+    pub fn pop_frame(&mut self, frame: &mut [T; N]) -> bool {
+        self.try_pop_frame(frame).is_ok()
+    }
+
+    /// Remove the first frame from the queue and copy it into
+    /// `frame`, or return `Error::Empty` if the queue is empty. Sizes
+    /// in the error are counted in frames
+    pub fn try_pop_frame(&mut self, frame: &mut [T; N]) -> Result<(), Error> {
+        self.rb
+            .try_pop_front_slice(frame)
+            .map_err(|_| Error::Empty {
+                needed: 1,
+                available: 0,
+            })
+    }
+
+    /// Returns the number of frames in the buffer
+    pub fn len(&self) -> usize {
+        self.rb.len() / N
+    }
+
+    /// Returns true if there are no frames in the buffer
+    pub fn is_empty(&self) -> bool {
+        self.rb.is_empty()
+    }
+
+    /// Returns the number of free frame slots in the buffer
+    pub fn capacity(&self) -> usize {
+        self.rb.capacity() / N
+    }
+
+    /// Returns the max number of frames that can ever be stored in
+    /// the buffer
+    pub fn max_len(&self) -> usize {
+        self.rb.max_len() / N
+    }
+}
+
+#[test]
+fn test_frames() {
+    let mut frb = FrameRingBuffer::<u8, 3>::new(2, 0);
+    let mut frame = [0u8; 3];
+
+    assert!(!frb.pop_frame(&mut frame));
+    assert!(frb.push_frame(&[1, 2, 3]));
+    assert!(frb.push_frame(&[4, 5, 6]));
+    assert_eq!(
+        frb.try_push_frame(&[7, 8, 9]),
+        Err(Error::Full {
+            needed: 1,
+            available: 0
+        })
+    );
+    assert_eq!(frb.len(), 2);
+    assert_eq!(frb.capacity(), 0);
+
+    assert!(frb.pop_frame(&mut frame));
+    assert_eq!(frame, [1, 2, 3]);
+    assert!(frb.push_frame(&[7, 8, 9]));
+    assert!(frb.pop_frame(&mut frame));
+    assert_eq!(frame, [4, 5, 6]);
+    assert!(frb.pop_frame(&mut frame));
+    assert_eq!(frame, [7, 8, 9]);
+    assert!(frb.is_empty());
+    assert_eq!(frb.max_len(), 2);
+}
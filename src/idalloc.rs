@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+/// The order an `IdAllocator` reuses freed IDs in
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum RecyclePolicy {
+    /// Reuse the most recently freed ID first, which keeps the IDs in
+    /// use as small as possible
+    Lifo,
+    /// Reuse the least recently freed ID first, which makes it less
+    /// likely that a stale ID refers to a new resource
+    Fifo,
+}
+
+/// Hands out small integer IDs starting at 0, and reuses the ones that
+/// are freed
+///
+/// The allocator tracks which IDs are live, so freeing an ID twice is
+/// caught instead of letting it be handed out to two owners.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut ids = IdAllocator::new();
+/// let a = ids.allocate();
+/// let b = ids.allocate();
+/// assert_eq!((a, b), (0, 1));
+///
+/// assert!(ids.free(a));
+/// assert!(!ids.free(a));
+/// assert_eq!(ids.allocate(), 0);
+/// assert_eq!(ids.high_water_mark(), 2);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct IdAllocator {
+    // Whether each ID handed out so far is live
+    live: Vec<bool>,
+    free: VecDeque<usize>,
+    policy: RecyclePolicy,
+    high_water: usize,
+}
+
+impl Default for IdAllocator {
+    fn default() -> IdAllocator {
+        IdAllocator::new()
+    }
+}
+
+impl IdAllocator {
+    /// Create an allocator that reuses the most recently freed ID first
+    pub fn new() -> IdAllocator {
+        IdAllocator::with_policy(RecyclePolicy::Lifo)
+    }
+
+    /// Create an allocator that reuses freed IDs in the order given by
+    /// `policy`
+    pub fn with_policy(policy: RecyclePolicy) -> IdAllocator {
+        IdAllocator {
+            live: vec![],
+            free: VecDeque::new(),
+            policy,
+            high_water: 0,
+        }
+    }
+
+    /// Return an ID that isn't live, reusing a freed one if there is
+    /// one
+    pub fn allocate(&mut self) -> usize {
+        let recycled = match self.policy {
+            RecyclePolicy::Lifo => self.free.pop_back(),
+            RecyclePolicy::Fifo => self.free.pop_front(),
+        };
+        let id = match recycled {
+            Some(id) => id,
+            None => {
+                self.live.push(false);
+                self.live.len() - 1
+            }
+        };
+        self.live[id] = true;
+        self.high_water = self.high_water.max(self.len());
+        id
+    }
+
+    /// Free `id` so it can be handed out again. Returns false if it
+    /// isn't live, e.g. because it was already freed
+    pub fn free(&mut self, id: usize) -> bool {
+        match self.live.get_mut(id) {
+            Some(live) if *live => {
+                *live = false;
+                self.free.push_back(id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if `id` has been allocated and not freed since
+    pub fn is_live(&self, id: usize) -> bool {
+        self.live.get(id).cloned().unwrap_or(false)
+    }
+
+    /// Return the number of live IDs
+    pub fn len(&self) -> usize {
+        self.live.len() - self.free.len()
+    }
+
+    /// Returns true if there are no live IDs
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the most IDs that have ever been live at once
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water
+    }
+
+    /// Return one more than the largest ID ever handed out, which is
+    /// the size a table indexed by these IDs needs to be
+    pub fn id_limit(&self) -> usize {
+        self.live.len()
+    }
+}
+
+#[test]
+fn test_id_allocator() {
+    let mut ids = IdAllocator::new();
+    assert!(ids.is_empty());
+    assert!(!ids.is_live(0));
+    assert!(!ids.free(0));
+
+    let all: Vec<_> = (0..4).map(|_| ids.allocate()).collect();
+    assert_eq!(all, vec![0, 1, 2, 3]);
+    assert!(ids.free(1));
+    assert!(ids.free(3));
+    assert!(!ids.free(3));
+    assert!(!ids.is_live(3));
+    assert_eq!(ids.len(), 2);
+
+    assert_eq!(ids.allocate(), 3);
+    assert_eq!(ids.allocate(), 1);
+    assert_eq!(ids.allocate(), 4);
+    assert_eq!(ids.high_water_mark(), 5);
+    assert_eq!(ids.id_limit(), 5);
+}
+
+#[test]
+fn test_id_allocator_fifo() {
+    let mut ids = IdAllocator::with_policy(RecyclePolicy::Fifo);
+    for _ in 0..4 {
+        ids.allocate();
+    }
+    for &id in &[2, 0, 3] {
+        ids.free(id);
+    }
+    let reused: Vec<_> = (0..4).map(|_| ids.allocate()).collect();
+    assert_eq!(reused, vec![2, 0, 3, 4]);
+    assert_eq!(ids.high_water_mark(), 5);
+    assert!(ids.is_live(1));
+}
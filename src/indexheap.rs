@@ -0,0 +1,329 @@
+use std::mem;
+
+use slotmap::{SlotKey, SlotMap};
+
+/// Identifies an entry pushed onto an `IndexedHeap`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct HeapHandle(SlotKey);
+
+#[derive(Debug, Clone)]
+struct Entry<P, T> {
+    priority: P,
+    value: T,
+    // Where the entry is in the heap
+    position: usize,
+}
+
+/// A priority queue that hands out a handle for each entry, so that any
+/// entry's priority can be changed, or the entry removed, in O(log n)
+///
+/// The entry with the smallest priority comes out first; wrap the
+/// priorities in `std::cmp::Reverse` to get the largest first. The
+/// entries are kept in a `SlotMap`, so a handle to an entry that has
+/// been popped or removed stays invalid, even once its slot is reused.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// // Dijkstra's shortest paths from node 0
+/// let edges = vec![vec![(1, 4), (2, 1)], vec![(3, 1)], vec![(1, 2), (3, 5)], vec![]];
+/// let mut dist = vec![u32::MAX; 4];
+/// let mut handles = vec![None; 4];
+/// let mut queue = IndexedHeap::new();
+/// dist[0] = 0;
+/// handles[0] = Some(queue.push(0, 0));
+///
+/// while let Some((d, node)) = queue.pop() {
+///     for &(next, weight) in &edges[node] {
+///         if d + weight < dist[next] {
+///             dist[next] = d + weight;
+///             match handles[next] {
+///                 Some(handle) if queue.contains(handle) => {
+///                     queue.decrease_key(handle, d + weight);
+///                 }
+///                 _ => handles[next] = Some(queue.push(d + weight, next)),
+///             }
+///         }
+///     }
+/// }
+/// assert_eq!(dist, vec![0, 3, 1, 4]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IndexedHeap<P, T> {
+    entries: SlotMap<Entry<P, T>>,
+    heap: Vec<SlotKey>,
+}
+
+impl<P: Ord, T> Default for IndexedHeap<P, T> {
+    fn default() -> IndexedHeap<P, T> {
+        IndexedHeap::new()
+    }
+}
+
+impl<P: Ord, T> IndexedHeap<P, T> {
+    /// Create an empty queue
+    pub fn new() -> IndexedHeap<P, T> {
+        IndexedHeap {
+            entries: SlotMap::new(),
+            heap: vec![],
+        }
+    }
+
+    /// Add `value` with `priority`, and return a handle to it
+    pub fn push(&mut self, priority: P, value: T) -> HeapHandle {
+        let position = self.heap.len();
+        let key = self.entries.insert(Entry {
+            priority,
+            value,
+            position,
+        });
+        self.heap.push(key);
+        self.sift_up(position);
+        HeapHandle(key)
+    }
+
+    /// Return the entry with the smallest priority, or `None` if the
+    /// queue is empty
+    pub fn peek(&self) -> Option<(&P, &T)> {
+        let entry = self.entry(*self.heap.first()?);
+        Some((&entry.priority, &entry.value))
+    }
+
+    /// Return the handle of the entry with the smallest priority, or
+    /// `None` if the queue is empty
+    pub fn peek_handle(&self) -> Option<HeapHandle> {
+        self.heap.first().map(|&key| HeapHandle(key))
+    }
+
+    /// Remove the entry with the smallest priority and return it, or
+    /// `None` if the queue is empty
+    pub fn pop(&mut self) -> Option<(P, T)> {
+        let key = *self.heap.first()?;
+        self.remove(HeapHandle(key))
+    }
+
+    /// Remove the entry identified by `handle` and return it, or `None`
+    /// if it was already removed
+    pub fn remove(&mut self, handle: HeapHandle) -> Option<(P, T)> {
+        let entry = self.entries.remove(handle.0)?;
+        let last = self.heap.pop().unwrap();
+        if entry.position < self.heap.len() {
+            // Fill the hole with the last entry, which may belong either
+            // above or below it
+            self.heap[entry.position] = last;
+            self.entry_mut(last).position = entry.position;
+            self.sift_up(entry.position);
+            let position = self.entry(last).position;
+            self.sift_down(position);
+        }
+        Some((entry.priority, entry.value))
+    }
+
+    /// Lower the priority of the entry identified by `handle` to
+    /// `priority`, and return true. If the entry was removed, or
+    /// `priority` is greater than its priority, this returns false and
+    /// changes nothing
+    pub fn decrease_key(&mut self, handle: HeapHandle, priority: P) -> bool {
+        let position = match self.entries.get_mut(handle.0) {
+            Some(entry) if priority <= entry.priority => {
+                entry.priority = priority;
+                entry.position
+            }
+            _ => return false,
+        };
+        self.sift_up(position);
+        true
+    }
+
+    /// Raise the priority of the entry identified by `handle` to
+    /// `priority`, and return true. If the entry was removed, or
+    /// `priority` is less than its priority, this returns false and
+    /// changes nothing
+    pub fn increase_key(&mut self, handle: HeapHandle, priority: P) -> bool {
+        let position = match self.entries.get_mut(handle.0) {
+            Some(entry) if priority >= entry.priority => {
+                entry.priority = priority;
+                entry.position
+            }
+            _ => return false,
+        };
+        self.sift_down(position);
+        true
+    }
+
+    /// Set the priority of the entry identified by `handle` to
+    /// `priority`, whether it is higher or lower, and return the old
+    /// one. Returns `None` if the entry was removed
+    pub fn set_priority(&mut self, handle: HeapHandle, priority: P) -> Option<P> {
+        let (old, position) = {
+            let entry = self.entries.get_mut(handle.0)?;
+            (mem::replace(&mut entry.priority, priority), entry.position)
+        };
+        self.sift_up(position);
+        let position = self.entry(handle.0).position;
+        self.sift_down(position);
+        Some(old)
+    }
+
+    /// Return the priority of the entry identified by `handle`, or
+    /// `None` if it was removed
+    pub fn priority(&self, handle: HeapHandle) -> Option<&P> {
+        self.entries.get(handle.0).map(|entry| &entry.priority)
+    }
+
+    /// Return the value of the entry identified by `handle`, or `None`
+    /// if it was removed
+    pub fn get(&self, handle: HeapHandle) -> Option<&T> {
+        self.entries.get(handle.0).map(|entry| &entry.value)
+    }
+
+    /// Return the value of the entry identified by `handle` for
+    /// modification, or `None` if it was removed
+    pub fn get_mut(&mut self, handle: HeapHandle) -> Option<&mut T> {
+        self.entries.get_mut(handle.0).map(|entry| &mut entry.value)
+    }
+
+    /// Returns true if the entry identified by `handle` is in the queue
+    pub fn contains(&self, handle: HeapHandle) -> bool {
+        self.entries.contains_key(handle.0)
+    }
+
+    /// Remove every entry from the queue. Handles to the removed
+    /// entries stay invalid
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.heap.clear();
+    }
+
+    /// Return the number of entries in the queue
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Returns true if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    fn entry(&self, key: SlotKey) -> &Entry<P, T> {
+        self.entries.get(key).unwrap()
+    }
+
+    fn entry_mut(&mut self, key: SlotKey) -> &mut Entry<P, T> {
+        self.entries.get_mut(key).unwrap()
+    }
+
+    fn less(&self, a: usize, b: usize) -> bool {
+        self.entry(self.heap[a]).priority < self.entry(self.heap[b]).priority
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        let (key_a, key_b) = (self.heap[a], self.heap[b]);
+        self.entry_mut(key_a).position = a;
+        self.entry_mut(key_b).position = b;
+    }
+
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if !self.less(position, parent) {
+                break;
+            }
+            self.swap(position, parent);
+            position = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let left = 2 * position + 1;
+            let right = left + 1;
+            let mut smallest = position;
+            if left < self.heap.len() && self.less(left, smallest) {
+                smallest = left;
+            }
+            if right < self.heap.len() && self.less(right, smallest) {
+                smallest = right;
+            }
+            if smallest == position {
+                break;
+            }
+            self.swap(position, smallest);
+            position = smallest;
+        }
+    }
+}
+
+#[test]
+fn test_indexed_heap() {
+    let mut heap = IndexedHeap::new();
+    assert!(heap.is_empty());
+    assert_eq!(heap.pop(), None);
+
+    let a = heap.push(50, 'a');
+    let b = heap.push(20, 'b');
+    let c = heap.push(30, 'c');
+    let d = heap.push(40, 'd');
+    assert_eq!(heap.peek(), Some((&20, &'b')));
+    assert_eq!(heap.peek_handle(), Some(b));
+    assert_eq!(heap.len(), 4);
+
+    assert!(heap.decrease_key(a, 10));
+    assert!(!heap.decrease_key(d, 45));
+    assert!(heap.increase_key(b, 35));
+    assert!(!heap.increase_key(c, 25));
+    assert_eq!(heap.set_priority(c, 5), Some(30));
+    assert_eq!(heap.priority(b), Some(&35));
+    *heap.get_mut(d).unwrap() = 'D';
+
+    assert_eq!(heap.remove(a), Some((10, 'a')));
+    assert_eq!(heap.remove(a), None);
+    assert!(!heap.contains(a));
+    assert!(!heap.decrease_key(a, 0));
+    assert_eq!(heap.pop(), Some((5, 'c')));
+    assert_eq!(heap.pop(), Some((35, 'b')));
+    assert_eq!(heap.get(d), Some(&'D'));
+
+    // The popped entries' slots are reused, but not their handles
+    let e = heap.push(1, 'e');
+    assert_eq!(heap.get(b), None);
+    assert_eq!(heap.set_priority(c, 0), None);
+    heap.clear();
+    assert!(!heap.contains(e));
+    assert!(heap.is_empty());
+}
+
+#[test]
+fn test_indexed_heap_order() {
+    use rng::{JitterRng, XorShiftRng};
+
+    let mut rng = XorShiftRng::new(5);
+    let mut heap = IndexedHeap::new();
+    let mut handles = vec![];
+    for i in 0..200 {
+        handles.push(heap.push(rng.next_u64() % 1000, i));
+    }
+    // Move and remove entries from all over the heap
+    for (i, &handle) in handles.iter().enumerate() {
+        match i % 4 {
+            0 => {
+                heap.remove(handle);
+            }
+            1 => {
+                heap.set_priority(handle, rng.next_u64() % 1000);
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(heap.len(), 150);
+
+    let mut last = 0;
+    while let Some((priority, value)) = heap.pop() {
+        assert!(priority >= last);
+        assert!(value % 4 != 0);
+        last = priority;
+    }
+}
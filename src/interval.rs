@@ -0,0 +1,289 @@
+use std::collections::btree_map;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A map from disjoint, half-open ranges of keys to values
+///
+/// Inserting a range overwrites whatever it overlaps, splitting the
+/// ranges at its ends as needed, and removing a range cuts it out the
+/// same way. Neighbouring ranges with equal values are merged, so the
+/// map always holds as few ranges as possible. Looking up a point is
+/// O(log n). Splitting a range copies its value, so values must be
+/// `Clone`.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut memory = IntervalMap::new();
+/// memory.insert(0x0000..0x8000, "rom");
+/// memory.insert(0x8000..0xa000, "vram");
+/// memory.insert(0xa000..0xc000, "ram");
+/// memory.insert(0x9000..0xb000, "ram");
+///
+/// assert_eq!(memory.get(&0x8fff), Some(&"vram"));
+/// assert_eq!(memory.get_interval(&0x9000), Some((0x9000..0xc000, &"ram")));
+///
+/// memory.remove(0x4000..0x5000);
+/// let regions: Vec<_> = memory.overlapping(0x3000..0x9000).collect();
+/// assert_eq!(
+///     regions,
+///     vec![(0x0000..0x4000, &"rom"), (0x5000..0x8000, &"rom"), (0x8000..0x9000, &"vram")]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct IntervalMap<K, V> {
+    // Each range's start, mapped to its end and value
+    map: BTreeMap<K, (K, V)>,
+}
+
+impl<K: Ord + Clone, V: Eq + Clone> Default for IntervalMap<K, V> {
+    fn default() -> IntervalMap<K, V> {
+        IntervalMap::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Eq + Clone> IntervalMap<K, V> {
+    /// Create an empty map
+    pub fn new() -> IntervalMap<K, V> {
+        IntervalMap {
+            map: BTreeMap::new(),
+        }
+    }
+
+    /// Map every key in `range` to `value`, replacing whatever it was
+    /// mapped to. Empty ranges are ignored
+    pub fn insert(&mut self, range: Range<K>, value: V) {
+        if range.start >= range.end {
+            return;
+        }
+        self.remove(range.clone());
+
+        let Range { mut start, mut end } = range;
+        let left = match self.map.range(..start.clone()).next_back() {
+            Some((left, (left_end, left_value))) if *left_end == start && *left_value == value => {
+                Some(left.clone())
+            }
+            _ => None,
+        };
+        if let Some(left) = left {
+            self.map.remove(&left);
+            start = left;
+        }
+        let right = match self.map.get(&end) {
+            Some((right_end, right_value)) if *right_value == value => Some(right_end.clone()),
+            _ => None,
+        };
+        if let Some(right_end) = right {
+            self.map.remove(&end);
+            end = right_end;
+        }
+        self.map.insert(start, (end, value));
+    }
+
+    /// Unmap every key in `range`, splitting any ranges that extend
+    /// past either end of it
+    pub fn remove(&mut self, range: Range<K>) {
+        let Range { start, end } = range;
+        if start >= end {
+            return;
+        }
+
+        // A range that starts before this one may overlap it, or even
+        // extend past its end
+        let mut tail = None;
+        if let Some((_, (left_end, value))) = self.map.range_mut(..start.clone()).next_back() {
+            if *left_end > end {
+                tail = Some((left_end.clone(), value.clone()));
+            }
+            if *left_end > start {
+                *left_end = start.clone();
+            }
+        }
+
+        let inside: Vec<K> = self
+            .map
+            .range(start..end.clone())
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in inside {
+            let (old_end, value) = self.map.remove(&key).unwrap();
+            if old_end > end {
+                tail = Some((old_end, value));
+            }
+        }
+        if let Some(tail) = tail {
+            self.map.insert(end, tail);
+        }
+    }
+
+    /// Return the value that `key` is mapped to, or `None` if it isn't
+    /// in any range
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_interval(key).map(|(_, value)| value)
+    }
+
+    /// Return the range holding `key` and its value, or `None` if it
+    /// isn't in any range
+    pub fn get_interval(&self, key: &K) -> Option<(Range<K>, &V)> {
+        let (start, (end, value)) = self.map.range(..=key).next_back()?;
+        if key < end {
+            Some((start.clone()..end.clone(), value))
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if `key` is in any range
+    pub fn contains(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Iterate over the ranges that overlap `range` and their values,
+    /// in order. The ranges aren't clipped to `range`
+    pub fn overlapping(&self, range: Range<K>) -> Intervals<'_, K, V> {
+        let first = match self.map.range(..range.start.clone()).next_back() {
+            Some((start, (end, _))) if *end > range.start => start.clone(),
+            _ => range.start,
+        };
+        Intervals {
+            iter: self.map.range(first..),
+            end: Some(range.end),
+        }
+    }
+
+    /// Iterate over every range and its value, in order
+    pub fn iter(&self) -> Intervals<'_, K, V> {
+        Intervals {
+            iter: self.map.range(..),
+            end: None,
+        }
+    }
+
+    /// Remove every range from the map
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    /// Return the number of ranges in the map
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if there are no ranges in the map
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// An iterator over the ranges of an `IntervalMap` and their values, in
+/// order
+pub struct Intervals<'a, K, V> {
+    iter: btree_map::Range<'a, K, (K, V)>,
+    // Stop at the first range starting at or after this
+    end: Option<K>,
+}
+
+impl<'a, K: Ord + Clone, V> Iterator for Intervals<'a, K, V> {
+    type Item = (Range<K>, &'a V);
+
+    fn next(&mut self) -> Option<(Range<K>, &'a V)> {
+        let (start, (end, value)) = self.iter.next()?;
+        match self.end {
+            Some(ref stop) if start >= stop => None,
+            _ => Some((start.clone()..end.clone(), value)),
+        }
+    }
+}
+
+#[test]
+fn test_interval_map() {
+    let mut map = IntervalMap::new();
+    assert!(map.is_empty());
+    map.insert(10..20, 'a');
+    map.insert(30..40, 'b');
+    map.insert(5..5, 'z');
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(&9), None);
+    assert_eq!(map.get(&10), Some(&'a'));
+    assert_eq!(map.get(&19), Some(&'a'));
+    assert_eq!(map.get(&20), None);
+
+    // Overwriting the middle of a range splits it
+    map.insert(12..15, 'c');
+    assert_eq!(
+        map.iter().collect::<Vec<_>>(),
+        vec![
+            (10..12, &'a'),
+            (12..15, &'c'),
+            (15..20, &'a'),
+            (30..40, &'b')
+        ]
+    );
+
+    // Covering several ranges replaces them
+    map.insert(14..35, 'd');
+    assert_eq!(
+        map.iter().collect::<Vec<_>>(),
+        vec![
+            (10..12, &'a'),
+            (12..14, &'c'),
+            (14..35, &'d'),
+            (35..40, &'b')
+        ]
+    );
+
+    map.remove(11..36);
+    assert_eq!(
+        map.iter().collect::<Vec<_>>(),
+        vec![(10..11, &'a'), (36..40, &'b')]
+    );
+    map.remove(0..100);
+    assert!(map.is_empty());
+
+    // Removing from inside a range leaves both ends
+    map.insert(0..10, 'e');
+    map.remove(3..4);
+    assert_eq!(map.get_interval(&2), Some((0..3, &'e')));
+    assert_eq!(map.get_interval(&4), Some((4..10, &'e')));
+    assert!(!map.contains(&3));
+    map.clear();
+    assert_eq!(map.iter().count(), 0);
+}
+
+#[test]
+fn test_interval_map_coalesces() {
+    let mut map = IntervalMap::new();
+    map.insert(0..10, 1);
+    map.insert(20..30, 1);
+    map.insert(10..20, 1);
+    assert_eq!(map.iter().collect::<Vec<_>>(), vec![(0..30, &1)]);
+
+    // Unequal neighbours stay apart
+    map.insert(30..40, 2);
+    map.insert(5..8, 3);
+    assert_eq!(map.len(), 4);
+    map.insert(5..8, 1);
+    assert_eq!(
+        map.iter().collect::<Vec<_>>(),
+        vec![(0..30, &1), (30..40, &2)]
+    );
+
+    // Overlapping a range with its own value just grows it
+    map.insert(25..35, 2);
+    assert_eq!(
+        map.iter().collect::<Vec<_>>(),
+        vec![(0..25, &1), (25..40, &2)]
+    );
+
+    assert_eq!(
+        map.overlapping(24..25).collect::<Vec<_>>(),
+        vec![(0..25, &1)]
+    );
+    assert_eq!(
+        map.overlapping(10..30).collect::<Vec<_>>(),
+        vec![(0..25, &1), (25..40, &2)]
+    );
+    assert_eq!(map.overlapping(40..50).count(), 0);
+}
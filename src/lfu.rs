@@ -0,0 +1,402 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::mem;
+
+use cache::Cache;
+
+// Marks the end of a frequency list
+const NIL: usize = usize::MAX;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    uses: u64,
+    // Toward the most recently used entry with the same count
+    prev: usize,
+    // Toward the least recently used entry with the same count
+    next: usize,
+}
+
+/// A map that holds up to a fixed number of entries, and evicts the
+/// least frequently used one to make room for a new one
+///
+/// Entries used equally often are evicted least recently used first.
+/// Unlike an `LruCache`, a scan over many keys that are each used once
+/// doesn't push out entries that are used all the time. Looking up,
+/// inserting and evicting an entry are all O(log c), where c is the
+/// number of distinct use counts, by keeping a list of entries for each
+/// use count.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut cache = LfuCache::new(2);
+/// cache.put("hot", 1);
+/// cache.get("hot");
+/// cache.get("hot");
+///
+/// // A scan only ever evicts the other scanned keys
+/// for &key in &["a", "b", "c"] {
+///     cache.put(key, 0);
+/// }
+/// assert!(cache.contains_key("hot"));
+/// assert_eq!(cache.uses("hot"), Some(3));
+/// ```
+pub struct LfuCache<K, V> {
+    map: HashMap<K, usize>,
+    nodes: Vec<Option<Node<K, V>>>,
+    // Slots of removed nodes, ready for reuse
+    free: Vec<usize>,
+    // The most and least recently used entries of each use count
+    lists: BTreeMap<u64, (usize, usize)>,
+    // The smallest key of `lists`
+    min_uses: u64,
+    size: usize,
+    on_evict: Option<Box<dyn FnMut(K, V)>>,
+}
+
+impl<K: Hash + Eq + Clone, V> LfuCache<K, V> {
+    /// Create an empty cache that holds up to `size` entries
+    pub fn new(size: usize) -> LfuCache<K, V> {
+        assert!(size > 0);
+        LfuCache {
+            map: HashMap::with_capacity(size),
+            nodes: Vec::with_capacity(size),
+            free: vec![],
+            lists: BTreeMap::new(),
+            min_uses: 0,
+            size,
+            on_evict: None,
+        }
+    }
+
+    /// Pass each entry that is evicted to make room for a new one to
+    /// `callback`. Entries that are removed or replaced explicitly
+    /// aren't passed to it
+    pub fn on_evict<F: FnMut(K, V) + 'static>(&mut self, callback: F) {
+        self.on_evict = Some(Box::new(callback));
+    }
+
+    /// Insert `value` under `key`, and return the value it replaced, if
+    /// any. Replacing a value counts as a use of it. If the cache is
+    /// full, the least frequently used entry is evicted first
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&index) = self.map.get(&key) {
+            self.touch(index);
+            let node = self.nodes[index].as_mut().unwrap();
+            return Some(mem::replace(&mut node.value, value));
+        }
+
+        if self.map.len() == self.size {
+            if let Some((old_key, old_value)) = self.pop_lfu() {
+                if let Some(ref mut callback) = self.on_evict {
+                    callback(old_key, old_value);
+                }
+            }
+        }
+
+        let node = Node {
+            key: key.clone(),
+            value,
+            uses: 1,
+            prev: NIL,
+            next: NIL,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.push_front(index);
+        self.min_uses = 1;
+        self.map.insert(key, index);
+        None
+    }
+
+    /// Return the value under `key` and count a use of it, or `None` if
+    /// it isn't in the cache
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// Return the value under `key` for modification and count a use
+    /// of it, or `None` if it isn't in the cache
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let index = *self.map.get(key)?;
+        self.touch(index);
+        self.nodes[index].as_mut().map(|node| &mut node.value)
+    }
+
+    /// Return the value under `key` without counting a use of it, or
+    /// `None` if it isn't in the cache
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let index = *self.map.get(key)?;
+        self.nodes[index].as_ref().map(|node| &node.value)
+    }
+
+    /// Return how many times the entry under `key` has been used, or
+    /// `None` if it isn't in the cache
+    pub fn uses<Q>(&self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let index = *self.map.get(key)?;
+        self.nodes[index].as_ref().map(|node| node.uses)
+    }
+
+    /// Returns true if there is an entry under `key`
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Remove the entry under `key` and return its value, or `None` if
+    /// it isn't in the cache
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let index = self.map.remove(key)?;
+        Some(self.take_node(index).1)
+    }
+
+    /// Remove the least frequently used entry and return it, or `None`
+    /// if the cache is empty
+    pub fn pop_lfu(&mut self) -> Option<(K, V)> {
+        if self.map.is_empty() {
+            return None;
+        }
+        let (_, tail) = self.lists[&self.min_uses];
+        let (key, value) = self.take_node(tail);
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    /// Remove every entry from the cache
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.lists.clear();
+        self.min_uses = 0;
+    }
+
+    /// Return the number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if there are no entries in the cache
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the max number of entries the cache holds
+    pub fn max_len(&self) -> usize {
+        self.size
+    }
+
+    // Count a use of a node, moving it to the front of the next list
+    fn touch(&mut self, index: usize) {
+        let uses = self.nodes[index].as_ref().unwrap().uses;
+        self.unlink(index);
+        if uses == self.min_uses && !self.lists.contains_key(&uses) {
+            self.min_uses += 1;
+        }
+        self.nodes[index].as_mut().unwrap().uses += 1;
+        self.push_front(index);
+    }
+
+    fn take_node(&mut self, index: usize) -> (K, V) {
+        self.unlink(index);
+        if !self.lists.contains_key(&self.min_uses) {
+            self.min_uses = self.lists.keys().next().cloned().unwrap_or(0);
+        }
+        self.free.push(index);
+        let node = self.nodes[index].take().unwrap();
+        (node.key, node.value)
+    }
+
+    // Remove a node from the list for its use count, dropping the list
+    // if it becomes empty
+    fn unlink(&mut self, index: usize) {
+        let (uses, prev, next) = {
+            let node = self.nodes[index].as_ref().unwrap();
+            (node.uses, node.prev, node.next)
+        };
+        if prev != NIL {
+            self.nodes[prev].as_mut().unwrap().next = next;
+        }
+        if next != NIL {
+            self.nodes[next].as_mut().unwrap().prev = prev;
+        }
+        match (prev, next) {
+            (NIL, NIL) => {
+                self.lists.remove(&uses);
+            }
+            (NIL, next) => self.lists.get_mut(&uses).unwrap().0 = next,
+            (prev, NIL) => self.lists.get_mut(&uses).unwrap().1 = prev,
+            _ => {}
+        }
+    }
+
+    // Add a node to the front of the list for its use count
+    fn push_front(&mut self, index: usize) {
+        let uses = self.nodes[index].as_ref().unwrap().uses;
+        let head = match self.lists.get_mut(&uses) {
+            Some(list) => mem::replace(&mut list.0, index),
+            None => {
+                self.lists.insert(uses, (index, index));
+                NIL
+            }
+        };
+        if head != NIL {
+            self.nodes[head].as_mut().unwrap().prev = index;
+        }
+        let node = self.nodes[index].as_mut().unwrap();
+        node.prev = NIL;
+        node.next = head;
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for LfuCache<K, V> {
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        LfuCache::put(self, key, value)
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        LfuCache::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        LfuCache::get_mut(self, key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        LfuCache::peek(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        LfuCache::remove(self, key)
+    }
+
+    fn on_evict<F: FnMut(K, V) + 'static>(&mut self, callback: F) {
+        LfuCache::on_evict(self, callback)
+    }
+
+    fn clear(&mut self) {
+        LfuCache::clear(self)
+    }
+
+    fn len(&self) -> usize {
+        LfuCache::len(self)
+    }
+
+    fn max_len(&self) -> usize {
+        LfuCache::max_len(self)
+    }
+}
+
+#[test]
+fn test_lfu_cache() {
+    let mut cache = LfuCache::new(3);
+    assert_eq!(cache.pop_lfu(), None);
+
+    cache.put('a', 1);
+    cache.put('b', 2);
+    cache.put('c', 3);
+    cache.get(&'a');
+    cache.get(&'a');
+    cache.get(&'b');
+    assert_eq!(cache.put('c', 30), Some(3));
+    assert_eq!(cache.peek(&'c'), Some(&30));
+    assert_eq!(
+        (cache.uses(&'a'), cache.uses(&'b'), cache.uses(&'c')),
+        (Some(3), Some(2), Some(2))
+    );
+
+    // 'b' and 'c' tie, and 'b' was used longer ago
+    cache.put('d', 4);
+    assert!(!cache.contains_key(&'b'));
+    assert_eq!(cache.pop_lfu(), Some(('d', 4)));
+    assert_eq!(cache.pop_lfu(), Some(('c', 30)));
+
+    assert_eq!(cache.remove(&'a'), Some(1));
+    assert!(cache.is_empty());
+    cache.put('e', 5);
+    *cache.get_mut(&'e').unwrap() += 1;
+    assert_eq!(cache.pop_lfu(), Some(('e', 6)));
+}
+
+#[test]
+fn test_lfu_cache_removed_minimum() {
+    let mut cache = LfuCache::new(2);
+    cache.put(1, ());
+    cache.put(2, ());
+    cache.get(&2);
+    // The only entry used once goes, so the minimum count is stale
+    cache.remove(&1);
+    cache.get(&2);
+    cache.put(3, ());
+    cache.get(&3);
+    cache.put(4, ());
+    assert!(cache.contains_key(&2));
+    assert!(!cache.contains_key(&3));
+    assert_eq!(cache.len(), 2);
+
+    let mut evicted = vec![];
+    cache.clear();
+    for i in 0..4 {
+        cache.put(i, ());
+    }
+    while let Some((key, _)) = cache.pop_lfu() {
+        evicted.push(key);
+    }
+    assert_eq!(evicted, vec![2, 3]);
+}
+
+#[test]
+fn test_lfu_cache_remove_minimum() {
+    let mut cache = LfuCache::new(3);
+    cache.put("hot", 0);
+    for _ in 0..1000 {
+        cache.get("hot");
+    }
+    cache.put("warm", 0);
+    cache.get("warm");
+    cache.put("cold", 0);
+
+    // Removing the only least used entry moves the minimum straight to
+    // the next count in use
+    cache.remove("cold");
+    assert_eq!(cache.min_uses, 2);
+    assert_eq!(cache.pop_lfu(), Some(("warm", 0)));
+    assert_eq!(cache.min_uses, 1001);
+    assert_eq!(cache.pop_lfu(), Some(("hot", 0)));
+    assert_eq!(cache.pop_lfu(), None);
+}
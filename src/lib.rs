@@ -1,8 +1,125 @@
 //! A collection of misc. data structures that aren't available in the
 //! standard library
 
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(feature = "async")]
+extern crate futures_core;
+
+mod aligned;
+mod arc;
+mod arrayvec;
+mod bitvec;
+mod bloom;
+mod cache;
+mod calendar;
 mod clock;
+mod counter;
+mod cuckoo;
+mod dary;
+mod error;
+mod fenwick;
+mod frame;
+mod idalloc;
+mod indexheap;
+mod interval;
+mod lfu;
+mod lru;
+mod minmax;
+mod minmaxheap;
+mod multimap;
+mod objpool;
+mod piece;
+mod pool;
+mod queue;
+mod radix;
+mod radixheap;
+mod recorder;
+mod resample;
 mod ring;
+mod rng;
+mod rope;
+mod schedule;
+mod scheduler;
+mod segtree;
+mod slotmap;
+mod smallvec;
+mod sorted;
+mod sortedvec;
+mod splay;
+mod spsc;
+#[cfg(feature = "async")]
+mod stream;
+mod timed;
+mod treap;
+mod trie;
+mod ttl;
+mod vecmap;
+mod watchdog;
+mod wheel;
 
-pub use clock::{next_timer_event, Clock, Timer, TimerEvent};
-pub use ring::{ElasticPopResult, ElasticRingBuffer, RingBuffer};
+pub use aligned::AlignedBuffer;
+pub use arc::ArcCache;
+pub use arrayvec::ArrayVec;
+pub use bitvec::{BitVec, Bits};
+pub use bloom::CountingBloomFilter;
+pub use cache::Cache;
+pub use calendar::CalendarQueue;
+pub use clock::{
+    next_timer_event, CallbackClock, Clock, ClockChain, ClockDomain, ClockId, Countdown, Counter,
+    Edges, JitteredEdges, JitteredTimer, MissedEventPolicy, MultiPhaseClock, PhaseEdges, PllTimer,
+    Pwm, RationalClock, RealTimer, ReconfigurePolicy, SquareWaveClock, Timer, TimerBuilder,
+    TimerEvent, WallClock, Wrapped,
+};
+pub use counter::{FreeCounter, Matches};
+pub use cuckoo::CuckooFilter;
+pub use dary::{DaryHeap, DaryIter};
+pub use error::Error;
+pub use fenwick::FenwickTree;
+pub use frame::FrameRingBuffer;
+pub use idalloc::{IdAllocator, RecyclePolicy};
+pub use indexheap::{HeapHandle, IndexedHeap};
+pub use interval::{IntervalMap, Intervals};
+pub use lfu::LfuCache;
+pub use lru::{LruCache, LruIter};
+pub use minmax::MinMaxRingBuffer;
+pub use minmaxheap::{MinMaxHeap, MinMaxIter};
+pub use multimap::{MultiMap, MultiMapGroups, MultiMapIter, ValueGroup};
+pub use objpool::{ObjectPool, Pooled};
+pub use piece::{PieceSnapshot, PieceTable, Pieces};
+pub use pool::{TimerEvents, TimerId, TimerMut, TimerPool};
+pub use queue::{Callback, EventHandle, EventQueue};
+pub use radix::{RadixIter, RadixTree};
+pub use radixheap::RadixHeap;
+pub use recorder::{EventRecord, EventRecorder, Recorded};
+pub use resample::{
+    Crossfade, HoldLast, Lerp, Linear, NearestNeighbor, ResamplePolicy, Sample, Samples, Sinc,
+    Wsola,
+};
+pub use ring::{
+    ElasticEvent, ElasticEventKind, ElasticPopResult, ElasticPushResult, ElasticRingBuffer,
+    ElasticRingBufferBuilder, ElasticStats, Iter, Residency, RingBuffer, Windows,
+};
+pub use rng::{JitterRng, XorShiftRng};
+pub use rope::{Chunks, Rope};
+pub use schedule::Schedule;
+pub use scheduler::{ComponentId, EventScheduler};
+pub use segtree::{Max, Min, SegmentOp, SegmentTree, Sum};
+pub use slotmap::{SlotKey, SlotMap, Slots};
+pub use smallvec::SmallVec;
+pub use sorted::{SortedIter, SortedList};
+pub use sortedvec::{SortedVec, SortedVecMap, SortedVecMapIter};
+pub use splay::{SplayIter, SplayTree};
+pub use spsc::{
+    elastic_spsc_ring_buffer, elastic_spsc_ring_buffer_with_policy, spsc_ring_buffer,
+    ElasticSpscConsumer, SpscConsumer, SpscProducer,
+};
+#[cfg(feature = "async")]
+pub use stream::{ManualTicks, TickSource, TimerStream};
+pub use timed::TimedRingBuffer;
+pub use treap::{Treap, TreapIter};
+pub use trie::{Trie, TrieIter};
+pub use ttl::TtlCache;
+pub use vecmap::{VecMap, VecMapEntry, VecMapIter, VecMapIterMut};
+pub use watchdog::{Timeout, Watchdog};
+pub use wheel::{DeadlineId, Expired, TimerWheel};
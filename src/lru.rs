@@ -0,0 +1,378 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+
+use cache::Cache;
+
+// Marks the end of the recency list
+const NIL: usize = usize::MAX;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // Toward the most recently used entry
+    prev: usize,
+    // Toward the least recently used entry
+    next: usize,
+}
+
+/// A map that holds up to a fixed number of entries, and evicts the
+/// least recently used one to make room for a new one
+///
+/// Looking up, inserting and evicting an entry are all O(1). Evicted
+/// entries are dropped, or passed to the callback set with `on_evict`.
+/// It implements `Cache`, so it can be swapped for the other caches.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut cache = LruCache::new(2);
+/// cache.put("a", 1);
+/// cache.put("b", 2);
+/// assert_eq!(cache.get("a"), Some(&1));
+///
+/// // "b" is now the least recently used
+/// cache.put("c", 3);
+/// assert_eq!(cache.get("b"), None);
+/// let keys: Vec<_> = cache.iter().map(|(k, _)| *k).collect();
+/// assert_eq!(keys, vec!["c", "a"]);
+/// ```
+pub struct LruCache<K, V> {
+    map: HashMap<K, usize>,
+    nodes: Vec<Option<Node<K, V>>>,
+    // Slots of removed nodes, ready for reuse
+    free: Vec<usize>,
+    // The most and least recently used nodes
+    head: usize,
+    tail: usize,
+    size: usize,
+    on_evict: Option<Box<dyn FnMut(K, V)>>,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// Create an empty cache that holds up to `size` entries
+    pub fn new(size: usize) -> LruCache<K, V> {
+        assert!(size > 0);
+        LruCache {
+            map: HashMap::with_capacity(size),
+            nodes: Vec::with_capacity(size),
+            free: vec![],
+            head: NIL,
+            tail: NIL,
+            size,
+            on_evict: None,
+        }
+    }
+
+    /// Pass each entry that is evicted to make room for a new one to
+    /// `callback`. Entries that are removed or replaced explicitly
+    /// aren't passed to it
+    pub fn on_evict<F: FnMut(K, V) + 'static>(&mut self, callback: F) {
+        self.on_evict = Some(Box::new(callback));
+    }
+
+    /// Insert `value` under `key` as the most recently used entry, and
+    /// return the value it replaced, if any. If the cache is full, the
+    /// least recently used entry is evicted first
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&index) = self.map.get(&key) {
+            self.touch(index);
+            let node = self.nodes[index].as_mut().unwrap();
+            return Some(mem::replace(&mut node.value, value));
+        }
+
+        if self.map.len() == self.size {
+            if let Some((old_key, old_value)) = self.pop_lru() {
+                if let Some(ref mut callback) = self.on_evict {
+                    callback(old_key, old_value);
+                }
+            }
+        }
+
+        let node = Node {
+            key: key.clone(),
+            value,
+            prev: NIL,
+            next: NIL,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.push_front(index);
+        self.map.insert(key, index);
+        None
+    }
+
+    /// Return the value under `key` and mark it as the most recently
+    /// used entry, or `None` if it isn't in the cache
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// Return the value under `key` for modification and mark it as
+    /// the most recently used entry, or `None` if it isn't in the cache
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let index = *self.map.get(key)?;
+        self.touch(index);
+        self.nodes[index].as_mut().map(|node| &mut node.value)
+    }
+
+    /// Return the value under `key` without changing how recently it
+    /// was used, or `None` if it isn't in the cache
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let index = *self.map.get(key)?;
+        self.nodes[index].as_ref().map(|node| &node.value)
+    }
+
+    /// Returns true if there is an entry under `key`
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Remove the entry under `key` and return its value, or `None` if
+    /// it isn't in the cache
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let index = self.map.remove(key)?;
+        Some(self.take_node(index).1)
+    }
+
+    /// Remove the least recently used entry and return it, or `None` if
+    /// the cache is empty
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        let (key, value) = self.take_node(self.tail);
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    /// Iterate over the entries, from the most recently used one
+    pub fn iter(&self) -> LruIter<'_, K, V> {
+        LruIter {
+            cache: self,
+            index: self.head,
+        }
+    }
+
+    /// Remove every entry from the cache
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.head = NIL;
+        self.tail = NIL;
+    }
+
+    /// Return the number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if there are no entries in the cache
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the max number of entries the cache holds
+    pub fn max_len(&self) -> usize {
+        self.size
+    }
+
+    // Move a node to the front of the list
+    fn touch(&mut self, index: usize) {
+        if self.head != index {
+            self.unlink(index);
+            self.push_front(index);
+        }
+    }
+
+    fn take_node(&mut self, index: usize) -> (K, V) {
+        self.unlink(index);
+        self.free.push(index);
+        let node = self.nodes[index].take().unwrap();
+        (node.key, node.value)
+    }
+
+    fn unlink(&mut self, index: usize) {
+        let (prev, next) = {
+            let node = self.nodes[index].as_ref().unwrap();
+            (node.prev, node.next)
+        };
+        match prev {
+            NIL => self.head = next,
+            prev => self.nodes[prev].as_mut().unwrap().next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.nodes[next].as_mut().unwrap().prev = prev,
+        }
+    }
+
+    fn push_front(&mut self, index: usize) {
+        {
+            let node = self.nodes[index].as_mut().unwrap();
+            node.prev = NIL;
+            node.next = self.head;
+        }
+        match self.head {
+            NIL => self.tail = index,
+            head => self.nodes[head].as_mut().unwrap().prev = index,
+        }
+        self.head = index;
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> for LruCache<K, V> {
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        LruCache::put(self, key, value)
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        LruCache::get(self, key)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        LruCache::get_mut(self, key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        LruCache::peek(self, key)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        LruCache::remove(self, key)
+    }
+
+    fn on_evict<F: FnMut(K, V) + 'static>(&mut self, callback: F) {
+        LruCache::on_evict(self, callback)
+    }
+
+    fn clear(&mut self) {
+        LruCache::clear(self)
+    }
+
+    fn len(&self) -> usize {
+        LruCache::len(self)
+    }
+
+    fn max_len(&self) -> usize {
+        LruCache::max_len(self)
+    }
+}
+
+impl<K: Hash + Eq + Clone + fmt::Debug, V: fmt::Debug> fmt::Debug for LruCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the entries of an `LruCache`, from the most
+/// recently used one
+pub struct LruIter<'a, K, V> {
+    cache: &'a LruCache<K, V>,
+    index: usize,
+}
+
+impl<'a, K, V> Iterator for LruIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        if self.index == NIL {
+            return None;
+        }
+        let node = self.cache.nodes[self.index].as_ref().unwrap();
+        self.index = node.next;
+        Some((&node.key, &node.value))
+    }
+}
+
+#[test]
+fn test_lru_cache() {
+    let mut cache = LruCache::new(3);
+    assert!(cache.is_empty());
+    assert_eq!(cache.pop_lru(), None);
+    assert_eq!(cache.max_len(), 3);
+
+    for i in 0..3 {
+        assert_eq!(cache.put(i, i * 10), None);
+    }
+    assert_eq!(cache.put(1, 11), Some(10));
+    *cache.get_mut(&0).unwrap() += 1;
+    assert_eq!(cache.peek(&2), Some(&20));
+    assert_eq!(format!("{:?}", cache), "{0: 1, 1: 11, 2: 20}");
+
+    // The peek didn't save 2 from eviction
+    cache.put(3, 30);
+    assert!(!cache.contains_key(&2));
+    assert_eq!(cache.remove(&0), Some(1));
+    assert_eq!(cache.remove(&0), None);
+    cache.put(4, 40);
+    assert_eq!(
+        cache.iter().collect::<Vec<_>>(),
+        vec![(&4, &40), (&3, &30), (&1, &11)]
+    );
+    assert_eq!(cache.pop_lru(), Some((1, 11)));
+    assert_eq!(cache.len(), 2);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!(cache.get(&4), None);
+    cache.put(5, 50);
+    assert_eq!(cache.iter().count(), 1);
+}
+
+#[test]
+fn test_lru_cache_on_evict() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let evicted = Rc::new(RefCell::new(vec![]));
+    let mut cache = LruCache::new(2);
+    {
+        let evicted = evicted.clone();
+        cache.on_evict(move |k: String, v| evicted.borrow_mut().push((k, v)));
+    }
+    cache.put("a".to_string(), 1);
+    cache.put("b".to_string(), 2);
+    cache.get("a");
+    cache.put("c".to_string(), 3);
+    cache.remove("a");
+    cache.put("d".to_string(), 4);
+    cache.put("e".to_string(), 5);
+    assert_eq!(
+        *RefCell::borrow(&evicted),
+        vec![("b".to_string(), 2), ("c".to_string(), 3)]
+    );
+}
@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+
+use error::Error;
+use ring::RingBuffer;
+
+/// A FIFO buffer with fixed length that keeps track of the smallest
+/// and largest values it currently holds
+///
+/// Both extremes are maintained in amortized O(1) time per push and
+/// pop, which makes this useful as a rolling window over a stream of
+/// samples.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut window = MinMaxRingBuffer::new(3, 0u8);
+/// window.push_back(4);
+/// window.push_back(1);
+/// window.push_back(7);
+/// assert_eq!(window.min(), Some(1));
+/// assert_eq!(window.max(), Some(7));
+///
+/// window.pop_front();
+/// window.pop_front();
+/// assert_eq!(window.min(), Some(7));
+/// ```
+pub struct MinMaxRingBuffer<T: Clone + Ord> {
+    rb: RingBuffer<T>,
+    // Candidates for the minimum, in increasing order
+    mins: VecDeque<T>,
+    // Candidates for the maximum, in decreasing order
+    maxes: VecDeque<T>,
+}
+
+impl<T: Clone + Ord> MinMaxRingBuffer<T> {
+    /// Create a new buffer that can hold up to `size` elements and
+    /// use `value` as the default value
+    pub fn new(size: usize, value: T) -> MinMaxRingBuffer<T> {
+        MinMaxRingBuffer {
+            rb: RingBuffer::new(size, value),
+            mins: VecDeque::with_capacity(size),
+            maxes: VecDeque::with_capacity(size),
+        }
+    }
+
+    /// Add `value` to the end of the queue. Returns false if there is
+    /// not enough room in the queue
+    pub fn push_back(&mut self, value: T) -> bool {
+        self.try_push_back(value).is_ok()
+    }
+
+    /// Add `value` to the end of the queue, or return `Error::Full`
+    /// if there is not enough room in the queue
+    pub fn try_push_back(&mut self, value: T) -> Result<(), Error> {
+        self.rb.try_push_back(value.clone())?;
+
+        while self.mins.back().map_or(false, |m| *m > value) {
+            self.mins.pop_back();
+        }
+        self.mins.push_back(value.clone());
+
+        while self.maxes.back().map_or(false, |m| *m < value) {
+            self.maxes.pop_back();
+        }
+        self.maxes.push_back(value);
+
+        Ok(())
+    }
+
+    /// Remove the first value from the queue, or returns `None` if
+    /// there are no values in the buffer
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.try_pop_front().ok()
+    }
+
+    /// Remove the first value from the queue, or return
+    /// `Error::Empty` if there are no values in the buffer
+    pub fn try_pop_front(&mut self) -> Result<T, Error> {
+        let value = self.rb.try_pop_front()?;
+
+        if self.mins.front() == Some(&value) {
+            self.mins.pop_front();
+        }
+        if self.maxes.front() == Some(&value) {
+            self.maxes.pop_front();
+        }
+
+        Ok(value)
+    }
+
+    /// Copy the first value from the queue but does not remove it;
+    /// returns `None` if there are no values in the buffer
+    pub fn peek_front(&self) -> Option<T> {
+        self.rb.peek_front()
+    }
+
+    /// Returns the smallest value in the buffer, or `None` if the
+    /// buffer is empty
+    pub fn min(&self) -> Option<T> {
+        self.mins.front().cloned()
+    }
+
+    /// Returns the largest value in the buffer, or `None` if the
+    /// buffer is empty
+    pub fn max(&self) -> Option<T> {
+        self.maxes.front().cloned()
+    }
+
+    /// Returns the number of values in the buffer
+    pub fn len(&self) -> usize {
+        self.rb.len()
+    }
+
+    /// Returns true if there are no values in the buffer
+    pub fn is_empty(&self) -> bool {
+        self.rb.is_empty()
+    }
+
+    /// Returns the number of free slots in the buffer
+    pub fn capacity(&self) -> usize {
+        self.rb.capacity()
+    }
+
+    /// Returns the max number of values that can ever be stored in
+    /// the buffer
+    pub fn max_len(&self) -> usize {
+        self.rb.max_len()
+    }
+}
+
+#[test]
+fn test_minmax() {
+    let mut mm = MinMaxRingBuffer::new(4, 0i32);
+    assert_eq!(mm.min(), None);
+    assert_eq!(mm.max(), None);
+
+    let samples = [5, 3, 3, 8, 1, 9, 9, 2, 6, 4, 7, 0];
+    for (i, &s) in samples.iter().enumerate() {
+        if mm.capacity() == 0 {
+            mm.pop_front();
+        }
+        assert!(mm.push_back(s));
+
+        let start = i.saturating_sub(3);
+        let window = &samples[start..i + 1];
+        assert_eq!(mm.min(), window.iter().cloned().min());
+        assert_eq!(mm.max(), window.iter().cloned().max());
+    }
+
+    while mm.pop_front().is_some() {}
+    assert_eq!(mm.min(), None);
+    assert_eq!(mm.max(), None);
+}
@@ -0,0 +1,307 @@
+use std::mem;
+use std::slice;
+
+// The levels of the heap alternate, starting with a min level at the root
+fn is_min_level(index: usize) -> bool {
+    let level = usize::BITS - 1 - (index + 1).leading_zeros();
+    level % 2 == 0
+}
+
+/// A double-ended priority queue, which can give up either its smallest
+/// or its largest value in O(log n)
+///
+/// The values are kept in a single vector as a min-max heap, where each
+/// value on an even level is the smallest in its subtree, and each value
+/// on an odd level is the largest. That makes it easy to keep the best
+/// `k` values of a stream while evicting the worst, without keeping two
+/// heaps of the same values.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// // Keep the three highest scores
+/// let mut best = MinMaxHeap::new();
+/// for &score in &[40, 85, 12, 97, 60, 71] {
+///     if best.len() < 3 {
+///         best.push(score);
+///     } else {
+///         best.push_pop_min(score);
+///     }
+/// }
+/// assert_eq!(best.peek_min(), Some(&71));
+/// assert_eq!(best.pop_max(), Some(97));
+/// assert_eq!(best.pop_max(), Some(85));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MinMaxHeap<T> {
+    values: Vec<T>,
+}
+
+impl<T: Ord> Default for MinMaxHeap<T> {
+    fn default() -> MinMaxHeap<T> {
+        MinMaxHeap::new()
+    }
+}
+
+impl<T: Ord> MinMaxHeap<T> {
+    /// Create an empty heap
+    pub fn new() -> MinMaxHeap<T> {
+        MinMaxHeap { values: vec![] }
+    }
+
+    /// Add `value` to the heap
+    pub fn push(&mut self, value: T) {
+        self.values.push(value);
+        let index = self.values.len() - 1;
+        self.bubble_up(index);
+    }
+
+    /// Return the smallest value, or `None` if the heap is empty
+    pub fn peek_min(&self) -> Option<&T> {
+        self.values.first()
+    }
+
+    /// Return the largest value, or `None` if the heap is empty
+    pub fn peek_max(&self) -> Option<&T> {
+        self.max_index().map(|index| &self.values[index])
+    }
+
+    /// Remove the smallest value and return it, or `None` if the heap is
+    /// empty
+    pub fn pop_min(&mut self) -> Option<T> {
+        if self.values.is_empty() {
+            return None;
+        }
+        Some(self.remove_at(0))
+    }
+
+    /// Remove the largest value and return it, or `None` if the heap is
+    /// empty
+    pub fn pop_max(&mut self) -> Option<T> {
+        let index = self.max_index()?;
+        Some(self.remove_at(index))
+    }
+
+    /// Add `value` and then remove the smallest value, and return it.
+    /// This is faster than a `push` followed by a `pop_min`
+    pub fn push_pop_min(&mut self, value: T) -> T {
+        match self.values.first() {
+            Some(min) if *min < value => {}
+            _ => return value,
+        }
+        let min = mem::replace(&mut self.values[0], value);
+        self.trickle_down(0);
+        min
+    }
+
+    /// Add `value` and then remove the largest value, and return it.
+    /// This is faster than a `push` followed by a `pop_max`
+    pub fn push_pop_max(&mut self, value: T) -> T {
+        let index = match self.max_index() {
+            Some(index) if self.values[index] > value => index,
+            _ => return value,
+        };
+        let max = mem::replace(&mut self.values[index], value);
+        // The new value may now be smaller than the root
+        if index > 0 && self.values[index] < self.values[0] {
+            self.values.swap(index, 0);
+        }
+        self.trickle_down(index);
+        max
+    }
+
+    /// Iterate over the values in no particular order
+    pub fn iter(&self) -> MinMaxIter<'_, T> {
+        MinMaxIter {
+            iter: self.values.iter(),
+        }
+    }
+
+    /// Remove every value from the heap, and return them from smallest to
+    /// largest
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        let mut values = self.values;
+        values.sort();
+        values
+    }
+
+    /// Remove every value from the heap
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Return the number of values in the heap
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if the heap is empty
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        match self.values.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ if self.values[1] >= self.values[2] => Some(1),
+            _ => Some(2),
+        }
+    }
+
+    fn remove_at(&mut self, index: usize) -> T {
+        let value = self.values.swap_remove(index);
+        if index < self.values.len() {
+            self.trickle_down(index);
+        }
+        value
+    }
+
+    // Returns true if `a` belongs above `b` on a level of the given kind
+    fn before(&self, a: usize, b: usize, min: bool) -> bool {
+        if min {
+            self.values[a] < self.values[b]
+        } else {
+            self.values[a] > self.values[b]
+        }
+    }
+
+    fn bubble_up(&mut self, index: usize) {
+        if index == 0 {
+            return;
+        }
+        let parent = (index - 1) / 2;
+        let min = is_min_level(index);
+        // A value that belongs on the other kind of level swaps with its
+        // parent, and carries on up the parent's levels
+        if self.before(parent, index, min) {
+            self.values.swap(index, parent);
+            self.bubble_up_levels(parent, !min);
+        } else {
+            self.bubble_up_levels(index, min);
+        }
+    }
+
+    fn bubble_up_levels(&mut self, mut index: usize, min: bool) {
+        while index >= 3 {
+            let grandparent = ((index - 1) / 2 - 1) / 2;
+            if !self.before(index, grandparent, min) {
+                break;
+            }
+            self.values.swap(index, grandparent);
+            index = grandparent;
+        }
+    }
+
+    fn trickle_down(&mut self, mut index: usize) {
+        let min = is_min_level(index);
+        loop {
+            // Find the best of the children and grandchildren
+            let first = 2 * index + 1;
+            let mut best = index;
+            let candidates = (first..first + 2).chain(2 * first + 1..2 * first + 5);
+            for candidate in candidates.filter(|&c| c < self.values.len()) {
+                if self.before(candidate, best, min) {
+                    best = candidate;
+                }
+            }
+            if best == index {
+                return;
+            }
+            self.values.swap(index, best);
+            if best < first + 2 {
+                // A child is on the other kind of level, so it is below
+                // its own subtree already
+                return;
+            }
+            let parent = (best - 1) / 2;
+            if self.before(parent, best, min) {
+                self.values.swap(best, parent);
+            }
+            index = best;
+        }
+    }
+}
+
+/// An iterator over the values of a `MinMaxHeap`, in no particular order
+pub struct MinMaxIter<'a, T: 'a> {
+    iter: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for MinMaxIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next()
+    }
+}
+
+#[test]
+fn test_min_max_heap() {
+    let mut heap = MinMaxHeap::new();
+    assert!(heap.is_empty());
+    assert_eq!((heap.peek_min(), heap.peek_max()), (None, None));
+    assert_eq!(heap.pop_max(), None);
+    assert_eq!(heap.push_pop_min(3), 3);
+
+    heap.push(5);
+    assert_eq!((heap.peek_min(), heap.peek_max()), (Some(&5), Some(&5)));
+    for &value in &[9, 1, 7, 3, 8, 2, 6] {
+        heap.push(value);
+    }
+    assert_eq!(heap.len(), 8);
+    assert_eq!((heap.peek_min(), heap.peek_max()), (Some(&1), Some(&9)));
+    assert_eq!(heap.iter().count(), 8);
+
+    assert_eq!(heap.push_pop_min(0), 0);
+    assert_eq!(heap.push_pop_min(4), 1);
+    assert_eq!(heap.push_pop_max(10), 10);
+    assert_eq!(heap.push_pop_max(4), 9);
+    assert_eq!(heap.pop_min(), Some(2));
+    assert_eq!(heap.pop_max(), Some(8));
+    assert_eq!(heap.clone().into_sorted_vec(), vec![3, 4, 4, 5, 6, 7]);
+
+    heap.clear();
+    assert_eq!(heap.pop_min(), None);
+}
+
+#[test]
+fn test_min_max_heap_random() {
+    use rng::{JitterRng, XorShiftRng};
+
+    // Checked against a sorted vector
+    let mut rng = XorShiftRng::new(11);
+    let mut heap = MinMaxHeap::new();
+    let mut expected: Vec<u64> = vec![];
+    for _ in 0..2000 {
+        let value = rng.next_u64() % 100;
+        match rng.next_u64() % 6 {
+            0 => assert_eq!(
+                heap.pop_min(),
+                (!expected.is_empty()).then(|| expected.remove(0))
+            ),
+            1 => assert_eq!(heap.pop_max(), expected.pop()),
+            2 => {
+                expected.push(value);
+                expected.sort();
+                assert_eq!(heap.push_pop_min(value), expected.remove(0));
+            }
+            3 => {
+                expected.push(value);
+                expected.sort();
+                assert_eq!(heap.push_pop_max(value), expected.pop().unwrap());
+            }
+            _ => {
+                heap.push(value);
+                expected.push(value);
+                expected.sort();
+            }
+        }
+        assert_eq!(heap.len(), expected.len());
+        assert_eq!(heap.peek_min(), expected.first());
+        assert_eq!(heap.peek_max(), expected.last());
+    }
+}
@@ -0,0 +1,427 @@
+use std::borrow::Borrow;
+use std::collections::{btree_set, hash_map, hash_set, BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+use std::slice;
+
+/// A collection holding the values under one key of a `MultiMap`
+///
+/// It is implemented for `Vec`, which keeps every value in the order it
+/// was inserted, and for `HashSet` and `BTreeSet`, which keep one of
+/// each value.
+pub trait ValueGroup<V>: Default {
+    /// An iterator over the values in the group
+    type Iter<'a>: Iterator<Item = &'a V>
+    where
+        Self: 'a,
+        V: 'a;
+
+    /// Add `value` to the group, and return false if it was not added
+    /// because the group already holds it
+    fn insert(&mut self, value: V) -> bool;
+
+    /// Remove one value equal to `value`, and return true if there was
+    /// one
+    fn remove(&mut self, value: &V) -> bool;
+
+    /// Returns true if the group holds a value equal to `value`
+    fn contains(&self, value: &V) -> bool;
+
+    /// Return the number of values in the group
+    fn len(&self) -> usize;
+
+    /// Returns true if the group is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the values in the group
+    fn iter(&self) -> Self::Iter<'_>;
+}
+
+impl<V: PartialEq> ValueGroup<V> for Vec<V> {
+    type Iter<'a>
+        = slice::Iter<'a, V>
+    where
+        V: 'a;
+
+    fn insert(&mut self, value: V) -> bool {
+        self.push(value);
+        true
+    }
+
+    fn remove(&mut self, value: &V) -> bool {
+        match self.iter().position(|v| v == value) {
+            Some(index) => {
+                Vec::remove(self, index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn contains(&self, value: &V) -> bool {
+        self[..].contains(value)
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn iter(&self) -> slice::Iter<'_, V> {
+        self[..].iter()
+    }
+}
+
+impl<V: Hash + Eq> ValueGroup<V> for HashSet<V> {
+    type Iter<'a>
+        = hash_set::Iter<'a, V>
+    where
+        V: 'a;
+
+    fn insert(&mut self, value: V) -> bool {
+        HashSet::insert(self, value)
+    }
+
+    fn remove(&mut self, value: &V) -> bool {
+        HashSet::remove(self, value)
+    }
+
+    fn contains(&self, value: &V) -> bool {
+        HashSet::contains(self, value)
+    }
+
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+
+    fn iter(&self) -> hash_set::Iter<'_, V> {
+        HashSet::iter(self)
+    }
+}
+
+impl<V: Ord> ValueGroup<V> for BTreeSet<V> {
+    type Iter<'a>
+        = btree_set::Iter<'a, V>
+    where
+        V: 'a;
+
+    fn insert(&mut self, value: V) -> bool {
+        BTreeSet::insert(self, value)
+    }
+
+    fn remove(&mut self, value: &V) -> bool {
+        BTreeSet::remove(self, value)
+    }
+
+    fn contains(&self, value: &V) -> bool {
+        BTreeSet::contains(self, value)
+    }
+
+    fn len(&self) -> usize {
+        BTreeSet::len(self)
+    }
+
+    fn iter(&self) -> btree_set::Iter<'_, V> {
+        BTreeSet::iter(self)
+    }
+}
+
+/// A map that holds any number of values under each key
+///
+/// The values under a key are kept in a group of type `G`, which is a
+/// `Vec` by default; use a `HashSet` or `BTreeSet` to ignore duplicate
+/// values. A key is only in the map while it has values, so removing
+/// the last value under a key removes the key too. Keys are visited in
+/// no particular order.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+/// use std::collections::BTreeSet;
+///
+/// let mut subscribers: MultiMap<&str, u32> = MultiMap::new();
+/// subscribers.insert("news", 1);
+/// subscribers.insert("news", 2);
+/// subscribers.insert("sports", 2);
+/// assert_eq!(subscribers.get_all("news"), Some(&vec![1, 2]));
+/// assert_eq!(subscribers.len(), 3);
+///
+/// subscribers.remove("sports", &2);
+/// assert!(!subscribers.contains_key("sports"));
+///
+/// // Set groups keep one of each value
+/// let mut tags: MultiMap<u32, &str, BTreeSet<&str>> = MultiMap::new();
+/// tags.insert(7, "red");
+/// assert!(!tags.insert(7, "red"));
+/// assert_eq!(tags.count(&7), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiMap<K, V, G = Vec<V>> {
+    groups: HashMap<K, G>,
+    // The number of values in all the groups
+    len: usize,
+    marker: PhantomData<V>,
+}
+
+impl<K: Hash + Eq, V, G: ValueGroup<V>> Default for MultiMap<K, V, G> {
+    fn default() -> MultiMap<K, V, G> {
+        MultiMap::new()
+    }
+}
+
+impl<K: Hash + Eq, V, G: ValueGroup<V>> MultiMap<K, V, G> {
+    /// Create an empty map
+    pub fn new() -> MultiMap<K, V, G> {
+        MultiMap {
+            groups: HashMap::new(),
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Add `value` under `key`. Returns false if it was not added because
+    /// the group under `key` is a set that already holds it
+    pub fn insert(&mut self, key: K, value: V) -> bool {
+        let inserted = self.groups.entry(key).or_default().insert(value);
+        if inserted {
+            self.len += 1;
+        }
+        inserted
+    }
+
+    /// Return the group of values under `key`, or `None` if there are
+    /// none
+    pub fn get_all<Q>(&self, key: &Q) -> Option<&G>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.groups.get(key)
+    }
+
+    /// Return the first value under `key`, or `None` if there are none
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.groups.get(key)?.iter().next()
+    }
+
+    /// Returns true if there are any values under `key`
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.groups.contains_key(key)
+    }
+
+    /// Returns true if `value` is one of the values under `key`
+    pub fn contains<Q>(&self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.groups
+            .get(key)
+            .map_or(false, |group| group.contains(value))
+    }
+
+    /// Return the number of values under `key`
+    pub fn count<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.groups.get(key).map_or(0, |group| group.len())
+    }
+
+    /// Remove one value equal to `value` from under `key`, and return
+    /// true if there was one. The key is removed with its last value
+    pub fn remove<Q>(&mut self, key: &Q, value: &V) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (removed, empty) = match self.groups.get_mut(key) {
+            Some(group) => (group.remove(value), group.is_empty()),
+            None => return false,
+        };
+        if empty {
+            self.groups.remove(key);
+        }
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Remove `key` and return the group of values under it, or `None`
+    /// if there were none
+    pub fn remove_all<Q>(&mut self, key: &Q) -> Option<G>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let group = self.groups.remove(key)?;
+        self.len -= group.len();
+        Some(group)
+    }
+
+    /// Iterate over the keys and their groups of values
+    pub fn groups(&self) -> MultiMapGroups<'_, K, G> {
+        MultiMapGroups {
+            iter: self.groups.iter(),
+        }
+    }
+
+    /// Iterate over every key and value pair, with the values under a key
+    /// together
+    pub fn iter(&self) -> MultiMapIter<'_, K, V, G> {
+        MultiMapIter {
+            groups: self.groups.iter(),
+            current: None,
+        }
+    }
+
+    /// Remove every key and value from the map
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.len = 0;
+    }
+
+    /// Return the number of keys with values
+    pub fn key_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Return the number of values under all the keys
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<K: Hash + Eq, V, G: ValueGroup<V>> FromIterator<(K, V)> for MultiMap<K, V, G> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> MultiMap<K, V, G> {
+        let mut map = MultiMap::new();
+        map.extend(entries);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V, G: ValueGroup<V>> Extend<(K, V)> for MultiMap<K, V, G> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, entries: I) {
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+    }
+}
+
+/// An iterator over the keys of a `MultiMap` and their groups of values
+pub struct MultiMapGroups<'a, K: 'a, G: 'a> {
+    iter: hash_map::Iter<'a, K, G>,
+}
+
+impl<'a, K, G> Iterator for MultiMapGroups<'a, K, G> {
+    type Item = (&'a K, &'a G);
+
+    fn next(&mut self) -> Option<(&'a K, &'a G)> {
+        self.iter.next()
+    }
+}
+
+/// An iterator over every key and value pair of a `MultiMap`
+pub struct MultiMapIter<'a, K: 'a, V: 'a, G: 'a + ValueGroup<V>> {
+    groups: hash_map::Iter<'a, K, G>,
+    // The key being visited, and the rest of its values
+    current: Option<(&'a K, G::Iter<'a>)>,
+}
+
+impl<'a, K, V, G: ValueGroup<V>> Iterator for MultiMapIter<'a, K, V, G> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        loop {
+            if let Some((key, ref mut values)) = self.current {
+                if let Some(value) = values.next() {
+                    return Some((key, value));
+                }
+            }
+            let (key, group) = self.groups.next()?;
+            self.current = Some((key, group.iter()));
+        }
+    }
+}
+
+#[test]
+fn test_multimap() {
+    let mut map: MultiMap<String, u32> = MultiMap::new();
+    assert!(map.is_empty());
+    assert_eq!(map.get("a"), None);
+    assert!(!map.remove("a", &1));
+
+    assert!(map.insert("a".to_string(), 1));
+    assert!(map.insert("a".to_string(), 2));
+    assert!(map.insert("a".to_string(), 1));
+    assert!(map.insert("b".to_string(), 3));
+    assert_eq!(map.len(), 4);
+    assert_eq!(map.key_count(), 2);
+    assert_eq!(map.get_all("a"), Some(&vec![1, 2, 1]));
+    assert_eq!(map.get("a"), Some(&1));
+    assert_eq!(map.count("a"), 3);
+    assert_eq!(map.count("z"), 0);
+    assert!(map.contains("a", &2));
+    assert!(!map.contains("b", &2));
+
+    // Only the first equal value goes
+    assert!(map.remove("a", &1));
+    assert_eq!(map.get_all("a"), Some(&vec![2, 1]));
+    assert!(!map.remove("b", &4));
+
+    // The key goes with its last value
+    assert!(map.remove("b", &3));
+    assert!(!map.contains_key("b"));
+    assert_eq!(map.len(), 2);
+
+    assert_eq!(map.remove_all("a"), Some(vec![2, 1]));
+    assert_eq!(map.remove_all("a"), None);
+    assert!(map.is_empty());
+    assert_eq!(map.iter().next(), None);
+}
+
+#[test]
+fn test_multimap_groups() {
+    let edges = vec![(1, 2), (1, 3), (2, 3), (1, 2), (3, 1)];
+    let mut graph: MultiMap<u32, u32, BTreeSet<u32>> = edges.into_iter().collect();
+    assert_eq!(graph.len(), 4);
+    assert_eq!(graph.key_count(), 3);
+    assert!(!graph.insert(2, 3));
+
+    let mut groups: Vec<_> = graph
+        .groups()
+        .map(|(k, g)| (*k, g.iter().cloned().collect::<Vec<_>>()))
+        .collect();
+    groups.sort();
+    assert_eq!(groups, vec![(1, vec![2, 3]), (2, vec![3]), (3, vec![1])]);
+
+    let mut pairs: Vec<_> = graph.iter().map(|(k, v)| (*k, *v)).collect();
+    pairs.sort();
+    assert_eq!(pairs, vec![(1, 2), (1, 3), (2, 3), (3, 1)]);
+
+    let mut seen: MultiMap<&str, &str, HashSet<&str>> = MultiMap::default();
+    seen.extend(vec![("x", "1"), ("x", "1"), ("x", "2")]);
+    assert_eq!(seen.count("x"), 2);
+    assert!(seen.remove("x", &"1"));
+    assert!(!seen.remove("x", &"1"));
+    seen.clear();
+    assert_eq!(seen.key_count(), 0);
+}
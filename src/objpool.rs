@@ -0,0 +1,167 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// A pool of pre-allocated objects, which are lent out through guards
+/// that give them back to the pool when dropped
+///
+/// The pool can be shared between threads by reference. Objects are
+/// returned as they are, so reset any state that shouldn't carry over
+/// before reusing one.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let packets = ObjectPool::new(2, Vec::<u8>::with_capacity(1500));
+/// {
+///     let mut a = packets.try_acquire().unwrap();
+///     let _b = packets.try_acquire().unwrap();
+///     a.extend_from_slice(b"hello");
+///     assert!(packets.try_acquire().is_none());
+///
+///     // Fall back to a fresh object when the pool runs dry
+///     let c = packets.acquire_or(Vec::new());
+///     assert!(c.is_empty());
+/// }
+/// assert_eq!(packets.available(), 2);
+/// ```
+#[derive(Debug)]
+pub struct ObjectPool<T> {
+    free: Mutex<Vec<T>>,
+    size: usize,
+}
+
+impl<T: Clone> ObjectPool<T> {
+    /// Create a pool of `size` copies of `value`
+    pub fn new(size: usize, value: T) -> ObjectPool<T> {
+        ObjectPool {
+            free: Mutex::new(vec![value; size]),
+            size,
+        }
+    }
+}
+
+impl<T> ObjectPool<T> {
+    /// Take an object out of the pool, or return `None` if they are
+    /// all in use
+    pub fn try_acquire(&self) -> Option<Pooled<'_, T>> {
+        let value = self.free.lock().unwrap().pop()?;
+        Some(Pooled {
+            pool: self,
+            value: Some(value),
+        })
+    }
+
+    /// Take an object out of the pool, or use `default` if they are all
+    /// in use. When the guard is dropped, `default` joins the pool if
+    /// there is room for it
+    pub fn acquire_or(&self, default: T) -> Pooled<'_, T> {
+        let value = self.free.lock().unwrap().pop().unwrap_or(default);
+        Pooled {
+            pool: self,
+            value: Some(value),
+        }
+    }
+
+    /// Return the number of objects waiting in the pool
+    pub fn available(&self) -> usize {
+        self.free.lock().unwrap().len()
+    }
+
+    /// Return the max number of objects the pool holds
+    pub fn max_len(&self) -> usize {
+        self.size
+    }
+
+    fn give_back(&self, value: T) {
+        let mut free = self.free.lock().unwrap();
+        if free.len() < self.size {
+            free.push(value);
+        }
+    }
+}
+
+/// An object lent out by an `ObjectPool`, which goes back to the pool
+/// when this is dropped
+#[derive(Debug)]
+pub struct Pooled<'a, T> {
+    pool: &'a ObjectPool<T>,
+    // Only `None` once the object has been given back or detached
+    value: Option<T>,
+}
+
+impl<'a, T> Pooled<'a, T> {
+    /// Keep the object instead of giving it back to the pool, which
+    /// then holds one fewer object
+    pub fn detach(mut self) -> T {
+        self.value.take().unwrap()
+    }
+}
+
+impl<'a, T> Deref for Pooled<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<'a, T> DerefMut for Pooled<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value.as_mut().unwrap()
+    }
+}
+
+impl<'a, T> Drop for Pooled<'a, T> {
+    fn drop(&mut self) {
+        if let Some(value) = self.value.take() {
+            self.pool.give_back(value);
+        }
+    }
+}
+
+#[test]
+fn test_object_pool() {
+    let pool = ObjectPool::new(3, 0u32);
+    assert_eq!(pool.max_len(), 3);
+    {
+        let mut a = pool.try_acquire().unwrap();
+        *a = 7;
+        let b = pool.try_acquire().unwrap();
+        assert_eq!(pool.available(), 1);
+        assert_eq!(b.detach(), 0);
+        assert_eq!(pool.available(), 1);
+    }
+    // The pool lost the detached object, and `a` came back as is
+    assert_eq!(pool.available(), 2);
+    assert_eq!(*pool.try_acquire().unwrap(), 7);
+
+    {
+        let held: Vec<_> = (0..4).map(|i| pool.acquire_or(100 + i)).collect();
+        assert_eq!(
+            held.iter().map(|p| **p).collect::<Vec<_>>(),
+            [7, 0, 102, 103]
+        );
+        assert!(pool.try_acquire().is_none());
+    }
+    // Only as many as fit are kept
+    assert_eq!(pool.available(), 3);
+}
+
+#[test]
+fn test_object_pool_threads() {
+    use std::thread;
+
+    let pool = ObjectPool::new(4, vec![0u8; 64]);
+    thread::scope(|s| {
+        for i in 0..8 {
+            let pool = &pool;
+            s.spawn(move || {
+                let mut buffer = pool.acquire_or(vec![0; 64]);
+                buffer[0] = i;
+            });
+        }
+    });
+    assert_eq!(pool.available(), 4);
+}
@@ -0,0 +1,350 @@
+use std::fmt;
+use std::ops::Range;
+use std::slice;
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+enum Source {
+    Original,
+    Added,
+}
+
+// A run of text from one of the buffers
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+struct Piece {
+    source: Source,
+    // In bytes within the buffer
+    start: usize,
+    len: usize,
+    chars: usize,
+}
+
+/// A saved state of a `PieceTable`, which it can be restored to
+///
+/// Only the list of pieces is saved, since the text they point to is
+/// never changed, so taking a snapshot is cheap no matter how large the
+/// document is. A snapshot must only be restored to the table it came
+/// from.
+#[derive(Debug, Clone)]
+pub struct PieceSnapshot {
+    pieces: Vec<Piece>,
+    chars: usize,
+}
+
+/// A text document made of pieces of an original buffer and an
+/// append-only buffer of added text
+///
+/// Edits never move or change any text; they only add to the added
+/// buffer and rearrange the list of pieces, so inserting and removing
+/// text is O(number of pieces), and undo is just restoring an older
+/// list of pieces with `snapshot` and `restore`. Offsets count `char`s,
+/// not bytes.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut doc = PieceTable::new("the fox jumps");
+/// let before = doc.snapshot();
+///
+/// doc.insert(4, "quick ");
+/// doc.remove(14..19);
+/// doc.insert(14, "leaps");
+/// assert_eq!(doc.to_string(), "the quick fox leaps");
+///
+/// doc.restore(&before);
+/// assert_eq!(doc.to_string(), "the fox jumps");
+/// ```
+#[derive(Debug, Clone)]
+pub struct PieceTable {
+    original: String,
+    added: String,
+    pieces: Vec<Piece>,
+    chars: usize,
+}
+
+impl Default for PieceTable {
+    fn default() -> PieceTable {
+        PieceTable::new("")
+    }
+}
+
+impl PieceTable {
+    /// Create a document holding `original`
+    pub fn new<S: Into<String>>(original: S) -> PieceTable {
+        let original = original.into();
+        let chars = original.chars().count();
+        let pieces = if original.is_empty() {
+            vec![]
+        } else {
+            vec![Piece {
+                source: Source::Original,
+                start: 0,
+                len: original.len(),
+                chars,
+            }]
+        };
+        PieceTable {
+            original,
+            added: String::new(),
+            pieces,
+            chars,
+        }
+    }
+
+    /// Insert `text` before the char at `at`
+    pub fn insert(&mut self, at: usize, text: &str) {
+        assert!(at <= self.chars, "insertion index out of bounds");
+        if text.is_empty() {
+            return;
+        }
+        let piece = Piece {
+            source: Source::Added,
+            start: self.added.len(),
+            len: text.len(),
+            chars: text.chars().count(),
+        };
+        self.added.push_str(text);
+        self.chars += piece.chars;
+
+        let (index, offset) = self.locate(at);
+        if offset == 0 {
+            // Typing grows the piece that was just added, instead of
+            // adding a new piece for every keystroke
+            if index > 0 {
+                let prev = &mut self.pieces[index - 1];
+                if prev.source == Source::Added && prev.start + prev.len == piece.start {
+                    prev.len += piece.len;
+                    prev.chars += piece.chars;
+                    return;
+                }
+            }
+            self.pieces.insert(index, piece);
+        } else {
+            let (left, right) = self.split(self.pieces[index], offset);
+            self.pieces[index] = left;
+            self.pieces.insert(index + 1, piece);
+            self.pieces.insert(index + 2, right);
+        }
+    }
+
+    /// Remove the chars in `range`
+    pub fn remove(&mut self, range: Range<usize>) {
+        assert!(range.end <= self.chars, "removal range out of bounds");
+        if range.start >= range.end {
+            return;
+        }
+        let mut pieces = Vec::with_capacity(self.pieces.len() + 1);
+        let mut pos = 0;
+        for &piece in &self.pieces {
+            let end = pos + piece.chars;
+            if end <= range.start || pos >= range.end {
+                pieces.push(piece);
+            } else {
+                if pos < range.start {
+                    pieces.push(self.split(piece, range.start - pos).0);
+                }
+                if end > range.end {
+                    pieces.push(self.split(piece, range.end - pos).1);
+                }
+            }
+            pos = end;
+        }
+        self.pieces = pieces;
+        self.chars -= range.end - range.start;
+    }
+
+    /// Return a copy of the chars in `range`
+    pub fn slice(&self, range: Range<usize>) -> String {
+        assert!(range.end <= self.chars, "slice range out of bounds");
+        let mut out = String::new();
+        let mut pos = 0;
+        for piece in self.pieces() {
+            let chars = piece.chars().count();
+            let start = range.start.max(pos);
+            let end = range.end.min(pos + chars);
+            if start < end {
+                out.extend(piece.chars().skip(start - pos).take(end - start));
+            }
+            pos += chars;
+        }
+        out
+    }
+
+    /// Save the current state of the document, to `restore` later
+    pub fn snapshot(&self) -> PieceSnapshot {
+        PieceSnapshot {
+            pieces: self.pieces.clone(),
+            chars: self.chars,
+        }
+    }
+
+    /// Return the document to the state it was in when `snapshot` was
+    /// taken
+    pub fn restore(&mut self, snapshot: &PieceSnapshot) {
+        self.pieces.clone_from(&snapshot.pieces);
+        self.chars = snapshot.chars;
+    }
+
+    /// Iterate over the pieces of text the document is made of, in
+    /// order
+    pub fn pieces(&self) -> Pieces<'_> {
+        Pieces {
+            table: self,
+            iter: self.pieces.iter(),
+        }
+    }
+
+    /// Return the number of pieces the document is made of
+    pub fn piece_count(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Return the number of chars in the document
+    pub fn len(&self) -> usize {
+        self.chars
+    }
+
+    /// Returns true if the document is empty
+    pub fn is_empty(&self) -> bool {
+        self.chars == 0
+    }
+
+    fn text(&self, piece: &Piece) -> &str {
+        let buffer = match piece.source {
+            Source::Original => &self.original,
+            Source::Added => &self.added,
+        };
+        &buffer[piece.start..piece.start + piece.len]
+    }
+
+    // Return the index of the piece holding char `at`, and how far into
+    // the piece it is
+    fn locate(&self, at: usize) -> (usize, usize) {
+        let mut pos = 0;
+        for (index, piece) in self.pieces.iter().enumerate() {
+            if at < pos + piece.chars {
+                return (index, at - pos);
+            }
+            pos += piece.chars;
+        }
+        (self.pieces.len(), 0)
+    }
+
+    // Split a piece before its `chars`th char
+    fn split(&self, piece: Piece, chars: usize) -> (Piece, Piece) {
+        let text = self.text(&piece);
+        let bytes = text
+            .char_indices()
+            .nth(chars)
+            .map_or(text.len(), |(i, _)| i);
+        let left = Piece {
+            len: bytes,
+            chars,
+            ..piece
+        };
+        let right = Piece {
+            start: piece.start + bytes,
+            len: piece.len - bytes,
+            chars: piece.chars - chars,
+            ..piece
+        };
+        (left, right)
+    }
+}
+
+impl<'a> From<&'a str> for PieceTable {
+    fn from(text: &'a str) -> PieceTable {
+        PieceTable::new(text)
+    }
+}
+
+impl From<String> for PieceTable {
+    fn from(text: String) -> PieceTable {
+        PieceTable::new(text)
+    }
+}
+
+impl fmt::Display for PieceTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for piece in self.pieces() {
+            f.write_str(piece)?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the pieces of text in a `PieceTable`, in order
+pub struct Pieces<'a> {
+    table: &'a PieceTable,
+    iter: slice::Iter<'a, Piece>,
+}
+
+impl<'a> Iterator for Pieces<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let piece = self.iter.next()?;
+        Some(self.table.text(piece))
+    }
+}
+
+#[test]
+fn test_piece_table() {
+    let mut doc = PieceTable::default();
+    assert!(doc.is_empty());
+    doc.insert(0, "");
+    assert_eq!(doc.piece_count(), 0);
+
+    doc.insert(0, "héllo");
+    doc.insert(5, " wörld");
+    // Typing at the end of the last insert grows the same piece
+    assert_eq!(doc.piece_count(), 1);
+    doc.insert(5, ",");
+    assert_eq!(doc.to_string(), "héllo, wörld");
+    assert_eq!(doc.piece_count(), 3);
+    assert_eq!(doc.len(), 12);
+    assert_eq!(doc.slice(4..9), "o, wö");
+    assert_eq!(doc.slice(2..2), "");
+
+    doc.remove(1..4);
+    assert_eq!(doc.to_string(), "ho, wörld");
+    doc.remove(0..9);
+    assert!(doc.is_empty());
+    assert_eq!(doc.piece_count(), 0);
+}
+
+#[test]
+fn test_piece_table_undo() {
+    let mut doc = PieceTable::from("line one\nline two\n");
+    let mut history = vec![];
+    let mut expected = vec![];
+
+    let edits: Vec<(usize, usize, &str)> = vec![
+        (0, 0, "# "),
+        (7, 11, "1"),
+        (9, 9, "inserted\n"),
+        (2, 6, ""),
+        (0, 5, "start: "),
+    ];
+    for &(start, end, text) in &edits {
+        history.push(doc.snapshot());
+        expected.push(doc.to_string());
+        doc.remove(start..end);
+        doc.insert(start, text);
+    }
+    assert_eq!(doc.to_string(), "start: inserted\nine two\n");
+
+    while let Some(snapshot) = history.pop() {
+        doc.restore(&snapshot);
+        assert_eq!(doc.to_string(), expected.pop().unwrap());
+        assert_eq!(doc.len(), doc.to_string().chars().count());
+    }
+    assert_eq!(doc.to_string(), "line one\nline two\n");
+
+    // The added text is still there, so a redo works too
+    let redo = doc.snapshot();
+    doc.insert(0, "x");
+    doc.restore(&redo);
+    assert_eq!(doc.slice(0..4), "line");
+}
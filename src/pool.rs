@@ -0,0 +1,317 @@
+use std::ops::{Deref, DerefMut};
+use std::slice;
+
+use clock::{Timer, TimerEvent};
+use indexheap::{HeapHandle, IndexedHeap};
+
+/// Identifies a timer within a `TimerPool`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct TimerId(HeapHandle);
+
+// `(disarmed, next event time, insertion order)`, so the armed timer
+// with the earliest event is at the top of the heap
+type Key = (bool, u64, u64);
+
+fn key(timer: &Timer, order: u64) -> Key {
+    (!timer.is_armed(), timer.next_event_time(), order)
+}
+
+/// A set of timers that are all updated together
+///
+/// Unlike `next_timer_event` on a slice, the pool keeps track of which
+/// timer each event came from, and reports the events of all of its
+/// timers in the order they happened. The timers are kept in a heap by
+/// their next event, so each event costs O(log n) however many timers
+/// there are. The handle of a removed timer stays invalid, even once its
+/// slot is reused.
+///
+/// Timers can also be chained with `trigger_on`, so that an edge of one
+/// timer restarts another, like a prescaler feeding a counter.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut pool = TimerPool::new();
+/// let fast = pool.insert(Timer::new(10, 0, 0));
+/// let slow = pool.insert(Timer::new(25, 5, 0));
+///
+/// let events: Vec<_> = pool.update_all(20).collect();
+/// assert_eq!(
+///     events,
+///     vec![
+///         (fast, TimerEvent::RisingEdge),
+///         (slow, TimerEvent::RisingEdge),
+///         (fast, TimerEvent::RisingEdge),
+///         (fast, TimerEvent::RisingEdge),
+///     ]
+/// );
+/// assert_eq!(pool.next_event_time(), Some(30));
+/// ```
+#[derive(Debug)]
+pub struct TimerPool {
+    timers: IndexedHeap<Key, Timer>,
+    // The insertion order of the next timer, to break ties between
+    // events at the same tick
+    next_order: u64,
+    // The events of the last update, with the time of each
+    events: Vec<(u64, TimerId, TimerEvent)>,
+    // `(source, event, target)`: each `event` of `source` restarts
+    // `target`
+    triggers: Vec<(TimerId, TimerEvent, TimerId)>,
+}
+
+impl Default for TimerPool {
+    fn default() -> TimerPool {
+        TimerPool::new()
+    }
+}
+
+impl TimerPool {
+    /// Create an empty pool
+    pub fn new() -> TimerPool {
+        TimerPool {
+            timers: IndexedHeap::new(),
+            next_order: 0,
+            events: vec![],
+            triggers: vec![],
+        }
+    }
+
+    /// Add `timer` to the pool, and return its handle
+    pub fn insert(&mut self, timer: Timer) -> TimerId {
+        let key = key(&timer, self.next_order);
+        self.next_order += 1;
+        TimerId(self.timers.push(key, timer))
+    }
+
+    /// Remove the timer identified by `id` from the pool, and return
+    /// it. Returns `None` if it was already removed
+    pub fn remove(&mut self, id: TimerId) -> Option<Timer> {
+        let (_, timer) = self.timers.remove(id.0)?;
+        self.triggers
+            .retain(|&(source, _, target)| source != id && target != id);
+        Some(timer)
+    }
+
+    /// Return the timer identified by `id`, or `None` if it was removed
+    pub fn get(&self, id: TimerId) -> Option<&Timer> {
+        self.timers.get(id.0)
+    }
+
+    /// Return the timer identified by `id` for modification, or `None`
+    /// if it was removed. The timer is moved to its new place in the
+    /// pool once the guard is dropped
+    pub fn get_mut(&mut self, id: TimerId) -> Option<TimerMut<'_>> {
+        if self.timers.contains(id.0) {
+            Some(TimerMut { pool: self, id })
+        } else {
+            None
+        }
+    }
+
+    /// Make every `event` of the `source` timer restart the `target`
+    /// timer, with its next rising edge `target.offset()` ticks after
+    /// the event. This also re-arms a cancelled or finished one-shot
+    /// target. A timer can't trigger itself, directly or through other
+    /// timers
+    pub fn trigger_on(&mut self, source: TimerId, event: TimerEvent, target: TimerId) {
+        assert!(self.get(source).is_some() && self.get(target).is_some());
+        assert!(
+            !self.triggers_reach(target, source),
+            "trigger would create a cycle"
+        );
+        self.triggers.push((source, event, target));
+    }
+
+    // Whether `from` triggers `to`, directly or through other timers
+    fn triggers_reach(&self, from: TimerId, to: TimerId) -> bool {
+        from == to
+            || self
+                .triggers
+                .iter()
+                .any(|&(source, _, target)| source == from && self.triggers_reach(target, to))
+    }
+
+    // Move the timer identified by `id` to its place in the heap after
+    // it changed
+    fn rekey(&mut self, id: TimerId) {
+        let order = match self.timers.priority(id.0) {
+            Some(&(_, _, order)) => order,
+            None => return,
+        };
+        let key = key(self.timers.get(id.0).unwrap(), order);
+        self.timers.set_priority(id.0, key);
+    }
+
+    /// Run every timer up to the absolute `time`, and return all of the
+    /// events that occured in chronological order. Events at the same
+    /// tick are in the order the timers were inserted, except that the
+    /// events of a triggered timer come after the edge that triggered it
+    pub fn update_all(&mut self, time: u64) -> TimerEvents<'_> {
+        self.events.clear();
+        while let Some(handle) = self.timers.peek_handle() {
+            match self.timers.priority(handle) {
+                Some(&(false, at, _)) if at <= time => {}
+                _ => break,
+            }
+            let id = TimerId(handle);
+            let edge = self.timers.get_mut(handle).unwrap().update_with_tick(time);
+            self.rekey(id);
+            let (at, event) = match edge {
+                Some(edge) => edge,
+                None => break,
+            };
+            self.events.push((at, id, event));
+
+            for i in 0..self.triggers.len() {
+                let (source, on, target) = self.triggers[i];
+                if source == id && on == event {
+                    if let Some(timer) = self.timers.get_mut(target.0) {
+                        timer.trigger(at);
+                    }
+                    self.rekey(target);
+                }
+            }
+        }
+
+        TimerEvents {
+            events: self.events.iter(),
+        }
+    }
+
+    /// Return the next tick that any timer in the pool will emit an
+    /// event, or `None` if no timer is armed
+    pub fn next_event_time(&self) -> Option<u64> {
+        match self.timers.peek() {
+            Some((&(false, at, _), _)) => Some(at),
+            _ => None,
+        }
+    }
+
+    /// Return the number of timers in the pool
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Returns true if the pool has no timers
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+}
+
+/// A timer borrowed from a `TimerPool` for modification, which is moved
+/// to its new place in the pool when dropped
+pub struct TimerMut<'a> {
+    pool: &'a mut TimerPool,
+    id: TimerId,
+}
+
+impl<'a> Deref for TimerMut<'a> {
+    type Target = Timer;
+
+    fn deref(&self) -> &Timer {
+        self.pool.timers.get(self.id.0).unwrap()
+    }
+}
+
+impl<'a> DerefMut for TimerMut<'a> {
+    fn deref_mut(&mut self) -> &mut Timer {
+        self.pool.timers.get_mut(self.id.0).unwrap()
+    }
+}
+
+impl<'a> Drop for TimerMut<'a> {
+    fn drop(&mut self) {
+        self.pool.rekey(self.id);
+    }
+}
+
+/// The events that occured during a `TimerPool::update_all`
+pub struct TimerEvents<'a> {
+    events: slice::Iter<'a, (u64, TimerId, TimerEvent)>,
+}
+
+impl<'a> Iterator for TimerEvents<'a> {
+    type Item = (TimerId, TimerEvent);
+
+    fn next(&mut self) -> Option<(TimerId, TimerEvent)> {
+        self.events.next().map(|&(_, id, event)| (id, event))
+    }
+}
+
+#[test]
+fn test_timer_pool() {
+    let mut pool = TimerPool::new();
+    assert!(pool.is_empty());
+    assert_eq!(pool.next_event_time(), None);
+
+    let a = pool.insert(Timer::new(100, 30, 40));
+    let b = pool.insert(Timer::new(50, 10, 0));
+    assert_eq!(pool.len(), 2);
+    assert_eq!(pool.next_event_time(), Some(10));
+
+    let events: Vec<_> = pool.update_all(70).collect();
+    assert_eq!(
+        events,
+        vec![
+            (b, TimerEvent::RisingEdge),
+            (a, TimerEvent::RisingEdge),
+            (b, TimerEvent::RisingEdge),
+            (a, TimerEvent::FallingEdge),
+        ]
+    );
+    assert_eq!(pool.update_all(70).count(), 0);
+    assert_eq!(pool.next_event_time(), Some(110));
+
+    pool.get_mut(b).unwrap().cancel();
+    assert_eq!(pool.next_event_time(), Some(130));
+
+    assert!(pool.remove(b).is_some());
+    assert!(pool.remove(b).is_none());
+    assert!(pool.get(b).is_none());
+    assert_eq!(pool.len(), 1);
+    let c = pool.insert(Timer::new(10, 0, 0));
+    assert_ne!(c, b);
+    assert!(pool.get(b).is_none());
+    assert!(pool.remove(b).is_none());
+    assert_eq!(pool.get(c).unwrap().next_event_time(), 0);
+    assert_eq!(pool.len(), 2);
+}
+
+#[test]
+fn test_timer_pool_triggers() {
+    let mut pool = TimerPool::new();
+    let prescaler = pool.insert(Timer::new(10, 0, 5));
+    let mut counter = Timer::new(100, 3, 2);
+    counter.set_one_shot(true);
+    counter.cancel();
+    let counter = pool.insert(counter);
+    pool.trigger_on(prescaler, TimerEvent::FallingEdge, counter);
+
+    let events: Vec<_> = pool
+        .update_all(28)
+        .filter(|&(id, _)| id == counter)
+        .map(|(_, e)| e)
+        .collect();
+    assert_eq!(events.len(), 5);
+    assert_eq!(pool.get(counter).unwrap().next_stop_time(), 30);
+
+    // Chained all the way through in one update
+    let irq = pool.insert(Timer::new(1000, 0, 0));
+    pool.get_mut(irq).unwrap().cancel();
+    pool.trigger_on(counter, TimerEvent::FallingEdge, irq);
+    let events: Vec<_> = pool.update_all(30).collect();
+    assert_eq!(
+        events,
+        vec![
+            (prescaler, TimerEvent::RisingEdge),
+            (counter, TimerEvent::FallingEdge),
+            (irq, TimerEvent::RisingEdge),
+        ]
+    );
+
+    pool.remove(counter);
+    assert!(pool.triggers.is_empty());
+}
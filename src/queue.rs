@@ -0,0 +1,228 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// A callback for `EventQueue::schedule_fn`, which is passed the tick
+/// it was scheduled for
+pub type Callback<'a> = Box<dyn FnOnce(u64) + 'a>;
+
+/// Identifies an event scheduled in an `EventQueue`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct EventHandle(u64);
+
+/// A discrete-event scheduler, which holds events of type `E` until the
+/// tick they were scheduled for
+///
+/// The ticks are the same absolute u64 ticks used by `Clock` and
+/// `Timer`, e.g. `Timer::next_event_time` can be scheduled directly.
+/// Events due at the same tick come out in the order they were
+/// scheduled.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut queue = EventQueue::new();
+/// queue.schedule(300, "draw");
+/// let dma = queue.schedule(100, "dma");
+/// queue.schedule(200, "irq");
+/// queue.cancel(dma);
+///
+/// assert_eq!(queue.next_event_time(), Some(200));
+/// assert_eq!(queue.pop_due(250), Some((200, "irq")));
+/// assert_eq!(queue.pop_due(250), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventQueue<E> {
+    // `(tick, id)` of every event, including cancelled ones that
+    // haven't reached the top yet
+    heap: BinaryHeap<Reverse<(u64, u64)>>,
+    events: HashMap<u64, E>,
+    next_id: u64,
+}
+
+impl<E> Default for EventQueue<E> {
+    fn default() -> EventQueue<E> {
+        EventQueue::new()
+    }
+}
+
+impl<E> EventQueue<E> {
+    /// Create an empty queue
+    pub fn new() -> EventQueue<E> {
+        EventQueue {
+            heap: BinaryHeap::new(),
+            events: HashMap::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Schedule `event` at the absolute `tick`, and return a handle to
+    /// cancel it with
+    pub fn schedule(&mut self, tick: u64, event: E) -> EventHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.heap.push(Reverse((tick, id)));
+        self.events.insert(id, event);
+        EventHandle(id)
+    }
+
+    /// Cancel the event identified by `handle`, and return it. Returns
+    /// `None` if it was already popped or cancelled
+    pub fn cancel(&mut self, handle: EventHandle) -> Option<E> {
+        let event = self.events.remove(&handle.0);
+        self.discard_cancelled();
+        event
+    }
+
+    /// Remove and return the earliest event that is due at or before
+    /// `now`, along with its tick. You should generally run this
+    /// function in a loop, as multiple events may be due
+    pub fn pop_due(&mut self, now: u64) -> Option<(u64, E)> {
+        match self.heap.peek() {
+            Some(&Reverse((tick, _))) if tick <= now => {}
+            _ => return None,
+        }
+        let Reverse((tick, id)) = self.heap.pop().unwrap();
+        let event = self.events.remove(&id).expect("cancelled event at top");
+        self.discard_cancelled();
+        Some((tick, event))
+    }
+
+    /// Get the tick of the earliest event, or `None` if the queue is
+    /// empty
+    pub fn next_event_time(&self) -> Option<u64> {
+        self.heap.peek().map(|&Reverse((tick, _))| tick)
+    }
+
+    /// Return the number of pending events
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns true if there are no pending events
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    // Keep a live event at the top of the heap, so it can be peeked
+    fn discard_cancelled(&mut self) {
+        while let Some(&Reverse((_, id))) = self.heap.peek() {
+            if self.events.contains_key(&id) {
+                break;
+            }
+            self.heap.pop();
+        }
+    }
+}
+
+impl<'a> EventQueue<Callback<'a>> {
+    /// Schedule `callback` to run at the absolute `tick`, during the
+    /// first `run_until` that reaches it
+    ///
+    /// Example:
+    ///
+    /// ```rust
+    /// use j2ds::*;
+    ///
+    /// let mut log = vec![];
+    /// {
+    ///     let mut queue = EventQueue::new();
+    ///     queue.schedule_fn(20, |tick| log.push(tick));
+    ///     queue.run_until(10);
+    ///     queue.run_until(30);
+    /// }
+    /// assert_eq!(log, vec![20]);
+    /// ```
+    pub fn schedule_fn<F: FnOnce(u64) + 'a>(&mut self, tick: u64, callback: F) -> EventHandle {
+        self.schedule(tick, Box::new(callback))
+    }
+
+    /// Run every callback that is due at or before `now`, in order,
+    /// and return how many ran
+    pub fn run_until(&mut self, now: u64) -> usize {
+        let mut ran = 0;
+        while let Some((tick, callback)) = self.pop_due(now) {
+            callback(tick);
+            ran += 1;
+        }
+        ran
+    }
+}
+
+#[test]
+fn test_event_queue() {
+    let mut queue = EventQueue::new();
+    assert!(queue.is_empty());
+    assert_eq!(queue.next_event_time(), None);
+
+    let a = queue.schedule(50, 'a');
+    let b = queue.schedule(10, 'b');
+    queue.schedule(50, 'c');
+    queue.schedule(20, 'd');
+    assert_eq!(queue.len(), 4);
+    assert_eq!(queue.next_event_time(), Some(10));
+
+    assert_eq!(queue.cancel(b), Some('b'));
+    assert_eq!(queue.cancel(b), None);
+    assert_eq!(queue.next_event_time(), Some(20));
+    assert_eq!(queue.pop_due(19), None);
+
+    let mut due = vec![];
+    while let Some(event) = queue.pop_due(100) {
+        due.push(event);
+    }
+    assert_eq!(due, vec![(20, 'd'), (50, 'a'), (50, 'c')]);
+    assert_eq!(queue.cancel(a), None);
+    assert!(queue.is_empty());
+    assert_eq!(queue.next_event_time(), None);
+}
+
+#[test]
+fn test_event_queue_timers() {
+    use clock::{Timer, TimerEvent};
+
+    let mut timers = [Timer::new(100, 0, 30), Timer::new(70, 5, 0)];
+    let mut queue = EventQueue::new();
+    for (i, timer) in timers.iter().enumerate() {
+        queue.schedule(timer.next_event_time(), i);
+    }
+
+    let mut log = vec![];
+    while let Some((tick, i)) = queue.pop_due(150) {
+        let event = timers[i].update(tick).unwrap();
+        log.push((tick, i, event));
+        queue.schedule(timers[i].next_event_time(), i);
+    }
+    assert_eq!(
+        log,
+        vec![
+            (0, 0, TimerEvent::RisingEdge),
+            (5, 1, TimerEvent::RisingEdge),
+            (30, 0, TimerEvent::FallingEdge),
+            (75, 1, TimerEvent::RisingEdge),
+            (100, 0, TimerEvent::RisingEdge),
+            (130, 0, TimerEvent::FallingEdge),
+            (145, 1, TimerEvent::RisingEdge),
+        ]
+    );
+    assert_eq!(queue.len(), 2);
+}
+
+#[test]
+fn test_event_queue_callbacks() {
+    use std::cell::RefCell;
+
+    let log = RefCell::new(vec![]);
+    let mut queue = EventQueue::new();
+    queue.schedule_fn(30, |tick| log.borrow_mut().push(("c", tick)));
+    let b = queue.schedule_fn(20, |tick| log.borrow_mut().push(("b", tick)));
+    queue.schedule_fn(10, |tick| log.borrow_mut().push(("a", tick)));
+
+    assert_eq!(queue.run_until(5), 0);
+    assert_eq!(queue.run_until(15), 1);
+    assert!(queue.cancel(b).is_some());
+    assert_eq!(queue.run_until(100), 1);
+    assert!(queue.is_empty());
+    assert_eq!(*log.borrow(), vec![("a", 10), ("c", 30)]);
+}
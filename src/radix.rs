@@ -0,0 +1,407 @@
+use std::mem;
+
+#[derive(Debug, Clone)]
+struct Node<V> {
+    // The bytes between this node and its parent
+    label: Vec<u8>,
+    value: Option<V>,
+    // Sorted by the first byte of their labels, which are never empty
+    children: Vec<Node<V>>,
+}
+
+impl<V> Node<V> {
+    fn new(label: Vec<u8>, value: Option<V>) -> Node<V> {
+        Node {
+            label,
+            value,
+            children: vec![],
+        }
+    }
+
+    fn find(&self, byte: u8) -> Result<usize, usize> {
+        self.children.binary_search_by_key(&byte, |c| c.label[0])
+    }
+
+    // Return the child whose label `key` starts with, and the rest of
+    // `key` after it
+    fn descend<'k>(&self, key: &'k [u8]) -> Option<(&Node<V>, &'k [u8])> {
+        let child = &self.children[self.find(*key.first()?).ok()?];
+        if key.starts_with(&child.label) {
+            Some((child, &key[child.label.len()..]))
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        if key.is_empty() {
+            return self.value.replace(value);
+        }
+        let index = match self.find(key[0]) {
+            Ok(index) => index,
+            Err(index) => {
+                self.children
+                    .insert(index, Node::new(key.to_vec(), Some(value)));
+                return None;
+            }
+        };
+
+        let child = &mut self.children[index];
+        let common = child
+            .label
+            .iter()
+            .zip(key)
+            .take_while(|&(a, b)| a == b)
+            .count();
+        if common < child.label.len() {
+            // Split the label where the keys part ways
+            let rest = child.label.split_off(common);
+            let label = mem::replace(&mut child.label, rest);
+            let old = mem::replace(child, Node::new(label, None));
+            child.children.push(old);
+        }
+        child.insert(&key[common..], value)
+    }
+
+    // Remove the value under `key`, and any nodes left with nothing
+    // under them, merging nodes left with a single child into it
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        if key.is_empty() {
+            return self.value.take();
+        }
+        let index = self.find(key[0]).ok()?;
+        let value = {
+            let child = &mut self.children[index];
+            if !key.starts_with(&child.label) {
+                return None;
+            }
+            child.remove(&key[child.label.len()..])
+        };
+
+        let child = &mut self.children[index];
+        if child.value.is_none() {
+            match child.children.len() {
+                0 => {
+                    self.children.remove(index);
+                }
+                1 => {
+                    let mut only = child.children.pop().unwrap();
+                    let mut label = mem::take(&mut child.label);
+                    label.extend_from_slice(&only.label);
+                    only.label = label;
+                    *child = only;
+                }
+                _ => {}
+            }
+        }
+        value
+    }
+}
+
+/// A map keyed by byte strings, like `Trie`, that stores each run of
+/// bytes that no other key branches from in a single node
+///
+/// This is also known as a PATRICIA tree. With sparse, long keys, such
+/// as IP prefixes or file paths, it needs far fewer nodes than a `Trie`,
+/// and so much less memory, while looking up a key is still O(length of
+/// the key). It has the same prefix queries as `Trie`.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut files = RadixTree::new();
+/// files.insert("/usr/lib/libc.so", 1);
+/// files.insert("/usr/lib/libm.so", 2);
+/// files.insert("/usr/bin/env", 3);
+///
+/// let libs: Vec<_> = files.iter_prefix("/usr/lib/").map(|(_, &id)| id).collect();
+/// assert_eq!(libs, vec![1, 2]);
+///
+/// files.insert("/usr", 0);
+/// let (prefix, value) = files.longest_prefix("/usr/share").unwrap();
+/// assert_eq!((prefix, *value), (&b"/usr"[..], 0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RadixTree<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> Default for RadixTree<V> {
+    fn default() -> RadixTree<V> {
+        RadixTree::new()
+    }
+}
+
+impl<V> RadixTree<V> {
+    /// Create an empty tree
+    pub fn new() -> RadixTree<V> {
+        RadixTree {
+            root: Node::new(vec![], None),
+            len: 0,
+        }
+    }
+
+    /// Insert `value` under `key`, and return the value it replaced, if
+    /// any
+    pub fn insert<K: AsRef<[u8]>>(&mut self, key: K, value: V) -> Option<V> {
+        let old = self.root.insert(key.as_ref(), value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Return the value under `key`, or `None` if it isn't in the tree
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&V> {
+        let mut node = &self.root;
+        let mut key = key.as_ref();
+        while !key.is_empty() {
+            let (child, rest) = node.descend(key)?;
+            node = child;
+            key = rest;
+        }
+        node.value.as_ref()
+    }
+
+    /// Return the value under `key` for modification, or `None` if it
+    /// isn't in the tree
+    pub fn get_mut<K: AsRef<[u8]>>(&mut self, key: K) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        let mut key = key.as_ref();
+        while !key.is_empty() {
+            let index = node.find(key[0]).ok()?;
+            let child = &mut node.children[index];
+            if !key.starts_with(&child.label) {
+                return None;
+            }
+            key = &key[child.label.len()..];
+            node = child;
+        }
+        node.value.as_mut()
+    }
+
+    /// Returns true if there is a value under `key`
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove the value under `key` and return it, or `None` if it isn't
+    /// in the tree
+    pub fn remove<K: AsRef<[u8]>>(&mut self, key: K) -> Option<V> {
+        let value = self.root.remove(key.as_ref());
+        if value.is_some() {
+            self.len -= 1;
+        }
+        value
+    }
+
+    /// Return the longest key in the tree that `key` starts with, along
+    /// with its value, or `None` if there is no such key
+    pub fn longest_prefix<'k, K: AsRef<[u8]> + ?Sized>(
+        &self,
+        key: &'k K,
+    ) -> Option<(&'k [u8], &V)> {
+        let full = key.as_ref();
+        let mut node = &self.root;
+        let mut key = full;
+        let mut best = node.value.as_ref().map(|value| (0, value));
+        while let Some((child, rest)) = node.descend(key) {
+            node = child;
+            key = rest;
+            if let Some(ref value) = node.value {
+                best = Some((full.len() - key.len(), value));
+            }
+        }
+        best.map(|(len, value)| (&full[..len], value))
+    }
+
+    /// Iterate over every entry in byte order of the keys
+    pub fn iter(&self) -> RadixIter<'_, V> {
+        RadixIter::new(Some(&self.root), vec![])
+    }
+
+    /// Iterate over the entries whose keys start with `prefix`, in byte
+    /// order of the keys
+    pub fn iter_prefix<K: AsRef<[u8]>>(&self, prefix: K) -> RadixIter<'_, V> {
+        let prefix = prefix.as_ref();
+        let mut node = &self.root;
+        let mut rest = prefix;
+        while !rest.is_empty() {
+            let child = match node.find(rest[0]) {
+                Ok(index) => &node.children[index],
+                Err(_) => return RadixIter::new(None, vec![]),
+            };
+            if child.label.starts_with(rest) {
+                // The prefix ends partway through this label, so
+                // everything under it matches
+                let mut key = prefix[..prefix.len() - rest.len()].to_vec();
+                key.extend_from_slice(&child.label);
+                return RadixIter::new(Some(child), key);
+            }
+            if !rest.starts_with(&child.label) {
+                return RadixIter::new(None, vec![]);
+            }
+            rest = &rest[child.label.len()..];
+            node = child;
+        }
+        RadixIter::new(Some(node), prefix.to_vec())
+    }
+
+    /// Remove every entry from the tree
+    pub fn clear(&mut self) {
+        self.root = Node::new(vec![], None);
+        self.len = 0;
+    }
+
+    /// Return the number of entries in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no entries in the tree
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// An iterator over the entries of a `RadixTree`, in byte order of the
+/// keys
+pub struct RadixIter<'a, V: 'a> {
+    // The node to start from, before it has been visited
+    start: Option<&'a Node<V>>,
+    // The nodes being visited, and the next child to visit of each
+    stack: Vec<(&'a Node<V>, usize)>,
+    key: Vec<u8>,
+}
+
+impl<'a, V> RadixIter<'a, V> {
+    fn new(start: Option<&'a Node<V>>, key: Vec<u8>) -> RadixIter<'a, V> {
+        RadixIter {
+            start,
+            stack: vec![],
+            key,
+        }
+    }
+}
+
+impl<'a, V> Iterator for RadixIter<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<(Vec<u8>, &'a V)> {
+        if let Some(node) = self.start.take() {
+            self.stack.push((node, 0));
+            if let Some(ref value) = node.value {
+                return Some((self.key.clone(), value));
+            }
+        }
+        loop {
+            let child = {
+                let &mut (node, ref mut next) = self.stack.last_mut()?;
+                let child = node.children.get(*next);
+                *next += 1;
+                child
+            };
+            match child {
+                Some(child) => {
+                    self.key.extend_from_slice(&child.label);
+                    self.stack.push((child, 0));
+                    if let Some(ref value) = child.value {
+                        return Some((self.key.clone(), value));
+                    }
+                }
+                None => {
+                    let (node, _) = self.stack.pop().unwrap();
+                    if !self.stack.is_empty() {
+                        let len = self.key.len() - node.label.len();
+                        self.key.truncate(len);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_radix_tree() {
+    let mut tree = RadixTree::new();
+    assert!(tree.is_empty());
+    assert_eq!(tree.insert("romane", 1), None);
+    assert_eq!(tree.insert("romanus", 2), None);
+    assert_eq!(tree.insert("romulus", 3), None);
+    assert_eq!(tree.insert("rubens", 4), None);
+    assert_eq!(tree.insert("rom", 5), None);
+    assert_eq!(tree.insert("romane", 6), Some(1));
+    assert_eq!(tree.len(), 5);
+
+    // "r" -> "om" -> "an" -> "e", "us"; "ulus"; "ubens"
+    assert_eq!(tree.root.children.len(), 1);
+    assert_eq!(tree.root.children[0].label, b"r");
+    assert_eq!(tree.root.children[0].children[0].label, b"om");
+
+    assert_eq!(tree.get("romanus"), Some(&2));
+    assert_eq!(tree.get("roman"), None);
+    assert_eq!(tree.get("romanusx"), None);
+    assert_eq!(tree.get("ro"), None);
+    *tree.get_mut("rubens").unwrap() += 10;
+    assert_eq!(tree.get_mut("rube"), None);
+    assert!(tree.contains_key("rom"));
+
+    assert_eq!(
+        tree.iter().collect::<Vec<_>>(),
+        vec![
+            (b"rom".to_vec(), &5),
+            (b"romane".to_vec(), &6),
+            (b"romanus".to_vec(), &2),
+            (b"romulus".to_vec(), &3),
+            (b"rubens".to_vec(), &14)
+        ]
+    );
+    let keys = |prefix| {
+        tree.iter_prefix(prefix)
+            .map(|(key, _)| String::from_utf8(key).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(keys("roma"), vec!["romane", "romanus"]);
+    assert_eq!(keys("rom"), vec!["rom", "romane", "romanus", "romulus"]);
+    assert_eq!(keys("ru"), vec!["rubens"]);
+    assert_eq!(keys("rubens"), vec!["rubens"]);
+    assert!(keys("rx").is_empty());
+    assert!(keys("romanusx").is_empty());
+    assert_eq!(keys("").len(), 5);
+
+    assert_eq!(tree.longest_prefix("romanes"), Some((&b"romane"[..], &6)));
+    assert_eq!(tree.longest_prefix("romanu"), Some((&b"rom"[..], &5)));
+    assert_eq!(tree.longest_prefix("ro"), None);
+}
+
+#[test]
+fn test_radix_tree_remove() {
+    let mut tree = RadixTree::new();
+    tree.insert("test", 1);
+    tree.insert("team", 2);
+    tree.insert("toast", 3);
+    assert_eq!(tree.remove("te"), None);
+    assert_eq!(tree.remove("toaster"), None);
+
+    // Removing "team" leaves "te" with a single child, so they merge
+    assert_eq!(tree.remove("team"), Some(2));
+    let t = &tree.root.children[0];
+    assert_eq!(t.label, b"t");
+    assert_eq!(t.children[0].label, b"est");
+    assert_eq!(tree.get("test"), Some(&1));
+
+    assert_eq!(tree.remove("toast"), Some(3));
+    assert_eq!(tree.root.children[0].label, b"test");
+    assert_eq!(tree.remove("test"), Some(1));
+    assert!(tree.root.children.is_empty());
+    assert!(tree.is_empty());
+
+    tree.insert("", 0);
+    tree.insert("a", 1);
+    assert_eq!(tree.longest_prefix("b"), Some((&b""[..], &0)));
+    tree.clear();
+    assert_eq!(tree.iter().count(), 0);
+}
@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+use std::mem;
+
+// One bucket for values at the last popped priority, and one for each
+// bit a priority can differ from it in
+const BUCKETS: usize = 65;
+
+/// A min-priority queue for integer priorities that never go below the
+/// last one popped, such as event times
+///
+/// Values are kept in buckets by the highest bit their priority differs
+/// from the last popped one in, so `push` is O(1), and `pop` is O(1)
+/// unless the current bucket has run out, when the next bucket is
+/// spread over the lower ones. Each value can only move down 64 times,
+/// so `pop` is O(log C) amortized, where C is the largest difference
+/// between priorities. Values with the same priority come out in the
+/// order they were pushed.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut events = RadixHeap::new();
+/// events.push(30, "draw");
+/// events.push(10, "dma");
+/// events.push(30, "vblank");
+/// assert_eq!(events.pop(), Some((10, "dma")));
+///
+/// // Handling an event can schedule more, no earlier than now
+/// events.push(20, "irq");
+/// assert_eq!(events.pop(), Some((20, "irq")));
+/// assert_eq!(events.pop(), Some((30, "draw")));
+/// assert_eq!(events.pop(), Some((30, "vblank")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RadixHeap<T> {
+    buckets: Vec<VecDeque<(u64, T)>>,
+    // The priority of the last value popped; nothing lower can be pushed
+    last: u64,
+    len: usize,
+}
+
+impl<T> Default for RadixHeap<T> {
+    fn default() -> RadixHeap<T> {
+        RadixHeap::new()
+    }
+}
+
+impl<T> RadixHeap<T> {
+    /// Create an empty queue
+    pub fn new() -> RadixHeap<T> {
+        RadixHeap {
+            buckets: (0..BUCKETS).map(|_| VecDeque::new()).collect(),
+            last: 0,
+            len: 0,
+        }
+    }
+
+    /// Add `value` with `priority`. Panics if `priority` is less than
+    /// the priority of the last value popped
+    pub fn push(&mut self, priority: u64, value: T) {
+        assert!(priority >= self.last, "priority is below the last popped");
+        let bucket = self.bucket(priority);
+        self.buckets[bucket].push_back((priority, value));
+        self.len += 1;
+    }
+
+    /// Remove the value with the smallest priority and return it, or
+    /// `None` if the queue is empty
+    pub fn pop(&mut self) -> Option<(u64, T)> {
+        if self.buckets[0].is_empty() {
+            self.refill()?;
+        }
+        self.len -= 1;
+        self.buckets[0].pop_front()
+    }
+
+    /// Return the value with the smallest priority, or `None` if the
+    /// queue is empty
+    pub fn peek(&self) -> Option<(u64, &T)> {
+        let bucket = self.buckets.iter().find(|bucket| !bucket.is_empty())?;
+        // The first of the smallest, to keep equal priorities in order
+        let mut min = &bucket[0];
+        for entry in bucket.iter().skip(1) {
+            if entry.0 < min.0 {
+                min = entry;
+            }
+        }
+        Some((min.0, &min.1))
+    }
+
+    /// Return the smallest priority that can still be pushed, which is
+    /// the priority of the last value popped
+    pub fn last(&self) -> u64 {
+        self.last
+    }
+
+    /// Remove every value from the queue. Priorities below `last` still
+    /// can't be pushed
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            bucket.clear();
+        }
+        self.len = 0;
+    }
+
+    /// Return the number of values in the queue
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn bucket(&self, priority: u64) -> usize {
+        (64 - (priority ^ self.last).leading_zeros()) as usize
+    }
+
+    // Move up to the smallest priority in the first non-empty bucket,
+    // and spread that bucket over the lower ones. The smallest values
+    // land in bucket 0
+    fn refill(&mut self) -> Option<()> {
+        let index = self.buckets.iter().position(|bucket| !bucket.is_empty())?;
+        let bucket = mem::take(&mut self.buckets[index]);
+        self.last = bucket.iter().map(|entry| entry.0).min().unwrap();
+        for (priority, value) in bucket {
+            let index = self.bucket(priority);
+            self.buckets[index].push_back((priority, value));
+        }
+        Some(())
+    }
+}
+
+#[test]
+fn test_radix_heap() {
+    let mut heap = RadixHeap::new();
+    assert!(heap.is_empty());
+    assert_eq!(heap.pop(), None);
+    assert_eq!(heap.peek(), None);
+
+    heap.push(u64::MAX, 'z');
+    heap.push(5, 'a');
+    heap.push(9, 'c');
+    heap.push(5, 'b');
+    assert_eq!(heap.len(), 4);
+    assert_eq!(heap.peek(), Some((5, &'a')));
+    assert_eq!(heap.pop(), Some((5, 'a')));
+    assert_eq!(heap.last(), 5);
+
+    heap.push(5, 'd');
+    heap.push(7, 'e');
+    assert_eq!(heap.pop(), Some((5, 'b')));
+    assert_eq!(heap.pop(), Some((5, 'd')));
+    assert_eq!(heap.peek(), Some((7, &'e')));
+    assert_eq!(heap.pop(), Some((7, 'e')));
+    assert_eq!(heap.pop(), Some((9, 'c')));
+    assert_eq!(heap.pop(), Some((u64::MAX, 'z')));
+    assert_eq!(heap.pop(), None);
+
+    heap.push(u64::MAX, 'y');
+    heap.clear();
+    assert!(heap.is_empty());
+    assert_eq!(heap.last(), u64::MAX);
+}
+
+#[test]
+fn test_radix_heap_order() {
+    use rng::{JitterRng, XorShiftRng};
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    // Simulate events scheduling later events, against a BinaryHeap
+    let mut rng = XorShiftRng::new(23);
+    let mut heap = RadixHeap::new();
+    let mut expected = BinaryHeap::new();
+    for id in 0..100u64 {
+        let time = rng.next_u64() % 50;
+        heap.push(time, id);
+        expected.push(Reverse((time, id)));
+    }
+    let mut id = 100;
+    while let Some((time, value)) = heap.pop() {
+        assert_eq!(expected.pop(), Some(Reverse((time, value))));
+        if id < 5000 {
+            for _ in 0..rng.next_u64() % 3 {
+                let later = time + (rng.next_u64() % 4) * (rng.next_u64() % 1000);
+                heap.push(later, id);
+                expected.push(Reverse((later, id)));
+                id += 1;
+            }
+        }
+        assert_eq!(heap.len(), expected.len());
+    }
+    assert!(expected.is_empty());
+}
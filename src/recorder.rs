@@ -0,0 +1,174 @@
+use clock::{Clock, Timer, TimerEvent};
+use ring::{Iter, RingBuffer};
+
+/// What happened in an `EventRecord`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Recorded {
+    /// A timer emitted an edge
+    Timer(TimerEvent),
+    /// A clock wrapped this many times
+    ClockWrap(u64),
+}
+
+/// One entry in an `EventRecorder`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct EventRecord {
+    /// The tick the event happened on
+    pub tick: u64,
+    /// The number the caller gave to the timer or clock
+    pub source: usize,
+    /// What happened
+    pub event: Recorded,
+}
+
+/// A bounded log of the timer events and clock wraps of a run, for
+/// debugging or test assertions
+///
+/// Timers and clocks are run through the recorder, which logs what they
+/// emit. Once the log is full, the oldest records are dropped to make
+/// room, so it always holds the most recent ones.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut recorder = EventRecorder::new(16);
+/// let mut timer = Timer::new(10, 0, 5);
+/// let mut clock = Clock::new(4);
+///
+/// while recorder.update_timer(0, &mut timer, 12).is_some() {}
+/// recorder.tick_clock(1, &mut clock, 12, 12);
+///
+/// let log: Vec<_> = recorder.iter().map(|r| (r.tick, r.event)).collect();
+/// assert_eq!(
+///     log,
+///     vec![
+///         (0, Recorded::Timer(TimerEvent::RisingEdge)),
+///         (5, Recorded::Timer(TimerEvent::FallingEdge)),
+///         (10, Recorded::Timer(TimerEvent::RisingEdge)),
+///         (12, Recorded::ClockWrap(3)),
+///     ]
+/// );
+/// ```
+pub struct EventRecorder {
+    log: RingBuffer<EventRecord>,
+    dropped: u64,
+}
+
+impl EventRecorder {
+    /// Create an empty recorder that keeps up to `size` records
+    pub fn new(size: usize) -> EventRecorder {
+        assert!(size > 0);
+        let blank = EventRecord {
+            tick: 0,
+            source: 0,
+            event: Recorded::ClockWrap(0),
+        };
+        EventRecorder {
+            log: RingBuffer::new(size, blank),
+            dropped: 0,
+        }
+    }
+
+    /// Add a record, dropping the oldest one if the log is full
+    pub fn record(&mut self, tick: u64, source: usize, event: Recorded) {
+        if self.log.capacity() == 0 {
+            self.log.pop_front();
+            self.dropped += 1;
+        }
+        self.log.push_back(EventRecord {
+            tick,
+            source,
+            event,
+        });
+    }
+
+    /// Run `timer` like `Timer::update`, and record the edge it emits
+    /// under `source`
+    pub fn update_timer<T>(
+        &mut self,
+        source: usize,
+        timer: &mut Timer<T>,
+        time: u64,
+    ) -> Option<TimerEvent> {
+        let (tick, event) = timer.update_with_tick(time)?;
+        self.record(tick, source, Recorded::Timer(event));
+        Some(event)
+    }
+
+    /// Advance `clock` by `n` ticks like `Clock::tick_by`, and record
+    /// its wraps under `source` at the absolute `time`, which should be
+    /// the tick the step ends on
+    pub fn tick_clock(&mut self, source: usize, clock: &mut Clock, time: u64, n: u64) -> u64 {
+        let wraps = clock.tick_by(n);
+        if wraps > 0 {
+            self.record(time, source, Recorded::ClockWrap(wraps));
+        }
+        wraps
+    }
+
+    /// Iterate over the records, oldest first
+    pub fn iter(&self) -> Iter<'_, EventRecord> {
+        self.log.iter()
+    }
+
+    /// Copy the records into a `Vec`, oldest first
+    pub fn to_vec(&self) -> Vec<EventRecord> {
+        self.log.to_vec()
+    }
+
+    /// Remove every record
+    pub fn clear(&mut self) {
+        self.log.clear();
+    }
+
+    /// Return the number of records held
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Returns true if there are no records
+    pub fn is_empty(&self) -> bool {
+        self.log.is_empty()
+    }
+
+    /// Return the number of records dropped to make room for newer
+    /// ones
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[test]
+fn test_event_recorder() {
+    let run = || {
+        let mut recorder = EventRecorder::new(4);
+        let mut timers = [Timer::new(10, 0, 0), Timer::new(15, 5, 5)];
+        for time in 0..40 {
+            for (i, timer) in timers.iter_mut().enumerate() {
+                while recorder.update_timer(i, timer, time).is_some() {}
+            }
+        }
+        recorder
+    };
+
+    let a = run();
+    let b = run();
+    assert_eq!(a.to_vec(), b.to_vec());
+    assert_eq!(a.len(), 4);
+    assert_eq!(a.dropped(), 4 + 5 - 4);
+
+    let last = a.iter().last().unwrap();
+    assert_eq!(
+        (last.tick, last.source, last.event),
+        (35, 1, Recorded::Timer(TimerEvent::RisingEdge))
+    );
+
+    let mut a = a;
+    a.clear();
+    assert!(a.is_empty());
+    let mut clock = Clock::new(10);
+    assert_eq!(a.tick_clock(7, &mut clock, 5, 5), 0);
+    assert!(a.is_empty());
+}
@@ -0,0 +1,795 @@
+use std::cmp;
+use std::ops::Index;
+
+/// Types whose values can be linearly interpolated, such as audio
+/// samples
+pub trait Lerp {
+    /// Returns the value `t` of the way from `self` to `other`, where
+    /// `t` is between 0 and 1
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &f32, t: f32) -> f32 {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &f64, t: f32) -> f64 {
+        self + (other - self) * f64::from(t)
+    }
+}
+
+/// Numeric types that can be converted to and from `f64` for filtering
+/// and analysis, such as audio samples
+pub trait Sample: Lerp + Clone {
+    /// Returns the value as an `f64`
+    fn to_f64(&self) -> f64;
+
+    /// Returns the closest value to `value`, saturating at the limits
+    /// of the type
+    fn from_f64(value: f64) -> Self;
+}
+
+impl Sample for f32 {
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+
+    fn from_f64(value: f64) -> f32 {
+        value as f32
+    }
+}
+
+impl Sample for f64 {
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn from_f64(value: f64) -> f64 {
+        value
+    }
+}
+
+macro_rules! impl_lerp_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Lerp for $t {
+                fn lerp(&self, other: &$t, t: f32) -> $t {
+                    let a = *self as f64;
+                    let b = *other as f64;
+                    (a + (b - a) * f64::from(t)).round() as $t
+                }
+            }
+
+            impl Sample for $t {
+                fn to_f64(&self) -> f64 {
+                    *self as f64
+                }
+
+                fn from_f64(value: f64) -> $t {
+                    value.round() as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_lerp_for_int!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+/// The values an `ElasticRingBuffer` is resampling, from oldest to
+/// newest. They may be split in two pieces in the underlying storage
+#[derive(Debug)]
+pub struct Samples<'a, T: 'a> {
+    first: &'a [T],
+    second: &'a [T],
+    // Used to view a single channel of interleaved frames
+    offset: usize,
+    stride: usize,
+    len: usize,
+}
+
+impl<'a, T> Samples<'a, T> {
+    /// Create a view of the values in `first` followed by the values
+    /// in `second`
+    pub fn new(first: &'a [T], second: &'a [T]) -> Samples<'a, T> {
+        Samples {
+            first,
+            second,
+            offset: 0,
+            stride: 1,
+            len: first.len() + second.len(),
+        }
+    }
+
+    // Returns a view of every `stride`-th value, starting at `offset`
+    fn strided(self, offset: usize, stride: usize) -> Samples<'a, T> {
+        Samples {
+            offset: self.offset + offset * self.stride,
+            stride: self.stride * stride,
+            len: (self.len.saturating_sub(offset) + stride - 1) / stride,
+            ..self
+        }
+    }
+
+    /// Returns the number of values
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the two pieces that make up the values. When the values
+    /// are a single channel of interleaved frames, the pieces hold all
+    /// of the channels
+    pub fn as_slices(&self) -> (&'a [T], &'a [T]) {
+        (self.first, self.second)
+    }
+
+    // Returns the two pieces when the values are all of them, in order,
+    // so they can be read straight from the slices
+    fn contiguous(&self) -> Option<(&'a [T], &'a [T])> {
+        if self.offset == 0 && self.stride == 1 {
+            Some((self.first, self.second))
+        } else {
+            None
+        }
+    }
+}
+
+// Derived impls would needlessly require `T: Copy`
+impl<'a, T> Clone for Samples<'a, T> {
+    fn clone(&self) -> Samples<'a, T> {
+        *self
+    }
+}
+
+impl<'a, T> Copy for Samples<'a, T> {}
+
+impl<'a, T> Index<usize> for Samples<'a, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        assert!(index < self.len);
+        let index = self.offset + index * self.stride;
+        if index < self.first.len() {
+            &self.first[index]
+        } else {
+            &self.second[index - self.first.len()]
+        }
+    }
+}
+
+/// Decides how an `ElasticRingBuffer` stretches or shrinks the values
+/// it has to fit the size of a request
+pub trait ResamplePolicy<T> {
+    /// Fill all of `output` using the values in `input`, exactly as
+    /// `resample` would, but without changing any state the policy
+    /// keeps. This is used to peek, and is only called when the lengths
+    /// differ and `input` is not empty
+    fn plan(&self, input: Samples<T>, output: &mut [T]);
+
+    /// Fill all of `output` like `plan`, and update any state the
+    /// policy keeps between requests. By default this is just `plan`
+    fn resample(&mut self, input: Samples<T>, output: &mut [T]) {
+        self.plan(input, output);
+    }
+}
+
+// Resample interleaved frames of `frame_size` values by running
+// `resample` over each channel separately, so that channels never get
+// mixed up. `scratch` holds one channel of output at a time
+pub(crate) fn resample_frames<T: Clone, F: FnMut(Samples<T>, &mut [T])>(
+    mut resample: F,
+    input: Samples<T>,
+    output: &mut [T],
+    frame_size: usize,
+    scratch: &mut Vec<T>,
+) {
+    if frame_size == 1 {
+        resample(input, output);
+        return;
+    }
+
+    for channel in 0..frame_size {
+        scratch.clear();
+        scratch.extend(output.iter().skip(channel).step_by(frame_size).cloned());
+        resample(input.strided(channel, frame_size), scratch);
+
+        let outputs = output.iter_mut().skip(channel).step_by(frame_size);
+        for (o, v) in outputs.zip(scratch.drain(..)) {
+            *o = v;
+        }
+    }
+}
+
+// Walks the positions `index * n / output_len` for each output index,
+// yielding the whole part and the remainder. Stepping this way avoids
+// a division per value, which dominates the cost of resampling cheap
+// types like `f32`
+struct Positions {
+    whole: usize,
+    rem: usize,
+    step_whole: usize,
+    step_rem: usize,
+    output_len: usize,
+}
+
+impl Positions {
+    fn new(n: usize, output_len: usize) -> Positions {
+        Positions::starting_at(n, output_len, 0)
+    }
+
+    // Positions from output index `index` on
+    fn starting_at(n: usize, output_len: usize, index: usize) -> Positions {
+        let start = index as u128 * n as u128;
+        Positions {
+            whole: (start / output_len as u128) as usize,
+            rem: (start % output_len as u128) as usize,
+            step_whole: n / output_len,
+            step_rem: n % output_len,
+            output_len,
+        }
+    }
+}
+
+// The first output index whose position is at or after input index
+// `whole`
+fn outputs_before(whole: usize, n: usize, output_len: usize) -> usize {
+    let n = n as u128;
+    ((whole as u128 * output_len as u128 + n - 1) / n) as usize
+}
+
+impl Iterator for Positions {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let current = (self.whole, self.rem);
+        self.whole += self.step_whole;
+        self.rem += self.step_rem;
+        if self.rem >= self.output_len {
+            self.rem -= self.output_len;
+            self.whole += 1;
+        }
+        Some(current)
+    }
+}
+
+/// Repeats values when stretching and drops evenly spaced values when
+/// shrinking; this is the default policy
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct NearestNeighbor;
+
+impl<T: Clone> ResamplePolicy<T> for NearestNeighbor {
+    fn plan(&self, input: Samples<T>, output: &mut [T]) {
+        let n = input.len();
+        let output_len = output.len();
+        if let Some((first, second)) = input.contiguous() {
+            // Read each piece straight from its slice, rather than
+            // checking which piece every value is in
+            let split = cmp::min(outputs_before(first.len(), n, output_len), output_len);
+            let (head, tail) = output.split_at_mut(split);
+            let positions = Positions::new(n, output_len);
+            for (o, (whole, _)) in head.iter_mut().zip(positions) {
+                *o = first[whole].clone();
+            }
+            let positions = Positions::starting_at(n, output_len, split);
+            for (o, (whole, _)) in tail.iter_mut().zip(positions) {
+                *o = second[whole - first.len()].clone();
+            }
+            return;
+        }
+
+        let positions = Positions::new(n, output_len);
+        for (o, (whole, _)) in output.iter_mut().zip(positions) {
+            *o = input[whole].clone();
+        }
+    }
+}
+
+/// Keeps the values in order, repeating the newest one when
+/// stretching and skipping the oldest ones when shrinking
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct HoldLast;
+
+impl<T: Clone> ResamplePolicy<T> for HoldLast {
+    fn plan(&self, input: Samples<T>, output: &mut [T]) {
+        let n = input.len();
+        let skip = n.saturating_sub(output.len());
+        for (index, o) in output.iter_mut().enumerate() {
+            *o = input[(skip + index).min(n - 1)].clone();
+        }
+    }
+}
+
+/// Linearly interpolates between neighboring values
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct Linear;
+
+// Interpolate `output`, which starts at output index `start`, from
+// `values`, which starts at input index `base`. Every position has to
+// have its next value in `values` too
+fn lerp_run<T: Lerp>(
+    values: &[T],
+    base: usize,
+    n: usize,
+    output: &mut [T],
+    start: usize,
+    output_len: usize,
+) {
+    let scale = 1.0 / output_len as f32;
+    let positions = Positions::starting_at(n, output_len, start);
+    for (o, (whole, rem)) in output.iter_mut().zip(positions) {
+        let pair = &values[whole - base..whole - base + 2];
+        *o = pair[0].lerp(&pair[1], rem as f32 * scale);
+    }
+}
+
+impl Linear {
+    // Interpolate the output from `start` on through `Samples`, for the
+    // values that straddle the pieces or have nothing after them
+    fn resample_from<T: Clone + Lerp>(
+        input: &Samples<T>,
+        output: &mut [T],
+        start: usize,
+        output_len: usize,
+    ) {
+        let n = input.len();
+        let scale = 1.0 / output_len as f32;
+        let positions = Positions::starting_at(n, output_len, start);
+        for (o, (whole, rem)) in output.iter_mut().zip(positions) {
+            let fraction = rem as f32 * scale;
+
+            *o = if whole + 1 < n {
+                input[whole].lerp(&input[whole + 1], fraction)
+            } else {
+                input[whole].clone()
+            };
+        }
+    }
+}
+
+impl<T: Clone + Lerp> ResamplePolicy<T> for Linear {
+    fn plan(&self, input: Samples<T>, output: &mut [T]) {
+        let n = input.len();
+        let output_len = output.len();
+        let (first, second) = match input.contiguous() {
+            Some(pieces) => pieces,
+            None => return Linear::resample_from(&input, output, 0, output_len),
+        };
+
+        // Interpolate within each piece straight from its slice. Only
+        // the outputs between the last value of `first` and the first
+        // of `second`, and after the newest value, go through `Samples`
+        let in_first = outputs_before(first.len().saturating_sub(1), n, output_len);
+        let seam = cmp::max(in_first, outputs_before(first.len(), n, output_len));
+        let in_second = cmp::max(seam, outputs_before(n - 1, n, output_len));
+        let (output, last) = output.split_at_mut(in_second);
+        let (output, second_run) = output.split_at_mut(seam);
+        let (first_run, straddle) = output.split_at_mut(in_first);
+
+        lerp_run(first, 0, n, first_run, 0, output_len);
+        Linear::resample_from(&input, straddle, in_first, output_len);
+        lerp_run(second, first.len(), n, second_run, seam, output_len);
+        Linear::resample_from(&input, last, in_second, output_len);
+    }
+}
+
+/// Keeps the values in order and, instead of cutting straight from
+/// one value to the next where values have to be dropped or repeated,
+/// blends the two sides of the cut over `fade_len` values. This avoids
+/// audible clicks in music at the cost of a little smearing
+///
+/// Stretching to more than twice the input length leaves nothing to
+/// blend, so those requests fall back to `Linear`.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Crossfade {
+    fade_len: usize,
+}
+
+impl Crossfade {
+    /// Create a policy that blends over `fade_len` values. The fade is
+    /// shortened when a request is too small to fit it
+    pub fn new(fade_len: usize) -> Crossfade {
+        Crossfade { fade_len }
+    }
+
+    /// Returns the configured length of the fade
+    pub fn fade_len(&self) -> usize {
+        self.fade_len
+    }
+}
+
+impl Default for Crossfade {
+    fn default() -> Crossfade {
+        Crossfade::new(32)
+    }
+}
+
+impl<T: Clone + Lerp> ResamplePolicy<T> for Crossfade {
+    fn plan(&self, input: Samples<T>, output: &mut [T]) {
+        let n = input.len();
+        let output_len = output.len();
+
+        // Output index `i` is `input[i]` before the fade and
+        // `input[i + n - output_len]` after it, so the output still
+        // starts on the oldest value and ends on the newest one. Both
+        // sides only exist in the range `start..end`
+        let (start, end) = if output_len < n {
+            (0, output_len)
+        } else {
+            (output_len - n, n)
+        };
+        if start >= end {
+            Linear.plan(input, output);
+            return;
+        }
+
+        let fade_len = cmp::max(cmp::min(self.fade_len, end - start), 1);
+        let fade_start = start + (end - start - fade_len) / 2;
+        for (index, o) in output.iter_mut().enumerate() {
+            let after = (index + n).wrapping_sub(output_len);
+            *o = if index < fade_start {
+                input[index].clone()
+            } else if index >= fade_start + fade_len {
+                input[after].clone()
+            } else {
+                let t = (index - fade_start + 1) as f32 / (fade_len + 1) as f32;
+                input[index].lerp(&input[after], t)
+            };
+        }
+    }
+}
+
+/// Time-stretches audio with WSOLA (waveform similarity overlap-add):
+/// the output is built from blocks of the input, and each block is
+/// taken from wherever near its ideal position it best lines up with
+/// the waveform that came before it, then crossfaded in. Stretched
+/// audio keeps its pitch and sounds far smoother than repeating
+/// values, at a much higher CPU cost
+///
+/// Inputs shorter than two blocks are too short to find any
+/// similarity in, so those requests fall back to `Linear`.
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Wsola {
+    block_len: usize,
+    search_len: usize,
+}
+
+impl Wsola {
+    /// Create a policy that works in blocks of `block_len` values, and
+    /// searches up to `search_len` values either side of each block's
+    /// ideal position. `block_len` must be at least 2
+    pub fn new(block_len: usize, search_len: usize) -> Wsola {
+        assert!(block_len >= 2);
+        Wsola {
+            block_len,
+            search_len,
+        }
+    }
+
+    /// Returns the length of each block
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Returns how far either side of its ideal position a block may
+    /// be taken from
+    pub fn search_len(&self) -> usize {
+        self.search_len
+    }
+}
+
+impl Default for Wsola {
+    fn default() -> Wsola {
+        Wsola::new(256, 128)
+    }
+}
+
+// How well `input[a..a + len]` matches `input[b..b + len]`, normalized
+// by the energy of the second range so louder ranges aren't favored
+fn similarity<T: Sample>(input: &Samples<T>, a: usize, b: usize, len: usize) -> f64 {
+    let mut dot = 0.0;
+    let mut energy = 0.0;
+    for j in 0..len {
+        let x = input[a + j].to_f64();
+        let y = input[b + j].to_f64();
+        dot += x * y;
+        energy += y * y;
+    }
+    dot / (energy + 1e-9).sqrt()
+}
+
+impl<T: Sample> ResamplePolicy<T> for Wsola {
+    fn plan(&self, input: Samples<T>, output: &mut [T]) {
+        let n = input.len();
+        let output_len = output.len();
+        let block_len = cmp::min(self.block_len, n / 2);
+        if block_len < 2 {
+            Linear.plan(input, output);
+            return;
+        }
+
+        // Where the input would continue if the previous block were
+        // simply carried on
+        let mut next = 0;
+        let mut out_pos = 0;
+        while out_pos < output_len {
+            let len = cmp::min(block_len, output_len - out_pos);
+            let ideal = out_pos * n / output_len;
+            let lowest = ideal.saturating_sub(self.search_len);
+            let highest = cmp::min(ideal + self.search_len, n - len);
+
+            let source = if out_pos == 0 {
+                0
+            } else if next + len > n {
+                cmp::min(ideal, n - len)
+            } else {
+                let mut best = cmp::min(lowest, highest);
+                let mut best_score = f64::MIN;
+                for candidate in lowest..=highest {
+                    let score = similarity(&input, next, candidate, len);
+                    if score > best_score {
+                        best = candidate;
+                        best_score = score;
+                    }
+                }
+                best
+            };
+
+            for j in 0..len {
+                let new = &input[source + j];
+                output[out_pos + j] = if out_pos == 0 || next + j >= n {
+                    new.clone()
+                } else {
+                    let t = (j + 1) as f32 / (len + 1) as f32;
+                    input[next + j].lerp(new, t)
+                };
+            }
+
+            next = source + len;
+            out_pos += len;
+        }
+    }
+}
+
+/// Band-limited resampling with a Blackman-windowed sinc kernel. This
+/// is the highest quality policy, and the most expensive one: each
+/// value costs about `4 * taps` multiplies when stretching, and more
+/// when shrinking, since the kernel widens to filter out frequencies
+/// that would otherwise alias
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Sinc {
+    taps: usize,
+}
+
+impl Sinc {
+    /// Create a policy whose kernel reaches `taps` input values either
+    /// side of each output value. More taps give a sharper filter.
+    /// `taps` must not be 0
+    pub fn new(taps: usize) -> Sinc {
+        assert!(taps > 0);
+        Sinc { taps }
+    }
+
+    /// Returns the number of taps either side of each output value
+    pub fn taps(&self) -> usize {
+        self.taps
+    }
+}
+
+impl Default for Sinc {
+    fn default() -> Sinc {
+        Sinc::new(8)
+    }
+}
+
+impl<T: Sample> ResamplePolicy<T> for Sinc {
+    fn plan(&self, input: Samples<T>, output: &mut [T]) {
+        use std::f64::consts::PI;
+
+        let n = input.len();
+        let ratio = n as f64 / output.len() as f64;
+        // Shrinking has to cut off everything above the new Nyquist
+        // frequency
+        let cutoff = f64::min(1.0, 1.0 / ratio);
+        let half_width = self.taps as f64 / cutoff;
+
+        for (index, o) in output.iter_mut().enumerate() {
+            let center = index as f64 * ratio;
+            let first = (center - half_width).ceil().max(0.0) as usize;
+            let last = cmp::min((center + half_width).floor() as usize, n - 1);
+
+            let mut total = 0.0;
+            let mut weights = 0.0;
+            for k in first..=last {
+                let distance = k as f64 - center;
+                let x = distance * cutoff * PI;
+                let sinc = if x == 0.0 { 1.0 } else { x.sin() / x };
+                let w = PI * distance / half_width;
+                let window = 0.42 + 0.5 * w.cos() + 0.08 * (2.0 * w).cos();
+
+                let weight = sinc * window;
+                total += weight * input[k].to_f64();
+                weights += weight;
+            }
+            *o = T::from_f64(total / weights);
+        }
+    }
+}
+
+#[test]
+fn test_policies() {
+    let input = Samples::new(&[1, 2], &[3, 4]);
+    let mut out3 = [0; 3];
+    let mut out6 = [0; 6];
+
+    NearestNeighbor.resample(input, &mut out3);
+    assert_eq!(out3, [1, 2, 3]);
+    NearestNeighbor.resample(input, &mut out6);
+    assert_eq!(out6, [1, 1, 2, 3, 3, 4]);
+
+    HoldLast.resample(input, &mut out3);
+    assert_eq!(out3, [2, 3, 4]);
+    HoldLast.resample(input, &mut out6);
+    assert_eq!(out6, [1, 2, 3, 4, 4, 4]);
+
+    Linear.resample(input, &mut out3);
+    assert_eq!(out3, [1, 2, 4]);
+    Linear.resample(Samples::new(&[0, 30], &[]), &mut out3);
+    assert_eq!(out3, [0, 20, 30]);
+
+    let ramp = [0, 10, 20, 30, 40, 50, 60, 70];
+    let input = Samples::new(&ramp, &[]);
+    let mut out5 = [0; 5];
+    Crossfade::new(3).resample(input, &mut out5);
+    assert_eq!(out5, [0, 18, 35, 53, 70]);
+    let mut out10 = [0; 10];
+    Crossfade::new(2).resample(input, &mut out10);
+    assert_eq!(out10, [0, 10, 20, 30, 33, 37, 40, 50, 60, 70]);
+    Crossfade::new(2).resample(Samples::new(&[1, 2], &[]), &mut out5);
+    assert_eq!(out5, [1, 1, 2, 2, 2]);
+}
+
+#[test]
+fn test_positions() {
+    for &(n, output_len) in &[(1, 1), (3, 7), (7, 3), (5, 5), (1000, 999)] {
+        let positions = Positions::new(n, output_len);
+        for (index, (whole, rem)) in positions.take(output_len).enumerate() {
+            assert_eq!(whole, index * n / output_len);
+            assert_eq!(rem, index * n % output_len);
+        }
+    }
+}
+
+#[test]
+fn test_contiguous_fast_path() {
+    use rng::{JitterRng, XorShiftRng};
+
+    // Reading the pieces straight from the slices gives the same values
+    // as working out each position in full, wherever the split is
+    let mut rng = XorShiftRng::new(31);
+    let values: Vec<f32> = (0..40).map(|_| (rng.next_u64() % 1000) as f32).collect();
+    for &(n, output_len) in &[(1, 5), (2, 3), (7, 3), (13, 40), (40, 17), (40, 40)] {
+        for split in 0..=n {
+            let input = Samples::new(&values[..split], &values[split..n]);
+            let mut expected = vec![0.0; output_len];
+            for (index, e) in expected.iter_mut().enumerate() {
+                let whole = index * n / output_len;
+                let t = (index * n % output_len) as f32 * (1.0 / output_len as f32);
+                *e = if whole + 1 < n {
+                    values[whole].lerp(&values[whole + 1], t)
+                } else {
+                    values[whole]
+                };
+            }
+            let mut out = vec![0.0; output_len];
+            Linear.resample(input, &mut out);
+            assert_eq!(out, expected);
+
+            let expected: Vec<f32> = (0..output_len)
+                .map(|index| values[index * n / output_len])
+                .collect();
+            NearestNeighbor.resample(input, &mut out);
+            assert_eq!(out, expected);
+        }
+    }
+}
+
+#[test]
+fn test_wsola() {
+    // A waveform with a period of 8
+    let wave: Vec<f32> = (0..64)
+        .map(|i| [0.0, 0.7, 1.0, 0.7, 0.0, -0.7, -1.0, -0.7][i % 8])
+        .collect();
+    let input = Samples::new(&wave, &[]);
+    let max_step = |values: &[f32]| {
+        values
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0, f32::max)
+    };
+
+    // Lining blocks up with the waveform keeps the output as smooth as
+    // the input, which repeating values doesn't manage
+    let mut stretched = vec![0.0; 100];
+    Wsola::new(8, 8).resample(input, &mut stretched);
+    assert_eq!(&stretched[..8], &wave[..8]);
+    assert!(max_step(&stretched) <= max_step(&wave) + 1e-6);
+
+    let mut shrunk = vec![0.0; 40];
+    Wsola::new(8, 8).resample(input, &mut shrunk);
+    assert!(max_step(&shrunk) <= max_step(&wave) + 1e-6);
+
+    // Same length is just a copy
+    let mut copy = vec![0.0; 64];
+    Wsola::new(8, 8).resample(input, &mut copy);
+    assert_eq!(copy, wave);
+
+    let mut out5 = [0i16; 5];
+    Wsola::default().resample(Samples::new(&[0, 40], &[]), &mut out5);
+    assert_eq!(out5, [0, 16, 32, 40, 40]);
+}
+
+#[test]
+fn test_sinc() {
+    let ramp: Vec<f64> = (0..32).map(f64::from).collect();
+    let input = Samples::new(&ramp[..20], &ramp[20..]);
+
+    // Whole positions land exactly on the input values
+    let mut copy = vec![0.0; 32];
+    Sinc::default().resample(input, &mut copy);
+    for (a, b) in copy.iter().zip(ramp.iter()) {
+        assert!((a - b).abs() < 1e-9);
+    }
+
+    // Away from the edges a ramp stays a ramp
+    let mut stretched = vec![0.0; 64];
+    Sinc::default().resample(input, &mut stretched);
+    for (index, v) in stretched.iter().enumerate().skip(16).take(32) {
+        assert!((v - index as f64 / 2.0).abs() < 0.01);
+    }
+
+    let mut out3 = [0i16; 3];
+    Sinc::new(2).resample(Samples::new(&[5; 6], &[]), &mut out3);
+    assert_eq!(out3, [5, 5, 5]);
+}
+
+#[test]
+fn test_resample_frames() {
+    let input = Samples::new(&[1, -1, 2], &[-2, 3, -3]);
+    let mut output = [0; 4];
+    let mut scratch = vec![];
+
+    resample_frames(
+        |i, o| NearestNeighbor.plan(i, o),
+        input,
+        &mut output,
+        2,
+        &mut scratch,
+    );
+    assert_eq!(output, [1, -1, 2, -2]);
+
+    let mut output = [0; 8];
+    resample_frames(
+        |i, o| HoldLast.plan(i, o),
+        input,
+        &mut output,
+        2,
+        &mut scratch,
+    );
+    assert_eq!(output, [1, -1, 2, -2, 3, -3, 3, -3]);
+}
+
+#[test]
+fn test_lerp() {
+    assert_eq!(1.0f32.lerp(&3.0, 0.25), 1.5);
+    assert_eq!(2.0f64.lerp(&-2.0, 0.5), 0.0);
+    assert_eq!(0i16.lerp(&100, 0.333), 33);
+    assert_eq!(200u8.lerp(&100, 0.5), 150);
+}
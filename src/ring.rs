@@ -1,3 +1,16 @@
+use std::cmp;
+use std::iter;
+use std::marker::PhantomData;
+use std::slice;
+use std::time::Duration;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::{self, Arbitrary, Unstructured};
+
+use aligned::AlignedBuffer;
+use error::Error;
+use resample::{resample_frames, Lerp, Linear, NearestNeighbor, ResamplePolicy, Samples};
+
 /// A FIFO buffer with fixed length
 ///
 /// Example:
@@ -13,10 +26,16 @@
 /// rb.pop_front_slice(&mut buf);
 /// assert_eq!(buf, [1, 2, 3]);
 /// ```
-pub struct RingBuffer<T: Clone> {
-    buffer: Box<[T]>,
+///
+/// By default the values are stored in a heap allocation owned by the
+/// buffer, but any storage that can be viewed as a slice may be used
+/// instead; see `RingBuffer::from_storage`.
+pub struct RingBuffer<T: Clone, S = Box<[T]>> {
+    buffer: S,
     read: usize,
     write: usize,
+    residency: Option<ResidencyTracker>,
+    _marker: PhantomData<T>,
 }
 
 impl<T: Clone> RingBuffer<T> {
@@ -32,31 +51,219 @@ impl<T: Clone> RingBuffer<T> {
             buffer: tmp_buf.into_boxed_slice(),
             read: 0,
             write: 0,
+            residency: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Create a new ring buffer that is completely filled with a copy
+    /// of `values`, in order. The buffer has no free slots afterwards.
+    /// Panics if `values` is empty; use `new` for an empty buffer
+    pub fn from_slice(values: &[T]) -> RingBuffer<T> {
+        assert!(!values.is_empty(), "no values to fill the buffer with");
+        let mut storage = Vec::with_capacity(values.len() + 1);
+        storage.extend_from_slice(values);
+        // The unused slot just needs some value in it
+        storage.push(values[0].clone());
+
+        RingBuffer {
+            buffer: storage.into_boxed_slice(),
+            read: 0,
+            write: values.len(),
+            residency: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Consume the buffer and return its contents as a `Vec`, from
+    /// front to back. The backing storage is reused
+    pub fn into_vec(self) -> Vec<T> {
+        let len = self.len();
+        let mut result = self.buffer.into_vec();
+        result.rotate_left(self.read);
+        result.truncate(len);
+        result
+    }
+}
+
+impl<T: Clone> RingBuffer<T, AlignedBuffer<T>> {
+    /// Create a new ring buffer that can hold up to `size` elements
+    /// and use `value` as the default value, with the backing storage
+    /// aligned to at least `align` bytes. `align` must be a power of
+    /// two
+    ///
+    /// ```rust
+    /// use j2ds::*;
+    ///
+    /// let mut rb = RingBuffer::new_aligned(1024, 0f32, 32);
+    /// let (ptr, _) = rb.raw_write_region();
+    /// assert_eq!(ptr as usize % 32, 0);
+    /// ```
+    pub fn new_aligned(size: usize, value: T, align: usize) -> RingBuffer<T, AlignedBuffer<T>> {
+        RingBuffer::from_storage(AlignedBuffer::new(size + 1, value, align))
+    }
+}
+
+impl<T: Clone, S: AsRef<[T]> + AsMut<[T]>> RingBuffer<T, S> {
+    /// Create an empty ring buffer that stores its values in
+    /// `storage`, which may be a static array, a borrowed slice, or
+    /// any other caller-managed memory. One slot of the storage is
+    /// reserved, so the buffer can hold up to `storage.len() - 1`
+    /// values. Panics if `storage` is empty
+    ///
+    /// ```rust
+    /// use j2ds::*;
+    ///
+    /// let mut storage = [0u8; 4];
+    /// let mut rb = RingBuffer::from_storage(&mut storage[..]);
+    /// assert_eq!(rb.max_len(), 3);
+    /// rb.push_back(1);
+    /// assert_eq!(rb.pop_front(), Some(1));
+    /// ```
+    pub fn from_storage(storage: S) -> RingBuffer<T, S> {
+        assert!(!storage.as_ref().is_empty());
+
+        RingBuffer {
+            buffer: storage,
+            read: 0,
+            write: 0,
+            residency: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Consume the buffer and return the underlying storage. The
+    /// values in the storage are in no particular order
+    pub fn into_storage(self) -> S {
+        self.buffer
+    }
+
+    /// Copy the contents of the buffer into a `Vec`, from front to
+    /// back, without removing them
+    pub fn to_vec(&self) -> Vec<T> {
+        let mut result = Vec::with_capacity(self.len());
+        let mut index = self.read;
+        while index != self.write {
+            result.push(self.buffer.as_ref()[index].clone());
+            index = self.advance_index(index, 1);
+        }
+        result
+    }
+
+    /// Returns an iterator over consecutive, possibly overlapping,
+    /// windows of `size` values at the front of the queue. Every
+    /// step yields the window as a pair of slices (which together hold
+    /// the values from front to back) and then removes the first
+    /// `hop` values from the queue. Iteration stops once fewer than
+    /// `size` values remain, leaving them in the queue for later
+    ///
+    /// `hop` must be at least 1 and no larger than `size`.
+    ///
+    /// ```rust
+    /// use j2ds::*;
+    ///
+    /// let mut rb = RingBuffer::new(10, 0u8);
+    /// rb.push_back_slice(&[1, 2, 3, 4, 5]);
+    ///
+    /// let mut windows = vec![];
+    /// for (first, second) in rb.windows(3, 2) {
+    ///     windows.push([first, second].concat());
+    /// }
+    /// assert_eq!(windows, vec![vec![1, 2, 3], vec![3, 4, 5]]);
+    /// assert_eq!(rb.to_vec(), vec![5]);
+    /// ```
+    pub fn windows(&mut self, size: usize, hop: usize) -> Windows<'_, T> {
+        assert!(hop > 0 && hop <= size);
+
+        Windows {
+            buffer: self.buffer.as_ref(),
+            read: &mut self.read,
+            write: self.write,
+            residency: self.residency.as_mut(),
+            size,
+            hop,
+        }
+    }
+
+    /// Returns an iterator over the values in the queue from front to
+    /// back, without removing them
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (first, second) = self.front_samples(self.len()).as_slices();
+        Iter {
+            inner: first.iter().chain(second.iter()),
+        }
+    }
+
+    /// Remove all values from the queue
+    pub fn clear(&mut self) {
+        let len = self.len();
+        self.read = self.write;
+        self.record_removed(len);
+    }
+
+    /// Rearrange the backing storage so that all of the values in the
+    /// queue are stored contiguously, and return them as a single
+    /// mutable slice from front to back. Values may be modified in
+    /// place (e.g. sorted) through the returned slice
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        if self.read > self.write {
+            self.realign();
         }
+        &mut self.buffer.as_mut()[self.read..self.write]
+    }
+
+    // Move the values so that the queue starts at the beginning of
+    // the storage
+    fn realign(&mut self) {
+        let len = self.len();
+        self.buffer.as_mut().rotate_left(self.read);
+        self.read = 0;
+        self.write = len;
     }
 
     /// Add `value` to the end of the queue. Returns false if there is
     /// not enough room in the queue
     pub fn push_back(&mut self, value: T) -> bool {
-        let next_write = self.advance_index(self.write, 1);
+        self.try_push_back(value).is_ok()
+    }
+
+    /// Add `value` to the end of the queue, or return `Error::Full`
+    /// if there is not enough room in the queue
+    pub fn try_push_back(&mut self, value: T) -> Result<(), Error> {
         if self.capacity() == 0 {
-            false
+            Err(Error::Full {
+                needed: 1,
+                available: 0,
+            })
         } else {
-            self.buffer[self.write] = value;
-            self.write = next_write;
-            true
+            self.buffer.as_mut()[self.write] = value;
+            self.write = self.advance_index(self.write, 1);
+            if let Some(r) = self.residency.as_mut() {
+                r.record_pushed(1);
+            }
+            Ok(())
         }
     }
 
     /// Remove the first value from the queue, or returns `None` if
     /// there are no values in the buffer
     pub fn pop_front(&mut self) -> Option<T> {
-        if self.len() == 0 {
-            None
+        self.try_pop_front().ok()
+    }
+
+    /// Remove the first value from the queue, or return
+    /// `Error::Empty` if there are no values in the buffer
+    pub fn try_pop_front(&mut self) -> Result<T, Error> {
+        if self.is_empty() {
+            Err(Error::Empty {
+                needed: 1,
+                available: 0,
+            })
         } else {
             let old_read = self.read;
             self.read = self.advance_index(self.read, 1);
-            Some(self.buffer[old_read].clone())
+            self.record_removed(1);
+            Ok(self.buffer.as_ref()[old_read].clone())
         }
     }
 
@@ -66,7 +273,7 @@ impl<T: Clone> RingBuffer<T> {
         if self.read == self.write {
             None
         } else {
-            Some(self.buffer[self.read].clone())
+            Some(self.buffer.as_ref()[self.read].clone())
         }
     }
 
@@ -74,14 +281,35 @@ impl<T: Clone> RingBuffer<T> {
     /// the queue then no values are added and the return value is
     /// false
     pub fn push_back_slice(&mut self, values: &[T]) -> bool {
-        if self.capacity() < values.len() {
-            false
+        self.try_push_back_slice(values).is_ok()
+    }
+
+    /// Add all `values` to the buffer. If there is not enough room in
+    /// the queue then no values are added and `Error::Full` is
+    /// returned
+    pub fn try_push_back_slice(&mut self, values: &[T]) -> Result<(), Error> {
+        let available = self.capacity();
+        if available < values.len() {
+            Err(Error::Full {
+                needed: values.len(),
+                available,
+            })
         } else {
-            for v in values.iter() {
-                let r = self.push_back(v.clone());
-                assert!(r);
+            // Copy in at most two runs rather than wrapping the index
+            // once per value
+            let total = self.buffer.as_ref().len();
+            let first = cmp::min(values.len(), total - self.write);
+            let (head, tail) = values.split_at(first);
+            let write = self.write;
+            let buffer = self.buffer.as_mut();
+            buffer[write..write + first].clone_from_slice(head);
+            buffer[..tail.len()].clone_from_slice(tail);
+
+            self.write = (write + values.len()) % total;
+            if let Some(r) = self.residency.as_mut() {
+                r.record_pushed(values.len());
             }
-            true
+            Ok(())
         }
     }
 
@@ -89,20 +317,134 @@ impl<T: Clone> RingBuffer<T> {
     /// slice. If there are not enough values in the queue then the
     /// output buffer is not modified and the function returns false
     pub fn pop_front_slice(&mut self, values: &mut [T]) -> bool {
-        if self.len() < values.len() {
-            false
+        self.try_pop_front_slice(values).is_ok()
+    }
+
+    /// Remove enough values from the buffer to fill the given
+    /// slice. If there are not enough values in the queue then the
+    /// output buffer is not modified and `Error::Empty` is returned
+    pub fn try_pop_front_slice(&mut self, values: &mut [T]) -> Result<(), Error> {
+        let available = self.len();
+        if available < values.len() {
+            Err(Error::Empty {
+                needed: values.len(),
+                available,
+            })
         } else {
-            for v in values.iter_mut() {
-                *v = self.pop_front().unwrap();
-            }
-            true
+            let (first, second) = self.front_samples(values.len()).as_slices();
+            let (head, tail) = values.split_at_mut(first.len());
+            head.clone_from_slice(first);
+            tail.clone_from_slice(second);
+
+            self.read = (self.read + values.len()) % self.buffer.as_ref().len();
+            self.record_removed(values.len());
+            Ok(())
+        }
+    }
+
+    /// Returns a pointer to the first free slot and the number of
+    /// free slots that directly follow it in memory, so that a DMA
+    /// controller or foreign code can write values into the buffer in
+    /// place. Call `advance_write` afterwards to add them to the
+    /// queue. There may be more free slots at the start of the
+    /// storage once the returned region has been used
+    ///
+    /// The pointer is only valid until the buffer is next modified,
+    /// moved, or dropped.
+    pub fn raw_write_region(&mut self) -> (*mut T, usize) {
+        let total = self.buffer.as_ref().len();
+        let len = if self.write >= self.read {
+            // The slot just before `read` must stay unused
+            total - self.write - if self.read == 0 { 1 } else { 0 }
+        } else {
+            self.read - self.write - 1
+        };
+        let ptr = self.buffer.as_mut()[self.write..].as_mut_ptr();
+        (ptr, len)
+    }
+
+    /// Add the first `n` values of the region returned by
+    /// `raw_write_region` to the end of the queue. Panics if `n` is
+    /// larger than that region
+    pub fn advance_write(&mut self, n: usize) {
+        let (_, len) = self.raw_write_region();
+        assert!(n <= len);
+        self.write = (self.write + n) % self.buffer.as_ref().len();
+        if let Some(r) = self.residency.as_mut() {
+            r.record_pushed(n);
+        }
+    }
+
+    /// Returns a pointer to the first value in the queue and the
+    /// number of values that directly follow it in memory, so that a
+    /// DMA controller or foreign code can read them in place. Call
+    /// `advance_read` afterwards to remove them from the queue. There
+    /// may be more values at the start of the storage once the
+    /// returned region has been used
+    ///
+    /// The pointer is only valid until the buffer is next modified,
+    /// moved, or dropped.
+    pub fn raw_read_region(&self) -> (*const T, usize) {
+        let len = if self.read <= self.write {
+            self.write - self.read
+        } else {
+            self.buffer.as_ref().len() - self.read
+        };
+        let ptr = self.buffer.as_ref()[self.read..].as_ptr();
+        (ptr, len)
+    }
+
+    /// Remove the first `n` values of the region returned by
+    /// `raw_read_region` from the queue. Panics if `n` is larger than
+    /// that region
+    pub fn advance_read(&mut self, n: usize) {
+        let (_, len) = self.raw_read_region();
+        assert!(n <= len);
+        self.read = (self.read + n) % self.buffer.as_ref().len();
+        self.record_removed(n);
+    }
+
+    /// Start measuring how long values stay in the buffer; see
+    /// `Residency`. Values already in the buffer are treated as if
+    /// they had just been added. Calling this again resets the
+    /// measurements
+    pub fn track_residency(&mut self) {
+        self.residency = Some(ResidencyTracker {
+            pushed: self.len() as u64,
+            popped: 0,
+            stats: Residency::default(),
+        });
+    }
+
+    /// Returns the residency measurements so far, or `None` if
+    /// `track_residency` was never called
+    pub fn residency(&self) -> Option<Residency> {
+        self.residency.map(|r| r.stats)
+    }
+
+    // Returns the first `n` values in the queue, which must exist
+    fn front_samples(&self, n: usize) -> Samples<'_, T> {
+        let buffer = self.buffer.as_ref();
+        let end = self.read + n;
+        if end <= buffer.len() {
+            Samples::new(&buffer[self.read..end], &[])
+        } else {
+            Samples::new(&buffer[self.read..], &buffer[..end - buffer.len()])
+        }
+    }
+
+    #[inline]
+    fn record_removed(&mut self, n: usize) {
+        if let Some(r) = self.residency.as_mut() {
+            r.record_removed(n);
         }
     }
 
     #[inline]
     fn advance_index(&self, index: usize, amount: isize) -> usize {
-        assert!((amount.abs() as usize) < self.buffer.len());
-        (index as isize + amount) as usize % self.buffer.len()
+        let len = self.buffer.as_ref().len();
+        assert!(amount.unsigned_abs() < len);
+        (index as isize + amount) as usize % len
     }
 
     /// Returns the number of values in the buffer
@@ -111,6 +453,12 @@ impl<T: Clone> RingBuffer<T> {
         self.max_len() - self.capacity()
     }
 
+    /// Returns true if there are no values in the buffer
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.read == self.write
+    }
+
     /// Returns the number of free slots in the buffer
     #[inline]
     pub fn capacity(&self) -> usize {
@@ -125,7 +473,156 @@ impl<T: Clone> RingBuffer<T> {
     /// the buffer
     #[inline]
     pub fn max_len(&self) -> usize {
-        self.buffer.len() - 1
+        self.buffer.as_ref().len() - 1
+    }
+}
+
+/// Statistics about how long values stayed in a buffer before being
+/// removed, enabled with `track_residency`
+///
+/// The residency of a value is the number of values that were added
+/// to the buffer after it, up until it was removed. For a stream of
+/// samples this is the latency the buffer added, in samples.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct Residency {
+    /// The number of values removed since tracking started
+    pub count: u64,
+    /// The sum of the residencies of all removed values
+    pub total: u64,
+    /// The longest residency of any removed value
+    pub max: u64,
+}
+
+impl Residency {
+    /// Returns the average residency of the removed values, or `None`
+    /// if no values have been removed yet
+    pub fn average(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.total as f64 / self.count as f64)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ResidencyTracker {
+    pushed: u64,
+    popped: u64,
+    stats: Residency,
+}
+
+impl ResidencyTracker {
+    fn record_pushed(&mut self, n: usize) {
+        self.pushed += n as u64;
+    }
+
+    fn record_removed(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        // Values leave in the same order they arrived, so the k-th
+        // removed value was also the k-th one added
+        let n = n as u64;
+        let oldest = self.pushed - self.popped - 1;
+        self.stats.count += n;
+        self.stats.total += n * oldest - n * (n - 1) / 2;
+        self.stats.max = self.stats.max.max(oldest);
+        self.popped += n;
+    }
+}
+
+/// An iterator over overlapping windows of a `RingBuffer`, created by
+/// `RingBuffer::windows`
+pub struct Windows<'a, T: 'a> {
+    buffer: &'a [T],
+    read: &'a mut usize,
+    write: usize,
+    residency: Option<&'a mut ResidencyTracker>,
+    size: usize,
+    hop: usize,
+}
+
+impl<'a, T> Iterator for Windows<'a, T> {
+    type Item = (&'a [T], &'a [T]);
+
+    fn next(&mut self) -> Option<(&'a [T], &'a [T])> {
+        let read = *self.read;
+        let total = self.buffer.len();
+        let len = (self.write + total - read) % total;
+        if len < self.size {
+            return None;
+        }
+
+        let end = read + self.size;
+        let window = if end <= total {
+            (&self.buffer[read..end], &self.buffer[0..0])
+        } else {
+            (&self.buffer[read..], &self.buffer[..end - total])
+        };
+        *self.read = (read + self.hop) % total;
+        if let Some(r) = self.residency.as_mut() {
+            r.record_removed(self.hop);
+        }
+        Some(window)
+    }
+}
+
+/// An iterator over the values of a `RingBuffer`, created by
+/// `RingBuffer::iter`
+pub struct Iter<'a, T: 'a> {
+    inner: iter::Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T: Clone + Default> From<Vec<T>> for RingBuffer<T> {
+    /// Turn `values` into a full ring buffer, reusing the `Vec`'s
+    /// allocation when possible
+    fn from(mut values: Vec<T>) -> RingBuffer<T> {
+        let write = values.len();
+        values.push(T::default());
+
+        RingBuffer {
+            buffer: values.into_boxed_slice(),
+            read: 0,
+            write,
+            residency: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: Clone + Arbitrary<'a>> Arbitrary<'a> for RingBuffer<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<RingBuffer<T>> {
+        let mut storage = Vec::<T>::arbitrary(u)?;
+        if storage.is_empty() {
+            storage.push(T::arbitrary(u)?);
+        }
+        // Any pair of cursors is a valid state, including ones where
+        // the values wrap around the end of the storage
+        let last = storage.len() - 1;
+        let read = u.int_in_range(0..=last)?;
+        let write = u.int_in_range(0..=last)?;
+
+        Ok(RingBuffer {
+            buffer: storage.into_boxed_slice(),
+            read,
+            write,
+            residency: None,
+            _marker: PhantomData,
+        })
     }
 }
 
@@ -193,6 +690,208 @@ fn test_slices() {
     }
 }
 
+#[test]
+fn test_from_storage() {
+    let mut storage = [0u8; 4];
+    {
+        let mut rb = RingBuffer::from_storage(&mut storage[..]);
+        assert_eq!(rb.max_len(), 3);
+        assert!(rb.push_back_slice(&[1, 2, 3]));
+        assert!(!rb.push_back(4));
+        assert_eq!(rb.pop_front(), Some(1));
+        assert!(rb.push_back(4));
+        assert_eq!(rb.to_vec(), vec![2, 3, 4]);
+    }
+    assert_eq!(storage, [1, 2, 3, 4]);
+
+    let mut rb = RingBuffer::from_storage([0u8; 3]);
+    assert!(rb.push_back_slice(&[5, 6]));
+    assert_eq!(rb.into_storage(), [5, 6, 0]);
+}
+
+#[test]
+fn test_make_contiguous() {
+    let mut rb = RingBuffer::new(4, 0u8);
+    assert!(rb.make_contiguous().is_empty());
+
+    assert!(rb.push_back_slice(&[9, 8, 7]));
+    assert_eq!(rb.make_contiguous(), &mut [9, 8, 7]);
+
+    // Wrap the write cursor so the values are split in the storage
+    assert_eq!(rb.pop_front(), Some(9));
+    assert!(rb.push_back_slice(&[6, 5]));
+    rb.make_contiguous().sort();
+    assert_eq!(rb.to_vec(), vec![5, 6, 7, 8]);
+
+    assert!(!rb.push_back(4));
+    assert_eq!(rb.pop_front(), Some(5));
+    assert!(rb.push_back(4));
+    assert_eq!(rb.make_contiguous(), &mut [6, 7, 8, 4]);
+}
+
+#[test]
+fn test_iter_clear() {
+    let mut rb = RingBuffer::new(4, 0u8);
+    rb.push_back_slice(&[1, 2, 3]);
+    rb.pop_front_slice(&mut [0; 2]);
+    rb.push_back_slice(&[4, 5, 6]);
+    assert_eq!(rb.iter().cloned().collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+    assert_eq!(rb.iter().size_hint(), (4, Some(4)));
+
+    rb.track_residency();
+    rb.clear();
+    assert!(rb.is_empty());
+    assert_eq!(rb.iter().next(), None);
+    assert_eq!(rb.residency().unwrap().count, 4);
+    assert!(rb.push_back_slice(&[7, 8, 9, 10]));
+}
+
+#[test]
+fn test_windows() {
+    let mut rb = RingBuffer::new(6, 0u8);
+    assert!(rb.push_back_slice(&[0, 0, 0, 1, 2, 3]));
+    assert!(rb.pop_front_slice(&mut [0; 3]));
+    // Wrap the values around the end of the storage
+    assert!(rb.push_back_slice(&[4, 5, 6]));
+
+    let windows: Vec<Vec<u8>> = rb.windows(4, 1).map(|(a, b)| [a, b].concat()).collect();
+    assert_eq!(
+        windows,
+        vec![vec![1, 2, 3, 4], vec![2, 3, 4, 5], vec![3, 4, 5, 6]]
+    );
+    assert_eq!(rb.to_vec(), vec![4, 5, 6]);
+
+    assert_eq!(rb.windows(4, 4).count(), 0);
+    assert!(rb.push_back(7));
+    assert_eq!(rb.windows(2, 2).count(), 2);
+    assert!(rb.is_empty());
+}
+
+#[test]
+fn test_residency() {
+    let mut rb = RingBuffer::new(8, 0u8);
+    assert_eq!(rb.residency(), None);
+    assert!(rb.push_back(1));
+    rb.track_residency();
+
+    assert!(rb.push_back_slice(&[2, 3, 4]));
+    // 1 saw three pushes after tracking started, 2 saw two more
+    assert!(rb.pop_front_slice(&mut [0; 2]));
+    let r = rb.residency().unwrap();
+    assert_eq!((r.count, r.total, r.max), (2, 5, 3));
+
+    assert!(rb.push_back(5));
+    // 3 saw two pushes, 4 saw one, and 5 saw none
+    assert_eq!(rb.windows(2, 2).count(), 1);
+    assert_eq!(rb.pop_front(), Some(5));
+    let r = rb.residency().unwrap();
+    assert_eq!((r.count, r.total, r.max), (5, 8, 3));
+    assert_eq!(r.average(), Some(8.0 / 5.0));
+}
+
+#[test]
+fn test_raw_regions() {
+    use std::ptr;
+
+    let mut rb = RingBuffer::new(4, 0u8);
+    let (_, len) = rb.raw_read_region();
+    assert_eq!(len, 0);
+
+    let (dst, len) = rb.raw_write_region();
+    assert_eq!(len, 4);
+    unsafe { ptr::copy_nonoverlapping([1u8, 2, 3].as_ptr(), dst, 3) };
+    rb.advance_write(3);
+    assert_eq!(rb.to_vec(), vec![1, 2, 3]);
+
+    let (src, len) = rb.raw_read_region();
+    assert_eq!(len, 3);
+    assert_eq!(unsafe { *src }, 1);
+    rb.advance_read(2);
+    assert_eq!(rb.to_vec(), vec![3]);
+
+    // The free space now wraps around the end of the storage
+    let (_, len) = rb.raw_write_region();
+    assert_eq!(len, 2);
+    rb.advance_write(2);
+    let (dst, len) = rb.raw_write_region();
+    assert_eq!(len, 1);
+    unsafe { *dst = 9 };
+    rb.advance_write(1);
+    assert_eq!(rb.capacity(), 0);
+
+    let (_, len) = rb.raw_read_region();
+    assert_eq!(len, 3);
+    rb.advance_read(3);
+    assert_eq!(rb.pop_front(), Some(9));
+}
+
+#[test]
+fn test_try_errors() {
+    let mut rb = RingBuffer::new(3, 0u8);
+    let mut buf2 = [0u8; 2];
+
+    assert_eq!(
+        rb.try_pop_front(),
+        Err(Error::Empty {
+            needed: 1,
+            available: 0
+        })
+    );
+    assert_eq!(rb.try_push_back_slice(&[1, 2]), Ok(()));
+    assert_eq!(
+        rb.try_push_back_slice(&[3, 4]),
+        Err(Error::Full {
+            needed: 2,
+            available: 1
+        })
+    );
+    assert_eq!(rb.try_push_back(3), Ok(()));
+    assert_eq!(
+        rb.try_push_back(4),
+        Err(Error::Full {
+            needed: 1,
+            available: 0
+        })
+    );
+    assert_eq!(rb.try_pop_front_slice(&mut buf2), Ok(()));
+    assert_eq!(buf2, [1, 2]);
+    assert_eq!(
+        rb.try_pop_front_slice(&mut buf2),
+        Err(Error::Empty {
+            needed: 2,
+            available: 1
+        })
+    );
+    assert_eq!(rb.try_pop_front(), Ok(3));
+}
+
+#[test]
+fn test_conversions() {
+    let mut rb = RingBuffer::from_slice(&[1u8, 2, 3]);
+    assert_eq!(rb.len(), 3);
+    assert_eq!(rb.capacity(), 0);
+    assert_eq!(rb.to_vec(), vec![1, 2, 3]);
+
+    // Wrap the cursors around the end of the backing storage
+    assert_eq!(rb.pop_front(), Some(1));
+    assert_eq!(rb.pop_front(), Some(2));
+    assert!(rb.push_back_slice(&[4, 5]));
+    assert_eq!(rb.to_vec(), vec![3, 4, 5]);
+    assert_eq!(rb.into_vec(), vec![3, 4, 5]);
+
+    // No `Default` is needed to copy values in
+    #[derive(Debug, Eq, PartialEq, Clone)]
+    struct Id(u32);
+    let mut rb = RingBuffer::from_slice(&[Id(7), Id(8)]);
+    assert_eq!(rb.pop_front(), Some(Id(7)));
+    assert_eq!(rb.to_vec(), vec![Id(8)]);
+
+    let rb = RingBuffer::from(Vec::<u8>::new());
+    assert!(rb.is_empty());
+    assert_eq!(rb.max_len(), 0);
+    assert_eq!(rb.into_vec(), vec![]);
+}
+
 /// A FIFO buffer with a fixed length that adjusts to requests that
 /// would otherwise overflow or underflow.
 ///
@@ -203,10 +902,36 @@ fn test_slices() {
 /// And when the buffer is getting too full (past its ideal max
 /// length), elements will be uniformly dropped to return the queue to
 /// its ideal length.
-pub struct ElasticRingBuffer<T: Clone> {
+///
+/// How values are repeated or dropped is decided by a
+/// `ResamplePolicy`, which is `NearestNeighbor` by default.
+///
+/// Optionally, an ideal min length can be set as well. When the
+/// buffer is about to drop below it, requests are filled with slightly
+/// fewer elements than asked for (stretching them), and this continues
+/// until the buffer is back halfway between the ideal min and max
+/// lengths. That way the buffer settles instead of alternating
+/// between stretching and exact requests.
+///
+/// For interleaved multichannel data, a frame size can be set so that
+/// only whole frames are ever stretched or dropped, and each channel
+/// is resampled on its own.
+pub struct ElasticRingBuffer<T: Clone, P = NearestNeighbor> {
     rb: RingBuffer<T>,
     ideal_max: usize,
+    ideal_min: usize,
+    // Set while recovering from dropping below `ideal_min`
+    refilling: bool,
+    stats: ElasticStats,
+    frame_size: usize,
+    sample_rate: Option<u32>,
+    scratch: Vec<T>,
+    adaptive: Option<AdaptiveIdealMax>,
+    event_hook: Option<EventHook>,
+    // How many requests have been filled, used to timestamp events
+    requests: u64,
     default_value: T,
+    policy: P,
 }
 
 /// Indicates what happened when the queue tried to satisfy the
@@ -230,55 +955,85 @@ pub enum ElasticPopResult {
     Downsampled(usize),
 }
 
-impl<T: Clone> ElasticRingBuffer<T> {
-    /// Create a new `ElasticRingBuffer` with the given size. `value`
-    /// will be used as the default value for the
-    /// queue. `ideal_max_len` is the threshold where the buffer will
-    /// begin dropping elements during requests
-    pub fn new(size: usize, value: T, ideal_max_len: usize) -> ElasticRingBuffer<T> {
-        ElasticRingBuffer {
-            rb: RingBuffer::new(size, value.clone()),
-            default_value: value,
-            ideal_max: ideal_max_len,
-        }
+// Decides how many of the `buffer_len` real elements should be used to
+// fill a request for `values_len` elements, or `None` if they can be
+// used as-is. Also returns whether the buffer is refilling afterwards
+pub(crate) fn plan_pop(
+    buffer_len: usize,
+    values_len: usize,
+    ideal_max: usize,
+    ideal_min: usize,
+    frame_size: usize,
+    refilling: bool,
+) -> (Option<usize>, bool) {
+    assert!(values_len % frame_size == 0);
+
+    let buffer_len = buffer_len / frame_size * frame_size;
+    if values_len > buffer_len {
+        return (Some(buffer_len), ideal_min > 0);
     }
 
-    /// Fill `values` with elements. See `ElasticPopResult` for the
-    /// possible outcomes of this request.
-    pub fn pop_front_slice(&mut self, values: &mut [T]) -> ElasticPopResult {
-        let buffer_len = self.rb.len();
-        let values_len = values.len();
-        if values_len <= buffer_len {
-            if buffer_len - values_len < self.ideal_max {
-                let r = self.rb.pop_front_slice(values);
-                assert!(r);
-                ElasticPopResult::Exact
-            } else {
-                let total_sample_size = (buffer_len - self.ideal_max) + values_len;
-                self.sample_n(values, total_sample_size)
-            }
+    let remaining = buffer_len - values_len;
+    if remaining >= ideal_max {
+        // Requests larger than the ideal max can't leave it behind,
+        // so they just use everything
+        let n = cmp::min(buffer_len - ideal_max + values_len, buffer_len);
+        let n = n / frame_size * frame_size;
+        return (if n > values_len { Some(n) } else { None }, false);
+    }
+
+    let mut refilling = refilling;
+    if remaining < ideal_min {
+        refilling = true;
+    } else if remaining >= (ideal_min + ideal_max) / 2 {
+        refilling = false;
+    }
+
+    let size = if refilling && values_len > 0 {
+        // Hold back up to an eighth of the request so the buffer
+        // refills gradually rather than in one audible jump. A request
+        // for a single frame has nothing to hold back, so it is used
+        // as-is
+        let step = cmp::max(frame_size, values_len / 8 / frame_size * frame_size);
+        let n = cmp::max(frame_size, values_len - step);
+        if n < values_len {
+            Some(n)
         } else {
-            self.sample_n(values, buffer_len)
+            None
         }
-    }
+    } else {
+        None
+    };
+    (size, refilling)
+}
 
-    fn sample_n(&mut self, values: &mut [T], n: usize) -> ElasticPopResult {
-        if n == 0 {
-            for i in values.iter_mut() {
-                *i = self.default_value.clone();
+// Fill `values` using `size` of the real elements at the front of `rb`
+// as planned by `plan_pop`, without removing any of them. `resample`
+// stretches or shrinks them when needed
+fn fill<T: Clone, F: FnOnce(Samples<T>, &mut [T])>(
+    rb: &RingBuffer<T>,
+    default_value: &T,
+    values: &mut [T],
+    size: Option<usize>,
+    resample: F,
+) -> ElasticPopResult {
+    let values_len = values.len();
+    match size {
+        None => {
+            let (first, second) = rb.front_samples(values_len).as_slices();
+            let (head, tail) = values.split_at_mut(first.len());
+            head.clone_from_slice(first);
+            tail.clone_from_slice(second);
+            ElasticPopResult::Exact
+        }
+        Some(0) => {
+            for v in values.iter_mut() {
+                *v = default_value.clone();
             }
             ElasticPopResult::Empty
-        } else {
-            let values_len = values.len();
-            for (index, i) in values.iter_mut().enumerate() {
-                let peek_index = self
-                    .rb
-                    .advance_index(self.rb.read, (index * n / values_len) as isize);
-                *i = self.rb.buffer[peek_index].clone();
-            }
-
-            self.rb.read = self.rb.advance_index(self.rb.read, n as isize);
-
+        }
+        Some(n) => {
+            resample(rb.front_samples(n), values);
             if values_len > n {
                 ElasticPopResult::Upsampled(n)
             } else {
@@ -286,34 +1041,875 @@ impl<T: Clone> ElasticRingBuffer<T> {
             }
         }
     }
+}
 
-    /// Add all `values` to the buffer. If there is not enough room in
-    /// the queue then no values are added and the return value is
-    /// false
-    pub fn push_back_slice(&mut self, values: &[T]) -> bool {
-        self.rb.push_back_slice(values)
+impl ElasticPopResult {
+    // Returns how many elements were taken from the queue to fill a
+    // request for `values_len` elements
+    fn consumed(self, values_len: usize) -> usize {
+        match self {
+            ElasticPopResult::Empty => 0,
+            ElasticPopResult::Exact => values_len,
+            ElasticPopResult::Upsampled(n) | ElasticPopResult::Downsampled(n) => n,
+        }
     }
+}
 
-    /// Add `value` to the end of the queue. Returns false if there is
-    /// not enough room in the queue
-    pub fn push_back(&mut self, value: T) -> bool {
-        self.rb.push_back(value)
-    }
+/// Running totals of the corrections an `ElasticRingBuffer` has made,
+/// useful for tuning its thresholds and for spotting clock drift
+/// between the producer and consumer
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct ElasticStats {
+    /// Requests that were satisfied exactly
+    pub exact: u64,
+    /// Requests where elements were stretched
+    pub upsampled: u64,
+    /// Requests where elements were shrunk
+    pub downsampled: u64,
+    /// Requests made while the buffer was completely empty
+    pub empty: u64,
+    /// Elements produced beyond the real ones, by stretching or by
+    /// filling with the default value
+    pub duplicated: u64,
+    /// Real elements discarded by shrinking, on either the pop or
+    /// push side
+    pub dropped: u64,
+    /// A smoothed estimate of how much the buffer's length changes
+    /// from one request to the next. Positive values mean the
+    /// producer is outpacing the consumer
+    pub trend: f64,
+    // The length seen at the start of the previous request
+    last_len: Option<usize>,
+}
 
-    /// Returns the number of values in the buffer
-    pub fn len(&self) -> usize {
-        self.rb.len()
+impl ElasticStats {
+    fn observe_len(&mut self, len: usize) {
+        if let Some(last) = self.last_len {
+            let delta = len as f64 - last as f64;
+            self.trend += (delta - self.trend) * 0.1;
+        }
+        self.last_len = Some(len);
     }
 
-    /// Returns the number of free slots in the buffer
-    pub fn capacity(&self) -> usize {
-        self.rb.capacity()
+    fn record_pop(&mut self, result: ElasticPopResult, values_len: usize) {
+        match result {
+            ElasticPopResult::Exact => self.exact += 1,
+            ElasticPopResult::Empty => {
+                self.empty += 1;
+                self.duplicated += values_len as u64;
+            }
+            ElasticPopResult::Upsampled(n) => {
+                self.upsampled += 1;
+                self.duplicated += (values_len - n) as u64;
+            }
+            ElasticPopResult::Downsampled(n) => {
+                self.downsampled += 1;
+                self.dropped += (n - values_len) as u64;
+            }
+        }
     }
+}
 
-    /// Returns the max number of values that can ever be stored in
-    /// the buffer
-    pub fn max_len(&self) -> usize {
-        self.rb.max_len()
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+type EventHook = Box<dyn FnMut(&ElasticEvent) + Send>;
+
+/// A correction made by an `ElasticRingBuffer`, passed to the hook
+/// set with `ElasticRingBuffer::set_event_hook`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct ElasticEvent {
+    /// What kind of correction was made
+    pub kind: ElasticEventKind,
+    /// How many elements were requested or offered
+    pub requested: usize,
+    /// The length of the buffer after the correction
+    pub len: usize,
+    /// How many requests the buffer had filled before this one; the
+    /// buffer has no clock of its own, so this serves as the time of
+    /// the event
+    pub tick: u64,
+}
+
+/// The kinds of `ElasticEvent`
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ElasticEventKind {
+    /// A request was stretched, shrunk, or found the buffer empty
+    Pop(ElasticPopResult),
+    /// An elastic push had to shrink its values or drop old ones
+    Push(ElasticPushResult),
+}
+
+// Tracks the producer's burst sizes and the consumer's request sizes
+// to pick the smallest ideal max length that avoids underruns
+#[derive(Debug, Default, Copy, Clone)]
+struct AdaptiveIdealMax {
+    // Elements pushed since the last request
+    burst: usize,
+    peak_burst: usize,
+    peak_request: usize,
+    // Extra headroom earned by underruns
+    margin: usize,
+    calm_pops: u32,
+}
+
+impl AdaptiveIdealMax {
+    // How many requests without an underrun before the peaks decay
+    const DECAY_INTERVAL: u32 = 256;
+
+    fn observe_push(&mut self, len: usize) {
+        self.burst += len;
+    }
+
+    // Returns the new ideal max length
+    fn observe_pop(&mut self, values_len: usize, underrun: bool) -> usize {
+        self.peak_burst = cmp::max(self.peak_burst, self.burst);
+        self.peak_request = cmp::max(self.peak_request, values_len);
+        self.burst = 0;
+
+        if underrun {
+            self.margin += values_len;
+            self.calm_pops = 0;
+        } else {
+            self.calm_pops += 1;
+            if self.calm_pops >= Self::DECAY_INTERVAL {
+                self.calm_pops = 0;
+                self.peak_burst -= (self.peak_burst + 7) / 8;
+                self.peak_request -= (self.peak_request + 7) / 8;
+                self.margin -= (self.margin + 7) / 8;
+            }
+        }
+
+        self.peak_burst + self.peak_request + self.margin
+    }
+}
+
+/// Indicates what happened when the queue tried to add elements with
+/// `ElasticRingBuffer::push_back_slice_elastic`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Copy, Clone)]
+pub enum ElasticPushResult {
+    /// There was enough room for all of the elements
+    Exact,
+    /// There was not enough room, so the elements were shrunk to fit
+    /// the free space; the value is how many elements were added
+    Downsampled(usize),
+    /// There was so little room that the oldest elements in the queue
+    /// were dropped to make more, and then the new elements were
+    /// shrunk to fit
+    Overflowed {
+        /// How many elements were dropped from the front of the queue
+        dropped: usize,
+        /// How many elements were added to the back of the queue
+        added: usize,
+    },
+}
+
+impl<T: Clone> ElasticRingBuffer<T> {
+    /// Create a new `ElasticRingBuffer` with the given size. `value`
+    /// will be used as the default value for the
+    /// queue. `ideal_max_len` is the threshold where the buffer will
+    /// begin dropping elements during requests
+    pub fn new(size: usize, value: T, ideal_max_len: usize) -> ElasticRingBuffer<T> {
+        ElasticRingBuffer::with_policy(size, value, ideal_max_len, NearestNeighbor)
+    }
+
+    /// Start configuring a new `ElasticRingBuffer` one named setting
+    /// at a time
+    ///
+    /// Example:
+    ///
+    /// ```rust
+    /// use j2ds::*;
+    ///
+    /// let erb = ElasticRingBuffer::builder()
+    ///     .capacity(1024)
+    ///     .default_value(0.0f32)
+    ///     .ideal_max(512)
+    ///     .ideal_min(128)
+    ///     .frame_size(2)
+    ///     .policy(Linear)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(erb.ideal_max(), 512);
+    /// ```
+    pub fn builder() -> ElasticRingBufferBuilder<T> {
+        ElasticRingBufferBuilder {
+            capacity: None,
+            default_value: None,
+            ideal_max: None,
+            ideal_min: 0,
+            frame_size: 1,
+            sample_rate: None,
+            policy: NearestNeighbor,
+        }
+    }
+}
+
+/// Collects the settings for an `ElasticRingBuffer`, created by
+/// `ElasticRingBuffer::builder`. The capacity, default value and ideal
+/// max length must be set; everything else is optional
+#[derive(Debug, Clone)]
+pub struct ElasticRingBufferBuilder<T, P = NearestNeighbor> {
+    capacity: Option<usize>,
+    default_value: Option<T>,
+    ideal_max: Option<usize>,
+    ideal_min: usize,
+    frame_size: usize,
+    sample_rate: Option<u32>,
+    policy: P,
+}
+
+impl<T: Clone, P: ResamplePolicy<T>> ElasticRingBufferBuilder<T, P> {
+    /// Set the max number of values the buffer can hold
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Set the value used to fill requests when the buffer is empty
+    pub fn default_value(mut self, value: T) -> Self {
+        self.default_value = Some(value);
+        self
+    }
+
+    /// Set the threshold where the buffer begins dropping elements; it
+    /// can't be larger than the capacity
+    pub fn ideal_max(mut self, ideal_max_len: usize) -> Self {
+        self.ideal_max = Some(ideal_max_len);
+        self
+    }
+
+    /// Set the threshold where the buffer begins stretching elements
+    /// ahead of running out; see `ElasticRingBuffer::set_ideal_min`
+    pub fn ideal_min(mut self, ideal_min_len: usize) -> Self {
+        self.ideal_min = ideal_min_len;
+        self
+    }
+
+    /// Set the number of values in each frame; see
+    /// `ElasticRingBuffer::set_frame_size`
+    pub fn frame_size(mut self, frame_size: usize) -> Self {
+        self.frame_size = frame_size;
+        self
+    }
+
+    /// Set the number of frames per second; see
+    /// `ElasticRingBuffer::set_sample_rate`
+    pub fn sample_rate(mut self, frames_per_second: u32) -> Self {
+        self.sample_rate = Some(frames_per_second);
+        self
+    }
+
+    /// Set the policy used to stretch and shrink the values
+    pub fn policy<Q: ResamplePolicy<T>>(self, policy: Q) -> ElasticRingBufferBuilder<T, Q> {
+        ElasticRingBufferBuilder {
+            capacity: self.capacity,
+            default_value: self.default_value,
+            ideal_max: self.ideal_max,
+            ideal_min: self.ideal_min,
+            frame_size: self.frame_size,
+            sample_rate: self.sample_rate,
+            policy,
+        }
+    }
+
+    /// Create the buffer, or return `Error::InvalidConfig` if a
+    /// required setting is missing or the settings contradict each
+    /// other
+    pub fn build(self) -> Result<ElasticRingBuffer<T, P>, Error> {
+        let capacity = self
+            .capacity
+            .ok_or(Error::InvalidConfig("capacity is not set"))?;
+        let value = self
+            .default_value
+            .ok_or(Error::InvalidConfig("default value is not set"))?;
+        let ideal_max = self
+            .ideal_max
+            .ok_or(Error::InvalidConfig("ideal max is not set"))?;
+
+        if ideal_max > capacity {
+            return Err(Error::InvalidConfig(
+                "ideal max is larger than the capacity",
+            ));
+        }
+        if self.ideal_min != 0 && self.ideal_min >= ideal_max {
+            return Err(Error::InvalidConfig("ideal min is not below the ideal max"));
+        }
+        if self.frame_size == 0 {
+            return Err(Error::InvalidConfig("frame size is 0"));
+        }
+        if self.sample_rate == Some(0) {
+            return Err(Error::InvalidConfig("sample rate is 0"));
+        }
+
+        let mut erb = ElasticRingBuffer::with_policy(capacity, value, ideal_max, self.policy);
+        erb.set_ideal_min(self.ideal_min);
+        erb.set_frame_size(self.frame_size);
+        erb.sample_rate = self.sample_rate;
+        Ok(erb)
+    }
+}
+
+impl<T: Clone, P: ResamplePolicy<T>> ElasticRingBuffer<T, P> {
+    /// Create a new `ElasticRingBuffer` like `new`, but using `policy`
+    /// to stretch and shrink the values
+    pub fn with_policy(
+        size: usize,
+        value: T,
+        ideal_max_len: usize,
+        policy: P,
+    ) -> ElasticRingBuffer<T, P> {
+        ElasticRingBuffer {
+            rb: RingBuffer::new(size, value.clone()),
+            default_value: value,
+            ideal_max: ideal_max_len,
+            ideal_min: 0,
+            refilling: false,
+            stats: ElasticStats::default(),
+            frame_size: 1,
+            sample_rate: None,
+            scratch: Vec::new(),
+            adaptive: None,
+            event_hook: None,
+            requests: 0,
+            policy,
+        }
+    }
+
+    /// Returns the statistics collected since the buffer was created
+    /// or since `reset_stats` was last called
+    pub fn stats(&self) -> ElasticStats {
+        self.stats
+    }
+
+    /// Reset all of the collected statistics to zero
+    pub fn reset_stats(&mut self) {
+        self.stats = ElasticStats::default();
+    }
+
+    /// Returns the threshold where the buffer begins dropping elements
+    pub fn ideal_max(&self) -> usize {
+        self.ideal_max
+    }
+
+    /// Set the threshold where the buffer begins dropping elements.
+    /// It must be larger than the ideal min length (if one is set) and
+    /// no larger than the max length of the buffer
+    ///
+    /// This can be called at any time. The next request compares the
+    /// buffer against the new threshold, so lowering it below the
+    /// current length makes that request drop the excess in one go,
+    /// while raising it lets the buffer fill up to the new level
+    /// through exact requests. If the adaptive ideal max is enabled it
+    /// keeps adjusting the threshold from here.
+    pub fn set_ideal_max(&mut self, ideal_max_len: usize) {
+        assert!(ideal_max_len <= self.rb.max_len());
+        assert!(self.ideal_min == 0 || self.ideal_min < ideal_max_len);
+        self.ideal_max = ideal_max_len;
+    }
+
+    /// Returns the threshold where the buffer begins stretching
+    /// elements ahead of running out; 0 (the default) means it only
+    /// stretches when a request can't be satisfied
+    pub fn ideal_min(&self) -> usize {
+        self.ideal_min
+    }
+
+    /// Set the threshold where the buffer begins stretching elements
+    /// ahead of running out. It must be smaller than the ideal max
+    /// length. This can be called at any time; the next request
+    /// starts refilling if the buffer is already below it
+    pub fn set_ideal_min(&mut self, ideal_min_len: usize) {
+        assert!(ideal_min_len == 0 || ideal_min_len < self.ideal_max);
+        self.ideal_min = ideal_min_len;
+        self.refilling = false;
+    }
+
+    /// Returns the number of values in each frame
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    /// Set the number of values in each frame, e.g. 2 for interleaved
+    /// stereo. Afterwards every request and every elastic push must
+    /// be a whole number of frames. This should be set while the
+    /// buffer is empty
+    pub fn set_frame_size(&mut self, frame_size: usize) {
+        assert!(frame_size > 0);
+        self.frame_size = frame_size;
+    }
+
+    /// Returns the number of frames per second, if one has been set
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.sample_rate
+    }
+
+    /// Set the number of frames per second, which allows the
+    /// thresholds and the current length to be worked with as
+    /// durations. The channel count is the frame size
+    pub fn set_sample_rate(&mut self, frames_per_second: u32) {
+        assert!(frames_per_second > 0);
+        self.sample_rate = Some(frames_per_second);
+    }
+
+    /// Returns how long the values in the buffer will take to play, or
+    /// `None` if no sample rate is set
+    pub fn latency(&self) -> Option<Duration> {
+        self.sample_rate
+            .map(|rate| self.len_to_duration(self.rb.len(), rate))
+    }
+
+    /// Returns the ideal max length as a duration, or `None` if no
+    /// sample rate is set
+    pub fn ideal_max_duration(&self) -> Option<Duration> {
+        self.sample_rate
+            .map(|rate| self.len_to_duration(self.ideal_max, rate))
+    }
+
+    /// Set the ideal max length as a duration, rounded to the nearest
+    /// whole frame; see `set_ideal_max`. Panics if no sample rate is
+    /// set
+    pub fn set_ideal_max_duration(&mut self, duration: Duration) {
+        let len = self.duration_to_len(duration);
+        self.set_ideal_max(len);
+    }
+
+    /// Returns the ideal min length as a duration, or `None` if no
+    /// sample rate is set
+    pub fn ideal_min_duration(&self) -> Option<Duration> {
+        self.sample_rate
+            .map(|rate| self.len_to_duration(self.ideal_min, rate))
+    }
+
+    /// Set the ideal min length as a duration, rounded to the nearest
+    /// whole frame; see `set_ideal_min`. Panics if no sample rate is
+    /// set
+    pub fn set_ideal_min_duration(&mut self, duration: Duration) {
+        let len = self.duration_to_len(duration);
+        self.set_ideal_min(len);
+    }
+
+    fn len_to_duration(&self, len: usize, rate: u32) -> Duration {
+        let frames = (len / self.frame_size) as u128;
+        let nanos = frames * NANOS_PER_SEC / u128::from(rate);
+        Duration::from_nanos(nanos as u64)
+    }
+
+    fn duration_to_len(&self, duration: Duration) -> usize {
+        let rate = self.sample_rate.expect("no sample rate set");
+        let frames = (duration.as_nanos() * u128::from(rate) + NANOS_PER_SEC / 2) / NANOS_PER_SEC;
+        frames as usize * self.frame_size
+    }
+
+    /// Returns the resampling policy
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Returns the resampling policy for modification
+    pub fn policy_mut(&mut self) -> &mut P {
+        &mut self.policy
+    }
+
+    /// Fill `values` with elements. See `ElasticPopResult` for the
+    /// possible outcomes of this request.
+    pub fn pop_front_slice(&mut self, values: &mut [T]) -> ElasticPopResult {
+        self.pop_with(values, |policy, input, output| {
+            policy.resample(input, output)
+        })
+    }
+
+    /// Fill `values` with exactly what `pop_front_slice` would, and
+    /// return the same result, but leave the queue, the collected
+    /// statistics and the state of the policy unchanged
+    pub fn peek_front_slice(&self, values: &mut [T]) -> ElasticPopResult {
+        let (size, _) = self.plan_size(values.len());
+        let mut scratch = Vec::new();
+        fill(
+            &self.rb,
+            &self.default_value,
+            values,
+            size,
+            |input, output| {
+                resample_frames(
+                    |i, o| self.policy.plan(i, o),
+                    input,
+                    output,
+                    self.frame_size,
+                    &mut scratch,
+                )
+            },
+        )
+    }
+
+    /// Fill `values` with elements like `pop_front_slice`, except that
+    /// when there are too few elements they are always stretched with
+    /// the `Linear` policy, regardless of the buffer's own policy
+    pub fn pop_front_slice_interpolated(&mut self, values: &mut [T]) -> ElasticPopResult
+    where
+        T: Lerp,
+    {
+        self.pop_with(values, |policy, input, output| {
+            if output.len() > input.len() {
+                Linear.resample(input, output);
+            } else {
+                policy.resample(input, output);
+            }
+        })
+    }
+
+    // Fill `values` like `pop_front_slice`, resampling each channel with
+    // `resample`, which is handed the buffer's policy
+    fn pop_with<F>(&mut self, values: &mut [T], mut resample: F) -> ElasticPopResult
+    where
+        F: FnMut(&mut P, Samples<T>, &mut [T]),
+    {
+        let len_before = self.rb.len();
+        let size = self.sample_size(values.len());
+        let policy = &mut self.policy;
+        let scratch = &mut self.scratch;
+        let frame_size = self.frame_size;
+        let result = fill(
+            &self.rb,
+            &self.default_value,
+            values,
+            size,
+            |input, output| {
+                resample_frames(
+                    |i, o| resample(policy, i, o),
+                    input,
+                    output,
+                    frame_size,
+                    scratch,
+                )
+            },
+        );
+        self.consume(result.consumed(values.len()));
+        self.finish_pop(len_before, values.len(), result)
+    }
+
+    fn finish_pop(
+        &mut self,
+        len_before: usize,
+        values_len: usize,
+        result: ElasticPopResult,
+    ) -> ElasticPopResult {
+        self.stats.observe_len(len_before);
+        self.stats.record_pop(result, values_len);
+        if result != ElasticPopResult::Exact {
+            self.emit(ElasticEventKind::Pop(result), values_len);
+        }
+        self.requests += 1;
+
+        if let Some(adaptive) = self.adaptive.as_mut() {
+            let ideal_max = adaptive.observe_pop(values_len, values_len > len_before);
+            // Stay above the ideal min, but never past what fits
+            self.ideal_max = cmp::min(cmp::max(ideal_max, self.ideal_min + 1), self.rb.max_len());
+        }
+        result
+    }
+
+    fn emit(&mut self, kind: ElasticEventKind, requested: usize) {
+        if let Some(hook) = self.event_hook.as_mut() {
+            hook(&ElasticEvent {
+                kind,
+                requested,
+                len: self.rb.len(),
+                tick: self.requests,
+            });
+        }
+    }
+
+    /// Call `hook` every time the buffer stretches or shrinks values,
+    /// or runs empty, replacing any previous hook. It is called from
+    /// whichever method made the correction, so on an audio thread it
+    /// should be quick and must not block
+    pub fn set_event_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&ElasticEvent) + Send + 'static,
+    {
+        self.event_hook = Some(Box::new(hook));
+    }
+
+    /// Stop calling the hook set with `set_event_hook`
+    pub fn clear_event_hook(&mut self) {
+        self.event_hook = None;
+    }
+
+    fn observe_push(&mut self, len: usize) {
+        if let Some(adaptive) = self.adaptive.as_mut() {
+            adaptive.observe_push(len);
+        }
+    }
+
+    /// Turn on automatic tuning of the ideal max length. While
+    /// enabled, the buffer watches how many elements arrive between
+    /// requests and how large the requests are, and keeps the ideal
+    /// max length just large enough to absorb that jitter. Every
+    /// underrun makes it more cautious, and long stretches without
+    /// one let it slowly tighten again
+    pub fn enable_adaptive_ideal_max(&mut self) {
+        self.adaptive = Some(AdaptiveIdealMax::default());
+    }
+
+    /// Turn off automatic tuning; the ideal max length keeps its
+    /// current value
+    pub fn disable_adaptive_ideal_max(&mut self) {
+        self.adaptive = None;
+    }
+
+    /// Returns true if the ideal max length is being tuned
+    /// automatically
+    pub fn is_adaptive(&self) -> bool {
+        self.adaptive.is_some()
+    }
+
+    // Returns how many real elements should be used to fill a request
+    // for `values_len` elements, or `None` if they can be used as-is
+    fn sample_size(&mut self, values_len: usize) -> Option<usize> {
+        let (size, refilling) = self.plan_size(values_len);
+        self.refilling = refilling;
+        size
+    }
+
+    // Like `sample_size`, but returns the new refilling state instead
+    // of storing it
+    fn plan_size(&self, values_len: usize) -> (Option<usize>, bool) {
+        plan_pop(
+            self.rb.len(),
+            values_len,
+            self.ideal_max,
+            self.ideal_min,
+            self.frame_size,
+            self.refilling,
+        )
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.rb.read = self.rb.advance_index(self.rb.read, n as isize);
+        self.rb.record_removed(n);
+    }
+
+    /// Remove the first value from the queue, or returns `None` if
+    /// there are no values in the buffer. Values are never resampled
+    /// and the statistics are not updated
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.rb.pop_front()
+    }
+
+    /// Remove the first value from the queue, or return
+    /// `Error::Empty` if there are no values in the buffer
+    pub fn try_pop_front(&mut self) -> Result<T, Error> {
+        self.rb.try_pop_front()
+    }
+
+    /// Copy the first value from the queue but does not remove it;
+    /// returns `None` if there are no values in the buffer
+    pub fn peek_front(&self) -> Option<T> {
+        self.rb.peek_front()
+    }
+
+    /// Returns an iterator over the values in the queue from front to
+    /// back, without removing them
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.rb.iter()
+    }
+
+    /// Remove all values from the queue
+    pub fn clear(&mut self) {
+        self.rb.clear();
+        self.refilling = false;
+    }
+
+    /// Returns the wrapped `RingBuffer`
+    pub fn inner(&self) -> &RingBuffer<T> {
+        &self.rb
+    }
+
+    /// Returns the wrapped `RingBuffer` for modification. Changes made
+    /// through it bypass the statistics and the adaptive ideal max
+    pub fn inner_mut(&mut self) -> &mut RingBuffer<T> {
+        &mut self.rb
+    }
+
+    /// Add all `values` to the buffer. If there is not enough room in
+    /// the queue then no values are added and the return value is
+    /// false
+    pub fn push_back_slice(&mut self, values: &[T]) -> bool {
+        self.try_push_back_slice(values).is_ok()
+    }
+
+    /// Add all `values` to the buffer. If there is not enough room in
+    /// the queue then no values are added and `Error::Full` is
+    /// returned
+    pub fn try_push_back_slice(&mut self, values: &[T]) -> Result<(), Error> {
+        self.rb.try_push_back_slice(values)?;
+        self.observe_push(values.len());
+        Ok(())
+    }
+
+    /// Add `values` to the buffer, shrinking them with the resampling
+    /// policy if there is not enough room for all of them. If the
+    /// free space is less than half of `values`, the oldest elements
+    /// in the queue are dropped first so that at least half can be
+    /// kept. See `ElasticPushResult` for the possible outcomes
+    pub fn push_back_slice_elastic(&mut self, values: &[T]) -> ElasticPushResult {
+        let frame_size = self.frame_size;
+        assert!(values.len() % frame_size == 0);
+
+        self.observe_push(values.len());
+        if values.len() <= self.rb.capacity() {
+            let r = self.rb.push_back_slice(values);
+            assert!(r);
+            return ElasticPushResult::Exact;
+        }
+
+        let frames = |n: usize| n / frame_size * frame_size;
+        let half = frames((values.len() + 2 * frame_size - 1) / (2 * frame_size) * frame_size);
+        let target = cmp::min(frames(self.rb.max_len()), half);
+        let wanted = target.saturating_sub(frames(self.rb.capacity()));
+        let dropped = cmp::min(wanted, self.rb.len());
+        self.consume(dropped);
+        if self.rb.is_empty() {
+            // Start the free space at the beginning of the storage, so
+            // it is all in one piece
+            self.rb.read = 0;
+            self.rb.write = 0;
+        }
+
+        let added = frames(self.rb.capacity());
+        let (_, room) = self.rb.raw_write_region();
+        if room >= added {
+            let start = self.rb.write;
+            let policy = &mut self.policy;
+            resample_frames(
+                |i, o| policy.resample(i, o),
+                Samples::new(values, &[]),
+                &mut self.rb.buffer[start..start + added],
+                frame_size,
+                &mut self.scratch,
+            );
+            self.rb.advance_write(added);
+        } else {
+            // The free space wraps around the end of the storage, so
+            // resample into a copy and push that
+            let mut resampled = values[..added].to_vec();
+            let policy = &mut self.policy;
+            resample_frames(
+                |i, o| policy.resample(i, o),
+                Samples::new(values, &[]),
+                &mut resampled,
+                frame_size,
+                &mut self.scratch,
+            );
+            let r = self.rb.push_back_slice(&resampled);
+            assert!(r);
+        }
+
+        self.stats.dropped += (dropped + values.len() - added) as u64;
+        let result = if dropped > 0 {
+            ElasticPushResult::Overflowed { dropped, added }
+        } else {
+            ElasticPushResult::Downsampled(added)
+        };
+        self.emit(ElasticEventKind::Push(result), values.len());
+        result
+    }
+
+    /// Add `n` copies of the default value to the end of the queue,
+    /// e.g. to start playback with a cushion of silence. Returns false
+    /// if there is not enough room in the queue, in which case nothing
+    /// is added
+    pub fn prefill(&mut self, n: usize) -> bool {
+        self.try_prefill(n).is_ok()
+    }
+
+    /// Add `n` copies of the default value to the end of the queue, or
+    /// return `Error::Full` if there is not enough room in the queue
+    pub fn try_prefill(&mut self, n: usize) -> Result<(), Error> {
+        let available = self.rb.capacity();
+        if available < n {
+            return Err(Error::Full {
+                needed: n,
+                available,
+            });
+        }
+        for _ in 0..n {
+            let r = self.rb.push_back(self.default_value.clone());
+            assert!(r);
+        }
+        Ok(())
+    }
+
+    /// Add `value` to the end of the queue. Returns false if there is
+    /// not enough room in the queue
+    pub fn push_back(&mut self, value: T) -> bool {
+        self.try_push_back(value).is_ok()
+    }
+
+    /// Add `value` to the end of the queue, or return `Error::Full`
+    /// if there is not enough room in the queue
+    pub fn try_push_back(&mut self, value: T) -> Result<(), Error> {
+        self.rb.try_push_back(value)?;
+        self.observe_push(1);
+        Ok(())
+    }
+
+    /// Returns the number of values in the buffer
+    pub fn len(&self) -> usize {
+        self.rb.len()
+    }
+
+    /// Returns true if there are no values in the buffer
+    pub fn is_empty(&self) -> bool {
+        self.rb.is_empty()
+    }
+
+    /// Start measuring how long values stay in the buffer; see
+    /// `RingBuffer::track_residency`. Values dropped while
+    /// downsampling count as removed
+    pub fn track_residency(&mut self) {
+        self.rb.track_residency()
+    }
+
+    /// Returns the residency measurements so far, or `None` if
+    /// `track_residency` was never called
+    pub fn residency(&self) -> Option<Residency> {
+        self.rb.residency()
+    }
+
+    /// Returns the number of free slots in the buffer
+    pub fn capacity(&self) -> usize {
+        self.rb.capacity()
+    }
+
+    /// Returns the max number of values that can ever be stored in
+    /// the buffer
+    pub fn max_len(&self) -> usize {
+        self.rb.max_len()
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T: Clone + Arbitrary<'a>> Arbitrary<'a> for ElasticRingBuffer<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<ElasticRingBuffer<T>> {
+        let rb = RingBuffer::arbitrary(u)?;
+        let ideal_max = u.int_in_range(0..=rb.max_len())?;
+
+        Ok(ElasticRingBuffer {
+            rb,
+            ideal_max,
+            default_value: T::arbitrary(u)?,
+            ideal_min: 0,
+            refilling: false,
+            stats: ElasticStats::default(),
+            frame_size: 1,
+            sample_rate: None,
+            scratch: Vec::new(),
+            adaptive: None,
+            event_hook: None,
+            requests: 0,
+            policy: NearestNeighbor,
+        })
     }
 }
 
@@ -366,3 +1962,489 @@ fn test_elastic_downscale() {
     assert!(erb.len() <= erb.ideal_max);
     assert_eq!(r, ElasticPopResult::Downsampled(8));
 }
+
+#[test]
+fn test_elastic_downscale_large() {
+    let mut erb = ElasticRingBuffer::new(20, 0u8, 2);
+
+    // A request larger than the ideal max uses what is in the buffer,
+    // without reading past it
+    erb.push_back_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+    let mut buf8 = [0; 8];
+    let r = erb.pop_front_slice(&mut buf8);
+
+    assert_eq!(buf8, [1, 2, 4, 5, 7, 8, 10, 11]);
+    assert_eq!(r, ElasticPopResult::Downsampled(12));
+    assert!(erb.is_empty());
+}
+
+#[test]
+fn test_elastic_ideal_min() {
+    let mut erb = ElasticRingBuffer::new(100, 0u8, 40);
+    erb.set_ideal_min(10);
+    assert!(erb.push_back_slice(&[1; 30]));
+
+    let mut buf16 = [0; 16];
+    assert_eq!(erb.pop_front_slice(&mut buf16), ElasticPopResult::Exact);
+    assert_eq!(erb.len(), 14);
+
+    // Dropping below the ideal min starts stretching
+    assert_eq!(
+        erb.pop_front_slice(&mut buf16),
+        ElasticPopResult::Upsampled(14)
+    );
+    assert_eq!(erb.len(), 0);
+    assert!(erb.push_back_slice(&[1; 32]));
+
+    // ...and it keeps stretching until it is halfway back to the max
+    assert_eq!(
+        erb.pop_front_slice(&mut buf16),
+        ElasticPopResult::Upsampled(14)
+    );
+    assert_eq!(erb.len(), 18);
+    for expected_len in [20, 22, 24, 26].iter() {
+        assert!(erb.push_back_slice(&[1; 16]));
+        assert_eq!(
+            erb.pop_front_slice(&mut buf16),
+            ElasticPopResult::Upsampled(14)
+        );
+        assert_eq!(erb.len(), *expected_len);
+    }
+    assert!(erb.push_back_slice(&[1; 16]));
+    assert_eq!(erb.pop_front_slice(&mut buf16), ElasticPopResult::Exact);
+    assert_eq!(erb.len(), 26);
+}
+
+#[test]
+fn test_elastic_ideal_min_single_frame() {
+    let mut erb = ElasticRingBuffer::new(20, 0u8, 10);
+    erb.set_ideal_min(4);
+    assert!(erb.push_back_slice(&[1, 2, 3, 4, 5]));
+
+    // Single-frame requests can't be stretched, so they are exact even
+    // while refilling
+    let mut buf1 = [0; 1];
+    for expected in 1..6 {
+        assert_eq!(erb.pop_front_slice(&mut buf1), ElasticPopResult::Exact);
+        assert_eq!(buf1, [expected]);
+    }
+    let stats = erb.stats();
+    assert_eq!((stats.exact, stats.downsampled), (5, 0));
+}
+
+#[test]
+fn test_elastic_push() {
+    let mut erb = ElasticRingBuffer::new(6, 0u8, 6);
+    let mut buf1 = [0; 1];
+    let mut buf6 = [0; 6];
+
+    assert_eq!(
+        erb.push_back_slice_elastic(&[1, 2, 3]),
+        ElasticPushResult::Exact
+    );
+    assert_eq!(erb.pop_front_slice(&mut buf1), ElasticPopResult::Exact);
+    assert_eq!(
+        erb.push_back_slice_elastic(&[4, 5, 6, 7, 8, 9]),
+        ElasticPushResult::Downsampled(4)
+    );
+    assert_eq!(erb.pop_front_slice(&mut buf6), ElasticPopResult::Exact);
+    assert_eq!(buf6, [2, 3, 4, 5, 7, 8]);
+
+    assert!(erb.push_back_slice(&[1, 2, 3, 4, 5]));
+    assert_eq!(
+        erb.push_back_slice_elastic(&[10, 11, 12, 13]),
+        ElasticPushResult::Overflowed {
+            dropped: 1,
+            added: 2
+        }
+    );
+    assert_eq!(erb.pop_front_slice(&mut buf6), ElasticPopResult::Exact);
+    assert_eq!(buf6, [2, 3, 4, 5, 10, 12]);
+
+    // Room is made in whole frames, but only the values that were
+    // actually queued count as dropped
+    let mut erb = ElasticRingBuffer::new(10, 0u8, 10);
+    erb.set_frame_size(2);
+    erb.inner_mut().push_back(1);
+    let values: Vec<u8> = (0..40).collect();
+    assert_eq!(
+        erb.push_back_slice_elastic(&values),
+        ElasticPushResult::Overflowed {
+            dropped: 1,
+            added: 10
+        }
+    );
+    assert_eq!(erb.len(), 10);
+    assert_eq!(erb.stats().dropped, 31);
+}
+
+#[test]
+fn test_elastic_stats() {
+    let mut erb = ElasticRingBuffer::new(20, 0u8, 8);
+    let mut buf4 = [0; 4];
+
+    erb.push_back_slice(&[1; 12]);
+    erb.pop_front_slice(&mut buf4);
+    erb.push_back_slice(&[1; 2]);
+    erb.pop_front_slice(&mut buf4);
+    erb.pop_front_slice(&mut buf4);
+    erb.push_back_slice(&[1; 1]);
+    erb.pop_front_slice(&mut buf4);
+    erb.pop_front_slice(&mut buf4);
+
+    let stats = erb.stats();
+    assert_eq!(stats.downsampled, 1);
+    assert_eq!(stats.exact, 1);
+    assert_eq!(stats.upsampled, 2);
+    assert_eq!(stats.empty, 1);
+    assert_eq!(stats.dropped, 4);
+    assert_eq!(stats.duplicated, 2 + 3 + 4);
+    assert!(stats.trend < 0.0);
+
+    erb.reset_stats();
+    assert_eq!(erb.stats(), ElasticStats::default());
+}
+
+#[test]
+fn test_elastic_frames() {
+    let mut erb = ElasticRingBuffer::new(20, 0i8, 8);
+    erb.set_frame_size(2);
+    let mut buf4 = [0; 4];
+    let mut buf8 = [0; 8];
+
+    // Dropping never splits up a frame
+    erb.push_back_slice(&[1, -1, 2, -2, 3, -3, 4, -4, 5, -5, 6, -6]);
+    assert_eq!(
+        erb.pop_front_slice(&mut buf4),
+        ElasticPopResult::Downsampled(8)
+    );
+    assert_eq!(buf4, [1, -1, 3, -3]);
+    assert_eq!(erb.len(), 4);
+
+    // ...and neither does stretching
+    assert_eq!(
+        erb.pop_front_slice(&mut buf8),
+        ElasticPopResult::Upsampled(4)
+    );
+    assert_eq!(buf8, [5, -5, 5, -5, 6, -6, 6, -6]);
+
+    erb.push_back_slice(&[0; 18]);
+    assert_eq!(
+        erb.push_back_slice_elastic(&[1, -1, 2, -2, 3, -3, 4, -4]),
+        ElasticPushResult::Overflowed {
+            dropped: 2,
+            added: 4
+        }
+    );
+}
+
+#[test]
+fn test_elastic_adaptive() {
+    let mut erb = ElasticRingBuffer::new(200, 0u8, 150);
+    let mut buf10 = [0; 10];
+    erb.enable_adaptive_ideal_max();
+    assert!(erb.is_adaptive());
+
+    // A producer that delivers 30 elements every third request
+    for i in 0..30 {
+        if i % 3 == 0 {
+            erb.push_back_slice(&[1; 30]);
+        }
+        erb.pop_front_slice(&mut buf10);
+    }
+    assert_eq!(erb.ideal_max(), 30 + 10);
+    assert_eq!(erb.stats().downsampled, 0);
+
+    // An underrun adds headroom
+    erb.pop_front_slice(&mut buf10);
+    assert_eq!(erb.ideal_max(), 30 + 10 + 10);
+
+    // ...which slowly goes away again once things calm down
+    for _ in 0..1024 {
+        erb.push_back_slice(&[1; 10]);
+        erb.pop_front_slice(&mut buf10);
+    }
+    assert!(erb.ideal_max() < 40);
+
+    erb.disable_adaptive_ideal_max();
+    assert!(!erb.is_adaptive());
+
+    // The ideal max never grows past what the buffer can hold, even
+    // with an ideal min that is too large for it
+    let mut erb = ElasticRingBuffer::new(10, 0u8, 50);
+    erb.set_ideal_min(20);
+    erb.enable_adaptive_ideal_max();
+    erb.pop_front_slice(&mut buf10);
+    assert_eq!(erb.ideal_max(), erb.inner().max_len());
+}
+
+#[test]
+fn test_elastic_peek() {
+    let mut erb = ElasticRingBuffer::new(10, 0u8, 4);
+    let mut peeked = [0; 4];
+    let mut popped = [0; 4];
+
+    assert_eq!(erb.peek_front_slice(&mut peeked), ElasticPopResult::Empty);
+    erb.push_back_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+    let r = erb.peek_front_slice(&mut peeked);
+    assert_eq!(r, ElasticPopResult::Downsampled(9));
+    assert_eq!(erb.len(), 9);
+    assert_eq!(erb.stats(), ElasticStats::default());
+    assert_eq!(erb.pop_front_slice(&mut popped), r);
+    assert_eq!(peeked, popped);
+
+    erb.push_back_slice(&[1, 2, 3]);
+    assert_eq!(
+        erb.peek_front_slice(&mut peeked),
+        ElasticPopResult::Upsampled(3)
+    );
+    assert_eq!(peeked, [1, 1, 2, 3]);
+    assert_eq!(erb.len(), 3);
+
+    // A policy that changes with every request
+    struct Counting(u8);
+    impl ResamplePolicy<u8> for Counting {
+        fn plan(&self, _: Samples<u8>, output: &mut [u8]) {
+            for o in output.iter_mut() {
+                *o = self.0 + 1;
+            }
+        }
+
+        fn resample(&mut self, input: Samples<u8>, output: &mut [u8]) {
+            self.plan(input, output);
+            self.0 += 1;
+        }
+    }
+
+    let mut erb = ElasticRingBuffer::with_policy(10, 0u8, 4, Counting(0));
+    erb.push_back_slice(&[1, 2]);
+    erb.peek_front_slice(&mut peeked);
+    erb.peek_front_slice(&mut peeked);
+    assert_eq!(peeked, [1; 4]);
+    assert_eq!(erb.policy().0, 0);
+    erb.pop_front_slice(&mut popped);
+    assert_eq!(popped, peeked);
+    assert_eq!(erb.policy().0, 1);
+}
+
+#[test]
+fn test_elastic_inner() {
+    let mut erb = ElasticRingBuffer::new(5, 0u8, 4);
+    erb.push_back_slice(&[1, 2, 3]);
+    assert_eq!(erb.peek_front(), Some(1));
+    assert_eq!(erb.pop_front(), Some(1));
+    assert_eq!(erb.iter().cloned().collect::<Vec<_>>(), vec![2, 3]);
+
+    erb.inner_mut().push_back(4);
+    assert_eq!(erb.inner().to_vec(), vec![2, 3, 4]);
+    assert_eq!(erb.stats(), ElasticStats::default());
+
+    erb.clear();
+    assert!(erb.is_empty());
+    assert_eq!(
+        erb.try_pop_front(),
+        Err(Error::Empty {
+            needed: 1,
+            available: 0
+        })
+    );
+}
+
+#[test]
+fn test_elastic_builder() {
+    let erb = ElasticRingBuffer::builder()
+        .capacity(16)
+        .default_value(0i16)
+        .ideal_max(8)
+        .ideal_min(2)
+        .frame_size(2)
+        .policy(Linear)
+        .build()
+        .unwrap();
+    assert_eq!(erb.max_len(), 16);
+    assert_eq!(erb.ideal_max(), 8);
+    assert_eq!(erb.ideal_min(), 2);
+    assert_eq!(erb.frame_size(), 2);
+    assert_eq!(*erb.policy(), Linear);
+
+    let builder = ElasticRingBuffer::builder().capacity(4).default_value(0u8);
+    assert_eq!(
+        builder.clone().build().err(),
+        Some(Error::InvalidConfig("ideal max is not set"))
+    );
+    assert_eq!(
+        builder.clone().ideal_max(5).build().err(),
+        Some(Error::InvalidConfig(
+            "ideal max is larger than the capacity"
+        ))
+    );
+    assert_eq!(
+        builder.clone().ideal_max(3).ideal_min(3).build().err(),
+        Some(Error::InvalidConfig("ideal min is not below the ideal max"))
+    );
+    assert!(builder.ideal_max(3).build().is_ok());
+}
+
+#[test]
+fn test_elastic_set_ideal_max() {
+    let mut erb = ElasticRingBuffer::new(20, 0u8, 16);
+    let mut buf2 = [0; 2];
+    erb.push_back_slice(&[1; 12]);
+
+    // Lowering the threshold drops the excess on the next request
+    erb.set_ideal_max(4);
+    assert_eq!(
+        erb.pop_front_slice(&mut buf2),
+        ElasticPopResult::Downsampled(10)
+    );
+    assert_eq!(erb.len(), 2);
+
+    // Raising it lets the buffer grow again without any drops
+    erb.set_ideal_max(10);
+    erb.push_back_slice(&[1; 6]);
+    assert_eq!(erb.pop_front_slice(&mut buf2), ElasticPopResult::Exact);
+    assert_eq!(erb.len(), 6);
+}
+
+#[test]
+fn test_elastic_events() {
+    use std::sync::{Arc, Mutex};
+
+    let events = Arc::new(Mutex::new(vec![]));
+    let mut erb = ElasticRingBuffer::new(6, 0u8, 4);
+    let log = events.clone();
+    erb.set_event_hook(move |e| log.lock().unwrap().push(*e));
+
+    let mut buf2 = [0; 2];
+    erb.push_back_slice(&[1, 2]);
+    erb.pop_front_slice(&mut buf2);
+    erb.pop_front_slice(&mut buf2);
+    erb.push_back_slice(&[1]);
+    erb.pop_front_slice(&mut buf2);
+    erb.push_back_slice_elastic(&[1; 10]);
+
+    erb.clear_event_hook();
+    erb.pop_front_slice(&mut buf2);
+
+    let events = events.lock().unwrap();
+    assert_eq!(
+        *events,
+        vec![
+            ElasticEvent {
+                kind: ElasticEventKind::Pop(ElasticPopResult::Empty),
+                requested: 2,
+                len: 0,
+                tick: 1,
+            },
+            ElasticEvent {
+                kind: ElasticEventKind::Pop(ElasticPopResult::Upsampled(1)),
+                requested: 2,
+                len: 0,
+                tick: 2,
+            },
+            ElasticEvent {
+                kind: ElasticEventKind::Push(ElasticPushResult::Downsampled(6)),
+                requested: 10,
+                len: 6,
+                tick: 3,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_elastic_prefill() {
+    let mut erb = ElasticRingBuffer::new(6, 7u8, 4);
+    erb.push_back(1);
+    assert!(erb.prefill(3));
+    assert_eq!(erb.iter().cloned().collect::<Vec<_>>(), vec![1, 7, 7, 7]);
+    assert_eq!(
+        erb.try_prefill(3),
+        Err(Error::Full {
+            needed: 3,
+            available: 2
+        })
+    );
+    assert_eq!(erb.len(), 4);
+}
+
+#[test]
+fn test_elastic_durations() {
+    let mut erb = ElasticRingBuffer::builder()
+        .capacity(9600)
+        .default_value(0i16)
+        .ideal_max(4800)
+        .frame_size(2)
+        .sample_rate(48_000)
+        .build()
+        .unwrap();
+    assert_eq!(erb.ideal_max_duration(), Some(Duration::from_millis(50)));
+    assert_eq!(erb.latency(), Some(Duration::from_millis(0)));
+
+    erb.prefill(960);
+    assert_eq!(erb.latency(), Some(Duration::from_millis(10)));
+
+    erb.set_ideal_max_duration(Duration::from_millis(80));
+    assert_eq!(erb.ideal_max(), 7680);
+    erb.set_ideal_min_duration(Duration::from_micros(20_015));
+    assert_eq!(erb.ideal_min(), 1922);
+    assert_eq!(
+        erb.ideal_min_duration(),
+        Some(Duration::from_nanos(20_020_833))
+    );
+
+    let erb = ElasticRingBuffer::new(10, 0u8, 5);
+    assert_eq!(erb.latency(), None);
+}
+
+#[test]
+fn test_elastic_policy() {
+    use resample::HoldLast;
+
+    let mut erb = ElasticRingBuffer::with_policy(5, 0u8, 3, HoldLast);
+
+    erb.push_back_slice(&[1, 2]);
+    let mut buf4 = [0; 4];
+    let r = erb.pop_front_slice(&mut buf4);
+    assert_eq!(buf4, [1, 2, 2, 2]);
+    assert_eq!(r, ElasticPopResult::Upsampled(2));
+}
+
+#[test]
+fn test_elastic_interpolated() {
+    let mut erb = ElasticRingBuffer::new(5, 0.0f32, 3);
+
+    erb.push_back_slice(&[0.0, 10.0]);
+    let mut buf4 = [0.0; 4];
+    let r = erb.pop_front_slice_interpolated(&mut buf4);
+    assert_eq!(buf4, [0.0, 5.0, 10.0, 10.0]);
+    assert_eq!(r, ElasticPopResult::Upsampled(2));
+
+    // Anything other than upsampling behaves as usual
+    erb.push_back_slice(&[1.0, 2.0, 3.0, 4.0]);
+    let r = erb.pop_front_slice_interpolated(&mut buf4);
+    assert_eq!(buf4, [1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(r, ElasticPopResult::Exact);
+    let r = erb.pop_front_slice_interpolated(&mut buf4);
+    assert_eq!(buf4, [0.0; 4]);
+    assert_eq!(r, ElasticPopResult::Empty);
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn test_arbitrary() {
+    let data: Vec<u8> = (0..=255).cycle().take(4096).collect();
+    let mut u = Unstructured::new(&data);
+    while !u.is_empty() {
+        let mut rb = RingBuffer::<u8>::arbitrary(&mut u).unwrap();
+        assert!(rb.len() <= rb.max_len());
+        assert_eq!(rb.len() + rb.capacity(), rb.max_len());
+        let len = rb.len();
+        assert_eq!(rb.to_vec().len(), len);
+        assert_eq!(rb.make_contiguous().len(), len);
+
+        let erb = ElasticRingBuffer::<u8>::arbitrary(&mut u).unwrap();
+        assert!(erb.ideal_max <= erb.max_len());
+    }
+}
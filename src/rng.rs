@@ -0,0 +1,40 @@
+/// A source of random numbers, used by `JitteredTimer` and the
+/// randomized structures
+///
+/// This is implemented for closures returning `u64`, so any RNG can be
+/// plugged in with e.g. `move || rng.gen()`.
+pub trait JitterRng {
+    /// Return the next random number
+    fn next_u64(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> JitterRng for F {
+    fn next_u64(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// A small xorshift RNG. It is fast, deterministic and good enough to
+/// spread timers apart or balance trees, but not for anything that needs
+/// real randomness
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    /// Create a new RNG from `seed`
+    pub fn new(seed: u64) -> XorShiftRng {
+        // The state must never be 0
+        XorShiftRng { state: seed | 1 }
+    }
+}
+
+impl JitterRng for XorShiftRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
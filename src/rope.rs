@@ -0,0 +1,408 @@
+use std::fmt;
+use std::ops::Range;
+
+use rng::{JitterRng, XorShiftRng};
+
+// The most bytes a chunk is built with, or grown to by an insert
+const MAX_CHUNK: usize = 512;
+
+// The seed used for the chunks' priorities
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+type Link = Option<Box<Node>>;
+
+#[derive(Clone)]
+struct Node {
+    text: String,
+    // The number of chars in `text`, and in this whole subtree
+    chars: usize,
+    total: usize,
+    // Every node's priority is at least that of its children
+    priority: u64,
+    left: Link,
+    right: Link,
+}
+
+impl Node {
+    fn update(&mut self) {
+        self.total = total(&self.left) + self.chars + total(&self.right);
+    }
+}
+
+fn total(link: &Link) -> usize {
+    link.as_ref().map_or(0, |node| node.total)
+}
+
+fn byte_offset(text: &str, chars: usize) -> usize {
+    text.char_indices()
+        .nth(chars)
+        .map_or(text.len(), |(i, _)| i)
+}
+
+// Join two ropes, with all of `a` before all of `b`
+fn merge(a: Link, b: Link) -> Link {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority > b.priority {
+                a.right = merge(a.right.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.left = merge(Some(a), b.left.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+// Split a rope into the chars before `at` and the rest
+fn split(link: Link, at: usize) -> (Link, Link) {
+    let mut node = match link {
+        Some(node) => node,
+        None => return (None, None),
+    };
+    let left = total(&node.left);
+    if at <= left {
+        let (a, b) = split(node.left.take(), at);
+        node.left = b;
+        node.update();
+        (a, Some(node))
+    } else if at >= left + node.chars {
+        let (a, b) = split(node.right.take(), at - left - node.chars);
+        node.right = a;
+        node.update();
+        (Some(node), b)
+    } else {
+        // Split the chunk itself. The new node shares the priority, so
+        // it can take over the right subtree
+        let offset = at - left;
+        let text = node.text.split_off(byte_offset(&node.text, offset));
+        let mut tail = Box::new(Node {
+            chars: node.chars - offset,
+            total: 0,
+            text,
+            priority: node.priority,
+            left: None,
+            right: node.right.take(),
+        });
+        node.chars = offset;
+        node.update();
+        tail.update();
+        (Some(node), Some(tail))
+    }
+}
+
+// Insert `text` into the chunk holding char `at`, if it has room
+fn insert_in_place(link: &mut Link, at: usize, text: &str, chars: usize) -> bool {
+    let node = match *link {
+        Some(ref mut node) => node,
+        None => return false,
+    };
+    let left = total(&node.left);
+    let inserted = if at < left {
+        insert_in_place(&mut node.left, at, text, chars)
+    } else if at > left + node.chars {
+        insert_in_place(&mut node.right, at - left - node.chars, text, chars)
+    } else if node.text.len() + text.len() <= MAX_CHUNK {
+        let offset = byte_offset(&node.text, at - left);
+        node.text.insert_str(offset, text);
+        node.chars += chars;
+        true
+    } else {
+        false
+    };
+    if inserted {
+        node.total += chars;
+    }
+    inserted
+}
+
+// Append the chars in `range` of a subtree to `out`
+fn collect(link: &Link, range: &Range<usize>, out: &mut String) {
+    let node = match *link {
+        Some(ref node) => node,
+        None => return,
+    };
+    let left = total(&node.left);
+    if range.start < left {
+        collect(&node.left, range, out);
+    }
+    let start = range.start.max(left);
+    let end = range.end.min(left + node.chars);
+    if start < end {
+        let from = byte_offset(&node.text, start - left);
+        let to = from + byte_offset(&node.text[from..], end - start);
+        out.push_str(&node.text[from..to]);
+    }
+    if range.end > left + node.chars {
+        let skip = left + node.chars;
+        let rest = range.start.saturating_sub(skip)..range.end - skip;
+        collect(&node.right, &rest, out);
+    }
+}
+
+/// A string stored as a balanced tree of chunks, for editing large
+/// texts
+///
+/// Inserting, removing and slicing text at any char offset are all
+/// O(log n), where a `String` would have to move everything after the
+/// edit. Offsets count `char`s, not bytes. The tree is a treap over the
+/// chunks, with priorities from a seeded `XorShiftRng`.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut rope = Rope::from("Hello world");
+/// rope.insert(5, ",");
+/// rope.insert(12, "! ✓");
+/// assert_eq!(rope.to_string(), "Hello, world! ✓");
+///
+/// rope.remove(0..7);
+/// assert_eq!(rope.slice(0..5), "world");
+/// assert_eq!(rope.len(), 8);
+/// assert_eq!(rope.len_bytes(), 10);
+/// ```
+#[derive(Clone)]
+pub struct Rope {
+    root: Link,
+    rng: XorShiftRng,
+}
+
+impl Default for Rope {
+    fn default() -> Rope {
+        Rope::new()
+    }
+}
+
+impl Rope {
+    /// Create an empty rope
+    pub fn new() -> Rope {
+        Rope {
+            root: None,
+            rng: XorShiftRng::new(SEED),
+        }
+    }
+
+    /// Insert `text` before the char at `at`
+    pub fn insert(&mut self, at: usize, text: &str) {
+        assert!(at <= self.len(), "insertion index out of bounds");
+        let chars = text.chars().count();
+        if chars == 0 || insert_in_place(&mut self.root, at, text, chars) {
+            return;
+        }
+        let (left, right) = split(self.root.take(), at);
+        let middle = self.build(text);
+        self.root = merge(merge(left, middle), right);
+    }
+
+    /// Remove the chars in `range`
+    pub fn remove(&mut self, range: Range<usize>) {
+        assert!(range.end <= self.len(), "removal range out of bounds");
+        if range.start >= range.end {
+            return;
+        }
+        let (rest, right) = split(self.root.take(), range.end);
+        let (left, _) = split(rest, range.start);
+        self.root = merge(left, right);
+    }
+
+    /// Return a copy of the chars in `range`
+    pub fn slice(&self, range: Range<usize>) -> String {
+        assert!(range.end <= self.len(), "slice range out of bounds");
+        let mut out = String::new();
+        if range.start < range.end {
+            collect(&self.root, &range, &mut out);
+        }
+        out
+    }
+
+    /// Return the char at `index`, or `None` if it is out of bounds
+    pub fn char(&self, index: usize) -> Option<char> {
+        let mut link = &self.root;
+        let mut index = index;
+        while let Some(ref node) = *link {
+            let left = total(&node.left);
+            if index < left {
+                link = &node.left;
+            } else if index < left + node.chars {
+                return node.text.chars().nth(index - left);
+            } else {
+                index -= left + node.chars;
+                link = &node.right;
+            }
+        }
+        None
+    }
+
+    /// Iterate over the chunks of text the rope is made of, in order
+    pub fn chunks(&self) -> Chunks<'_> {
+        let mut chunks = Chunks { stack: vec![] };
+        chunks.push_left(&self.root);
+        chunks
+    }
+
+    /// Return the number of chars in the rope
+    pub fn len(&self) -> usize {
+        total(&self.root)
+    }
+
+    /// Return the number of bytes in the rope. This is O(n) in the
+    /// number of chunks
+    pub fn len_bytes(&self) -> usize {
+        self.chunks().map(str::len).sum()
+    }
+
+    /// Returns true if the rope holds no text
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    // Make a balanced tree of chunks holding `text`
+    fn build(&mut self, text: &str) -> Link {
+        let mut root = None;
+        let mut start = 0;
+        let mut chars = 0;
+        for (i, c) in text.char_indices() {
+            if i + c.len_utf8() - start > MAX_CHUNK {
+                root = merge(root, self.chunk(&text[start..i], chars));
+                start = i;
+                chars = 0;
+            }
+            chars += 1;
+        }
+        if start < text.len() {
+            root = merge(root, self.chunk(&text[start..], chars));
+        }
+        root
+    }
+
+    fn chunk(&mut self, text: &str, chars: usize) -> Link {
+        Some(Box::new(Node {
+            text: text.to_string(),
+            chars,
+            total: chars,
+            priority: self.rng.next_u64(),
+            left: None,
+            right: None,
+        }))
+    }
+}
+
+impl<'a> From<&'a str> for Rope {
+    fn from(text: &'a str) -> Rope {
+        let mut rope = Rope::new();
+        rope.root = rope.build(text);
+        rope
+    }
+}
+
+impl From<String> for Rope {
+    fn from(text: String) -> Rope {
+        Rope::from(text.as_str())
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string(), f)
+    }
+}
+
+/// An iterator over the chunks of text in a `Rope`, in order
+pub struct Chunks<'a> {
+    // The nodes whose left subtrees are being visited
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Chunks<'a> {
+    fn push_left(&mut self, mut link: &'a Link) {
+        while let Some(ref node) = *link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        Some(&node.text)
+    }
+}
+
+#[test]
+fn test_rope() {
+    let mut rope = Rope::new();
+    assert!(rope.is_empty());
+    assert_eq!(rope.to_string(), "");
+    rope.insert(0, "");
+    assert!(rope.is_empty());
+
+    rope.insert(0, "wörld");
+    rope.insert(0, "hello ");
+    rope.insert(11, "!");
+    assert_eq!(rope.to_string(), "hello wörld!");
+    assert_eq!(rope.len(), 12);
+    assert_eq!(rope.len_bytes(), 13);
+    assert_eq!(rope.char(7), Some('ö'));
+    assert_eq!(rope.char(12), None);
+    assert_eq!(rope.slice(6..11), "wörld");
+    assert_eq!(rope.slice(3..3), "");
+
+    rope.remove(5..11);
+    assert_eq!(format!("{:?}", rope), "\"hello!\"");
+    rope.remove(0..6);
+    assert!(rope.is_empty());
+}
+
+#[test]
+fn test_rope_large_edits() {
+    // Enough text for many chunks, checked against a plain string
+    let line = "the quick brown fox jumps över the lazy dog\n";
+    let mut expected: String = line.repeat(200);
+    let mut rope = Rope::from(expected.clone());
+    assert!(rope.chunks().count() > 10);
+    assert!(rope.chunks().all(|c| c.len() <= MAX_CHUNK));
+
+    let mut rng = XorShiftRng::new(3);
+    for step in 0..300 {
+        let len = expected.chars().count();
+        let at = rng.next_u64() as usize % (len + 1);
+        let offset = byte_offset(&expected, at);
+        if step % 3 == 0 {
+            let end = (at + rng.next_u64() as usize % 300).min(len);
+            let end_offset = byte_offset(&expected, end);
+            expected.replace_range(offset..end_offset, "");
+            rope.remove(at..end);
+        } else {
+            let text: String = line.chars().take(1 + step % 40).collect();
+            expected.insert_str(offset, &text);
+            rope.insert(at, &text);
+        }
+        assert_eq!(rope.len(), expected.chars().count());
+    }
+    assert_eq!(rope.to_string(), expected);
+
+    let len = rope.len();
+    let start = byte_offset(&expected, len / 3);
+    let end = byte_offset(&expected, 2 * len / 3);
+    assert_eq!(rope.slice(len / 3..2 * len / 3), &expected[start..end]);
+    assert_eq!(rope.len_bytes(), expected.len());
+}
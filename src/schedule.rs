@@ -0,0 +1,157 @@
+/// Fires events at an explicit list of ticks, which don't need to be
+/// evenly spaced, optionally repeating every `modulus` ticks
+///
+/// Each event is identified by the index of its tick in the list the
+/// schedule was created with. Like `Timer`, it is driven with absolute
+/// times through `update`.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// // Raster effects on scanlines 40, 8 and 100 of each 154 line frame
+/// let mut effects = Schedule::repeating(vec![40, 8, 100], 154);
+///
+/// let mut fired = vec![];
+/// while let Some(effect) = effects.update(200) {
+///     fired.push(effect);
+/// }
+/// assert_eq!(fired, vec![1, 0, 2, 1, 0]);
+/// assert_eq!(effects.next_event_time(), 254);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Schedule {
+    // The ticks, sorted, along with their original index
+    ticks: Vec<(u64, usize)>,
+    modulus: Option<u64>,
+    // The start of the current repetition
+    base: u64,
+    // The position in `ticks` of the next event
+    next: usize,
+}
+
+impl Schedule {
+    /// Create a schedule that fires once at each of `ticks`
+    pub fn new(ticks: Vec<u64>) -> Schedule {
+        let mut ticks: Vec<_> = ticks.into_iter().zip(0..).collect();
+        ticks.sort();
+        Schedule {
+            ticks,
+            modulus: None,
+            base: 0,
+            next: 0,
+        }
+    }
+
+    /// Create a schedule like `new` that starts over every `modulus`
+    /// ticks. Every tick must be less than the modulus
+    pub fn repeating(ticks: Vec<u64>, modulus: u64) -> Schedule {
+        assert!(ticks.iter().all(|&tick| tick < modulus));
+        Schedule {
+            modulus: Some(modulus),
+            ..Schedule::new(ticks)
+        }
+    }
+
+    /// Get the next tick that will fire an event, or `u64::MAX` if the
+    /// schedule has run out
+    pub fn next_event_time(&self) -> u64 {
+        match self.ticks.get(self.next) {
+            Some(&(tick, _)) => self.base + tick,
+            None => u64::MAX,
+        }
+    }
+
+    /// Runs the schedule until either the given absolute `time` is
+    /// reached, or until the next event occurs, and return the index
+    /// of the event's tick. Like `Timer::update`, you should generally
+    /// run this function in a loop
+    pub fn update(&mut self, time: u64) -> Option<usize> {
+        self.update_with_tick(time).map(|(_, index)| index)
+    }
+
+    /// Like `update`, but also return the tick the event occured on
+    pub fn update_with_tick(&mut self, time: u64) -> Option<(u64, usize)> {
+        // An exhausted schedule reports `u64::MAX`, which `time` can
+        // reach, so check for it first
+        if self.next >= self.ticks.len() {
+            return None;
+        }
+        let at = self.next_event_time();
+        if at > time {
+            return None;
+        }
+
+        let index = self.ticks[self.next].1;
+        self.next += 1;
+        if let Some(modulus) = self.modulus {
+            if self.next == self.ticks.len() {
+                self.next = 0;
+                self.base += modulus;
+            }
+        }
+        Some((at, index))
+    }
+
+    /// Start the schedule over, with its first repetition starting at
+    /// the absolute tick `base`
+    pub fn restart_at(&mut self, base: u64) {
+        self.base = base;
+        self.next = 0;
+    }
+
+    /// Return the modulus, or `None` if the schedule doesn't repeat
+    pub fn modulus(&self) -> Option<u64> {
+        self.modulus
+    }
+
+    /// Return the number of ticks in the list
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    /// Returns true if the list of ticks is empty
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+}
+
+#[test]
+fn test_schedule() {
+    let mut s = Schedule::new(vec![30, 10, 20, 10]);
+    assert_eq!(s.len(), 4);
+    assert_eq!(s.modulus(), None);
+    assert_eq!(s.next_event_time(), 10);
+    assert_eq!(s.update(5), None);
+
+    let mut events = vec![];
+    while let Some(event) = s.update_with_tick(1000) {
+        events.push(event);
+    }
+    assert_eq!(events, vec![(10, 1), (10, 3), (20, 2), (30, 0)]);
+    assert_eq!(s.next_event_time(), u64::MAX);
+    assert_eq!(s.update(u64::MAX), None);
+
+    s.restart_at(100);
+    assert_eq!(s.update_with_tick(110), Some((110, 1)));
+
+    let mut empty = Schedule::new(vec![]);
+    assert_eq!(empty.next_event_time(), u64::MAX);
+    assert_eq!(empty.update_with_tick(u64::MAX), None);
+}
+
+#[test]
+fn test_schedule_repeating() {
+    let mut s = Schedule::repeating(vec![0, 3], 5);
+    let mut events = vec![];
+    while let Some(event) = s.update_with_tick(12) {
+        events.push(event);
+    }
+    assert_eq!(events, vec![(0, 0), (3, 1), (5, 0), (8, 1), (10, 0)]);
+    assert_eq!(s.next_event_time(), 13);
+
+    let mut empty = Schedule::repeating(vec![], 5);
+    assert!(empty.is_empty());
+    assert_eq!(empty.update(100), None);
+}
@@ -0,0 +1,246 @@
+/// Identifies a component within an `EventScheduler`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct ComponentId(usize);
+
+/// A scheduler for the usual emulator main loop, where each component
+/// has at most one pending event, the CPU runs until the earliest of
+/// them, and then the due events are dispatched and rescheduled
+///
+/// The CPU may overshoot an event, e.g. when it only stops between
+/// instructions. Due events are still dispatched with the tick they
+/// were scheduled for, in order, so rescheduling relative to that tick
+/// keeps every component cycle-accurate. The earliest event is cached,
+/// so checking how long the CPU can run is cheap.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut scheduler = EventScheduler::new();
+/// let hblank = scheduler.add_component();
+/// let timer = scheduler.add_component();
+/// scheduler.schedule(hblank, 456);
+/// scheduler.schedule(timer, 256);
+///
+/// let mut log = vec![];
+/// while scheduler.now() < 900 {
+///     // Run the CPU in 12 cycle instructions, up to the next event
+///     let budget = scheduler.cycles_until_next_event();
+///     let ran = (budget + 11) / 12 * 12;
+///     scheduler.advance(ran);
+///
+///     scheduler.dispatch_due(|id, tick| {
+///         log.push((tick, id));
+///         Some(if id == hblank { tick + 456 } else { tick + 256 })
+///     });
+/// }
+/// assert_eq!(
+///     log,
+///     vec![(256, timer), (456, hblank), (512, timer), (768, timer), (912, hblank)]
+/// );
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
+pub struct EventScheduler {
+    now: u64,
+    // The pending event of each component
+    ticks: Vec<Option<u64>>,
+    // The earliest pending event, as `(tick, component)`
+    next: Option<(u64, usize)>,
+}
+
+impl EventScheduler {
+    /// Create a scheduler with no components, whose time starts at 0
+    pub fn new() -> EventScheduler {
+        EventScheduler::default()
+    }
+
+    /// Add a component with no pending event, and return its handle
+    pub fn add_component(&mut self) -> ComponentId {
+        self.ticks.push(None);
+        ComponentId(self.ticks.len() - 1)
+    }
+
+    /// Set the pending event of the component `id` to the absolute
+    /// `tick`, replacing any previous one. A tick that has already
+    /// passed is due immediately
+    pub fn schedule(&mut self, id: ComponentId, tick: u64) {
+        self.ticks[id.0] = Some(tick);
+        match self.next {
+            Some(next) if next.1 != id.0 && next < (tick, id.0) => {}
+            Some(next) if next.1 == id.0 && next.0 < tick => self.find_next(),
+            _ => self.next = Some((tick, id.0)),
+        }
+    }
+
+    /// Set the pending event of the component `id` to `delay` ticks
+    /// after the current time
+    pub fn schedule_in(&mut self, id: ComponentId, delay: u64) {
+        let tick = self.now + delay;
+        self.schedule(id, tick);
+    }
+
+    /// Remove the pending event of the component `id`, and return its
+    /// tick
+    pub fn unschedule(&mut self, id: ComponentId) -> Option<u64> {
+        let tick = self.ticks[id.0].take();
+        if self.next.map(|(_, i)| i) == Some(id.0) {
+            self.find_next();
+        }
+        tick
+    }
+
+    /// Return the tick of the pending event of the component `id`
+    pub fn scheduled(&self, id: ComponentId) -> Option<u64> {
+        self.ticks[id.0]
+    }
+
+    /// Get the tick of the earliest pending event, or `u64::MAX` if
+    /// there are none
+    pub fn next_event_time(&self) -> u64 {
+        self.next.map_or(u64::MAX, |(tick, _)| tick)
+    }
+
+    /// Return how many ticks the CPU can run before the earliest
+    /// pending event, which is 0 if one is already due
+    pub fn cycles_until_next_event(&self) -> u64 {
+        self.next_event_time().saturating_sub(self.now)
+    }
+
+    /// Move the current time forward by the `cycles` the CPU ran
+    pub fn advance(&mut self, cycles: u64) {
+        self.now += cycles;
+    }
+
+    /// Move the current time forward to the absolute `time`
+    pub fn advance_to(&mut self, time: u64) {
+        assert!(time >= self.now);
+        self.now = time;
+    }
+
+    /// Return the current time
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Remove and return the earliest event that is due at or before
+    /// the current time, along with its tick. Events at the same tick
+    /// are ordered by handle. The component has no pending event until
+    /// it is scheduled again
+    pub fn pop_due(&mut self) -> Option<(ComponentId, u64)> {
+        match self.next {
+            Some((tick, i)) if tick <= self.now => {
+                self.ticks[i] = None;
+                self.find_next();
+                Some((ComponentId(i), tick))
+            }
+            _ => None,
+        }
+    }
+
+    /// Pass every due event to `dispatch` in order, and schedule each
+    /// component again at the absolute tick it returns, if any. Events
+    /// rescheduled at or before the current time are dispatched too.
+    /// Returns the number of events dispatched
+    pub fn dispatch_due<F>(&mut self, mut dispatch: F) -> usize
+    where
+        F: FnMut(ComponentId, u64) -> Option<u64>,
+    {
+        let mut dispatched = 0;
+        while let Some((id, tick)) = self.pop_due() {
+            if let Some(next) = dispatch(id, tick) {
+                self.schedule(id, next);
+            }
+            dispatched += 1;
+        }
+        dispatched
+    }
+
+    /// Return the number of components
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    /// Returns true if there are no components
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    fn find_next(&mut self) {
+        self.next = self
+            .ticks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, tick)| tick.map(|tick| (tick, i)))
+            .min();
+    }
+}
+
+#[test]
+fn test_event_scheduler() {
+    let mut s = EventScheduler::new();
+    assert_eq!(s.next_event_time(), u64::MAX);
+    assert_eq!(s.pop_due(), None);
+
+    let a = s.add_component();
+    let b = s.add_component();
+    let c = s.add_component();
+    assert_eq!(s.len(), 3);
+
+    s.schedule(a, 30);
+    s.schedule(b, 10);
+    s.schedule(c, 10);
+    assert_eq!(s.next_event_time(), 10);
+    assert_eq!(s.cycles_until_next_event(), 10);
+
+    // Moving the earliest event later finds the next one
+    s.schedule(b, 40);
+    assert_eq!(s.next_event_time(), 10);
+    assert_eq!(s.unschedule(c), Some(10));
+    assert_eq!(s.next_event_time(), 30);
+    assert_eq!(s.scheduled(c), None);
+
+    s.schedule_in(c, 30);
+    s.advance_to(35);
+    assert_eq!(s.cycles_until_next_event(), 0);
+    assert_eq!(s.pop_due(), Some((a, 30)));
+    assert_eq!(s.pop_due(), Some((c, 30)));
+    assert_eq!(s.pop_due(), None);
+    assert_eq!(s.cycles_until_next_event(), 5);
+}
+
+#[test]
+fn test_event_scheduler_catch_up() {
+    let mut s = EventScheduler::new();
+    let fast = s.add_component();
+    let slow = s.add_component();
+    s.schedule(fast, 0);
+    s.schedule(slow, 25);
+
+    // Jump far past several events at once
+    s.advance(100);
+    let mut log = vec![];
+    let n = s.dispatch_due(|id, tick| {
+        log.push((tick, id));
+        if id == fast {
+            Some(tick + 20)
+        } else {
+            None
+        }
+    });
+    assert_eq!(n, 7);
+    assert_eq!(
+        log,
+        vec![
+            (0, fast),
+            (20, fast),
+            (25, slow),
+            (40, fast),
+            (60, fast),
+            (80, fast),
+            (100, fast),
+        ]
+    );
+    assert_eq!(s.next_event_time(), 120);
+    assert_eq!(s.scheduled(slow), None);
+}
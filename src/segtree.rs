@@ -0,0 +1,343 @@
+use std::ops::{Add, Range};
+
+/// An associative operation that a `SegmentTree` combines its values
+/// with, such as a sum or a minimum
+pub trait SegmentOp<T> {
+    /// Combine the aggregates of two neighbouring runs of values
+    fn combine(&self, a: T, b: T) -> T;
+
+    /// Returns the aggregate of a run of `len` values that have each had
+    /// `delta` added to them, given the run's old `aggregate`
+    fn add(&self, aggregate: T, delta: T, len: usize) -> T;
+
+    /// Returns the aggregate of a run of `len` copies of `value`. By
+    /// default this combines the copies by repeated doubling
+    fn repeat(&self, value: T, len: usize) -> T
+    where
+        T: Copy,
+    {
+        let mut result = value;
+        let mut power = value;
+        let mut n = len - 1;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = self.combine(result, power);
+            }
+            power = self.combine(power, power);
+            n >>= 1;
+        }
+        result
+    }
+}
+
+/// Sums the values
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct Sum;
+
+impl<T: Copy + Add<Output = T>> SegmentOp<T> for Sum {
+    fn combine(&self, a: T, b: T) -> T {
+        a + b
+    }
+
+    fn add(&self, aggregate: T, delta: T, len: usize) -> T {
+        aggregate + self.repeat(delta, len)
+    }
+}
+
+/// Takes the smallest of the values
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct Min;
+
+impl<T: Copy + PartialOrd + Add<Output = T>> SegmentOp<T> for Min {
+    fn combine(&self, a: T, b: T) -> T {
+        if b < a {
+            b
+        } else {
+            a
+        }
+    }
+
+    fn add(&self, aggregate: T, delta: T, _len: usize) -> T {
+        aggregate + delta
+    }
+
+    fn repeat(&self, value: T, _len: usize) -> T {
+        value
+    }
+}
+
+/// Takes the largest of the values
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct Max;
+
+impl<T: Copy + PartialOrd + Add<Output = T>> SegmentOp<T> for Max {
+    fn combine(&self, a: T, b: T) -> T {
+        if b > a {
+            b
+        } else {
+            a
+        }
+    }
+
+    fn add(&self, aggregate: T, delta: T, _len: usize) -> T {
+        aggregate + delta
+    }
+
+    fn repeat(&self, value: T, _len: usize) -> T {
+        value
+    }
+}
+
+// An update waiting to be passed down to a node's children
+#[derive(Debug, Copy, Clone)]
+enum Pending<T> {
+    Add(T),
+    Assign(T),
+}
+
+/// A fixed length array that answers queries over any range of its
+/// values, combined with an associative operation, in O(log n)
+///
+/// Adding to or assigning every value in a range is also O(log n),
+/// because updates are only passed down the tree when a later update or
+/// query needs them. The operation is given as a `SegmentOp`, such as
+/// `Sum`, `Min` or `Max`.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut totals = SegmentTree::new(Sum, &[3, 1, 4, 1, 5, 9, 2, 6]);
+/// assert_eq!(totals.query(2..6), Some(19));
+///
+/// totals.add(0..4, 10);
+/// totals.assign(6..8, 0);
+/// assert_eq!(totals.query(3..8), Some(25));
+/// assert_eq!(totals.get(0), Some(13));
+///
+/// let lowest = SegmentTree::new(Min, &[3, 1, 4, 1, 5]);
+/// assert_eq!(lowest.query(2..5), Some(1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SegmentTree<T, O> {
+    op: O,
+    len: usize,
+    // The aggregate of each node's range, with the root at 1 and the
+    // children of node i at 2i and 2i + 1
+    nodes: Vec<T>,
+    pending: Vec<Option<Pending<T>>>,
+}
+
+impl<T: Copy + Add<Output = T>, O: SegmentOp<T>> SegmentTree<T, O> {
+    /// Create a tree over a copy of `values`, combined with `op`
+    pub fn new(op: O, values: &[T]) -> SegmentTree<T, O> {
+        let size = 2 * values.len().next_power_of_two();
+        let mut tree = SegmentTree {
+            op,
+            len: values.len(),
+            nodes: Vec::with_capacity(size),
+            pending: vec![None; size],
+        };
+        if let Some(&first) = values.first() {
+            tree.nodes.resize(size, first);
+            tree.build(1, 0, values.len(), values);
+        }
+        tree
+    }
+
+    /// Return the values in `range` combined, or `None` if the range is
+    /// empty
+    pub fn query(&self, range: Range<usize>) -> Option<T> {
+        assert!(range.end <= self.len, "query range out of bounds");
+        if range.start >= range.end {
+            return None;
+        }
+        Some(self.query_node(1, 0, self.len, &range))
+    }
+
+    /// Return the value at `index`, or `None` if it is out of bounds
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < self.len {
+            self.query(index..index + 1)
+        } else {
+            None
+        }
+    }
+
+    /// Add `delta` to every value in `range`
+    pub fn add(&mut self, range: Range<usize>, delta: T) {
+        self.update(range, Pending::Add(delta));
+    }
+
+    /// Set every value in `range` to `value`
+    pub fn assign(&mut self, range: Range<usize>, value: T) {
+        self.update(range, Pending::Assign(value));
+    }
+
+    /// Set the value at `index` to `value`
+    pub fn set(&mut self, index: usize, value: T) {
+        self.assign(index..index + 1, value);
+    }
+
+    /// Return the number of values in the tree
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the tree has no values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn build(&mut self, node: usize, lo: usize, hi: usize, values: &[T]) {
+        if hi - lo == 1 {
+            self.nodes[node] = values[lo];
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        self.build(2 * node, lo, mid, values);
+        self.build(2 * node + 1, mid, hi, values);
+        self.nodes[node] = self
+            .op
+            .combine(self.nodes[2 * node], self.nodes[2 * node + 1]);
+    }
+
+    fn update(&mut self, range: Range<usize>, update: Pending<T>) {
+        assert!(range.end <= self.len, "update range out of bounds");
+        if range.start < range.end {
+            self.update_node(1, 0, self.len, &range, update);
+        }
+    }
+
+    fn update_node(
+        &mut self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        range: &Range<usize>,
+        update: Pending<T>,
+    ) {
+        if range.end <= lo || hi <= range.start {
+            return;
+        }
+        if range.start <= lo && hi <= range.end {
+            self.apply(node, hi - lo, update);
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        if let Some(pending) = self.pending[node].take() {
+            self.apply(2 * node, mid - lo, pending);
+            self.apply(2 * node + 1, hi - mid, pending);
+        }
+        self.update_node(2 * node, lo, mid, range, update);
+        self.update_node(2 * node + 1, mid, hi, range, update);
+        self.nodes[node] = self
+            .op
+            .combine(self.nodes[2 * node], self.nodes[2 * node + 1]);
+    }
+
+    // Apply an update to all `len` values under a node, and save it to
+    // pass down to the node's children later
+    fn apply(&mut self, node: usize, len: usize, update: Pending<T>) {
+        self.nodes[node] = self.updated(self.nodes[node], len, update);
+        self.pending[node] = Some(match (self.pending[node], update) {
+            (Some(Pending::Add(a)), Pending::Add(b)) => Pending::Add(a + b),
+            (Some(Pending::Assign(a)), Pending::Add(b)) => Pending::Assign(a + b),
+            (_, update) => update,
+        });
+    }
+
+    fn updated(&self, aggregate: T, len: usize, update: Pending<T>) -> T {
+        match update {
+            Pending::Add(delta) => self.op.add(aggregate, delta, len),
+            Pending::Assign(value) => self.op.repeat(value, len),
+        }
+    }
+
+    fn query_node(&self, node: usize, lo: usize, hi: usize, range: &Range<usize>) -> T {
+        if range.start <= lo && hi <= range.end {
+            return self.nodes[node];
+        }
+        let mid = lo + (hi - lo) / 2;
+        let result = if range.end <= mid {
+            self.query_node(2 * node, lo, mid, range)
+        } else if range.start >= mid {
+            self.query_node(2 * node + 1, mid, hi, range)
+        } else {
+            let left = self.query_node(2 * node, lo, mid, range);
+            let right = self.query_node(2 * node + 1, mid, hi, range);
+            self.op.combine(left, right)
+        };
+        // The children haven't seen this node's pending update yet
+        match self.pending[node] {
+            Some(update) => {
+                let len = range.end.min(hi) - range.start.max(lo);
+                self.updated(result, len, update)
+            }
+            None => result,
+        }
+    }
+}
+
+#[test]
+fn test_segment_tree() {
+    let values = [5i64, -2, 7, 0, 3, 3, -8, 1, 4, 6, -1];
+    let mut naive = values.to_vec();
+    let mut sums = SegmentTree::new(Sum, &values);
+    let mut mins = SegmentTree::new(Min, &values);
+    let mut maxes = SegmentTree::new(Max, &values);
+    assert_eq!(sums.len(), 11);
+
+    let updates = [
+        (0, 11, 2, false),
+        (3, 7, -4, false),
+        (2, 9, 10, true),
+        (5, 6, 1, false),
+        (0, 4, -3, true),
+        (4, 11, 5, false),
+        (7, 8, 20, true),
+    ];
+    for &(start, end, value, assign) in &updates {
+        for v in &mut naive[start..end] {
+            *v = if assign { value } else { *v + value };
+        }
+        if assign {
+            sums.assign(start..end, value);
+            mins.assign(start..end, value);
+            maxes.assign(start..end, value);
+        } else {
+            sums.add(start..end, value);
+            mins.add(start..end, value);
+            maxes.add(start..end, value);
+        }
+
+        for start in 0..11 {
+            for end in start + 1..12 {
+                let window = &naive[start..end];
+                assert_eq!(sums.query(start..end), Some(window.iter().sum()));
+                assert_eq!(mins.query(start..end), window.iter().min().cloned());
+                assert_eq!(maxes.query(start..end), window.iter().max().cloned());
+            }
+        }
+    }
+
+    assert_eq!(sums.query(4..4), None);
+    assert_eq!(sums.get(7), Some(20));
+    assert_eq!(sums.get(11), None);
+    sums.set(7, 0);
+    assert_eq!(sums.query(0..11), Some(naive.iter().sum::<i64>() - 20));
+}
+
+#[test]
+fn test_segment_tree_empty() {
+    let mut tree = SegmentTree::new(Sum, &[] as &[u32]);
+    assert!(tree.is_empty());
+    assert_eq!(tree.query(0..0), None);
+    tree.add(0..0, 1);
+    assert_eq!(tree.get(0), None);
+
+    let mut one = SegmentTree::new(Max, &[1.5]);
+    one.add(0..1, 1.0);
+    assert_eq!(one.get(0), Some(2.5));
+}
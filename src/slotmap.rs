@@ -0,0 +1,203 @@
+use std::iter::Enumerate;
+use std::slice;
+
+/// Identifies a value within a `SlotMap`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct SlotKey {
+    index: usize,
+    generation: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<T> {
+    generation: u64,
+    value: Option<T>,
+}
+
+/// A map that stores values in reusable slots and hands out keys for
+/// them
+///
+/// Inserting, removing and looking up a value are O(1). Each key also
+/// records the generation of its slot, so once a value is removed its
+/// key stays invalid, even after the slot is reused by another value.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut entities = SlotMap::new();
+/// let player = entities.insert("player");
+/// let enemy = entities.insert("enemy");
+///
+/// assert_eq!(entities.remove(enemy), Some("enemy"));
+/// let pickup = entities.insert("pickup");
+/// assert_eq!(entities.get(enemy), None);
+/// assert_eq!(entities.get(pickup), Some(&"pickup"));
+/// assert_eq!(entities.get(player), Some(&"player"));
+/// assert_eq!(entities.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlotMap<T> {
+    slots: Vec<Slot<T>>,
+    // Indices of empty slots, ready for reuse
+    free: Vec<usize>,
+}
+
+impl<T> Default for SlotMap<T> {
+    fn default() -> SlotMap<T> {
+        SlotMap::new()
+    }
+}
+
+impl<T> SlotMap<T> {
+    /// Create an empty map
+    pub fn new() -> SlotMap<T> {
+        SlotMap {
+            slots: vec![],
+            free: vec![],
+        }
+    }
+
+    /// Add `value` to the map, and return its key
+    pub fn insert(&mut self, value: T) -> SlotKey {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    value: None,
+                });
+                self.slots.len() - 1
+            }
+        };
+
+        let slot = &mut self.slots[index];
+        slot.value = Some(value);
+        SlotKey {
+            index,
+            generation: slot.generation,
+        }
+    }
+
+    /// Remove the value identified by `key` from the map, and return
+    /// it. Returns `None` if it was already removed
+    pub fn remove(&mut self, key: SlotKey) -> Option<T> {
+        let slot = self.slots.get_mut(key.index)?;
+        if slot.generation != key.generation {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation += 1;
+        self.free.push(key.index);
+        Some(value)
+    }
+
+    /// Return the value identified by `key`, or `None` if it was
+    /// removed
+    pub fn get(&self, key: SlotKey) -> Option<&T> {
+        match self.slots.get(key.index) {
+            Some(slot) if slot.generation == key.generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Return the value identified by `key` for modification, or
+    /// `None` if it was removed
+    pub fn get_mut(&mut self, key: SlotKey) -> Option<&mut T> {
+        match self.slots.get_mut(key.index) {
+            Some(slot) if slot.generation == key.generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the value identified by `key` is in the map
+    pub fn contains_key(&self, key: SlotKey) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Iterate over every value in the map along with its key, in slot
+    /// order
+    pub fn iter(&self) -> Slots<'_, T> {
+        Slots {
+            slots: self.slots.iter().enumerate(),
+        }
+    }
+
+    /// Remove every value from the map. Keys to the removed values stay
+    /// invalid
+    pub fn clear(&mut self) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.value.take().is_some() {
+                slot.generation += 1;
+                self.free.push(index);
+            }
+        }
+    }
+
+    /// Return the number of values in the map
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// Returns true if the map has no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// An iterator over the keys and values of a `SlotMap`
+pub struct Slots<'a, T> {
+    slots: Enumerate<slice::Iter<'a, Slot<T>>>,
+}
+
+impl<'a, T> Iterator for Slots<'a, T> {
+    type Item = (SlotKey, &'a T);
+
+    fn next(&mut self) -> Option<(SlotKey, &'a T)> {
+        for (index, slot) in &mut self.slots {
+            if let Some(ref value) = slot.value {
+                let key = SlotKey {
+                    index,
+                    generation: slot.generation,
+                };
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn test_slot_map() {
+    let mut map = SlotMap::new();
+    assert!(map.is_empty());
+
+    let a = map.insert('a');
+    let b = map.insert('b');
+    let c = map.insert('c');
+    *map.get_mut(b).unwrap() = 'B';
+    assert_eq!(map.len(), 3);
+
+    assert_eq!(map.remove(b), Some('B'));
+    assert_eq!(map.remove(b), None);
+    assert!(!map.contains_key(b));
+
+    // The slot is reused, but the old key stays invalid
+    let d = map.insert('d');
+    assert_ne!(b, d);
+    assert_eq!(map.get(b), None);
+    assert_eq!(map.get_mut(b), None);
+    assert_eq!(map.remove(b), None);
+    assert_eq!(map.get(d), Some(&'d'));
+
+    let all: Vec<_> = map.iter().collect();
+    assert_eq!(all, vec![(a, &'a'), (d, &'d'), (c, &'c')]);
+
+    map.clear();
+    assert!(map.is_empty());
+    assert!(!map.contains_key(a));
+    let e = map.insert('e');
+    assert_eq!(map.get(e), Some(&'e'));
+    assert_eq!(map.len(), 1);
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+
+use arrayvec::ArrayVec;
+
+enum Storage<T, const N: usize> {
+    Inline(ArrayVec<T, N>),
+    Heap(Vec<T>),
+}
+
+/// A vector that holds up to `N` values inline, and moves them to the
+/// heap once it grows past that
+///
+/// Small vectors never allocate, so many of them can be created and
+/// dropped cheaply. Otherwise it behaves like a `Vec`, and dereferences
+/// to a slice.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut v = SmallVec::<u8, 4>::new();
+/// v.extend(1..=4);
+/// assert!(!v.spilled());
+///
+/// v.push(5);
+/// assert!(v.spilled());
+/// assert_eq!(&v[..], &[1, 2, 3, 4, 5]);
+///
+/// v.truncate(2);
+/// v.shrink_to_fit();
+/// assert!(!v.spilled());
+/// ```
+pub struct SmallVec<T, const N: usize> {
+    storage: Storage<T, N>,
+}
+
+impl<T, const N: usize> SmallVec<T, N> {
+    /// Create an empty vector
+    pub fn new() -> SmallVec<T, N> {
+        SmallVec {
+            storage: Storage::Inline(ArrayVec::new()),
+        }
+    }
+
+    /// Add `value` to the end of the vector
+    pub fn push(&mut self, value: T) {
+        let len = self.len();
+        self.insert(len, value);
+    }
+
+    /// Remove the last value from the vector and return it, or `None`
+    /// if the vector is empty
+    pub fn pop(&mut self) -> Option<T> {
+        match self.storage {
+            Storage::Inline(ref mut v) => v.pop(),
+            Storage::Heap(ref mut v) => v.pop(),
+        }
+    }
+
+    /// Insert `value` at `index`, shifting the values after it to the
+    /// right. Panics if `index` is greater than the length
+    pub fn insert(&mut self, index: usize, value: T) {
+        if let Storage::Inline(ref mut v) = self.storage {
+            if !v.is_full() {
+                v.insert(index, value);
+                return;
+            }
+        }
+        self.spill().insert(index, value);
+    }
+
+    /// Remove the value at `index` and return it, shifting the values
+    /// after it to the left. Panics if `index` is out of bounds
+    pub fn remove(&mut self, index: usize) -> T {
+        match self.storage {
+            Storage::Inline(ref mut v) => v.remove(index),
+            Storage::Heap(ref mut v) => v.remove(index),
+        }
+    }
+
+    /// Remove the value at `index` and return it, replacing it with the
+    /// last value. Panics if `index` is out of bounds
+    pub fn swap_remove(&mut self, index: usize) -> T {
+        match self.storage {
+            Storage::Inline(ref mut v) => v.swap_remove(index),
+            Storage::Heap(ref mut v) => v.swap_remove(index),
+        }
+    }
+
+    /// Shorten the vector to `len` values, dropping the rest. Does
+    /// nothing if it is already shorter
+    pub fn truncate(&mut self, len: usize) {
+        match self.storage {
+            Storage::Inline(ref mut v) => v.truncate(len),
+            Storage::Heap(ref mut v) => v.truncate(len),
+        }
+    }
+
+    /// Remove all values from the vector. Values on the heap stay
+    /// there, use `shrink_to_fit` to move them back inline
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+
+    /// Move the values back inline if they fit, or otherwise shrink the
+    /// heap allocation to fit them
+    pub fn shrink_to_fit(&mut self) {
+        let inline = match self.storage {
+            Storage::Heap(ref mut v) if v.len() <= N => {
+                let mut inline = ArrayVec::new();
+                for value in v.drain(..) {
+                    inline.push(value);
+                }
+                inline
+            }
+            Storage::Heap(ref mut v) => {
+                v.shrink_to_fit();
+                return;
+            }
+            Storage::Inline(_) => return,
+        };
+        self.storage = Storage::Inline(inline);
+    }
+
+    /// Returns true if the values have been moved to the heap
+    pub fn spilled(&self) -> bool {
+        match self.storage {
+            Storage::Inline(_) => false,
+            Storage::Heap(_) => true,
+        }
+    }
+
+    /// Return the values as a slice
+    pub fn as_slice(&self) -> &[T] {
+        match self.storage {
+            Storage::Inline(ref v) => v,
+            Storage::Heap(ref v) => v,
+        }
+    }
+
+    /// Return the values as a mutable slice
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match self.storage {
+            Storage::Inline(ref mut v) => v,
+            Storage::Heap(ref mut v) => v,
+        }
+    }
+
+    /// Move the values into a `Vec`. This doesn't allocate if they are
+    /// already on the heap
+    pub fn into_vec(self) -> Vec<T> {
+        match self.storage {
+            Storage::Inline(mut v) => {
+                let mut heap = Vec::with_capacity(v.len());
+                while let Some(value) = v.pop() {
+                    heap.push(value);
+                }
+                heap.reverse();
+                heap
+            }
+            Storage::Heap(v) => v,
+        }
+    }
+
+    /// Returns the number of values in the vector
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns true if there are no values in the vector
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Move the values to the heap, with room to add at least one more
+    fn spill(&mut self) -> &mut Vec<T> {
+        if let Storage::Inline(ref mut v) = self.storage {
+            let mut heap = Vec::with_capacity(2 * N + 1);
+            let mut inline = mem::take(v);
+            while let Some(value) = inline.pop() {
+                heap.push(value);
+            }
+            heap.reverse();
+            self.storage = Storage::Heap(heap);
+        }
+        match self.storage {
+            Storage::Heap(ref mut v) => v,
+            Storage::Inline(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T, const N: usize> Default for SmallVec<T, N> {
+    fn default() -> SmallVec<T, N> {
+        SmallVec::new()
+    }
+}
+
+impl<T, const N: usize> Deref for SmallVec<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, const N: usize> DerefMut for SmallVec<T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a SmallVec<T, N> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for SmallVec<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        for value in values {
+            self.push(value);
+        }
+    }
+}
+
+impl<T, const N: usize> From<Vec<T>> for SmallVec<T, N> {
+    fn from(values: Vec<T>) -> SmallVec<T, N> {
+        let mut v = SmallVec {
+            storage: Storage::Heap(values),
+        };
+        v.shrink_to_fit();
+        v
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for SmallVec<T, N> {
+    fn clone(&self) -> SmallVec<T, N> {
+        let mut v = SmallVec::new();
+        v.extend(self.iter().cloned());
+        v
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for SmallVec<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for SmallVec<T, N> {
+    fn eq(&self, other: &SmallVec<T, N>) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for SmallVec<T, N> {}
+
+#[test]
+fn test_small_vec() {
+    let mut v = SmallVec::<i32, 2>::new();
+    assert!(v.is_empty());
+    assert_eq!(v.pop(), None);
+
+    v.push(1);
+    v.insert(0, 0);
+    assert!(!v.spilled());
+    v.insert(1, 5);
+    assert!(v.spilled());
+    v.push(2);
+    assert_eq!(format!("{:?}", v), "[0, 5, 1, 2]");
+    assert_eq!(v.clone(), v);
+
+    assert_eq!(v.remove(1), 5);
+    assert_eq!(v.swap_remove(0), 0);
+    assert_eq!(&v[..], &[2, 1]);
+    v.sort();
+    v.shrink_to_fit();
+    assert!(!v.spilled());
+    assert_eq!(v.into_vec(), vec![1, 2]);
+
+    let v = SmallVec::<i32, 2>::from(vec![1, 2, 3]);
+    assert!(v.spilled());
+    assert_eq!(v.len(), 3);
+    let v = SmallVec::<i32, 4>::from(vec![1, 2, 3]);
+    assert!(!v.spilled());
+}
@@ -0,0 +1,228 @@
+use std::cmp::Ordering;
+
+use treap::{Treap, TreapIter};
+
+/// A list that keeps its values sorted, and can find the value at any
+/// index, or the index of any value, in O(log n)
+///
+/// Unlike a `BTreeSet`, the list can hold equal values, which are kept
+/// in the order they were inserted, so it works for things like
+/// leaderboards where scores tie. It is built on a `Treap`, so
+/// inserting and removing values are O(log n) as well.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut latencies = SortedList::new();
+/// for &ms in &[12, 7, 30, 7, 18, 95, 21, 9, 14, 11] {
+///     latencies.insert(ms);
+/// }
+///
+/// // The median and 90th percentile
+/// assert_eq!(latencies.select(latencies.len() / 2), Some(&14));
+/// assert_eq!(latencies.select(latencies.len() * 9 / 10), Some(&95));
+///
+/// // How many were faster than 12ms
+/// assert_eq!(latencies.rank(&12), 4);
+/// assert_eq!(latencies.count(&7), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SortedList<T> {
+    // Each value is paired with the order it was inserted in, to tell
+    // equal values apart
+    values: Treap<(T, u64), ()>,
+    next: u64,
+}
+
+impl<T: Ord + Clone> Default for SortedList<T> {
+    fn default() -> SortedList<T> {
+        SortedList::new()
+    }
+}
+
+impl<T: Ord + Clone> SortedList<T> {
+    /// Create an empty list
+    pub fn new() -> SortedList<T> {
+        SortedList {
+            values: Treap::new(),
+            next: 0,
+        }
+    }
+
+    /// Add `value` to the list, after any values equal to it
+    pub fn insert(&mut self, value: T) {
+        self.values.insert((value, self.next), ());
+        self.next += 1;
+    }
+
+    /// Remove the first value equal to `value`, and return true if there
+    /// was one
+    pub fn remove(&mut self, value: &T) -> bool {
+        let index = self.rank(value);
+        let key = match self.values.select(index) {
+            Some((&(ref found, order), _)) if found.cmp(value) == Ordering::Equal => {
+                (found.clone(), order)
+            }
+            _ => return false,
+        };
+        self.values.remove(&key);
+        true
+    }
+
+    /// Remove the value at `index` and return it, or `None` if `index` is
+    /// out of bounds
+    pub fn remove_index(&mut self, index: usize) -> Option<T> {
+        let key = self.values.select(index)?.0.clone();
+        self.values.remove(&key);
+        Some(key.0)
+    }
+
+    /// Return the value at `index` in sorted order, counting from 0, or
+    /// `None` if `index` is out of bounds
+    pub fn select(&self, index: usize) -> Option<&T> {
+        self.values.select(index).map(|((value, _), _)| value)
+    }
+
+    /// Return the number of values less than `value`, which is the
+    /// index of the first value equal to it, if there is one
+    pub fn rank(&self, value: &T) -> usize {
+        self.values.rank(&(value.clone(), 0))
+    }
+
+    /// Return the number of values equal to `value`
+    pub fn count(&self, value: &T) -> usize {
+        self.values.rank(&(value.clone(), u64::MAX)) - self.rank(value)
+    }
+
+    /// Returns true if there is a value equal to `value` in the list
+    pub fn contains(&self, value: &T) -> bool {
+        self.count(value) > 0
+    }
+
+    /// Return the smallest value, or `None` if the list is empty
+    pub fn first(&self) -> Option<&T> {
+        self.values.first().map(|((value, _), _)| value)
+    }
+
+    /// Return the largest value, or `None` if the list is empty
+    pub fn last(&self) -> Option<&T> {
+        self.values.last().map(|((value, _), _)| value)
+    }
+
+    /// Iterate over the values in sorted order
+    pub fn iter(&self) -> SortedIter<'_, T> {
+        SortedIter {
+            iter: self.values.iter(),
+        }
+    }
+
+    /// Remove every value from the list
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Return the number of values in the list
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if there are no values in the list
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// An iterator over the values of a `SortedList`, in sorted order
+pub struct SortedIter<'a, T: 'a> {
+    iter: TreapIter<'a, (T, u64), ()>,
+}
+
+impl<'a, T> Iterator for SortedIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.iter.next().map(|((value, _), _)| value)
+    }
+}
+
+#[test]
+fn test_sorted_list() {
+    let mut list = SortedList::new();
+    assert!(list.is_empty());
+    assert_eq!(list.select(0), None);
+    assert!(!list.remove(&1));
+
+    for &value in &[5, 3, 8, 3, 1, 9, 3, 6] {
+        list.insert(value);
+    }
+    assert_eq!(list.len(), 8);
+    assert_eq!(
+        list.iter().cloned().collect::<Vec<_>>(),
+        vec![1, 3, 3, 3, 5, 6, 8, 9]
+    );
+    assert_eq!((list.first(), list.last()), (Some(&1), Some(&9)));
+    assert_eq!(list.select(4), Some(&5));
+    assert_eq!(list.select(8), None);
+    assert_eq!(list.rank(&3), 1);
+    assert_eq!(list.rank(&4), 4);
+    assert_eq!(list.rank(&100), 8);
+    assert_eq!(list.count(&3), 3);
+    assert!(!list.contains(&4));
+
+    assert!(list.remove(&3));
+    assert!(!list.remove(&4));
+    assert_eq!(list.count(&3), 2);
+    assert_eq!(list.remove_index(0), Some(1));
+    assert_eq!(list.remove_index(5), Some(9));
+    assert_eq!(list.remove_index(5), None);
+    assert_eq!(
+        list.iter().cloned().collect::<Vec<_>>(),
+        vec![3, 3, 5, 6, 8]
+    );
+
+    list.clear();
+    assert_eq!(list.len(), 0);
+}
+
+#[test]
+fn test_sorted_list_ties() {
+    // Compared by score alone, so players can tie
+    #[derive(Debug, Clone)]
+    struct Player(u32, &'static str);
+
+    impl PartialEq for Player {
+        fn eq(&self, other: &Player) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Player {}
+
+    impl Ord for Player {
+        fn cmp(&self, other: &Player) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    impl PartialOrd for Player {
+        fn partial_cmp(&self, other: &Player) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    let mut board = SortedList::new();
+    board.insert(Player(300, "carol"));
+    board.insert(Player(100, "dave"));
+    board.insert(Player(300, "alice"));
+    board.insert(Player(300, "bob"));
+    assert_eq!(board.rank(&Player(300, "")), 1);
+    assert_eq!(board.count(&Player(300, "")), 3);
+
+    // Ties keep the order they were inserted in
+    let names: Vec<_> = board.iter().map(|p| p.1).collect();
+    assert_eq!(names, vec!["dave", "carol", "alice", "bob"]);
+    assert!(board.remove(&Player(300, "")));
+    assert_eq!(board.select(1).map(|p| p.1), Some("alice"));
+}
@@ -0,0 +1,493 @@
+use std::borrow::Borrow;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Bound, Deref, Range, RangeBounds};
+use std::slice;
+
+// Find the indexes of the sorted `values` whose keys are in `range`
+fn range_indexes<T, Q, F, R>(values: &[T], key: F, range: R) -> Range<usize>
+where
+    Q: ?Sized + Ord,
+    F: Fn(&T) -> &Q,
+    R: RangeBounds<Q>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(start) => values.partition_point(|v| key(v) < start),
+        Bound::Excluded(start) => values.partition_point(|v| key(v) <= start),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => values.partition_point(|v| key(v) <= end),
+        Bound::Excluded(end) => values.partition_point(|v| key(v) < end),
+        Bound::Unbounded => values.len(),
+    };
+    start..end.max(start)
+}
+
+/// A set that keeps its values sorted in one contiguous vector
+///
+/// Lookups are binary searches and iterating is walking a slice, which
+/// for small and medium sets that are mostly read is faster than a
+/// `BTreeSet`. Inserting and removing values are O(n), since the values
+/// after them have to move, so build large sets in bulk with `from` or
+/// `collect`, which sort once. The set dereferences to a sorted slice.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut ports: SortedVec<u16> = vec![443, 22, 8080, 80, 22].into();
+/// assert_eq!(&ports[..], &[22, 80, 443, 8080]);
+///
+/// ports.insert(3000);
+/// assert!(ports.contains(&80));
+/// assert_eq!(ports.range(80..1024), &[80, 443]);
+/// assert_eq!(ports.range(1024..), &[3000, 8080]);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SortedVec<T> {
+    values: Vec<T>,
+}
+
+impl<T: Ord> Default for SortedVec<T> {
+    fn default() -> SortedVec<T> {
+        SortedVec::new()
+    }
+}
+
+impl<T: Ord> SortedVec<T> {
+    /// Create an empty set
+    pub fn new() -> SortedVec<T> {
+        SortedVec { values: vec![] }
+    }
+
+    /// Add `value` to the set. Returns false, and leaves the set as it
+    /// was, if an equal value is already in it
+    pub fn insert(&mut self, value: T) -> bool {
+        match self.values.binary_search(&value) {
+            Ok(_) => false,
+            Err(index) => {
+                self.values.insert(index, value);
+                true
+            }
+        }
+    }
+
+    /// Remove the value equal to `value` and return it, or `None` if
+    /// there isn't one
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let index = self.position(value)?;
+        Some(self.values.remove(index))
+    }
+
+    /// Return the value equal to `value`, or `None` if there isn't one
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.position(value).map(|index| &self.values[index])
+    }
+
+    /// Returns true if there is a value equal to `value` in the set
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.position(value).is_some()
+    }
+
+    /// Return the index of the value equal to `value`, or `None` if
+    /// there isn't one
+    pub fn position<Q>(&self, value: &Q) -> Option<usize>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.values.binary_search_by(|v| v.borrow().cmp(value)).ok()
+    }
+
+    /// Return the values in `range`, in order
+    pub fn range<Q, R>(&self, range: R) -> &[T]
+    where
+        T: Borrow<Q>,
+        Q: ?Sized + Ord,
+        R: RangeBounds<Q>,
+    {
+        let indexes = range_indexes(&self.values, |v| v.borrow(), range);
+        &self.values[indexes]
+    }
+
+    /// Keep only the values that `keep` returns true for
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, keep: F) {
+        self.values.retain(keep);
+    }
+
+    /// Remove every value from the set
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Return the values as a sorted slice
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+
+    /// Return the values as a sorted vector
+    pub fn into_vec(self) -> Vec<T> {
+        self.values
+    }
+
+    /// Return the number of values in the set
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl<T> Deref for SortedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.values
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SortedVec<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.values.iter()
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for SortedVec<T> {
+    /// Sort `values` and drop duplicates, keeping the first of each
+    fn from(mut values: Vec<T>) -> SortedVec<T> {
+        values.sort();
+        values.dedup();
+        SortedVec { values }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SortedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(values: I) -> SortedVec<T> {
+        SortedVec::from(values.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T: Ord> Extend<T> for SortedVec<T> {
+    /// Add every value, sorting once at the end. Values equal to ones
+    /// already in the set are dropped
+    fn extend<I: IntoIterator<Item = T>>(&mut self, values: I) {
+        self.values.extend(values);
+        self.values.sort();
+        self.values.dedup();
+    }
+}
+
+/// A map that keeps its entries sorted by key in one contiguous vector
+///
+/// Like `SortedVec`, lookups are binary searches, and it is best built
+/// in bulk with `from` or `collect`, which sort once. When the input has
+/// more than one entry for a key, the last one wins, as with `insert`.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut ages: SortedVecMap<&str, u32> =
+///     vec![("carol", 41), ("alice", 30), ("bob", 25), ("alice", 31)].into();
+/// assert_eq!(ages.get("alice"), Some(&31));
+///
+/// ages.insert("dave", 19);
+/// let names: Vec<_> = ages.range("b".."d").iter().map(|e| e.0).collect();
+/// assert_eq!(names, vec!["bob", "carol"]);
+/// assert_eq!(ages.remove("bob"), Some(25));
+/// assert_eq!(ages.len(), 3);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SortedVecMap<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K: Ord, V> Default for SortedVecMap<K, V> {
+    fn default() -> SortedVecMap<K, V> {
+        SortedVecMap::new()
+    }
+}
+
+impl<K: Ord, V> SortedVecMap<K, V> {
+    /// Create an empty map
+    pub fn new() -> SortedVecMap<K, V> {
+        SortedVecMap { entries: vec![] }
+    }
+
+    /// Store `value` under `key`, and return the value it replaced, if
+    /// any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search(&key) {
+            Ok(index) => Some(mem::replace(&mut self.entries[index].1, value)),
+            Err(index) => {
+                self.entries.insert(index, (key, value));
+                None
+            }
+        }
+    }
+
+    /// Remove the entry under `key` and return its value, or `None` if
+    /// there isn't one
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let index = self.search(key).ok()?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Return the value under `key`, or `None` if there isn't one
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let index = self.search(key).ok()?;
+        Some(&self.entries[index].1)
+    }
+
+    /// Return the value under `key` for modification, or `None` if
+    /// there isn't one
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let index = self.search(key).ok()?;
+        Some(&mut self.entries[index].1)
+    }
+
+    /// Returns true if there is an entry under `key`
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.search(key).is_ok()
+    }
+
+    /// Return the entries whose keys are in `range`, in order
+    pub fn range<Q, R>(&self, range: R) -> &[(K, V)]
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+        R: RangeBounds<Q>,
+    {
+        let indexes = range_indexes(&self.entries, |e| e.0.borrow(), range);
+        &self.entries[indexes]
+    }
+
+    /// Return the entry with the smallest key, or `None` if the map is
+    /// empty
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.entries.first().map(|(k, v)| (k, v))
+    }
+
+    /// Return the entry with the largest key, or `None` if the map is
+    /// empty
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|(k, v)| (k, v))
+    }
+
+    /// Iterate over the entries in order of their keys
+    pub fn iter(&self) -> SortedVecMapIter<'_, K, V> {
+        SortedVecMapIter {
+            iter: self.entries.iter(),
+        }
+    }
+
+    /// Keep only the entries that `keep` returns true for
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut keep: F) {
+        self.entries.retain_mut(|(k, v)| keep(k, v));
+    }
+
+    /// Remove every entry from the map
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Return the entries as a slice sorted by key
+    pub fn as_slice(&self) -> &[(K, V)] {
+        &self.entries
+    }
+
+    /// Return the entries as a vector sorted by key
+    pub fn into_vec(self) -> Vec<(K, V)> {
+        self.entries
+    }
+
+    /// Return the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.entries.binary_search_by(|e| e.0.borrow().cmp(key))
+    }
+
+    // Sort the entries by key, keeping the last entry for each key
+    fn sort_entries(&mut self) {
+        self.entries.reverse();
+        // The sort is stable, so the last entries stay first and are
+        // the ones dedup keeps
+        self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        self.entries.dedup_by(|a, b| a.0 == b.0);
+    }
+}
+
+impl<K: Ord, V> From<Vec<(K, V)>> for SortedVecMap<K, V> {
+    fn from(entries: Vec<(K, V)>) -> SortedVecMap<K, V> {
+        let mut map = SortedVecMap { entries };
+        map.sort_entries();
+        map
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SortedVecMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> SortedVecMap<K, V> {
+        SortedVecMap::from(entries.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SortedVecMap<K, V> {
+    /// Add every entry, sorting once at the end. Entries replace any
+    /// already in the map under the same key
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, entries: I) {
+        self.entries.extend(entries);
+        self.sort_entries();
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SortedVecMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = SortedVecMapIter<'a, K, V>;
+
+    fn into_iter(self) -> SortedVecMapIter<'a, K, V> {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a `SortedVecMap`, in order of their
+/// keys
+pub struct SortedVecMapIter<'a, K: 'a, V: 'a> {
+    iter: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for SortedVecMapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.iter.next().map(|(k, v)| (k, v))
+    }
+}
+
+#[test]
+fn test_sorted_vec() {
+    let mut set = SortedVec::new();
+    assert!(set.is_empty());
+    assert_eq!(set.range(..), &[] as &[u32]);
+
+    assert!(set.insert(5));
+    assert!(set.insert(1));
+    assert!(!set.insert(5));
+    set.extend(vec![9, 3, 1, 7]);
+    assert_eq!(set.as_slice(), &[1, 3, 5, 7, 9]);
+    assert_eq!(set.len(), 5);
+    assert_eq!(set.position(&7), Some(3));
+    assert_eq!(set.get(&4), None);
+    assert_eq!(set.range(3..=7), &[3, 5, 7]);
+    assert_eq!(set.range(4..5), &[] as &[u32]);
+    assert_eq!(
+        set.range((Bound::Excluded(1), Bound::Unbounded)),
+        &[3, 5, 7, 9]
+    );
+    assert_eq!(
+        set.range((Bound::Included(8), Bound::Excluded(2))),
+        &[] as &[u32]
+    );
+    assert_eq!(set.first(), Some(&1));
+    assert_eq!((&set).into_iter().sum::<u32>(), 25);
+
+    assert_eq!(set.remove(&3), Some(3));
+    assert_eq!(set.remove(&3), None);
+    set.retain(|v| v % 3 != 0);
+    assert_eq!(set.clone().into_vec(), vec![1, 5, 7]);
+
+    // Borrowed lookups
+    let words: SortedVec<String> = "the quick brown fox".split(' ').map(String::from).collect();
+    assert!(words.contains("fox"));
+    assert_eq!(
+        words
+            .range::<str, _>((Bound::Included("c"), Bound::Excluded("r")))
+            .len(),
+        2
+    );
+    set.clear();
+    assert!(set.is_empty());
+}
+
+#[test]
+fn test_sorted_vec_map() {
+    let mut map: SortedVecMap<u32, char> = (0..10).rev().map(|i| (i * 2, 'x')).collect();
+    assert_eq!(map.len(), 10);
+    assert_eq!(map.insert(4, 'a'), Some('x'));
+    assert_eq!(map.insert(5, 'b'), None);
+    *map.get_mut(&6).unwrap() = 'c';
+    assert_eq!(map.get(&4), Some(&'a'));
+    assert!(map.contains_key(&5));
+    assert!(!map.contains_key(&7));
+    assert_eq!(map.range(4..7), &[(4, 'a'), (5, 'b'), (6, 'c')]);
+    assert_eq!(
+        (map.first(), map.last()),
+        (Some((&0, &'x')), Some((&18, &'x')))
+    );
+
+    // Later entries win when extending, too
+    map.extend(vec![(5, 'd'), (100, 'e'), (5, 'f')]);
+    assert_eq!(map.get(&5), Some(&'f'));
+    assert_eq!(map.len(), 12);
+
+    map.retain(|k, v| {
+        *v = 'r';
+        k % 4 == 0
+    });
+    let keys: Vec<_> = map.iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys, vec![0, 4, 8, 12, 16, 100]);
+    assert_eq!(map.remove(&8), Some('r'));
+    assert_eq!(map.remove(&8), None);
+    assert_eq!((&map).into_iter().count(), 5);
+    assert_eq!(map.clone().into_vec()[0], (0, 'r'));
+    map.clear();
+    assert!(map.is_empty());
+}
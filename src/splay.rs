@@ -0,0 +1,441 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::mem;
+
+// Marks a missing node
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    parent: usize,
+    left: usize,
+    right: usize,
+}
+
+/// An ordered map that moves each key it looks up to the root of the
+/// tree, so that keys used often stay cheap to reach
+///
+/// Every operation is O(log n) amortized, and much faster when a few
+/// keys get most of the lookups, since they are always near the root.
+/// Because lookups reshape the tree, `get` takes `&mut self`; `peek`
+/// looks up a key without moving it. The nodes are kept in a `Vec` and
+/// splayed without recursion, so even a tree that has degenerated into
+/// a long chain can't overflow the stack.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut symbols = SplayTree::new();
+/// for (address, name) in vec![(0x100, "reset"), (0x150, "main"), (0x40, "irq")] {
+///     symbols.insert(address, name);
+/// }
+///
+/// assert_eq!(symbols.get(&0x150), Some(&"main"));
+/// assert_eq!(symbols.root(), Some((&0x150, &"main")));
+/// assert_eq!(symbols.peek(&0x40), Some(&"irq"));
+/// assert_eq!(symbols.root(), Some((&0x150, &"main")));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SplayTree<K, V> {
+    nodes: Vec<Option<Node<K, V>>>,
+    // Slots of removed nodes, ready for reuse
+    free: Vec<usize>,
+    root: usize,
+    len: usize,
+}
+
+impl<K: Ord, V> Default for SplayTree<K, V> {
+    fn default() -> SplayTree<K, V> {
+        SplayTree::new()
+    }
+}
+
+impl<K: Ord, V> SplayTree<K, V> {
+    /// Create an empty map
+    pub fn new() -> SplayTree<K, V> {
+        SplayTree {
+            nodes: vec![],
+            free: vec![],
+            root: NIL,
+            len: 0,
+        }
+    }
+
+    /// Insert `value` under `key` and move it to the root, and return
+    /// the value it replaced, if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (index, ordering) = match self.find(&key) {
+            Some((index, Ordering::Equal)) => {
+                self.splay(index);
+                return Some(mem::replace(&mut self.node_mut(index).value, value));
+            }
+            Some(found) => found,
+            // The new node becomes the root
+            None => (NIL, Ordering::Equal),
+        };
+
+        let node = Node {
+            key,
+            value,
+            parent: index,
+            left: NIL,
+            right: NIL,
+        };
+        let new = match self.free.pop() {
+            Some(new) => {
+                self.nodes[new] = Some(node);
+                new
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        match ordering {
+            Ordering::Less => self.node_mut(index).left = new,
+            Ordering::Greater => self.node_mut(index).right = new,
+            Ordering::Equal => self.root = new,
+        }
+        self.len += 1;
+        self.splay(new);
+        None
+    }
+
+    /// Return the value under `key` and move it to the root, or `None`
+    /// if it isn't in the map
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    /// Return the value under `key` for modification and move it to the
+    /// root, or `None` if it isn't in the map
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let (index, ordering) = self.find(key)?;
+        // The closest node is splayed even on a miss, which keeps the
+        // amortized bounds
+        self.splay(index);
+        match ordering {
+            Ordering::Equal => Some(&mut self.node_mut(index).value),
+            _ => None,
+        }
+    }
+
+    /// Return the value under `key` without changing the tree, or
+    /// `None` if it isn't in the map
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        match self.find(key) {
+            Some((index, Ordering::Equal)) => Some(&self.node(index).value),
+            _ => None,
+        }
+    }
+
+    /// Returns true if there is an entry under `key`. This doesn't
+    /// change the tree
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.peek(key).is_some()
+    }
+
+    /// Remove the entry under `key` and return its value, or `None` if
+    /// it isn't in the map
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let (index, ordering) = self.find(key)?;
+        self.splay(index);
+        if ordering != Ordering::Equal {
+            return None;
+        }
+
+        let node = self.nodes[index].take().unwrap();
+        self.free.push(index);
+        self.len -= 1;
+        if node.left == NIL {
+            self.set_root(node.right);
+        } else {
+            // Join the two sides under the largest key on the left
+            self.set_root(node.left);
+            let mut max = node.left;
+            while self.node(max).right != NIL {
+                max = self.node(max).right;
+            }
+            self.splay(max);
+            self.node_mut(max).right = node.right;
+            if node.right != NIL {
+                self.node_mut(node.right).parent = max;
+            }
+        }
+        Some(node.value)
+    }
+
+    /// Return the entry at the root, which is the one most recently
+    /// inserted or looked up, or `None` if the map is empty
+    pub fn root(&self) -> Option<(&K, &V)> {
+        self.entry(self.root)
+    }
+
+    /// Return the entry with the smallest key without changing the
+    /// tree, or `None` if the map is empty
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut index = self.root;
+        while index != NIL && self.node(index).left != NIL {
+            index = self.node(index).left;
+        }
+        self.entry(index)
+    }
+
+    /// Return the entry with the largest key without changing the tree,
+    /// or `None` if the map is empty
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut index = self.root;
+        while index != NIL && self.node(index).right != NIL {
+            index = self.node(index).right;
+        }
+        self.entry(index)
+    }
+
+    /// Iterate over the entries in order of their keys
+    pub fn iter(&self) -> SplayIter<'_, K, V> {
+        let mut iter = SplayIter {
+            tree: self,
+            stack: vec![],
+        };
+        iter.push_left(self.root);
+        iter
+    }
+
+    /// Remove every entry from the map
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.root = NIL;
+        self.len = 0;
+    }
+
+    /// Return the number of entries in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no entries in the map
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, index: usize) -> &Node<K, V> {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<K, V> {
+        self.nodes[index].as_mut().unwrap()
+    }
+
+    fn entry(&self, index: usize) -> Option<(&K, &V)> {
+        if index == NIL {
+            return None;
+        }
+        let node = self.node(index);
+        Some((&node.key, &node.value))
+    }
+
+    fn set_root(&mut self, index: usize) {
+        self.root = index;
+        if index != NIL {
+            self.node_mut(index).parent = NIL;
+        }
+    }
+
+    // Return the node with `key`, or the last node on the way to where
+    // it would be, along with how `key` compares to that node. Returns
+    // `None` if the tree is empty
+    fn find<Q>(&self, key: &Q) -> Option<(usize, Ordering)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        if self.root == NIL {
+            return None;
+        }
+        let mut index = self.root;
+        let mut ordering = Ordering::Equal;
+        let mut next = self.root;
+        while next != NIL {
+            index = next;
+            let node = self.node(index);
+            ordering = key.cmp(node.key.borrow());
+            next = match ordering {
+                Ordering::Less => node.left,
+                Ordering::Greater => node.right,
+                Ordering::Equal => break,
+            };
+        }
+        Some((index, ordering))
+    }
+
+    // Move a node above its parent, keeping the keys in order
+    fn rotate(&mut self, x: usize) {
+        let parent = self.node(x).parent;
+        let grandparent = self.node(parent).parent;
+        if self.node(parent).left == x {
+            let child = self.node(x).right;
+            self.node_mut(parent).left = child;
+            if child != NIL {
+                self.node_mut(child).parent = parent;
+            }
+            self.node_mut(x).right = parent;
+        } else {
+            let child = self.node(x).left;
+            self.node_mut(parent).right = child;
+            if child != NIL {
+                self.node_mut(child).parent = parent;
+            }
+            self.node_mut(x).left = parent;
+        }
+        self.node_mut(parent).parent = x;
+        self.node_mut(x).parent = grandparent;
+        if grandparent == NIL {
+            self.root = x;
+        } else if self.node(grandparent).left == parent {
+            self.node_mut(grandparent).left = x;
+        } else {
+            self.node_mut(grandparent).right = x;
+        }
+    }
+
+    // Move a node to the root
+    fn splay(&mut self, x: usize) {
+        loop {
+            let parent = self.node(x).parent;
+            if parent == NIL {
+                break;
+            }
+            let grandparent = self.node(parent).parent;
+            if grandparent != NIL {
+                let zig_zig =
+                    (self.node(grandparent).left == parent) == (self.node(parent).left == x);
+                self.rotate(if zig_zig { parent } else { x });
+            }
+            self.rotate(x);
+        }
+    }
+}
+
+/// An iterator over the entries of a `SplayTree`, in order of their keys
+pub struct SplayIter<'a, K: 'a, V: 'a> {
+    tree: &'a SplayTree<K, V>,
+    // The nodes whose left subtrees are being visited
+    stack: Vec<usize>,
+}
+
+impl<'a, K: Ord, V> SplayIter<'a, K, V> {
+    fn push_left(&mut self, mut index: usize) {
+        while index != NIL {
+            self.stack.push(index);
+            index = self.tree.node(index).left;
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for SplayIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let index = self.stack.pop()?;
+        let tree = self.tree;
+        let node = tree.node(index);
+        self.push_left(node.right);
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+fn check_splay_tree<K: Ord, V>(tree: &SplayTree<K, V>, index: usize, parent: usize) -> usize {
+    if index == NIL {
+        return 0;
+    }
+    let node = tree.node(index);
+    assert_eq!(node.parent, parent);
+    if node.left != NIL {
+        assert!(tree.node(node.left).key < node.key);
+    }
+    if node.right != NIL {
+        assert!(tree.node(node.right).key > node.key);
+    }
+    1 + check_splay_tree(tree, node.left, index) + check_splay_tree(tree, node.right, index)
+}
+
+#[test]
+fn test_splay_tree() {
+    let mut tree = SplayTree::new();
+    assert!(tree.is_empty());
+    assert_eq!(tree.get(&1), None);
+    assert_eq!(tree.remove(&1), None);
+    for i in 0..50u32 {
+        let key = (i * 17) % 50;
+        assert_eq!(tree.insert(key, key * 2), None);
+        assert_eq!(tree.root(), Some((&key, &(key * 2))));
+    }
+    assert_eq!(check_splay_tree(&tree, tree.root, NIL), 50);
+    assert_eq!(tree.insert(20, 0), Some(40));
+    *tree.get_mut(&21).unwrap() += 1;
+    assert_eq!(tree.root(), Some((&21, &43)));
+    assert_eq!(tree.peek(&20), Some(&0));
+    assert!(!tree.contains_key(&50));
+    assert_eq!(tree.get(&50), None);
+
+    for key in (0..50).filter(|k| k % 4 == 0) {
+        assert!(tree.remove(&key).is_some());
+        assert_eq!(tree.remove(&key), None);
+    }
+    assert_eq!(check_splay_tree(&tree, tree.root, NIL), 37);
+    assert_eq!(tree.len(), 37);
+    assert_eq!(tree.first(), Some((&1, &2)));
+    assert_eq!(tree.last(), Some((&49, &98)));
+    let keys: Vec<_> = tree.iter().map(|(&k, _)| k).collect();
+    assert_eq!(keys, (0..50).filter(|k| k % 4 != 0).collect::<Vec<_>>());
+
+    // Freed slots are reused
+    tree.insert(100, 0);
+    assert_eq!(tree.nodes.len(), 50);
+    tree.clear();
+    assert_eq!(tree.iter().count(), 0);
+    assert_eq!(tree.root(), None);
+}
+
+#[test]
+fn test_splay_tree_chain() {
+    // Inserting keys in order leaves a chain as deep as the tree
+    let mut tree = SplayTree::new();
+    for key in 0..100_000 {
+        tree.insert(key, ());
+    }
+    assert_eq!(tree.get(&0), Some(&()));
+    assert_eq!(tree.root(), Some((&0, &())));
+    assert_eq!(tree.remove(&50_000), Some(()));
+    assert_eq!(tree.len(), 99_999);
+    assert!(tree
+        .iter()
+        .map(|(&k, _)| k)
+        .eq((0..50_000).chain(50_001..100_000)));
+}
@@ -0,0 +1,563 @@
+use std::cell::UnsafeCell;
+use std::cmp;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use error::Error;
+use resample::{NearestNeighbor, ResamplePolicy, Samples};
+use ring::{plan_pop, ElasticPopResult};
+
+/// Keeps its contents on a cache line of its own, so that values
+/// written by different threads don't contend with each other
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+struct Shared<T> {
+    // Only ever written by the consumer
+    read: CachePadded<AtomicUsize>,
+    // Only ever written by the producer
+    write: CachePadded<AtomicUsize>,
+    buffer: Box<[UnsafeCell<T>]>,
+}
+
+// The slots between `read` and `write` belong to the consumer and
+// the rest belong to the producer, so a slot is never accessed from
+// both threads at once
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    #[inline]
+    fn advance_index(&self, index: usize, amount: usize) -> usize {
+        (index + amount) % self.buffer.len()
+    }
+
+    #[inline]
+    fn distance(&self, from: usize, to: usize) -> usize {
+        if to >= from {
+            to - from
+        } else {
+            self.buffer.len() - from + to
+        }
+    }
+}
+
+/// Create a lock-free FIFO buffer with fixed length that can be
+/// shared between exactly one producing thread and one consuming
+/// thread. The buffer can hold up to `size` elements and uses
+/// `value` as the default value
+///
+/// Neither half ever allocates or blocks after creation, so both are
+/// safe to use from real-time threads such as audio callbacks.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+/// use std::thread;
+///
+/// let (mut producer, mut consumer) = spsc_ring_buffer(16, 0u32);
+/// let t = thread::spawn(move || {
+///     for i in 0..100 {
+///         while !producer.push_back(i) {
+///             thread::yield_now();
+///         }
+///     }
+/// });
+///
+/// let mut next = 0;
+/// while next < 100 {
+///     if let Some(v) = consumer.pop_front() {
+///         assert_eq!(v, next);
+///         next += 1;
+///     } else {
+///         thread::yield_now();
+///     }
+/// }
+/// t.join().unwrap();
+/// ```
+pub fn spsc_ring_buffer<T: Clone + Send>(
+    size: usize,
+    value: T,
+) -> (SpscProducer<T>, SpscConsumer<T>) {
+    // As with `RingBuffer`, one element is wasted so that a full
+    // buffer can be told apart from an empty one
+    let mut tmp_buf = Vec::with_capacity(size + 1);
+    tmp_buf.resize_with(size + 1, || UnsafeCell::new(value.clone()));
+
+    let shared = Arc::new(Shared {
+        read: CachePadded(AtomicUsize::new(0)),
+        write: CachePadded(AtomicUsize::new(0)),
+        buffer: tmp_buf.into_boxed_slice(),
+    });
+
+    (
+        SpscProducer {
+            shared: shared.clone(),
+            write: 0,
+            cached_read: 0,
+        },
+        SpscConsumer {
+            shared,
+            read: 0,
+            cached_write: 0,
+        },
+    )
+}
+
+/// The writing half of a buffer created by `spsc_ring_buffer`
+pub struct SpscProducer<T> {
+    shared: Arc<Shared<T>>,
+    write: usize,
+    // The last read index we saw, which avoids touching the
+    // consumer's cache line until the buffer looks full
+    cached_read: usize,
+}
+
+impl<T: Clone + Send> SpscProducer<T> {
+    /// Add `value` to the end of the queue. Returns false if there is
+    /// not enough room in the queue
+    pub fn push_back(&mut self, value: T) -> bool {
+        self.try_push_back(value).is_ok()
+    }
+
+    /// Add `value` to the end of the queue, or return `Error::Full`
+    /// if there is not enough room in the queue
+    pub fn try_push_back(&mut self, value: T) -> Result<(), Error> {
+        self.reserve(1)?;
+        self.write_slot(value);
+        self.publish();
+        Ok(())
+    }
+
+    /// Add all `values` to the buffer. If there is not enough room in
+    /// the queue then no values are added and the return value is
+    /// false
+    pub fn push_back_slice(&mut self, values: &[T]) -> bool {
+        self.try_push_back_slice(values).is_ok()
+    }
+
+    /// Add all `values` to the buffer. If there is not enough room in
+    /// the queue then no values are added and `Error::Full` is
+    /// returned. The consumer sees either all or none of the values
+    pub fn try_push_back_slice(&mut self, values: &[T]) -> Result<(), Error> {
+        self.reserve(values.len())?;
+        for v in values.iter() {
+            self.write_slot(v.clone());
+        }
+        self.publish();
+        Ok(())
+    }
+
+    /// Returns the number of free slots in the buffer. The consumer
+    /// may free up more slots at any time
+    pub fn capacity(&mut self) -> usize {
+        self.cached_read = self.shared.read.0.load(Ordering::Acquire);
+        self.free_slots()
+    }
+
+    /// Returns the max number of values that can ever be stored in
+    /// the buffer
+    pub fn max_len(&self) -> usize {
+        self.shared.buffer.len() - 1
+    }
+
+    fn free_slots(&self) -> usize {
+        self.max_len() - self.shared.distance(self.cached_read, self.write)
+    }
+
+    fn reserve(&mut self, needed: usize) -> Result<(), Error> {
+        if self.free_slots() >= needed {
+            return Ok(());
+        }
+        let available = self.capacity();
+        if available < needed {
+            Err(Error::Full { needed, available })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn write_slot(&mut self, value: T) {
+        // Safety: `reserve` made sure this slot is not between the
+        // read and write indices, so the consumer won't touch it
+        // until we publish it
+        unsafe {
+            *self.shared.buffer[self.write].get() = value;
+        }
+        self.write = self.shared.advance_index(self.write, 1);
+    }
+
+    fn publish(&self) {
+        self.shared.write.0.store(self.write, Ordering::Release);
+    }
+}
+
+/// The reading half of a buffer created by `spsc_ring_buffer`
+pub struct SpscConsumer<T> {
+    shared: Arc<Shared<T>>,
+    read: usize,
+    // The last write index we saw, which avoids touching the
+    // producer's cache line until the buffer looks empty
+    cached_write: usize,
+}
+
+impl<T: Clone + Send> SpscConsumer<T> {
+    /// Remove the first value from the queue, or returns `None` if
+    /// there are no values in the buffer
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.try_pop_front().ok()
+    }
+
+    /// Remove the first value from the queue, or return
+    /// `Error::Empty` if there are no values in the buffer
+    pub fn try_pop_front(&mut self) -> Result<T, Error> {
+        self.reserve(1)?;
+        let value = self.read_slot();
+        self.release();
+        Ok(value)
+    }
+
+    /// Copy the first value from the queue but does not remove it;
+    /// returns `None` if there are no values in the buffer
+    pub fn peek_front(&mut self) -> Option<T> {
+        match self.reserve(1) {
+            // Safety: the slot is between the read and write indices,
+            // so the producer won't touch it until we release it
+            Ok(()) => Some(unsafe { (*self.shared.buffer[self.read].get()).clone() }),
+            Err(_) => None,
+        }
+    }
+
+    /// Remove enough values from the buffer to fill the given
+    /// slice. If there are not enough values in the queue then the
+    /// output buffer is not modified and the function returns false
+    pub fn pop_front_slice(&mut self, values: &mut [T]) -> bool {
+        self.try_pop_front_slice(values).is_ok()
+    }
+
+    /// Remove enough values from the buffer to fill the given
+    /// slice. If there are not enough values in the queue then the
+    /// output buffer is not modified and `Error::Empty` is returned
+    pub fn try_pop_front_slice(&mut self, values: &mut [T]) -> Result<(), Error> {
+        self.reserve(values.len())?;
+        for v in values.iter_mut() {
+            *v = self.read_slot();
+        }
+        self.release();
+        Ok(())
+    }
+
+    /// Returns the number of values in the buffer. The producer may
+    /// add more values at any time
+    pub fn len(&mut self) -> usize {
+        self.cached_write = self.shared.write.0.load(Ordering::Acquire);
+        self.shared.distance(self.read, self.cached_write)
+    }
+
+    /// Returns true if there are no values in the buffer
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the max number of values that can ever be stored in
+    /// the buffer
+    pub fn max_len(&self) -> usize {
+        self.shared.buffer.len() - 1
+    }
+
+    fn reserve(&mut self, needed: usize) -> Result<(), Error> {
+        if self.shared.distance(self.read, self.cached_write) >= needed {
+            return Ok(());
+        }
+        let available = self.len();
+        if available < needed {
+            Err(Error::Empty { needed, available })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_slot(&mut self) -> T {
+        // Safety: `reserve` made sure this slot is between the read
+        // and write indices, so the producer won't touch it until we
+        // release it
+        let value = unsafe { (*self.shared.buffer[self.read].get()).clone() };
+        self.read = self.shared.advance_index(self.read, 1);
+        value
+    }
+
+    fn release(&self) {
+        self.shared.read.0.store(self.read, Ordering::Release);
+    }
+
+    // Returns the first `n` values, which `reserve` must have made
+    // sure are present
+    fn front_samples(&self, n: usize) -> Samples<'_, T> {
+        let buffer = &self.shared.buffer;
+        let first_len = cmp::min(n, buffer.len() - self.read);
+        // Safety: `UnsafeCell<T>` has the same layout as `T`, and both
+        // ranges are between the read and write indices, so the
+        // producer won't touch them until we release them
+        unsafe {
+            let start = buffer.as_ptr() as *const T;
+            Samples::new(
+                slice::from_raw_parts(start.add(self.read), first_len),
+                slice::from_raw_parts(start, n - first_len),
+            )
+        }
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.read = (self.read + n) % self.shared.buffer.len();
+        self.release();
+    }
+}
+
+/// Create a lock-free `ElasticRingBuffer` that is split between one
+/// producing thread and one consuming thread, such as an audio
+/// callback. The producer is an ordinary `SpscProducer`, and the
+/// consumer stretches and shrinks values just like
+/// `ElasticRingBuffer::pop_front_slice` does
+///
+/// Requests never allocate, lock, or block. The buffer can hold up to
+/// `size` elements, uses `value` as the default value, and begins
+/// dropping elements past `ideal_max_len`.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let (mut producer, mut consumer) = elastic_spsc_ring_buffer(64, 0i16, 32);
+/// producer.push_back_slice(&[1, 2, 3]);
+///
+/// let mut block = [0; 4];
+/// assert_eq!(consumer.pop_front_slice(&mut block), ElasticPopResult::Upsampled(3));
+/// ```
+pub fn elastic_spsc_ring_buffer<T: Clone + Send>(
+    size: usize,
+    value: T,
+    ideal_max_len: usize,
+) -> (SpscProducer<T>, ElasticSpscConsumer<T>) {
+    elastic_spsc_ring_buffer_with_policy(size, value, ideal_max_len, NearestNeighbor)
+}
+
+/// Create a buffer like `elastic_spsc_ring_buffer`, but using `policy`
+/// to stretch and shrink the values
+pub fn elastic_spsc_ring_buffer_with_policy<T: Clone + Send, P: ResamplePolicy<T>>(
+    size: usize,
+    value: T,
+    ideal_max_len: usize,
+    policy: P,
+) -> (SpscProducer<T>, ElasticSpscConsumer<T, P>) {
+    let (producer, consumer) = spsc_ring_buffer(size, value.clone());
+    (
+        producer,
+        ElasticSpscConsumer {
+            consumer,
+            ideal_max: ideal_max_len,
+            ideal_min: 0,
+            refilling: false,
+            default_value: value,
+            policy,
+        },
+    )
+}
+
+/// The reading half of a buffer created by `elastic_spsc_ring_buffer`
+pub struct ElasticSpscConsumer<T, P = NearestNeighbor> {
+    consumer: SpscConsumer<T>,
+    ideal_max: usize,
+    ideal_min: usize,
+    // Set while recovering from dropping below `ideal_min`
+    refilling: bool,
+    default_value: T,
+    policy: P,
+}
+
+impl<T: Clone + Send, P: ResamplePolicy<T>> ElasticSpscConsumer<T, P> {
+    /// Fill `values` with elements. See `ElasticPopResult` for the
+    /// possible outcomes of this request.
+    pub fn pop_front_slice(&mut self, values: &mut [T]) -> ElasticPopResult {
+        let values_len = values.len();
+        let buffer_len = self.consumer.len();
+        let (size, refilling) = plan_pop(
+            buffer_len,
+            values_len,
+            self.ideal_max,
+            self.ideal_min,
+            1,
+            self.refilling,
+        );
+        self.refilling = refilling;
+
+        match size {
+            None => {
+                let r = self.consumer.pop_front_slice(values);
+                assert!(r);
+                ElasticPopResult::Exact
+            }
+            Some(0) => {
+                for v in values.iter_mut() {
+                    *v = self.default_value.clone();
+                }
+                ElasticPopResult::Empty
+            }
+            Some(n) => {
+                self.policy.resample(self.consumer.front_samples(n), values);
+                self.consumer.consume(n);
+                if values_len > n {
+                    ElasticPopResult::Upsampled(n)
+                } else {
+                    ElasticPopResult::Downsampled(n)
+                }
+            }
+        }
+    }
+
+    /// Returns the threshold where the buffer begins dropping elements
+    pub fn ideal_max(&self) -> usize {
+        self.ideal_max
+    }
+
+    /// Set the threshold where the buffer begins dropping elements;
+    /// see `ElasticRingBuffer::set_ideal_max`
+    pub fn set_ideal_max(&mut self, ideal_max_len: usize) {
+        assert!(ideal_max_len <= self.max_len());
+        assert!(self.ideal_min == 0 || self.ideal_min < ideal_max_len);
+        self.ideal_max = ideal_max_len;
+    }
+
+    /// Returns the threshold where the buffer begins stretching
+    /// elements ahead of running out
+    pub fn ideal_min(&self) -> usize {
+        self.ideal_min
+    }
+
+    /// Set the threshold where the buffer begins stretching elements
+    /// ahead of running out; see `ElasticRingBuffer::set_ideal_min`
+    pub fn set_ideal_min(&mut self, ideal_min_len: usize) {
+        assert!(ideal_min_len == 0 || ideal_min_len < self.ideal_max);
+        self.ideal_min = ideal_min_len;
+        self.refilling = false;
+    }
+
+    /// Returns the resampling policy
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Returns the number of values in the buffer. The producer may
+    /// add more values at any time
+    pub fn len(&mut self) -> usize {
+        self.consumer.len()
+    }
+
+    /// Returns true if there are no values in the buffer
+    pub fn is_empty(&mut self) -> bool {
+        self.consumer.is_empty()
+    }
+
+    /// Returns the max number of values that can ever be stored in
+    /// the buffer
+    pub fn max_len(&self) -> usize {
+        self.consumer.max_len()
+    }
+}
+
+#[test]
+fn test_spsc_single_thread() {
+    let (mut p, mut c) = spsc_ring_buffer(3, 0u8);
+    let mut buf2 = [0u8; 2];
+
+    assert_eq!(c.pop_front(), None);
+    assert!(p.push_back_slice(&[1, 2]));
+    assert_eq!(
+        p.try_push_back_slice(&[3, 4]),
+        Err(Error::Full {
+            needed: 2,
+            available: 1
+        })
+    );
+    assert!(p.push_back(3));
+    assert!(!p.push_back(4));
+    assert_eq!(c.len(), 3);
+
+    assert_eq!(c.peek_front(), Some(1));
+    assert!(c.pop_front_slice(&mut buf2));
+    assert_eq!(buf2, [1, 2]);
+    assert!(p.push_back_slice(&[4, 5]));
+    assert_eq!(c.pop_front(), Some(3));
+    assert!(!c.pop_front_slice(&mut [0u8; 3]));
+    assert!(c.pop_front_slice(&mut buf2));
+    assert_eq!(buf2, [4, 5]);
+    assert!(c.is_empty());
+    assert_eq!(p.capacity(), 3);
+}
+
+#[test]
+fn test_spsc_threads() {
+    use std::thread;
+
+    let (mut p, mut c) = spsc_ring_buffer(7, 0u32);
+    let producer = thread::spawn(move || {
+        let mut i = 0;
+        while i < 10_000 {
+            if p.push_back_slice(&[i, i + 1, i + 2]) {
+                i += 3;
+            } else {
+                thread::yield_now();
+            }
+        }
+    });
+
+    let mut expected = 0;
+    let mut buf = [0u32; 2];
+    while expected < 10_000 {
+        if c.pop_front_slice(&mut buf) {
+            assert_eq!(buf, [expected, expected + 1]);
+            expected += 2;
+        } else if let Some(v) = c.pop_front() {
+            assert_eq!(v, expected);
+            expected += 1;
+        } else {
+            thread::yield_now();
+        }
+    }
+    producer.join().unwrap();
+}
+
+#[test]
+fn test_elastic_spsc() {
+    let (mut p, mut c) = elastic_spsc_ring_buffer(8, 0u8, 4);
+    let mut buf4 = [0u8; 4];
+
+    assert_eq!(c.pop_front_slice(&mut buf4), ElasticPopResult::Empty);
+    assert!(p.push_back_slice(&[1, 2]));
+    assert_eq!(c.pop_front_slice(&mut buf4), ElasticPopResult::Upsampled(2));
+    assert_eq!(buf4, [1, 1, 2, 2]);
+
+    // Wrap around the end of the storage while downsampling
+    assert!(p.push_back_slice(&[1, 2, 3, 4, 5, 6, 7, 8]));
+    assert_eq!(
+        c.pop_front_slice(&mut buf4),
+        ElasticPopResult::Downsampled(8)
+    );
+    assert_eq!(buf4, [1, 3, 5, 7]);
+    assert!(c.is_empty());
+
+    assert!(p.push_back_slice(&[9, 10, 11, 12, 13]));
+    assert_eq!(c.pop_front_slice(&mut buf4), ElasticPopResult::Exact);
+    assert_eq!(buf4, [9, 10, 11, 12]);
+    assert_eq!(c.len(), 1);
+
+    // Single values are never resampled, even while refilling
+    c.set_ideal_min(2);
+    let mut buf1 = [0u8; 1];
+    assert!(p.push_back_slice(&[14, 15]));
+    for expected in 13..16 {
+        assert_eq!(c.pop_front_slice(&mut buf1), ElasticPopResult::Exact);
+        assert_eq!(buf1, [expected]);
+    }
+}
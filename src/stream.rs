@@ -0,0 +1,238 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+
+use clock::{Timer, TimerEvent};
+use pool::{TimerId, TimerPool};
+
+/// Tells a `TimerStream` what tick it is, and wakes it up when a later
+/// tick is reached
+///
+/// For a stream driven by real time, `now` can come from a `RealTimer`
+/// or `WallClock`, and `wake_at` can spawn a sleep on the async runtime
+/// that wakes the waker at the instant of `tick`.
+pub trait TickSource {
+    /// Return the current absolute tick
+    fn now(&mut self) -> u64;
+
+    /// Arrange for `waker` to be woken once the absolute `tick` is
+    /// reached
+    fn wake_at(&mut self, tick: u64, waker: &Waker);
+}
+
+/// A stream of the events of a `TimerPool`, driven by a `TickSource`
+///
+/// Each poll runs the pool up to the source's current tick. If no event
+/// is due yet, the stream registers its waker for the pool's next event
+/// time and waits. The stream ends once no timer in the pool is armed.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let ticks = ManualTicks::new();
+/// let (mut stream, id) = TimerStream::from_timer(Timer::new(10, 0, 0), ticks.clone());
+///
+/// // No async runtime here, so drain what is due without waiting
+/// ticks.advance_to(25);
+/// let mut events = vec![];
+/// while let Some(event) = stream.poll_due() {
+///     events.push(event);
+/// }
+/// assert_eq!(events, vec![(id, TimerEvent::RisingEdge); 3]);
+/// ```
+pub struct TimerStream<S> {
+    pool: TimerPool,
+    source: S,
+    // Events of the last update that haven't been yielded yet
+    pending: VecDeque<(TimerId, TimerEvent)>,
+}
+
+impl<S: TickSource> TimerStream<S> {
+    /// Create a stream of the events of every timer in `pool`
+    pub fn new(pool: TimerPool, source: S) -> TimerStream<S> {
+        TimerStream {
+            pool,
+            source,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Create a stream of the events of a single `timer`, and return
+    /// the handle its events are reported with
+    pub fn from_timer(timer: Timer, source: S) -> (TimerStream<S>, TimerId) {
+        let mut pool = TimerPool::new();
+        let id = pool.insert(timer);
+        (TimerStream::new(pool, source), id)
+    }
+
+    /// Return the next event that is due at the source's current tick,
+    /// without registering for a wake-up
+    pub fn poll_due(&mut self) -> Option<(TimerId, TimerEvent)> {
+        if self.pending.is_empty() {
+            let now = self.source.now();
+            self.pending.extend(self.pool.update_all(now));
+        }
+        self.pending.pop_front()
+    }
+
+    /// Return the pool of timers
+    pub fn pool(&self) -> &TimerPool {
+        &self.pool
+    }
+
+    /// Return the pool of timers for modification. Timers changed here
+    /// are picked up on the next poll
+    pub fn pool_mut(&mut self) -> &mut TimerPool {
+        &mut self.pool
+    }
+
+    /// Return the tick source
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Take the pool and tick source back out of the stream. Events
+    /// that were due but not yet yielded are dropped
+    pub fn into_inner(self) -> (TimerPool, S) {
+        (self.pool, self.source)
+    }
+}
+
+impl<S: TickSource + Unpin> Stream for TimerStream<S> {
+    type Item = (TimerId, TimerEvent);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(event) = this.poll_due() {
+            return Poll::Ready(Some(event));
+        }
+        match this.pool.next_event_time() {
+            Some(next) => {
+                this.source.wake_at(next, cx.waker());
+                Poll::Pending
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ManualState {
+    now: u64,
+    wakers: Vec<(u64, Waker)>,
+}
+
+/// A `TickSource` whose tick is advanced by hand, e.g. by the main loop
+/// of a simulation
+///
+/// Clones share the same tick, so one clone can drive a `TimerStream`
+/// while another advances it, from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct ManualTicks {
+    state: Arc<Mutex<ManualState>>,
+}
+
+impl ManualTicks {
+    /// Create a source whose tick starts at 0
+    pub fn new() -> ManualTicks {
+        ManualTicks::default()
+    }
+
+    /// Move the tick forward to the absolute `tick`, and wake every
+    /// stream waiting for a tick up to it
+    pub fn advance_to(&self, tick: u64) {
+        let due: Vec<_> = {
+            let mut state = self.state.lock().unwrap();
+            assert!(tick >= state.now);
+            state.now = tick;
+            let (due, waiting) = state.wakers.drain(..).partition(|&(at, _)| at <= tick);
+            state.wakers = waiting;
+            due
+        };
+        for (_, waker) in due {
+            waker.wake();
+        }
+    }
+
+    /// Return the current tick
+    pub fn tick(&self) -> u64 {
+        self.state.lock().unwrap().now
+    }
+}
+
+impl TickSource for ManualTicks {
+    fn now(&mut self) -> u64 {
+        self.tick()
+    }
+
+    fn wake_at(&mut self, tick: u64, waker: &Waker) {
+        let mut state = self.state.lock().unwrap();
+        if tick <= state.now {
+            waker.wake_by_ref();
+        } else {
+            state.wakers.retain(|(_, w)| !w.will_wake(waker));
+            state.wakers.push((tick, waker.clone()));
+        }
+    }
+}
+
+#[test]
+fn test_timer_stream() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+
+    struct CountWakes(AtomicUsize);
+    impl Wake for CountWakes {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let wakes = Arc::new(CountWakes(AtomicUsize::new(0)));
+    let waker = Waker::from(wakes.clone());
+    let mut cx = Context::from_waker(&waker);
+
+    let ticks = ManualTicks::new();
+    let mut pool = TimerPool::new();
+    let a = pool.insert(Timer::new(10, 5, 3));
+    let mut b = Timer::new(100, 20, 0);
+    b.set_one_shot(true);
+    let b = pool.insert(b);
+    let mut stream = TimerStream::new(pool, ticks.clone());
+
+    let mut poll = |cx: &mut Context| Pin::new(&mut stream).poll_next(cx);
+    assert_eq!(poll(&mut cx), Poll::Pending);
+    ticks.advance_to(4);
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 0);
+    ticks.advance_to(5);
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        poll(&mut cx),
+        Poll::Ready(Some((a, TimerEvent::RisingEdge)))
+    );
+    assert_eq!(poll(&mut cx), Poll::Pending);
+
+    ticks.advance_to(20);
+    assert_eq!(wakes.0.load(Ordering::SeqCst), 2);
+    let mut events = vec![];
+    while let Poll::Ready(Some(event)) = poll(&mut cx) {
+        events.push(event);
+    }
+    assert_eq!(
+        events,
+        vec![
+            (a, TimerEvent::FallingEdge),
+            (a, TimerEvent::RisingEdge),
+            (a, TimerEvent::FallingEdge),
+            (b, TimerEvent::RisingEdge),
+        ]
+    );
+
+    stream.pool_mut().remove(a);
+    assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+}
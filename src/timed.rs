@@ -0,0 +1,128 @@
+use error::Error;
+use ring::RingBuffer;
+
+/// A FIFO buffer with fixed length where every value is tagged with
+/// the tick it was added at
+///
+/// This makes it easy to keep a history window bounded by time rather
+/// than only by count, by periodically dropping everything older than
+/// some tick.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut history = TimedRingBuffer::new(100, 0u8);
+/// history.push_back(10, 1);
+/// history.push_back(20, 2);
+/// history.push_back(30, 3);
+///
+/// assert_eq!(history.drop_older_than(25), 2);
+/// assert_eq!(history.pop_front(), Some((30, 3)));
+/// ```
+pub struct TimedRingBuffer<T: Clone> {
+    rb: RingBuffer<(u64, T)>,
+    newest: u64,
+}
+
+impl<T: Clone> TimedRingBuffer<T> {
+    /// Create a new buffer that can hold up to `size` elements and
+    /// use `value` as the default value
+    pub fn new(size: usize, value: T) -> TimedRingBuffer<T> {
+        TimedRingBuffer {
+            rb: RingBuffer::new(size, (0, value)),
+            newest: 0,
+        }
+    }
+
+    /// Add `value` to the end of the queue, tagged with `tick`.
+    /// Returns false if there is not enough room in the queue. Ticks
+    /// must never go backwards between pushes
+    pub fn push_back(&mut self, tick: u64, value: T) -> bool {
+        self.try_push_back(tick, value).is_ok()
+    }
+
+    /// Add `value` to the end of the queue, tagged with `tick`, or
+    /// return `Error::Full` if there is not enough room in the
+    /// queue. Ticks must never go backwards between pushes
+    pub fn try_push_back(&mut self, tick: u64, value: T) -> Result<(), Error> {
+        assert!(self.rb.is_empty() || tick >= self.newest);
+        self.rb.try_push_back((tick, value))?;
+        self.newest = tick;
+        Ok(())
+    }
+
+    /// Remove the first value and its tick from the queue, or returns
+    /// `None` if there are no values in the buffer
+    pub fn pop_front(&mut self) -> Option<(u64, T)> {
+        self.rb.pop_front()
+    }
+
+    /// Copy the first value and its tick from the queue but does not
+    /// remove it; returns `None` if there are no values in the buffer
+    pub fn peek_front(&self) -> Option<(u64, T)> {
+        self.rb.peek_front()
+    }
+
+    /// Remove the first value from the queue only if it was added
+    /// before `tick`; otherwise returns `None`
+    pub fn pop_older_than(&mut self, tick: u64) -> Option<(u64, T)> {
+        match self.rb.peek_front() {
+            Some((t, _)) if t < tick => self.rb.pop_front(),
+            _ => None,
+        }
+    }
+
+    /// Remove every value that was added before `tick` and return how
+    /// many were removed
+    pub fn drop_older_than(&mut self, tick: u64) -> usize {
+        let mut count = 0;
+        while self.pop_older_than(tick).is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns the number of values in the buffer
+    pub fn len(&self) -> usize {
+        self.rb.len()
+    }
+
+    /// Returns true if there are no values in the buffer
+    pub fn is_empty(&self) -> bool {
+        self.rb.is_empty()
+    }
+
+    /// Returns the number of free slots in the buffer
+    pub fn capacity(&self) -> usize {
+        self.rb.capacity()
+    }
+
+    /// Returns the max number of values that can ever be stored in
+    /// the buffer
+    pub fn max_len(&self) -> usize {
+        self.rb.max_len()
+    }
+}
+
+#[test]
+fn test_timed() {
+    let mut trb = TimedRingBuffer::new(4, 'x');
+    assert_eq!(trb.pop_older_than(100), None);
+
+    assert!(trb.push_back(5, 'a'));
+    assert!(trb.push_back(5, 'b'));
+    assert!(trb.push_back(9, 'c'));
+    assert!(trb.push_back(12, 'd'));
+    assert!(!trb.push_back(13, 'e'));
+
+    assert_eq!(trb.pop_older_than(5), None);
+    assert_eq!(trb.pop_older_than(6), Some((5, 'a')));
+    assert_eq!(trb.peek_front(), Some((5, 'b')));
+    assert_eq!(trb.drop_older_than(12), 2);
+    assert_eq!(trb.len(), 1);
+    assert_eq!(trb.drop_older_than(12), 0);
+    assert_eq!(trb.pop_front(), Some((12, 'd')));
+    assert!(trb.is_empty());
+}
@@ -0,0 +1,454 @@
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::mem;
+
+use rng::{JitterRng, XorShiftRng};
+
+// The seed used by `Treap::new`
+const DEFAULT_SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Debug, Clone)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // Every node's priority is at least that of its children
+    priority: u64,
+    // The number of nodes in this subtree
+    size: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+    fn update(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn size<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+// Split a tree into the keys less than `key`, the node with `key` if
+// there is one, and the keys greater than `key`
+fn split<K: Ord, V>(link: Link<K, V>, key: &K) -> (Link<K, V>, Link<K, V>, Link<K, V>) {
+    let mut node = match link {
+        Some(node) => node,
+        None => return (None, None, None),
+    };
+    match node.key.cmp(key) {
+        Ordering::Less => {
+            let (left, mid, right) = split(node.right.take(), key);
+            node.right = left;
+            node.update();
+            (Some(node), mid, right)
+        }
+        Ordering::Greater => {
+            let (left, mid, right) = split(node.left.take(), key);
+            node.left = right;
+            node.update();
+            (left, mid, Some(node))
+        }
+        Ordering::Equal => {
+            let left = node.left.take();
+            let right = node.right.take();
+            node.update();
+            (left, Some(node), right)
+        }
+    }
+}
+
+// Join two trees, where every key in `a` is less than every key in `b`
+fn merge<K, V>(a: Link<K, V>, b: Link<K, V>) -> Link<K, V> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority > b.priority {
+                a.right = merge(a.right.take(), Some(b));
+                a.update();
+                Some(a)
+            } else {
+                b.left = merge(Some(a), b.left.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+// Join two trees with keys in any order, keeping the values from `b`
+// for keys in both
+fn union<K: Ord, V>(a: Link<K, V>, b: Link<K, V>) -> Link<K, V> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority > b.priority {
+                let (left, mid, right) = split(Some(b), &a.key);
+                if let Some(mid) = mid {
+                    a.value = mid.value;
+                }
+                a.left = union(a.left.take(), left);
+                a.right = union(a.right.take(), right);
+                a.update();
+                Some(a)
+            } else {
+                let (left, _, right) = split(Some(a), &b.key);
+                b.left = union(left, b.left.take());
+                b.right = union(right, b.right.take());
+                b.update();
+                Some(b)
+            }
+        }
+    }
+}
+
+/// An ordered map that can be split in two at a key, or joined with
+/// another map, in O(log n)
+///
+/// A treap is a binary search tree where each node also gets a random
+/// priority, and is kept above the nodes with lower priorities. This
+/// keeps the tree balanced with high probability, and makes splitting
+/// and joining trees simple. The priorities come from a seeded
+/// `XorShiftRng`, so the shape of the tree is deterministic. Each node
+/// also counts the nodes under it, so finding the key at an index, or
+/// the index of a key, is O(log n) too.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut pages = Treap::new();
+/// for page in 1..=10 {
+///     pages.insert(page, format!("page {}", page));
+/// }
+///
+/// // Move the second half out into its own document
+/// let mut second = pages.split_off(&6);
+/// assert_eq!(pages.len(), 5);
+/// assert_eq!(second.first(), Some((&6, &"page 6".to_string())));
+///
+/// second.insert(11, "page 11".to_string());
+/// pages.append(&mut second);
+/// assert!(second.is_empty());
+/// assert_eq!(pages.last(), Some((&11, &"page 11".to_string())));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Treap<K, V> {
+    root: Link<K, V>,
+    rng: XorShiftRng,
+}
+
+impl<K: Ord, V> Default for Treap<K, V> {
+    fn default() -> Treap<K, V> {
+        Treap::new()
+    }
+}
+
+impl<K: Ord, V> Treap<K, V> {
+    /// Create an empty map
+    pub fn new() -> Treap<K, V> {
+        Treap::with_seed(DEFAULT_SEED)
+    }
+
+    /// Create an empty map that seeds its priorities with `seed`
+    pub fn with_seed(seed: u64) -> Treap<K, V> {
+        Treap {
+            root: None,
+            rng: XorShiftRng::new(seed),
+        }
+    }
+
+    /// Insert `value` under `key`, and return the value it replaced, if
+    /// any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(old) = self.get_mut(&key) {
+            return Some(mem::replace(old, value));
+        }
+        let node = Box::new(Node {
+            key,
+            value,
+            priority: self.rng.next_u64(),
+            size: 1,
+            left: None,
+            right: None,
+        });
+        let (left, _, right) = split(self.root.take(), &node.key);
+        self.root = merge(merge(left, Some(node)), right);
+        None
+    }
+
+    /// Return the value under `key`, or `None` if it isn't in the map
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    /// Return the value under `key` for modification, or `None` if it
+    /// isn't in the map
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let mut link = &mut self.root;
+        while let Some(ref mut node) = *link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => &mut node.left,
+                Ordering::Greater => &mut node.right,
+                Ordering::Equal => return Some(&mut node.value),
+            };
+        }
+        None
+    }
+
+    /// Returns true if there is an entry under `key`
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Remove the entry under `key` and return its value, or `None` if
+    /// it isn't in the map
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (left, mid, right) = split(self.root.take(), key);
+        self.root = merge(left, right);
+        mid.map(|node| node.value)
+    }
+
+    /// Move every entry with a key of at least `key` into a new map, and
+    /// return it
+    pub fn split_off(&mut self, key: &K) -> Treap<K, V> {
+        let (left, mid, right) = split(self.root.take(), key);
+        self.root = left;
+        Treap {
+            root: merge(mid, right),
+            rng: XorShiftRng::new(self.rng.next_u64()),
+        }
+    }
+
+    /// Move every entry in `other` into this map, replacing the values
+    /// of keys that are in both. This is O(log n) when all of the keys
+    /// in one map are less than all of those in the other
+    pub fn append(&mut self, other: &mut Treap<K, V>) {
+        self.root = union(self.root.take(), other.root.take());
+    }
+
+    /// Return the entry with the smallest key, or `None` if the map is
+    /// empty
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// Return the entry with the largest key, or `None` if the map is
+    /// empty
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// Return the entry with the `index`th smallest key, counting from
+    /// 0, or `None` if `index` is out of bounds
+    pub fn select(&self, mut index: usize) -> Option<(&K, &V)> {
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            let left = size(&node.left);
+            link = match index.cmp(&left) {
+                Ordering::Less => &node.left,
+                Ordering::Equal => return Some((&node.key, &node.value)),
+                Ordering::Greater => {
+                    index -= left + 1;
+                    &node.right
+                }
+            };
+        }
+        None
+    }
+
+    /// Return the number of keys less than `key`, which is the index
+    /// `key` has or would have in order
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Ord,
+    {
+        let mut rank = 0;
+        let mut link = &self.root;
+        while let Some(ref node) = *link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => &node.left,
+                Ordering::Equal => return rank + size(&node.left),
+                Ordering::Greater => {
+                    rank += size(&node.left) + 1;
+                    &node.right
+                }
+            };
+        }
+        rank
+    }
+
+    /// Iterate over the entries in order of their keys
+    pub fn iter(&self) -> TreapIter<'_, K, V> {
+        let mut iter = TreapIter { stack: vec![] };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    /// Remove every entry from the map
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    /// Return the number of entries in the map
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    /// Returns true if there are no entries in the map
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+}
+
+/// An iterator over the entries of a `Treap`, in order of their keys
+pub struct TreapIter<'a, K: 'a, V: 'a> {
+    // The nodes whose left subtrees are being visited
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> TreapIter<'a, K, V> {
+    fn push_left(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(ref node) = *link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for TreapIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+fn check_treap<K: Ord, V>(link: &Link<K, V>) -> usize {
+    match *link {
+        None => 0,
+        Some(ref node) => {
+            if let Some(ref left) = node.left {
+                assert!(left.key < node.key && left.priority <= node.priority);
+            }
+            if let Some(ref right) = node.right {
+                assert!(right.key > node.key && right.priority <= node.priority);
+            }
+            let size = 1 + check_treap(&node.left) + check_treap(&node.right);
+            assert_eq!(size, node.size);
+            size
+        }
+    }
+}
+
+#[test]
+fn test_treap() {
+    let mut treap = Treap::new();
+    assert!(treap.is_empty());
+    assert_eq!(treap.first(), None);
+    for i in 0..100u32 {
+        let key = (i * 37) % 100;
+        assert_eq!(treap.insert(key, key * 2), None);
+    }
+    check_treap(&treap.root);
+    assert_eq!(treap.len(), 100);
+    assert_eq!(treap.insert(40, 0), Some(80));
+    *treap.get_mut(&41).unwrap() += 1;
+    assert_eq!(treap.get(&41), Some(&83));
+    assert_eq!(treap.get(&100), None);
+
+    for key in (0..100).filter(|k| k % 3 == 0) {
+        assert_eq!(
+            treap.remove(&key),
+            Some(if key == 40 { 0 } else { key * 2 })
+        );
+    }
+    assert_eq!(treap.remove(&3), None);
+    check_treap(&treap.root);
+    assert_eq!(treap.len(), 66);
+    assert!(!treap.contains_key(&99));
+    assert_eq!(treap.first(), Some((&1, &2)));
+    assert_eq!(treap.last(), Some((&98, &196)));
+
+    let keys: Vec<_> = treap.iter().map(|(&k, _)| k).collect();
+    let expected: Vec<_> = (0..100).filter(|k| k % 3 != 0).collect();
+    assert_eq!(keys, expected);
+    for (index, key) in expected.iter().enumerate() {
+        assert_eq!(treap.select(index).map(|(k, _)| k), Some(key));
+        assert_eq!(treap.rank(key), index);
+        // Whether or not the next key is in the map
+        assert_eq!(treap.rank(&(key + 1)), index + 1);
+    }
+    assert_eq!(treap.select(66), None);
+    assert_eq!(treap.rank(&1000), 66);
+
+    treap.clear();
+    assert_eq!(treap.iter().count(), 0);
+}
+
+#[test]
+fn test_treap_split_append() {
+    let mut treap = Treap::with_seed(7);
+    for key in 0..50 {
+        treap.insert(key, 'a');
+    }
+    let mut high = treap.split_off(&20);
+    check_treap(&treap.root);
+    check_treap(&high.root);
+    assert_eq!((treap.len(), high.len()), (20, 30));
+    assert_eq!(treap.last(), Some((&19, &'a')));
+    assert_eq!(high.first(), Some((&20, &'a')));
+    assert_eq!(treap.split_off(&100).len(), 0);
+
+    // Interleaved keys, where the values from `other` win
+    let mut other = Treap::with_seed(8);
+    for key in (10..60).step_by(5) {
+        other.insert(key, 'b');
+    }
+    high.append(&mut other);
+    treap.append(&mut high);
+    check_treap(&treap.root);
+    assert!(other.is_empty() && high.is_empty());
+    assert_eq!(treap.len(), 52);
+    assert_eq!(treap.get(&15), Some(&'b'));
+    assert_eq!(treap.get(&16), Some(&'a'));
+    assert_eq!(treap.get(&55), Some(&'b'));
+    assert!(treap.iter().map(|(&k, _)| k).eq((0..51).chain(Some(55))));
+}
@@ -0,0 +1,324 @@
+#[derive(Debug, Clone)]
+struct Node<V> {
+    value: Option<V>,
+    // Sorted by byte
+    children: Vec<(u8, Node<V>)>,
+}
+
+impl<V> Node<V> {
+    fn new() -> Node<V> {
+        Node {
+            value: None,
+            children: vec![],
+        }
+    }
+
+    fn child(&self, byte: u8) -> Option<&Node<V>> {
+        let index = self.children.binary_search_by_key(&byte, |c| c.0).ok()?;
+        Some(&self.children[index].1)
+    }
+
+    fn child_mut(&mut self, byte: u8) -> Option<&mut Node<V>> {
+        let index = self.children.binary_search_by_key(&byte, |c| c.0).ok()?;
+        Some(&mut self.children[index].1)
+    }
+
+    // Remove the value under `key`, and any nodes left with nothing
+    // under them
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let (&byte, rest) = match key.split_first() {
+            Some(split) => split,
+            None => return self.value.take(),
+        };
+        let index = self.children.binary_search_by_key(&byte, |c| c.0).ok()?;
+        let value = self.children[index].1.remove(rest);
+        let child = &self.children[index].1;
+        if child.value.is_none() && child.children.is_empty() {
+            self.children.remove(index);
+        }
+        value
+    }
+}
+
+/// A map keyed by byte strings, which can find every key that starts
+/// with a given prefix, or the longest key that is a prefix of a given
+/// string
+///
+/// Each node holds one byte of a key, so looking up a key is O(length
+/// of the key) no matter how many keys there are, and keys with a
+/// common prefix share the nodes for it. Keys can be anything that is
+/// `AsRef<[u8]>`, such as `&str` or `&[u8]`, and entries are iterated
+/// in byte order. For sparse, long keys, `RadixTree` uses less memory.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut commands = Trie::new();
+/// commands.insert("help", 1);
+/// commands.insert("hello", 2);
+/// commands.insert("halt", 3);
+///
+/// let matches: Vec<_> = commands.iter_prefix("hel").map(|(key, _)| key).collect();
+/// assert_eq!(matches, vec![b"hello".to_vec(), b"help".to_vec()]);
+///
+/// let (prefix, value) = commands.longest_prefix("helpful").unwrap();
+/// assert_eq!((prefix, *value), (&b"help"[..], 1));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Trie<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Trie<V> {
+        Trie::new()
+    }
+}
+
+impl<V> Trie<V> {
+    /// Create an empty trie
+    pub fn new() -> Trie<V> {
+        Trie {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    /// Insert `value` under `key`, and return the value it replaced, if
+    /// any
+    pub fn insert<K: AsRef<[u8]>>(&mut self, key: K, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for &byte in key.as_ref() {
+            let index = match node.children.binary_search_by_key(&byte, |c| c.0) {
+                Ok(index) => index,
+                Err(index) => {
+                    node.children.insert(index, (byte, Node::new()));
+                    index
+                }
+            };
+            node = &mut node.children[index].1;
+        }
+        let old = node.value.replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Return the value under `key`, or `None` if it isn't in the trie
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&V> {
+        self.node(key.as_ref())?.value.as_ref()
+    }
+
+    /// Return the value under `key` for modification, or `None` if it
+    /// isn't in the trie
+    pub fn get_mut<K: AsRef<[u8]>>(&mut self, key: K) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        for &byte in key.as_ref() {
+            node = node.child_mut(byte)?;
+        }
+        node.value.as_mut()
+    }
+
+    /// Returns true if there is a value under `key`
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove the value under `key` and return it, or `None` if it isn't
+    /// in the trie
+    pub fn remove<K: AsRef<[u8]>>(&mut self, key: K) -> Option<V> {
+        let value = self.root.remove(key.as_ref());
+        if value.is_some() {
+            self.len -= 1;
+        }
+        value
+    }
+
+    /// Return the longest key in the trie that `key` starts with, along
+    /// with its value, or `None` if there is no such key
+    pub fn longest_prefix<'k, K: AsRef<[u8]> + ?Sized>(
+        &self,
+        key: &'k K,
+    ) -> Option<(&'k [u8], &V)> {
+        let key = key.as_ref();
+        let mut node = &self.root;
+        let mut best = node.value.as_ref().map(|value| (0, value));
+        for (depth, &byte) in key.iter().enumerate() {
+            node = match node.child(byte) {
+                Some(child) => child,
+                None => break,
+            };
+            if let Some(ref value) = node.value {
+                best = Some((depth + 1, value));
+            }
+        }
+        best.map(|(len, value)| (&key[..len], value))
+    }
+
+    /// Iterate over every entry in byte order of the keys
+    pub fn iter(&self) -> TrieIter<'_, V> {
+        TrieIter::new(Some(&self.root), vec![])
+    }
+
+    /// Iterate over the entries whose keys start with `prefix`, in byte
+    /// order of the keys
+    pub fn iter_prefix<K: AsRef<[u8]>>(&self, prefix: K) -> TrieIter<'_, V> {
+        let prefix = prefix.as_ref();
+        TrieIter::new(self.node(prefix), prefix.to_vec())
+    }
+
+    /// Remove every entry from the trie
+    pub fn clear(&mut self) {
+        self.root = Node::new();
+        self.len = 0;
+    }
+
+    /// Return the number of entries in the trie
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no entries in the trie
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn node(&self, key: &[u8]) -> Option<&Node<V>> {
+        let mut node = &self.root;
+        for &byte in key {
+            node = node.child(byte)?;
+        }
+        Some(node)
+    }
+}
+
+/// An iterator over the entries of a `Trie`, in byte order of the keys
+pub struct TrieIter<'a, V: 'a> {
+    // The node to start from, before it has been visited
+    start: Option<&'a Node<V>>,
+    // The nodes being visited, and the next child to visit of each
+    stack: Vec<(&'a Node<V>, usize)>,
+    key: Vec<u8>,
+}
+
+impl<'a, V> TrieIter<'a, V> {
+    fn new(start: Option<&'a Node<V>>, key: Vec<u8>) -> TrieIter<'a, V> {
+        TrieIter {
+            start,
+            stack: vec![],
+            key,
+        }
+    }
+}
+
+impl<'a, V> Iterator for TrieIter<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<(Vec<u8>, &'a V)> {
+        if let Some(node) = self.start.take() {
+            self.stack.push((node, 0));
+            if let Some(ref value) = node.value {
+                return Some((self.key.clone(), value));
+            }
+        }
+        loop {
+            let child = {
+                let &mut (node, ref mut next) = self.stack.last_mut()?;
+                let child = node.children.get(*next);
+                *next += 1;
+                child
+            };
+            match child {
+                Some(&(byte, ref child)) => {
+                    self.key.push(byte);
+                    self.stack.push((child, 0));
+                    if let Some(ref value) = child.value {
+                        return Some((self.key.clone(), value));
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                    if !self.stack.is_empty() {
+                        self.key.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_trie() {
+    let mut trie = Trie::new();
+    assert!(trie.is_empty());
+    assert_eq!(trie.insert("tea", 1), None);
+    assert_eq!(trie.insert("ten", 2), None);
+    assert_eq!(trie.insert("to", 3), None);
+    assert_eq!(trie.insert("tea", 4), Some(1));
+    assert_eq!(trie.insert("", 5), None);
+    assert_eq!(trie.len(), 4);
+
+    assert_eq!(trie.get("tea"), Some(&4));
+    assert_eq!(trie.get("te"), None);
+    assert_eq!(trie.get(b"to"), Some(&3));
+    *trie.get_mut("ten").unwrap() += 10;
+    assert!(trie.contains_key(""));
+
+    assert_eq!(
+        trie.iter().collect::<Vec<_>>(),
+        vec![
+            (b"".to_vec(), &5),
+            (b"tea".to_vec(), &4),
+            (b"ten".to_vec(), &12),
+            (b"to".to_vec(), &3)
+        ]
+    );
+    assert_eq!(trie.iter_prefix("te").count(), 2);
+    assert_eq!(trie.iter_prefix("tea").count(), 1);
+    assert_eq!(trie.iter_prefix("x").count(), 0);
+
+    assert_eq!(trie.longest_prefix("tenth"), Some((&b"ten"[..], &12)));
+    assert_eq!(trie.longest_prefix("t"), Some((&b""[..], &5)));
+
+    // Removing a key prunes the nodes only it used
+    assert_eq!(trie.remove("ten"), Some(12));
+    assert_eq!(trie.remove("ten"), None);
+    assert_eq!(trie.remove("te"), None);
+    assert_eq!(
+        trie.root
+            .child(b't')
+            .unwrap()
+            .child(b'e')
+            .unwrap()
+            .children
+            .len(),
+        1
+    );
+    assert_eq!(trie.remove(""), Some(5));
+    assert_eq!(trie.longest_prefix("t"), None);
+    assert_eq!(trie.len(), 2);
+
+    trie.clear();
+    assert!(trie.is_empty());
+    assert_eq!(trie.iter().count(), 0);
+}
+
+#[test]
+fn test_trie_routes() {
+    let mut routes = Trie::new();
+    routes.insert([10u8], "private");
+    routes.insert([10u8, 1], "lab");
+    routes.insert([10u8, 1, 2, 3], "printer");
+    routes.insert([192u8, 168], "home");
+
+    let route = |addr: [u8; 4]| routes.longest_prefix(&addr[..]).map(|(_, &name)| name);
+    assert_eq!(route([10, 1, 2, 3]), Some("printer"));
+    assert_eq!(route([10, 1, 2, 4]), Some("lab"));
+    assert_eq!(route([10, 7, 0, 1]), Some("private"));
+    assert_eq!(route([192, 168, 0, 1]), Some("home"));
+    assert_eq!(route([8, 8, 8, 8]), None);
+}
@@ -0,0 +1,186 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::vec;
+
+use wheel::{DeadlineId, TimerWheel};
+
+/// A map whose entries expire a fixed number of ticks after they are
+/// inserted
+///
+/// Time only moves when the cache is advanced, in the same absolute
+/// ticks as `Clock` and `Timer`, so expiry is fully deterministic. The
+/// deadlines are kept in a `TimerWheel`, so advancing is cheap no
+/// matter how many entries are waiting.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut sessions = TtlCache::new(100);
+/// sessions.insert("alice", 1);
+/// sessions.advance(60);
+/// sessions.insert("bob", 2);
+///
+/// let expired: Vec<_> = sessions.advance(60).collect();
+/// assert_eq!(expired, vec![("alice", 1)]);
+/// assert_eq!(sessions.get("bob"), Some(&2));
+/// assert_eq!(sessions.ttl_remaining("bob"), Some(40));
+/// ```
+#[derive(Debug, Clone)]
+pub struct TtlCache<K, V> {
+    entries: HashMap<K, (V, DeadlineId)>,
+    keys: HashMap<DeadlineId, K>,
+    wheel: TimerWheel,
+    ttl: u64,
+    expired: Vec<(K, V)>,
+}
+
+impl<K: Hash + Eq + Clone, V> TtlCache<K, V> {
+    /// Create an empty cache whose entries live for `ttl` ticks, with
+    /// its time starting at 0
+    pub fn new(ttl: u64) -> TtlCache<K, V> {
+        TtlCache::starting_at(ttl, 0)
+    }
+
+    /// Create an empty cache whose entries live for `ttl` ticks, with
+    /// its time starting at `now`
+    pub fn starting_at(ttl: u64, now: u64) -> TtlCache<K, V> {
+        TtlCache {
+            entries: HashMap::new(),
+            keys: HashMap::new(),
+            wheel: TimerWheel::starting_at(now),
+            ttl,
+            expired: vec![],
+        }
+    }
+
+    /// Insert `value` under `key` to live for the cache's TTL, and
+    /// return the value it replaced, if any
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let ttl = self.ttl;
+        self.insert_with_ttl(key, value, ttl)
+    }
+
+    /// Insert `value` under `key` to live for `ttl` ticks, and return
+    /// the value it replaced, if any
+    pub fn insert_with_ttl(&mut self, key: K, value: V, ttl: u64) -> Option<V> {
+        let id = self.wheel.insert(self.wheel.now().saturating_add(ttl));
+        self.keys.insert(id, key.clone());
+        let (old, old_id) = self.entries.insert(key, (value, id))?;
+        self.wheel.cancel(old_id);
+        self.keys.remove(&old_id);
+        Some(old)
+    }
+
+    /// Return the value under `key`, or `None` if it isn't in the cache
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    /// Return the value under `key` for modification, or `None` if it
+    /// isn't in the cache. This doesn't change when it expires
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.entries.get_mut(key).map(|(value, _)| value)
+    }
+
+    /// Return the number of ticks until the entry under `key` expires,
+    /// or `None` if it isn't in the cache
+    pub fn ttl_remaining<Q>(&self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let &(_, id) = self.entries.get(key)?;
+        self.wheel.deadline(id).map(|d| d - self.wheel.now())
+    }
+
+    /// Remove the entry under `key` and return its value, or `None` if
+    /// it isn't in the cache
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        let (value, id) = self.entries.remove(key)?;
+        self.wheel.cancel(id);
+        self.keys.remove(&id);
+        Some(value)
+    }
+
+    /// Move the cache's time forward by `ticks`, and return every entry
+    /// that expired, in the order they expired
+    pub fn advance(&mut self, ticks: u64) -> vec::Drain<'_, (K, V)> {
+        let time = self.wheel.now() + ticks;
+        self.advance_to(time)
+    }
+
+    /// Move the cache's time forward to the absolute tick `time`, and
+    /// return every entry that expired, in the order they expired
+    pub fn advance_to(&mut self, time: u64) -> vec::Drain<'_, (K, V)> {
+        self.expired.clear();
+        for (id, _) in self.wheel.advance(time) {
+            let key = self.keys.remove(&id).unwrap();
+            let (value, _) = self.entries.remove(&key).unwrap();
+            self.expired.push((key, value));
+        }
+        self.expired.drain(..)
+    }
+
+    /// Return the cache's current time
+    pub fn now(&self) -> u64 {
+        self.wheel.now()
+    }
+
+    /// Return the number of ticks new entries live for
+    pub fn ttl(&self) -> u64 {
+        self.ttl
+    }
+
+    /// Return the number of entries in the cache
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if there are no entries in the cache
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[test]
+fn test_ttl_cache() {
+    let mut cache = TtlCache::starting_at(10, 1000);
+    assert_eq!(cache.ttl(), 10);
+    assert!(cache.is_empty());
+
+    cache.insert('a', 1);
+    cache.insert_with_ttl('b', 2, 3);
+    cache.insert_with_ttl('c', 3, 25);
+    assert_eq!(cache.advance_to(1002).count(), 0);
+
+    // Replacing an entry restarts its TTL
+    assert_eq!(cache.insert('b', 20), Some(2));
+    *cache.get_mut(&'b').unwrap() += 1;
+    assert_eq!(cache.ttl_remaining(&'a'), Some(8));
+    assert_eq!(
+        cache.advance(10).collect::<Vec<_>>(),
+        vec![('a', 1), ('b', 21)]
+    );
+    assert_eq!(cache.now(), 1012);
+    assert_eq!(cache.get(&'a'), None);
+
+    assert_eq!(cache.remove(&'c'), Some(3));
+    assert_eq!(cache.remove(&'c'), None);
+    assert_eq!(cache.advance(100).count(), 0);
+    assert_eq!(cache.len(), 0);
+}
@@ -0,0 +1,313 @@
+use std::iter::{Enumerate, FromIterator};
+use std::slice;
+
+/// A map from small `usize` keys to values, stored in a vector indexed
+/// by key
+///
+/// There is no hashing, so every lookup is a bounds check and an index,
+/// which suits tables keyed by dense ids like entity ids or the keys of
+/// an `IdAllocator`. The vector is as long as the largest key, so keys
+/// should be small; iteration is in order of key and visits the empty
+/// slots too.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut health = VecMap::new();
+/// health.insert(3, 100);
+/// health.insert(0, 80);
+/// *health.entry(3).or_insert(0) -= 25;
+/// *health.entry(7).or_insert(50) += 1;
+///
+/// let all: Vec<_> = health.iter().collect();
+/// assert_eq!(all, vec![(0, &80), (3, &75), (7, &51)]);
+/// assert_eq!(health.remove(0), Some(80));
+/// assert_eq!(health.len(), 2);
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct VecMap<V> {
+    slots: Vec<Option<V>>,
+    len: usize,
+}
+
+impl<V> Default for VecMap<V> {
+    fn default() -> VecMap<V> {
+        VecMap::new()
+    }
+}
+
+impl<V> VecMap<V> {
+    /// Create an empty map
+    pub fn new() -> VecMap<V> {
+        VecMap {
+            slots: vec![],
+            len: 0,
+        }
+    }
+
+    /// Store `value` under `key`, and return the value it replaced, if
+    /// any
+    pub fn insert(&mut self, key: usize, value: V) -> Option<V> {
+        if key >= self.slots.len() {
+            self.slots.resize_with(key + 1, || None);
+        }
+        let old = self.slots[key].replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Remove the value under `key` and return it, or `None` if there
+    /// isn't one
+    pub fn remove(&mut self, key: usize) -> Option<V> {
+        let value = self.slots.get_mut(key)?.take()?;
+        self.len -= 1;
+        // Don't hold on to empty slots past the largest key
+        while let Some(None) = self.slots.last() {
+            self.slots.pop();
+        }
+        Some(value)
+    }
+
+    /// Return the value under `key`, or `None` if there isn't one
+    pub fn get(&self, key: usize) -> Option<&V> {
+        self.slots.get(key)?.as_ref()
+    }
+
+    /// Return the value under `key` for modification, or `None` if there
+    /// isn't one
+    pub fn get_mut(&mut self, key: usize) -> Option<&mut V> {
+        self.slots.get_mut(key)?.as_mut()
+    }
+
+    /// Returns true if there is a value under `key`
+    pub fn contains_key(&self, key: usize) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Return the entry under `key`, to look at or change in place
+    pub fn entry(&mut self, key: usize) -> VecMapEntry<'_, V> {
+        VecMapEntry { map: self, key }
+    }
+
+    /// Iterate over the keys and values, in order of key
+    pub fn iter(&self) -> VecMapIter<'_, V> {
+        VecMapIter {
+            iter: self.slots.iter().enumerate(),
+        }
+    }
+
+    /// Iterate over the keys and values for modification, in order of
+    /// key
+    pub fn iter_mut(&mut self) -> VecMapIterMut<'_, V> {
+        VecMapIterMut {
+            iter: self.slots.iter_mut().enumerate(),
+        }
+    }
+
+    /// Keep only the entries that `keep` returns true for
+    pub fn retain<F: FnMut(usize, &mut V) -> bool>(&mut self, mut keep: F) {
+        for (key, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(ref mut value) = *slot {
+                if !keep(key, value) {
+                    *slot = None;
+                    self.len -= 1;
+                }
+            }
+        }
+        while let Some(None) = self.slots.last() {
+            self.slots.pop();
+        }
+    }
+
+    /// Remove every value from the map
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+
+    /// Return the number of values in the map
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<V> FromIterator<(usize, V)> for VecMap<V> {
+    fn from_iter<I: IntoIterator<Item = (usize, V)>>(entries: I) -> VecMap<V> {
+        let mut map = VecMap::new();
+        map.extend(entries);
+        map
+    }
+}
+
+impl<V> Extend<(usize, V)> for VecMap<V> {
+    fn extend<I: IntoIterator<Item = (usize, V)>>(&mut self, entries: I) {
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a VecMap<V> {
+    type Item = (usize, &'a V);
+    type IntoIter = VecMapIter<'a, V>;
+
+    fn into_iter(self) -> VecMapIter<'a, V> {
+        self.iter()
+    }
+}
+
+/// A key in a `VecMap`, which may or may not have a value
+pub struct VecMapEntry<'a, V: 'a> {
+    map: &'a mut VecMap<V>,
+    key: usize,
+}
+
+impl<'a, V> VecMapEntry<'a, V> {
+    /// Return the key of the entry
+    pub fn key(&self) -> usize {
+        self.key
+    }
+
+    /// Returns true if there is a value under the key
+    pub fn is_occupied(&self) -> bool {
+        self.map.contains_key(self.key)
+    }
+
+    /// Return the value under the key, storing `value` there first if
+    /// there isn't one
+    pub fn or_insert(self, value: V) -> &'a mut V {
+        self.or_insert_with(|| value)
+    }
+
+    /// Return the value under the key, storing the result of `make`
+    /// there first if there isn't one
+    pub fn or_insert_with<F: FnOnce() -> V>(self, make: F) -> &'a mut V {
+        if !self.is_occupied() {
+            self.map.insert(self.key, make());
+        }
+        self.map.get_mut(self.key).unwrap()
+    }
+
+    /// Return the value under the key, storing the default value there
+    /// first if there isn't one
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// Call `f` on the value under the key, if there is one
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> VecMapEntry<'a, V> {
+        if let Some(value) = self.map.get_mut(self.key) {
+            f(value);
+        }
+        self
+    }
+}
+
+/// An iterator over the keys and values of a `VecMap`, in order of key
+pub struct VecMapIter<'a, V: 'a> {
+    iter: Enumerate<slice::Iter<'a, Option<V>>>,
+}
+
+impl<'a, V> Iterator for VecMapIter<'a, V> {
+    type Item = (usize, &'a V);
+
+    fn next(&mut self) -> Option<(usize, &'a V)> {
+        for (key, slot) in &mut self.iter {
+            if let Some(ref value) = *slot {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over the keys and values of a `VecMap`, with the values
+/// open for modification, in order of key
+pub struct VecMapIterMut<'a, V: 'a> {
+    iter: Enumerate<slice::IterMut<'a, Option<V>>>,
+}
+
+impl<'a, V> Iterator for VecMapIterMut<'a, V> {
+    type Item = (usize, &'a mut V);
+
+    fn next(&mut self) -> Option<(usize, &'a mut V)> {
+        for (key, slot) in &mut self.iter {
+            if let Some(ref mut value) = *slot {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn test_vec_map() {
+    let mut map = VecMap::new();
+    assert!(map.is_empty());
+    assert_eq!(map.get(0), None);
+    assert_eq!(map.remove(5), None);
+
+    assert_eq!(map.insert(4, "four"), None);
+    assert_eq!(map.insert(1, "one"), None);
+    assert_eq!(map.insert(4, "FOUR"), Some("four"));
+    assert_eq!(map.len(), 2);
+    assert!(map.contains_key(1));
+    assert!(!map.contains_key(2));
+    assert!(!map.contains_key(100));
+    *map.get_mut(1).unwrap() = "ONE";
+    assert_eq!(map.get(1), Some(&"ONE"));
+
+    // Removing the largest key frees the slots after the next largest
+    assert_eq!(map.remove(4), Some("FOUR"));
+    assert_eq!(map.slots.len(), 2);
+    assert_eq!(map.remove(4), None);
+    assert_eq!(map.len(), 1);
+
+    map.extend(vec![(9, "nine"), (2, "two")]);
+    for (key, value) in map.iter_mut() {
+        if key > 1 {
+            *value = "big";
+        }
+    }
+    let all: Vec<_> = (&map).into_iter().collect();
+    assert_eq!(all, vec![(1, &"ONE"), (2, &"big"), (9, &"big")]);
+
+    map.retain(|key, _| key < 5);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.slots.len(), 3);
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.iter().next(), None);
+}
+
+#[test]
+fn test_vec_map_entry() {
+    let mut counts: VecMap<u32> = VecMap::new();
+    for &id in &[3, 1, 3, 3, 0, 1] {
+        *counts.entry(id).or_default() += 1;
+    }
+    assert_eq!(counts.get(3), Some(&3));
+    assert_eq!(counts.get(1), Some(&2));
+
+    let entry = counts.entry(8);
+    assert_eq!(entry.key(), 8);
+    assert!(!entry.is_occupied());
+    entry.and_modify(|v| *v += 100).or_insert_with(|| 7);
+    counts.entry(0).and_modify(|v| *v += 100).or_insert(7);
+    let all: VecMap<u32> = counts.iter().map(|(k, v)| (k, *v)).collect();
+    assert_eq!(all, counts);
+    assert_eq!(all.get(8), Some(&7));
+    assert_eq!(all.get(0), Some(&101));
+}
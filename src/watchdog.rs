@@ -0,0 +1,141 @@
+/// Reported by `Watchdog::update` when the watchdog wasn't fed in time
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Timeout {
+    /// The tick the watchdog expired on
+    pub deadline: u64,
+}
+
+/// A watchdog timer, which times out if it isn't fed at least once
+/// every `window` ticks
+///
+/// Without auto-reset, a watchdog times out once and then stays
+/// expired until it is fed again. With auto-reset, it times out again
+/// after every further `window` ticks without being fed.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut watchdog = Watchdog::new(100);
+/// watchdog.feed(80);
+/// assert_eq!(watchdog.update(150), None);
+/// assert_eq!(watchdog.update(180), Some(Timeout { deadline: 180 }));
+/// assert_eq!(watchdog.update(500), None);
+/// assert!(watchdog.is_expired());
+/// ```
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Watchdog {
+    window: u64,
+    deadline: u64,
+    auto_reset: bool,
+    expired: bool,
+}
+
+impl Watchdog {
+    /// Create a new watchdog that was last fed at tick 0
+    pub fn new(window: u64) -> Watchdog {
+        assert!(window > 0);
+        Watchdog {
+            window,
+            deadline: window,
+            auto_reset: false,
+            expired: false,
+        }
+    }
+
+    /// Feed the watchdog at the absolute `time`, which pushes the
+    /// deadline back to `time + window` and clears an expired watchdog
+    pub fn feed(&mut self, time: u64) {
+        self.deadline = time + self.window;
+        self.expired = false;
+    }
+
+    /// Check the watchdog at the absolute `time`, and return the
+    /// timeout if it passed its deadline. With auto-reset, run this in
+    /// a loop to get every timeout up to `time`
+    pub fn update(&mut self, time: u64) -> Option<Timeout> {
+        if self.expired || time < self.deadline {
+            return None;
+        }
+
+        let timeout = Timeout {
+            deadline: self.deadline,
+        };
+        if self.auto_reset {
+            self.deadline += self.window;
+        } else {
+            self.expired = true;
+        }
+        Some(timeout)
+    }
+
+    /// Set whether the watchdog starts a new window by itself after it
+    /// times out. It doesn't by default
+    pub fn set_auto_reset(&mut self, auto_reset: bool) {
+        self.auto_reset = auto_reset;
+    }
+
+    /// Indicates if the watchdog starts a new window by itself after
+    /// it times out
+    pub fn is_auto_reset(&self) -> bool {
+        self.auto_reset
+    }
+
+    /// Indicates if the watchdog has timed out and is waiting to be
+    /// fed. An auto-reset watchdog never stays expired
+    pub fn is_expired(&self) -> bool {
+        self.expired
+    }
+
+    /// Get the next tick the watchdog will time out on, or `u64::MAX`
+    /// if it is expired
+    pub fn next_event_time(&self) -> u64 {
+        if self.expired {
+            u64::MAX
+        } else {
+            self.deadline
+        }
+    }
+
+    /// Return the number of ticks the watchdog waits to be fed
+    pub fn window(&self) -> u64 {
+        self.window
+    }
+}
+
+#[test]
+fn test_watchdog() {
+    let mut watchdog = Watchdog::new(10);
+    assert_eq!(watchdog.window(), 10);
+    for time in 0..100 {
+        watchdog.feed(time);
+        assert_eq!(watchdog.update(time + 9), None);
+    }
+    assert_eq!(watchdog.next_event_time(), 109);
+    assert_eq!(watchdog.update(109), Some(Timeout { deadline: 109 }));
+    assert_eq!(watchdog.next_event_time(), u64::MAX);
+
+    watchdog.feed(200);
+    assert!(!watchdog.is_expired());
+    assert_eq!(watchdog.update(209), None);
+    assert_eq!(watchdog.update(210), Some(Timeout { deadline: 210 }));
+}
+
+#[test]
+fn test_watchdog_auto_reset() {
+    let mut watchdog = Watchdog::new(10);
+    watchdog.set_auto_reset(true);
+    assert!(watchdog.is_auto_reset());
+
+    let mut timeouts = vec![];
+    while let Some(timeout) = watchdog.update(35) {
+        timeouts.push(timeout.deadline);
+    }
+    assert_eq!(timeouts, vec![10, 20, 30]);
+    assert!(!watchdog.is_expired());
+
+    watchdog.feed(36);
+    assert_eq!(watchdog.update(45), None);
+    assert_eq!(watchdog.next_event_time(), 46);
+}
@@ -0,0 +1,370 @@
+use std::mem;
+use std::slice;
+
+// Each level of the wheel has 64 slots, and covers 64 times the span
+// of the level below it. 11 levels are enough for the whole u64 range
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: usize = 11;
+
+/// Identifies a deadline within a `TimerWheel`
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct DeadlineId {
+    index: usize,
+    generation: u64,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+struct Entry {
+    generation: u64,
+    deadline: u64,
+    // 0 for one-shot deadlines
+    period: u64,
+    live: bool,
+}
+
+/// A hierarchical timing wheel, for scheduling very large numbers of
+/// one-shot and periodic deadlines in the same tick domain as `Clock`
+/// and `Timer`
+///
+/// Inserting and cancelling a deadline are O(1). Deadlines far in the
+/// future sit in coarse buckets, and are cascaded down into finer ones
+/// as the wheel's time approaches them. Advancing skips over empty
+/// stretches of time, so large jumps stay cheap.
+///
+/// Example:
+///
+/// ```rust
+/// use j2ds::*;
+///
+/// let mut wheel = TimerWheel::new();
+/// let timeout = wheel.insert(5000);
+/// let poll = wheel.insert_periodic(100, 100);
+///
+/// assert_eq!(wheel.advance(250).count(), 2);
+/// assert!(wheel.cancel(timeout));
+/// let expired: Vec<_> = wheel.advance(10_000).collect();
+/// assert_eq!(expired.len(), 98);
+/// assert_eq!(expired[0], (poll, 300));
+/// ```
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TimerWheel {
+    now: u64,
+    entries: Vec<Entry>,
+    // Indices of entries that are no longer live, ready for reuse
+    free: Vec<usize>,
+    // `LEVELS * SLOTS` buckets of `(index, generation)`. Cancelled
+    // entries are left behind, and skipped when their bucket is
+    // drained
+    buckets: Vec<Vec<(usize, u64)>>,
+    // A bit for each possibly non-empty bucket, per level
+    occupied: [u64; LEVELS],
+    expired: Vec<(DeadlineId, u64)>,
+}
+
+impl Default for TimerWheel {
+    fn default() -> TimerWheel {
+        TimerWheel::new()
+    }
+}
+
+impl TimerWheel {
+    /// Create an empty wheel whose time starts at 0
+    pub fn new() -> TimerWheel {
+        TimerWheel::starting_at(0)
+    }
+
+    /// Create an empty wheel whose time starts at `now`
+    pub fn starting_at(now: u64) -> TimerWheel {
+        TimerWheel {
+            now,
+            entries: vec![],
+            free: vec![],
+            buckets: vec![vec![]; LEVELS * SLOTS],
+            occupied: [0; LEVELS],
+            expired: vec![],
+        }
+    }
+
+    /// Schedule a one-shot deadline at the absolute tick `deadline`. A
+    /// deadline that has already passed expires on the next `advance`
+    pub fn insert(&mut self, deadline: u64) -> DeadlineId {
+        self.insert_entry(deadline, 0)
+    }
+
+    /// Schedule a deadline at the absolute tick `first` that repeats
+    /// every `period` ticks until it is cancelled
+    pub fn insert_periodic(&mut self, first: u64, period: u64) -> DeadlineId {
+        assert!(period > 0);
+        self.insert_entry(first, period)
+    }
+
+    fn insert_entry(&mut self, deadline: u64, period: u64) -> DeadlineId {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.entries.push(Entry {
+                    generation: 0,
+                    deadline: 0,
+                    period: 0,
+                    live: false,
+                });
+                self.entries.len() - 1
+            }
+        };
+
+        let entry = &mut self.entries[index];
+        entry.generation += 1;
+        entry.deadline = deadline.max(self.now);
+        entry.period = period;
+        entry.live = true;
+        let generation = entry.generation;
+        self.schedule(index);
+
+        DeadlineId { index, generation }
+    }
+
+    /// Cancel the deadline identified by `id`. Returns `false` if it had
+    /// already expired or been cancelled
+    pub fn cancel(&mut self, id: DeadlineId) -> bool {
+        let entry = &mut self.entries[id.index];
+        if !entry.live || entry.generation != id.generation {
+            return false;
+        }
+        entry.live = false;
+        self.free.push(id.index);
+        true
+    }
+
+    /// Return the deadline identified by `id`, or `None` if it has
+    /// expired or been cancelled. For a periodic deadline this is the
+    /// next time it will expire
+    pub fn deadline(&self, id: DeadlineId) -> Option<u64> {
+        let entry = &self.entries[id.index];
+        if entry.live && entry.generation == id.generation {
+            Some(entry.deadline)
+        } else {
+            None
+        }
+    }
+
+    /// Advance the wheel's time to the absolute tick `time`, and return
+    /// every deadline that expired along with the tick it was due, in
+    /// chronological order
+    pub fn advance(&mut self, time: u64) -> Expired<'_> {
+        assert!(time >= self.now);
+        self.expired.clear();
+
+        loop {
+            self.expire_current();
+            match self.next_occupied() {
+                Some(next) if next <= time => {
+                    self.now = next;
+                    self.cascade();
+                }
+                _ => break,
+            }
+        }
+        self.now = time;
+
+        Expired {
+            expired: self.expired.iter(),
+        }
+    }
+
+    /// Return the wheel's current time
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Return the number of pending deadlines
+    pub fn len(&self) -> usize {
+        self.entries.len() - self.free.len()
+    }
+
+    /// Returns true if there are no pending deadlines
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Put a live entry in the bucket for its deadline relative to now
+    fn schedule(&mut self, index: usize) {
+        let entry = &self.entries[index];
+        let level = match entry.deadline ^ self.now {
+            0 => 0,
+            diff => ((63 - diff.leading_zeros()) / SLOT_BITS) as usize,
+        };
+        let slot = slot_at(entry.deadline, level);
+        self.buckets[level * SLOTS + slot].push((index, entry.generation));
+        self.occupied[level] |= 1 << slot;
+    }
+
+    // Expire everything in the level 0 bucket for now
+    fn expire_current(&mut self) {
+        let slot = slot_at(self.now, 0);
+        if self.occupied[0] & (1 << slot) == 0 {
+            return;
+        }
+        self.occupied[0] &= !(1 << slot);
+
+        let bucket = mem::take(&mut self.buckets[slot]);
+        for (index, generation) in bucket {
+            let entry = &mut self.entries[index];
+            if !entry.live || entry.generation != generation {
+                continue;
+            }
+            let id = DeadlineId { index, generation };
+            self.expired.push((id, entry.deadline));
+
+            let next = match entry.period {
+                0 => None,
+                period => entry.deadline.checked_add(period),
+            };
+            match next {
+                Some(next) => {
+                    entry.deadline = next;
+                    self.schedule(index);
+                }
+                None => {
+                    entry.live = false;
+                    self.free.push(index);
+                }
+            }
+        }
+    }
+
+    // Return the start of the earliest bucket after now that might hold
+    // a deadline
+    fn next_occupied(&self) -> Option<u64> {
+        for level in 0..LEVELS {
+            let slot = slot_at(self.now, level);
+            let later = if slot == SLOTS - 1 {
+                0
+            } else {
+                !0u64 << (slot + 1)
+            };
+            let mask = self.occupied[level] & later;
+            if mask != 0 {
+                let shift = SLOT_BITS * level as u32;
+                let base = self.now.checked_shr(shift + SLOT_BITS).unwrap_or(0) << SLOT_BITS;
+                return Some((base + u64::from(mask.trailing_zeros())) << shift);
+            }
+        }
+        None
+    }
+
+    // Move the deadlines in the buckets that now has just entered down
+    // into finer buckets, coarsest first
+    fn cascade(&mut self) {
+        for level in (1..LEVELS).rev() {
+            let shift = SLOT_BITS * level as u32;
+            if self.now & ((1 << shift) - 1) != 0 {
+                continue;
+            }
+            let slot = slot_at(self.now, level);
+            if self.occupied[level] & (1 << slot) == 0 {
+                continue;
+            }
+            self.occupied[level] &= !(1 << slot);
+
+            let bucket = mem::take(&mut self.buckets[level * SLOTS + slot]);
+            for (index, generation) in bucket {
+                let entry = &self.entries[index];
+                if entry.live && entry.generation == generation {
+                    self.schedule(index);
+                }
+            }
+        }
+    }
+}
+
+fn slot_at(time: u64, level: usize) -> usize {
+    (time.checked_shr(SLOT_BITS * level as u32).unwrap_or(0) as usize) & (SLOTS - 1)
+}
+
+/// The deadlines that expired during a `TimerWheel::advance`
+pub struct Expired<'a> {
+    expired: slice::Iter<'a, (DeadlineId, u64)>,
+}
+
+impl<'a> Iterator for Expired<'a> {
+    type Item = (DeadlineId, u64);
+
+    fn next(&mut self) -> Option<(DeadlineId, u64)> {
+        self.expired.next().cloned()
+    }
+}
+
+#[test]
+fn test_timer_wheel() {
+    let mut wheel = TimerWheel::new();
+    assert!(wheel.is_empty());
+
+    let deadlines = [0, 1, 63, 64, 65, 4095, 4096, 1 << 40, u64::MAX];
+    let ids: Vec<_> = deadlines.iter().map(|&d| wheel.insert(d)).collect();
+    assert_eq!(wheel.len(), deadlines.len());
+
+    let expired: Vec<_> = wheel.advance(1 << 40).collect();
+    let expected: Vec<_> = ids.iter().cloned().zip(deadlines.iter().cloned()).collect();
+    assert_eq!(expired, &expected[..8]);
+    assert_eq!(wheel.now(), 1 << 40);
+    assert_eq!(wheel.deadline(ids[8]), Some(u64::MAX));
+    assert_eq!(wheel.deadline(ids[0]), None);
+
+    assert_eq!(wheel.advance(u64::MAX - 1).count(), 0);
+    assert_eq!(
+        wheel.advance(u64::MAX).collect::<Vec<_>>(),
+        vec![(ids[8], u64::MAX)]
+    );
+    assert!(wheel.is_empty());
+}
+
+#[test]
+fn test_timer_wheel_cancel() {
+    let mut wheel = TimerWheel::starting_at(1000);
+    let a = wheel.insert(2000);
+    let b = wheel.insert(500);
+    assert_eq!(wheel.deadline(b), Some(1000));
+
+    assert!(wheel.cancel(a));
+    assert!(!wheel.cancel(a));
+    // The slot is reused, but the old handle stays cancelled
+    let c = wheel.insert(3000);
+    assert!(!wheel.cancel(a));
+    assert_eq!(wheel.deadline(a), None);
+
+    let expired: Vec<_> = wheel.advance(5000).collect();
+    assert_eq!(expired, vec![(b, 1000), (c, 3000)]);
+    assert!(!wheel.cancel(c));
+}
+
+#[test]
+fn test_timer_wheel_periodic() {
+    let mut wheel = TimerWheel::new();
+    let fast = wheel.insert_periodic(3, 7);
+    let slow = wheel.insert_periodic(0, 100);
+    let once = wheel.insert(50);
+
+    let mut expected = vec![];
+    for t in 0..=300 {
+        if t >= 3 && (t - 3) % 7 == 0 {
+            expected.push((fast, t));
+        }
+        if t % 100 == 0 {
+            expected.push((slow, t));
+        }
+        if t == 50 {
+            expected.push((once, t));
+        }
+    }
+    // Split into uneven steps
+    let mut expired = vec![];
+    for &time in &[0, 5, 64, 65, 200, 300] {
+        expired.extend(wheel.advance(time));
+    }
+    assert!(expired.windows(2).all(|w| w[0].1 <= w[1].1));
+    expired.sort_by_key(|&(id, t)| (t, id));
+    expected.sort_by_key(|&(id, t)| (t, id));
+    assert_eq!(expired, expected);
+    assert_eq!(wheel.len(), 2);
+}